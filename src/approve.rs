@@ -0,0 +1,153 @@
+//! `meta project approve` — admit a quarantined project into the workspace
+//!
+//! `meta project import --quarantine` parks new projects in the `pending`
+//! section of .meta and clones them into `.meta-quarantine/`. Approving one
+//! moves its entry to `projects` and its review clone to the project's path,
+//! after which meta syncs it like any other project.
+
+use crate::import::{PENDING_KEY, QUARANTINE_DIR};
+use crate::rename::{check_destination, relocate};
+use crate::{manifest, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use serde_json::Value;
+use std::path::Path;
+
+/// Handle `meta project approve [<name>]`; without a name, list what's pending
+pub(crate) fn handle_approve(
+    args: &[String],
+    options: &ExecuteOptions,
+    cwd: &Path,
+) -> CommandResult {
+    let Some(meta_path) = manifest::find(cwd) else {
+        return CommandResult::Error(format!("No .meta config found in {}", cwd.display()));
+    };
+    let mut document = match manifest::read(&meta_path) {
+        Ok(document) => document,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let pending = document.get(PENDING_KEY).and_then(|p| p.as_object());
+    let Some(name) = args.iter().find(|a| !a.starts_with('-')) else {
+        let lines: Vec<String> = pending
+            .into_iter()
+            .flatten()
+            .map(|(name, entry)| {
+                let url = entry.get("repo").and_then(|r| r.as_str()).unwrap_or("");
+                format!("  {name}  {url}")
+            })
+            .collect();
+        if lines.is_empty() {
+            return CommandResult::Message("No projects are pending approval.".to_string());
+        }
+        return CommandResult::Message(format!("Pending approval:\n{}", lines.join("\n")));
+    };
+
+    let Some(fields) = document.as_object_mut() else {
+        return CommandResult::Error("Manifest must be an object".to_string());
+    };
+    let entry = fields
+        .get_mut(PENDING_KEY)
+        .and_then(|p| p.as_object_mut())
+        .and_then(|pending| pending.remove(name.as_str()));
+    let Some(entry) = entry else {
+        return CommandResult::Error(format!("Project '{name}' is not pending approval"));
+    };
+    if fields
+        .get(PENDING_KEY)
+        .and_then(|p| p.as_object())
+        .is_some_and(|p| p.is_empty())
+    {
+        fields.remove(PENDING_KEY);
+    }
+    let projects = fields
+        .entry("projects")
+        .or_insert_with(|| Value::Object(Default::default()));
+    let Some(projects) = projects.as_object_mut() else {
+        return CommandResult::Error("\"projects\" in .meta must be an object".to_string());
+    };
+    if projects.contains_key(name.as_str()) {
+        return CommandResult::Error(format!("A project named '{name}' is already in .meta"));
+    }
+
+    let path = entry
+        .get("path")
+        .and_then(|p| p.as_str())
+        .unwrap_or(name)
+        .to_string();
+    let review_path = format!("{QUARANTINE_DIR}/{name}");
+    if let Err(e) = check_destination(projects, cwd, &review_path, &path) {
+        return CommandResult::Error(e);
+    }
+    projects.insert(name.clone(), entry);
+
+    let summary = if cwd.join(&review_path).exists() {
+        format!("Approved '{name}' and moved {review_path} to {path}.")
+    } else {
+        format!("Approved '{name}'. Run 'meta project check --fix' to clone it.")
+    };
+    if options.dry_run {
+        return CommandResult::Message(format!("Dry run: {summary}"));
+    }
+    relocate(cwd, &meta_path, &document, &review_path, &path)
+        .map_or_else(CommandResult::Error, |()| CommandResult::Message(summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Fixture;
+
+    #[test]
+    fn test_quarantined_import_is_approved() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let workspace = fixture.workspace();
+        fixture.manifest(&[]);
+        std::fs::write(
+            workspace.join(".gitmodules"),
+            format!(
+                "[submodule \"api\"]\n\tpath = libs/api\n\turl = {}\n",
+                api.display()
+            ),
+        )
+        .unwrap();
+
+        match fixture.run("project import", &["--from-gitmodules", "--quarantine"]) {
+            CommandResult::Message(msg) => assert!(
+                msg.starts_with("Quarantined 1 project(s) from .gitmodules: api."),
+                "{msg}"
+            ),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(workspace.join(".meta-quarantine/api/.git").exists());
+        assert!(std::fs::read_to_string(workspace.join(".gitignore"))
+            .unwrap()
+            .contains("/.meta-quarantine/"));
+        // Pending projects aren't part of the workspace yet
+        let (projects, _) = manifest::projects(&workspace.join(".meta")).unwrap();
+        assert!(projects.is_empty());
+
+        match fixture.run("project approve", &[]) {
+            CommandResult::Message(msg) => assert!(msg.contains("  api  "), "{msg}"),
+            _ => panic!("Expected Message result"),
+        }
+        match fixture.run("project approve", &["api"]) {
+            CommandResult::Message(msg) => {
+                assert_eq!(
+                    msg,
+                    "Approved 'api' and moved .meta-quarantine/api to libs/api."
+                )
+            }
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(workspace.join("libs/api/.git").exists());
+        let document = manifest::read(&workspace.join(".meta")).unwrap();
+        assert_eq!(document["projects"]["api"]["path"], "libs/api");
+        assert!(document.get(PENDING_KEY).is_none());
+        match fixture.run("project approve", &["api"]) {
+            CommandResult::Error(e) => assert!(e.contains("not pending"), "{e}"),
+            _ => panic!("Expected Error result"),
+        }
+    }
+}
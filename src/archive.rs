@@ -0,0 +1,374 @@
+//! `meta project archive export` — a point-in-time snapshot of the workspace
+//!
+//! Every cloned project is written as a `git bundle` of all its refs next to
+//! a metadata file (URL, HEAD, refs, whether the working tree had local
+//! changes, which the bundle doesn't contain). The .meta manifest is copied
+//! alongside, and `manifest.json` lists every file with its SHA-256, as does
+//! `SHA256SUMS` in `sha256sum -c` format:
+//!
+//! ```text
+//! <dir>/manifest.json
+//! <dir>/SHA256SUMS
+//! <dir>/meta/.meta
+//! <dir>/projects/<path>/repo.bundle
+//! <dir>/projects/<path>/metadata.json
+//! ```
+//!
+//! A bundle restores with `git clone <path>/repo.bundle`. Object storage is
+//! not written to directly; export to a directory and copy it up with the
+//! storage provider's tools.
+
+use crate::pool::{default_jobs, parallel_map_grouped};
+use crate::summary::{RowStatus, RunSummary, Span};
+use crate::{
+    deadline, flag_value, git, manifest, parse_jobs, progress, settings, style, workspace_projects,
+    ExecuteOptions,
+};
+use meta_plugin_protocol::CommandResult;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Version of the `manifest.json` layout
+const ARCHIVE_VERSION: u32 = 1;
+
+/// Contents of `manifest.json`
+#[derive(Debug, Serialize)]
+struct ArchiveManifest {
+    version: u32,
+    /// Seconds since the Unix epoch when the export started
+    created_at: u64,
+    meta: Option<ArchivedFile>,
+    projects: Vec<ArchivedProject>,
+}
+
+#[derive(Debug, Serialize)]
+struct ArchivedProject {
+    path: String,
+    url: String,
+    head: Option<String>,
+    bundle: ArchivedFile,
+    metadata: ArchivedFile,
+}
+
+/// A file in the archive, relative to its root
+#[derive(Debug, Clone, Serialize)]
+struct ArchivedFile {
+    file: String,
+    sha256: String,
+    bytes: u64,
+}
+
+/// Contents of a project's `metadata.json`
+#[derive(Debug, Serialize)]
+struct ProjectMetadata {
+    path: String,
+    url: String,
+    head: Option<String>,
+    branch: Option<String>,
+    /// Uncommitted changes in the working tree, not part of the bundle
+    dirty: bool,
+    /// `(sha, refname)` of every ref in the bundle
+    refs: Vec<(String, String)>,
+}
+
+/// Handle `meta project archive export --to <dir> [-j N]`
+pub(crate) fn handle_archive(
+    args: &[String],
+    options: &ExecuteOptions,
+    provided_projects: &[String],
+    cwd: &Path,
+    summary: &mut RunSummary,
+) -> CommandResult {
+    let usage = "Usage: meta project archive export --to <dir> [-j N]";
+    if args.first().map(String::as_str) != Some("export") {
+        return CommandResult::Error(usage.to_string());
+    }
+    let Some(to) = flag_value(args, "--to") else {
+        return CommandResult::Error(usage.to_string());
+    };
+    if to.contains("://") {
+        return CommandResult::Error(format!(
+            "Cannot write to {to}: object storage isn't supported. Export to a directory \
+             and upload it with your storage tool (e.g. 'aws s3 sync <dir> {to}')."
+        ));
+    }
+    let jobs = match parse_jobs(args) {
+        Ok(jobs) => jobs.unwrap_or_else(default_jobs),
+        Err(e) => return CommandResult::Error(e),
+    };
+    let groups = match settings::concurrency_groups(cwd) {
+        Ok(groups) => groups,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let dest = cwd.join(to);
+    if std::fs::read_dir(&dest).is_ok_and(|mut entries| entries.next().is_some()) {
+        return CommandResult::Error(format!("{to} is not empty; archive into a new directory"));
+    }
+    let projects = match workspace_projects(provided_projects, cwd) {
+        Ok(projects) => projects,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let cloned: Vec<_> = projects
+        .into_iter()
+        .filter(|p| git::is_repo_root(&cwd.join(&p.path)))
+        .collect();
+
+    if options.dry_run {
+        for project in &cloned {
+            println!(
+                "[dry-run] git -C {} bundle create {to}/projects/{}/repo.bundle --all",
+                project.path, project.path
+            );
+        }
+        return CommandResult::Message(format!(
+            "Dry run: would archive {} project(s) to {to}.",
+            cloned.len()
+        ));
+    }
+
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let meta = match manifest::find(cwd) {
+        Some(meta_path) => match copy_meta(&meta_path, &dest) {
+            Ok(file) => Some(file),
+            Err(e) => return CommandResult::Error(format!("Failed to copy .meta: {e}")),
+        },
+        None => None,
+    };
+
+    let completed = AtomicUsize::new(0);
+    progress::begin("archive", cloned.len());
+    let outcomes = parallel_map_grouped(
+        &cloned,
+        jobs,
+        &groups,
+        |p| &p.path,
+        |project| {
+            let started = Instant::now();
+            let result = deadline::check().and_then(|()| {
+                archive_project(&cwd.join(&project.path), &project.path, &project.url, &dest)
+            });
+            let done = completed.fetch_add(1, Ordering::SeqCst) + 1;
+            let bytes = result.as_ref().ok().map(|entry| entry.bundle.bytes);
+            let status = progress::status(&result);
+            progress::project("archive", &project.path, status, done, cloned.len(), bytes);
+            match &result {
+                Ok(_) => println!(
+                    "[{done}/{}] {} {}",
+                    cloned.len(),
+                    style::ok(options.plain),
+                    project.path
+                ),
+                Err(e) => println!(
+                    "[{done}/{}] {} {}: {e}",
+                    cloned.len(),
+                    style::failed(options.plain),
+                    project.path
+                ),
+            }
+            (result, Span::since(started))
+        },
+    );
+
+    let mut archived = Vec::new();
+    let mut failed = Vec::new();
+    for (project, (result, span)) in cloned.iter().zip(outcomes) {
+        let (status, detail) = match result {
+            Ok(entry) => {
+                archived.push(entry);
+                (RowStatus::Ok, String::new())
+            }
+            Err(e) if e == deadline::CANCELLED => (RowStatus::Skipped, e),
+            Err(e) => {
+                failed.push(format!("{}: {e}", project.path));
+                (RowStatus::Failed, e)
+            }
+        };
+        summary.record(&project.path, "archive", status, detail, Some(span));
+    }
+
+    let count = archived.len();
+    let manifest = ArchiveManifest {
+        version: ARCHIVE_VERSION,
+        created_at,
+        meta,
+        projects: archived,
+    };
+    if let Err(e) = write_manifest(&manifest, &dest) {
+        return CommandResult::Error(format!("Failed to write the archive manifest: {e}"));
+    }
+    let message = format!("Archived {count} of {} project(s) to {to}.", cloned.len());
+    if failed.is_empty() {
+        CommandResult::Message(message)
+    } else {
+        CommandResult::Error(format!("{message}\n{}", failed.join("\n")))
+    }
+}
+
+/// Bundle the repo at `dir` into `dest/projects/<path>/` with its metadata
+fn archive_project(
+    dir: &Path,
+    path: &str,
+    url: &str,
+    dest: &Path,
+) -> Result<ArchivedProject, String> {
+    let relative = format!("projects/{path}");
+    let target = dest.join(&relative);
+    std::fs::create_dir_all(&target).map_err(|e| e.to_string())?;
+    let bundle = target.join("repo.bundle");
+    git::run(
+        dir,
+        &[
+            "bundle",
+            "create",
+            "--quiet",
+            &bundle.to_string_lossy(),
+            "--all",
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let refs = git::output(dir, &["for-each-ref", "--format=%(objectname) %(refname)"])
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(sha, name)| (sha.to_string(), name.to_string()))
+        .collect();
+    let head = git::output(dir, &["rev-parse", "--verify", "-q", "HEAD"]);
+    let metadata = ProjectMetadata {
+        path: path.to_string(),
+        url: url.to_string(),
+        head: head.clone(),
+        branch: git::current_branch(dir),
+        dirty: git::is_dirty(dir),
+        refs,
+    };
+    let json = serde_json::to_string_pretty(&metadata).map_err(|e| e.to_string())?;
+    std::fs::write(target.join("metadata.json"), json + "\n").map_err(|e| e.to_string())?;
+
+    Ok(ArchivedProject {
+        path: path.to_string(),
+        url: url.to_string(),
+        head,
+        bundle: checksum(dest, &format!("{relative}/repo.bundle"))?,
+        metadata: checksum(dest, &format!("{relative}/metadata.json"))?,
+    })
+}
+
+fn copy_meta(meta_path: &Path, dest: &Path) -> Result<ArchivedFile, String> {
+    let name = meta_path
+        .file_name()
+        .map_or(".meta".into(), |n| n.to_string_lossy());
+    let file = format!("meta/{name}");
+    std::fs::create_dir_all(dest.join("meta")).map_err(|e| e.to_string())?;
+    std::fs::copy(meta_path, dest.join(&file)).map_err(|e| e.to_string())?;
+    checksum(dest, &file)
+}
+
+/// Write `manifest.json` and `SHA256SUMS` listing every archived file
+fn write_manifest(manifest: &ArchiveManifest, dest: &Path) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(manifest).map_err(|e| e.to_string())?;
+    std::fs::write(dest.join("manifest.json"), json + "\n").map_err(|e| e.to_string())?;
+    let files = manifest.meta.iter().chain(
+        manifest
+            .projects
+            .iter()
+            .flat_map(|p| [&p.bundle, &p.metadata]),
+    );
+    let mut sums: Vec<String> = files.map(|f| format!("{}  {}", f.sha256, f.file)).collect();
+    sums.push(format!(
+        "{}  manifest.json",
+        checksum(dest, "manifest.json")?.sha256
+    ));
+    std::fs::write(dest.join("SHA256SUMS"), sums.join("\n") + "\n").map_err(|e| e.to_string())
+}
+
+/// The SHA-256 and size of `dest/file`
+fn checksum(dest: &Path, file: &str) -> Result<ArchivedFile, String> {
+    let mut reader = std::fs::File::open(dest.join(file)).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let bytes = std::io::copy(&mut reader, &mut hasher).map_err(|e| e.to_string())?;
+    let sha256 = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    Ok(ArchivedFile {
+        file: file.to_string(),
+        sha256,
+        bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{run_git, Fixture};
+
+    #[test]
+    fn test_archive_export() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        fixture.manifest(&[("api", &api), ("libs/web", &web)]);
+        fixture.clone_project("api", &api);
+        fixture.clone_project("libs/web", &web);
+        let workspace = fixture.workspace();
+        std::fs::write(workspace.join("api/notes.txt"), "draft").unwrap();
+
+        match fixture.run("project archive", &["export", "--to", "snapshot"]) {
+            CommandResult::Message(msg) => {
+                assert_eq!(msg, "Archived 2 of 2 project(s) to snapshot.")
+            }
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        let snapshot = workspace.join("snapshot");
+        let manifest: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(snapshot.join("manifest.json")).unwrap())
+                .unwrap();
+        assert_eq!(manifest["version"], 1);
+        assert_eq!(manifest["meta"]["file"], "meta/.meta");
+        assert_eq!(manifest["projects"][1]["path"], "libs/web");
+        assert_eq!(
+            manifest["projects"][1]["bundle"]["file"],
+            "projects/libs/web/repo.bundle"
+        );
+        let metadata: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(snapshot.join("projects/api/metadata.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(metadata["dirty"], true);
+        assert_eq!(metadata["branch"], "main");
+
+        // The checksums verify, and the bundles clone
+        let sums = std::fs::read_to_string(snapshot.join("SHA256SUMS")).unwrap();
+        assert_eq!(sums.lines().count(), 6);
+        for line in sums.lines() {
+            let (sha, file) = line.split_once("  ").unwrap();
+            assert_eq!(checksum(&snapshot, file).unwrap().sha256, sha, "{file}");
+        }
+        run_git(
+            &snapshot,
+            &["clone", "-q", "projects/api/repo.bundle", "restored"],
+        );
+        assert_eq!(
+            git::output(&snapshot.join("restored"), &["rev-parse", "HEAD"]),
+            manifest["projects"][0]["head"].as_str().map(str::to_string)
+        );
+
+        match fixture.run("project archive", &["export", "--to", "snapshot"]) {
+            CommandResult::Error(e) => assert!(e.contains("not empty"), "{e}"),
+            _ => panic!("Expected Error result"),
+        }
+        match fixture.run("project archive", &["export", "--to", "s3://bucket/x"]) {
+            CommandResult::Error(e) => assert!(e.contains("object storage"), "{e}"),
+            _ => panic!("Expected Error result"),
+        }
+    }
+}
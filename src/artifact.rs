@@ -0,0 +1,237 @@
+//! Archive projects — tarballs that live in the workspace next to clones
+//!
+//! A .meta entry with `"type": "archive"`, a `url` and a `sha256` (see
+//! [`settings::ArchiveSource`]) is a vendored SDK or dataset rather than a
+//! git repository. `check --fix` downloads it with `curl`, verifies the
+//! checksum, unpacks it with `tar` and records what it unpacked in
+//! `<path>/.meta-archive.json`; `check` compares that record with .meta.
+//!
+//! Directories without the record were not unpacked by meta and are never
+//! replaced.
+
+use crate::manifest;
+use crate::settings::{self, ArchiveSource};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Written into an unpacked archive project
+pub(crate) const MARKER_FILE: &str = ".meta-archive.json";
+
+/// What an archive project was unpacked from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Marker {
+    url: String,
+    sha256: String,
+}
+
+/// How an archive project's directory differs from .meta
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ArchiveState {
+    Current,
+    /// Nothing at the path yet
+    Missing,
+    /// Unpacked from a tarball with this checksum
+    Outdated(String),
+    /// Something that meta didn't unpack is at the path
+    Unmanaged,
+}
+
+/// The archive projects of the workspace at `cwd`
+pub(crate) fn sources(cwd: &Path) -> Result<Vec<ArchiveSource>, String> {
+    match manifest::find(cwd) {
+        Some(meta_path) => settings::archive_sources(&meta_path),
+        None => Ok(Vec::new()),
+    }
+}
+
+pub(crate) fn state(source: &ArchiveSource, cwd: &Path) -> ArchiveState {
+    let dir = cwd.join(&source.path);
+    if !dir.exists() {
+        return ArchiveState::Missing;
+    }
+    let marker = std::fs::read_to_string(dir.join(MARKER_FILE))
+        .ok()
+        .and_then(|text| serde_json::from_str::<Marker>(&text).ok());
+    match marker {
+        Some(marker) if marker.sha256 == source.sha256 => ArchiveState::Current,
+        Some(marker) => ArchiveState::Outdated(marker.sha256),
+        None => ArchiveState::Unmanaged,
+    }
+}
+
+/// Download, verify and unpack the archive project at `path`, replacing a
+/// previously unpacked version
+///
+/// The tarball is unpacked next to the project and moved into place only
+/// once it is complete, so a failed download leaves the old version alone.
+pub(crate) fn install(path: &str, cwd: &Path) -> anyhow::Result<String> {
+    let sources = sources(cwd).map_err(anyhow::Error::msg)?;
+    let Some(source) = sources.iter().find(|s| s.path == path) else {
+        anyhow::bail!("{path} is not an archive project in .meta");
+    };
+    if state(source, cwd) == ArchiveState::Unmanaged {
+        anyhow::bail!("{path} exists but wasn't unpacked from an archive; move it aside first");
+    }
+    let dir = cwd.join(path);
+    let download = sibling(&dir, "download");
+    let staging = sibling(&dir, "unpack");
+    let result = unpack(source, &download, &staging).and_then(|()| {
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        std::fs::rename(&staging, &dir)?;
+        Ok(())
+    });
+    let _ = std::fs::remove_file(&download);
+    let _ = std::fs::remove_dir_all(&staging);
+    result.map(|()| format!("unpacked {} into {path}", source.url))
+}
+
+fn unpack(source: &ArchiveSource, download: &Path, staging: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = download.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let download_arg = download.to_string_lossy();
+    run("curl", &["-fsSL", "-o", &download_arg, &source.url])?;
+    let actual = sha256(download)?;
+    if actual != source.sha256 {
+        anyhow::bail!(
+            "checksum mismatch for {}: got {actual}, .meta expects {}",
+            source.url,
+            source.sha256
+        );
+    }
+    let _ = std::fs::remove_dir_all(staging);
+    std::fs::create_dir_all(staging)?;
+    run(
+        "tar",
+        &["-xf", &download_arg, "-C", &staging.to_string_lossy()],
+    )?;
+    let marker = Marker {
+        url: source.url.clone(),
+        sha256: source.sha256.clone(),
+    };
+    std::fs::write(
+        staging.join(MARKER_FILE),
+        serde_json::to_string_pretty(&marker)? + "\n",
+    )?;
+    Ok(())
+}
+
+/// `<dir>.meta-<suffix>`, a scratch path next to the project
+fn sibling(dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = dir.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".meta-{suffix}"));
+    dir.with_file_name(name)
+}
+
+fn sha256(file: &Path) -> anyhow::Result<String> {
+    let mut reader = std::fs::File::open(file)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut reader, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// Run `program`, surfacing stderr in the error when it fails
+fn run(program: &str, args: &[&str]) -> anyhow::Result<()> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow::anyhow!("failed to run {program}: {e}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        anyhow::bail!(
+            "{program} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Fixture;
+    use meta_plugin_protocol::CommandResult;
+
+    #[test]
+    fn test_archive_projects() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let workspace = fixture.workspace();
+        let dist = workspace.parent().unwrap().join("dist");
+        std::fs::create_dir_all(dist.join("v1")).unwrap();
+        std::fs::write(dist.join("v1/VERSION"), "1\n").unwrap();
+        let tarball = dist.join("sdk-1.tar.gz");
+        let status = Command::new("tar")
+            .args(["-czf", &tarball.to_string_lossy(), "-C", "v1", "."])
+            .current_dir(&dist)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let checksum = sha256(&tarball).unwrap();
+        let write_meta = |sha256: &str| {
+            let meta = serde_json::json!({"projects": {
+                "api": api.to_string_lossy(),
+                "vendor/sdk": {
+                    "type": "archive",
+                    "url": format!("file://{}", tarball.display()),
+                    "sha256": sha256
+                }
+            }});
+            std::fs::write(workspace.join(".meta"), meta.to_string()).unwrap();
+        };
+        write_meta(&"0".repeat(64));
+        fixture.clone_project("api", &api);
+
+        match fixture.run("project check", &[]) {
+            CommandResult::Message(msg) => assert!(
+                msg.contains("1 archive project(s) are not unpacked at the checksum in .meta."),
+                "{msg}"
+            ),
+            _ => panic!("Expected Message result"),
+        }
+        match fixture.run("project check", &["--fix", "--yes"]) {
+            CommandResult::Error(e) => assert!(e.contains("checksum mismatch"), "{e}"),
+            _ => panic!("Expected Error result"),
+        }
+        assert!(!workspace.join("vendor/sdk").exists());
+
+        write_meta(&checksum);
+        match fixture.run("project check", &["--fix", "--yes"]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Applied 1 fix(es)."),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert_eq!(
+            std::fs::read_to_string(workspace.join("vendor/sdk/VERSION")).unwrap(),
+            "1\n"
+        );
+        match fixture.run("project check", &[]) {
+            CommandResult::Message(msg) => {
+                assert_eq!(msg, "All projects are cloned and present.")
+            }
+            _ => panic!("Expected Message result"),
+        }
+
+        // Unpacked from another tarball: reported, and replaced by --fix
+        write_meta(&"f".repeat(64));
+        let sources = sources(&workspace).unwrap();
+        assert_eq!(
+            state(&sources[0], &workspace),
+            ArchiveState::Outdated(checksum)
+        );
+        std::fs::remove_file(workspace.join("vendor/sdk").join(MARKER_FILE)).unwrap();
+        assert_eq!(state(&sources[0], &workspace), ArchiveState::Unmanaged);
+        match fixture.run("project check", &["--fix", "--yes"]) {
+            CommandResult::Error(e) => assert!(e.contains("move it aside first"), "{e}"),
+            _ => panic!("Expected Error result"),
+        }
+    }
+}
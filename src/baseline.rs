@@ -0,0 +1,257 @@
+//! Accepted check findings: the baseline file and per-project suppressions
+//!
+//! `meta project check --write-baseline` records the current findings in
+//! `.meta-baseline.json` next to .meta. Later checks hide findings that are
+//! in the baseline, so CI fails only on new problems while the old ones are
+//! worked off. A finding is identified by project, category, rule and what
+//! was expected, not by the details that drift (e.g. how far behind).
+//!
+//! Suppressions are the targeted variant: a project's `suppress` entries in
+//! .meta hide one code each, optionally until a date (see [`Suppression`]).
+//! An expired suppression stops applying and is reported.
+
+use crate::check::{Finding, FindingCategory};
+use crate::settings::{self, Suppression};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name of the baseline, stored next to the .meta config
+pub(crate) const BASELINE_FILE: &str = ".meta-baseline.json";
+
+/// Contents of `.meta-baseline.json`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Baseline {
+    pub findings: Vec<BaselineEntry>,
+}
+
+/// The stable identity of a recorded finding
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) struct BaselineEntry {
+    pub project: String,
+    pub category: FindingCategory,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    pub expected: String,
+}
+
+impl From<&Finding> for BaselineEntry {
+    fn from(finding: &Finding) -> Self {
+        BaselineEntry {
+            project: finding.project.clone(),
+            category: finding.category,
+            rule: finding.rule.clone(),
+            expected: finding.expected.clone(),
+        }
+    }
+}
+
+/// Record `findings` as the baseline of the workspace at `cwd`
+pub(crate) fn write(findings: &[Finding], cwd: &Path) -> Result<(), String> {
+    let mut entries: Vec<BaselineEntry> = findings.iter().map(BaselineEntry::from).collect();
+    entries.sort_by(|a, b| (&a.project, &a.expected).cmp(&(&b.project, &b.expected)));
+    entries.dedup();
+    let baseline = Baseline { findings: entries };
+    let path = cwd.join(BASELINE_FILE);
+    serde_json::to_string_pretty(&baseline)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(&path, json + "\n").map_err(|e| e.to_string()))
+        .map_err(|e| format!("Failed to write {BASELINE_FILE}: {e}"))
+}
+
+fn read(cwd: &Path) -> Result<Baseline, String> {
+    let path = cwd.join(BASELINE_FILE);
+    if !path.exists() {
+        return Ok(Baseline::default());
+    }
+    std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+        .map_err(|e| format!("Failed to read {BASELINE_FILE}: {e}"))
+}
+
+/// What [`filter`] left out
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Hidden {
+    pub baselined: usize,
+    pub suppressed: usize,
+    /// `project: code (expired YYYY-MM-DD)` for each lapsed suppression
+    pub expired: Vec<String>,
+}
+
+/// Drop baselined and suppressed findings
+///
+/// With `use_baseline` false (`--no-baseline`) only suppressions apply.
+pub(crate) fn filter(
+    findings: Vec<Finding>,
+    meta_path: Option<&Path>,
+    cwd: &Path,
+    use_baseline: bool,
+) -> Result<(Vec<Finding>, Hidden), String> {
+    let baseline: HashSet<BaselineEntry> = if use_baseline {
+        read(cwd)?.findings.into_iter().collect()
+    } else {
+        HashSet::new()
+    };
+    let suppressions = match meta_path {
+        Some(meta_path) => settings::suppressions(meta_path)?,
+        None => HashMap::new(),
+    };
+    let today = today();
+
+    let mut hidden = Hidden::default();
+    for (project, list) in &suppressions {
+        for suppression in list {
+            if let Some(until) = suppression
+                .until
+                .as_deref()
+                .filter(|until| *until < today.as_str())
+            {
+                hidden
+                    .expired
+                    .push(format!("{project}: {} (expired {until})", suppression.code));
+            }
+        }
+    }
+    let kept = findings
+        .into_iter()
+        .filter(|finding| {
+            let active = suppressions
+                .get(&finding.project)
+                .into_iter()
+                .flatten()
+                .any(|s| suppresses(s, finding, &today));
+            if active {
+                hidden.suppressed += 1;
+                return false;
+            }
+            if baseline.contains(&BaselineEntry::from(finding)) {
+                hidden.baselined += 1;
+                return false;
+            }
+            true
+        })
+        .collect();
+    Ok((kept, hidden))
+}
+
+fn suppresses(suppression: &Suppression, finding: &Finding, today: &str) -> bool {
+    if suppression
+        .until
+        .as_deref()
+        .is_some_and(|until| until < today)
+    {
+        return false;
+    }
+    let code = serde_json::to_value(finding.category)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string));
+    code.as_deref() == Some(suppression.code.as_str())
+        || finding.rule.as_deref() == Some(suppression.code.as_str())
+}
+
+/// Today's UTC date as `YYYY-MM-DD`
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    civil_date(secs / 86_400)
+}
+
+/// The proleptic Gregorian date `days` after 1970-01-01
+fn civil_date(days: u64) -> String {
+    // Howard Hinnant's civil_from_days, for non-negative day counts
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn finding(project: &str, category: FindingCategory) -> Finding {
+        Finding {
+            category,
+            project: project.to_string(),
+            expected: "origin/main".to_string(),
+            actual: Some("3 commit(s) behind".to_string()),
+            rule: None,
+        }
+    }
+
+    #[test]
+    fn test_civil_date() {
+        assert_eq!(civil_date(0), "1970-01-01");
+        assert_eq!(civil_date(11_016), "2000-02-29");
+        assert_eq!(civil_date(20_819), "2027-01-01");
+    }
+
+    #[test]
+    fn test_baseline_hides_recorded_findings_only() {
+        let temp_dir = TempDir::new().unwrap();
+        let old = finding("a", FindingCategory::Behind);
+        write(std::slice::from_ref(&old), temp_dir.path()).unwrap();
+
+        // Drifted details don't make a recorded finding new
+        let drifted = Finding {
+            actual: Some("5 commit(s) behind".to_string()),
+            ..old
+        };
+        let new = finding("b", FindingCategory::Behind);
+        let (kept, hidden) = filter(
+            vec![drifted.clone(), new.clone()],
+            None,
+            temp_dir.path(),
+            true,
+        )
+        .unwrap();
+        assert_eq!(kept, vec![new.clone()]);
+        assert_eq!(hidden.baselined, 1);
+
+        let (kept, _) = filter(vec![drifted, new], None, temp_dir.path(), false).unwrap();
+        assert_eq!(kept.len(), 2);
+    }
+
+    #[test]
+    fn test_suppressions_expire() {
+        let temp_dir = TempDir::new().unwrap();
+        let meta = temp_dir.path().join(".meta");
+        std::fs::write(
+            &meta,
+            r#"{"projects": {
+                "a": {"repo": "x", "suppress": [{"code": "behind", "until": "2999-01-01"}]},
+                "b": {"repo": "y", "suppress": [{"code": "behind", "until": "2000-01-01", "reason": "old"}]}
+            }}"#,
+        )
+        .unwrap();
+        let findings = vec![
+            finding("a", FindingCategory::Behind),
+            finding("a", FindingCategory::Upstream),
+            finding("b", FindingCategory::Behind),
+        ];
+        let (kept, hidden) = filter(findings, Some(&meta), temp_dir.path(), true).unwrap();
+        assert_eq!(kept.len(), 2);
+        assert_eq!(hidden.suppressed, 1);
+        assert_eq!(hidden.expired, vec!["b: behind (expired 2000-01-01)"]);
+
+        std::fs::write(
+            &meta,
+            r#"{"projects": {"a": {"repo": "x", "suppress": [{"code": "behind", "until": "soon"}]}}}"#,
+        )
+        .unwrap();
+        assert!(filter(vec![], Some(&meta), temp_dir.path(), true)
+            .unwrap_err()
+            .contains("not a YYYY-MM-DD date"));
+    }
+}
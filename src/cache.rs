@@ -0,0 +1,287 @@
+//! `meta project cache` — inspect and garbage-collect meta-project's caches
+//!
+//! Everything the plugin keeps outside the workspace lives under
+//! [`state::cache_dir`], grouped by kind (`workspaces/<key>` today). Each
+//! child of a kind directory is one entry, the unit `gc` keeps or deletes:
+//!
+//! 1. entries not used for `--max-age` are deleted;
+//! 2. if the rest still exceed `--max-size`, the least recently used go
+//!    until they fit.
+//!
+//! The current workspace's entry and entries whose workspace lock is held
+//! are never deleted.
+
+use crate::stats::format_bytes;
+use crate::{dir_size, flag_value, state, workspace_lock, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Entries unused for longer than this are deleted by default
+const DEFAULT_MAX_AGE_DAYS: u64 = 90;
+
+/// Default budget for the whole cache
+const DEFAULT_MAX_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Limits `gc` enforces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Policy {
+    max_age_days: u64,
+    max_size: u64,
+}
+
+/// One cache entry as `gc` sees it
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    path: PathBuf,
+    bytes: u64,
+    /// Newest modification inside the entry, seconds since the Unix epoch
+    last_used: u64,
+}
+
+/// Handle `meta project cache [info] | gc [--max-age DAYS] [--max-size SIZE]`
+pub(crate) fn handle_cache(args: &[String], options: &ExecuteOptions, cwd: &Path) -> CommandResult {
+    let Some(root) = state::cache_dir() else {
+        return CommandResult::Error("Cannot locate the cache directory".to_string());
+    };
+    match args
+        .iter()
+        .find(|a| !a.starts_with('-'))
+        .map(String::as_str)
+    {
+        None | Some("info") => info(&root),
+        Some("gc") => {
+            let policy = match parse_policy(args) {
+                Ok(policy) => policy,
+                Err(e) => return CommandResult::Error(e),
+            };
+            let keep = state::workspace_cache_dir(cwd);
+            let evicted = plan(&root, policy, keep.as_deref(), now());
+            report(&evicted, options.dry_run)
+        }
+        Some(other) => CommandResult::Error(format!(
+            "Unknown cache action '{other}': expected 'info' or 'gc'"
+        )),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn parse_policy(args: &[String]) -> Result<Policy, String> {
+    let max_age_days = match flag_value(args, "--max-age") {
+        Some(value) => value
+            .strip_suffix('d')
+            .unwrap_or(value)
+            .parse()
+            .map_err(|_| format!("Invalid --max-age value '{value}': expected days, e.g. 30d"))?,
+        None => DEFAULT_MAX_AGE_DAYS,
+    };
+    let max_size = match flag_value(args, "--max-size") {
+        Some(value) => parse_size(value).ok_or_else(|| {
+            format!("Invalid --max-size value '{value}': expected e.g. 500M or 2G")
+        })?,
+        None => DEFAULT_MAX_SIZE,
+    };
+    Ok(Policy {
+        max_age_days,
+        max_size,
+    })
+}
+
+/// Parse a byte count with an optional binary unit: `512`, `500K`, `2G`, `1.5GiB`
+fn parse_size(value: &str) -> Option<u64> {
+    let digits = value.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let unit = value[digits.len()..].to_ascii_uppercase();
+    let multiplier: u64 = match unit.trim_end_matches("IB").trim_end_matches('B') {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return None,
+    };
+    let number: f64 = digits.parse().ok()?;
+    (number >= 0.0).then_some((number * multiplier as f64) as u64)
+}
+
+/// Every entry under the cache root: the children of each kind directory
+fn entries(root: &Path) -> Vec<Entry> {
+    let Ok(kinds) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    kinds
+        .flatten()
+        .filter(|kind| kind.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|kind| std::fs::read_dir(kind.path()).ok())
+        .flat_map(|children| children.flatten())
+        .map(|child| {
+            let path = child.path();
+            Entry {
+                bytes: if path.is_dir() {
+                    dir_size(&path)
+                } else {
+                    child.metadata().map(|m| m.len()).unwrap_or(0)
+                },
+                last_used: last_modified(&path),
+                path,
+            }
+        })
+        .collect()
+}
+
+fn last_modified(path: &Path) -> u64 {
+    let own = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs());
+    let Ok(children) = std::fs::read_dir(path) else {
+        return own;
+    };
+    children
+        .flatten()
+        .map(|child| last_modified(&child.path()))
+        .fold(own, u64::max)
+}
+
+/// The entries `policy` evicts, oldest first, each with the reason
+fn plan(root: &Path, policy: Policy, keep: Option<&Path>, now: u64) -> Vec<(Entry, String)> {
+    let all = entries(root);
+    let mut total: u64 = all.iter().map(|e| e.bytes).sum();
+    let mut candidates: Vec<Entry> = all
+        .into_iter()
+        .filter(|entry| {
+            Some(entry.path.as_path()) != keep && !workspace_lock::is_held_in(&entry.path)
+        })
+        .collect();
+    candidates.sort_by_key(|entry| entry.last_used);
+
+    let mut evicted = Vec::new();
+    for entry in candidates {
+        let days = now.saturating_sub(entry.last_used) / 86_400;
+        let reason = if days > policy.max_age_days {
+            format!("unused for {days} day(s)")
+        } else if total > policy.max_size {
+            format!("over the {} budget", format_bytes(policy.max_size))
+        } else {
+            continue;
+        };
+        total -= entry.bytes;
+        evicted.push((entry, reason));
+    }
+    evicted
+}
+
+fn report(evicted: &[(Entry, String)], dry_run: bool) -> CommandResult {
+    if evicted.is_empty() {
+        return CommandResult::Message(
+            "Cache is within its limits; nothing to remove.".to_string(),
+        );
+    }
+    let mut lines = Vec::new();
+    let mut freed = 0;
+    for (entry, reason) in evicted {
+        if !dry_run {
+            let removed = if entry.path.is_dir() {
+                std::fs::remove_dir_all(&entry.path)
+            } else {
+                std::fs::remove_file(&entry.path)
+            };
+            if let Err(e) = removed {
+                lines.push(format!("  {}: failed to remove: {e}", entry.path.display()));
+                continue;
+            }
+        }
+        freed += entry.bytes;
+        lines.push(format!(
+            "  {} ({}, {reason})",
+            entry.path.display(),
+            format_bytes(entry.bytes)
+        ));
+    }
+    let verb = if dry_run {
+        "Dry run: would free"
+    } else {
+        "Freed"
+    };
+    lines.insert(0, format!("{verb} {}:", format_bytes(freed)));
+    CommandResult::Message(lines.join("\n"))
+}
+
+fn info(root: &Path) -> CommandResult {
+    let entries = entries(root);
+    let total: u64 = entries.iter().map(|e| e.bytes).sum();
+    CommandResult::Message(format!(
+        "Cache directory: {}\n{} entr{}, {}",
+        root.display(),
+        entries.len(),
+        if entries.len() == 1 { "y" } else { "ies" },
+        format_bytes(total)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(root: &Path, name: &str, bytes: usize) -> PathBuf {
+        let dir = root.join("workspaces").join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("history.json"), vec![b'x'; bytes]).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_gc_evicts_by_age_then_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let current = entry(root, "current", 400);
+        entry(root, "a", 300);
+        entry(root, "b", 200);
+        let now = now();
+
+        let roomy = Policy {
+            max_age_days: 90,
+            max_size: 10_000,
+        };
+        assert!(plan(root, roomy, Some(&current), now).is_empty());
+
+        // A year later everything but the current workspace is stale
+        let stale = plan(root, roomy, Some(&current), now + 365 * 86_400);
+        assert_eq!(stale.len(), 2);
+        assert!(stale[0].1.starts_with("unused for 365 day(s)"));
+
+        let tight = Policy {
+            max_age_days: 90,
+            max_size: 700,
+        };
+        let over = plan(root, tight, Some(&current), now);
+        assert_eq!(over.len(), 1, "removing one entry gets under budget");
+        assert!(over[0].1.contains("budget"));
+
+        assert!(matches!(
+            report(&over, true),
+            CommandResult::Message(msg) if msg.starts_with("Dry run: would free")
+        ));
+        assert!(over[0].0.path.exists());
+        report(&over, false);
+        assert!(!over[0].0.path.exists());
+        assert!(current.exists());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("500K"), Some(500 * 1024));
+        assert_eq!(parse_size("2G"), Some(2 << 30));
+        assert_eq!(parse_size("1.5GiB"), Some(3 << 29));
+        assert_eq!(parse_size("10mb"), Some(10 << 20));
+        assert_eq!(parse_size("lots"), None);
+    }
+}
@@ -0,0 +1,364 @@
+//! `meta project changeset` — named cross-repo change sets
+//!
+//! A changeset records the branch and commit of every project touched by a
+//! multi-repo change, so the change has one identity reviewers can check
+//! out. Changesets are stored in the meta repo as
+//! `.meta-changesets/<id>.json`, in the same format as meta-lock.json (so
+//! `diff-lock` can compare them), and are shared by committing that file.
+
+use crate::lock::{LockedProject, Lockfile, LOCKFILE_VERSION};
+use crate::style;
+use crate::workspace_lock;
+use crate::{git, jj, workspace_projects, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use std::path::{Path, PathBuf};
+
+/// Directory in the meta repo holding one file per changeset
+const CHANGESET_DIR: &str = ".meta-changesets";
+
+/// Handle `meta project changeset create|checkout|list`
+pub(crate) fn handle_changeset(
+    args: &[String],
+    options: &ExecuteOptions,
+    provided_projects: &[String],
+    cwd: &Path,
+) -> CommandResult {
+    let mut positional = args.iter().filter(|a| !a.starts_with('-'));
+    let action = positional.next().map(String::as_str);
+    let id = positional.next().map(String::as_str);
+    match (action, id) {
+        (Some("list"), _) => list(cwd),
+        (Some("create"), Some(id)) => create(id, args, options, provided_projects, cwd),
+        (Some("checkout"), Some(id)) => checkout(id, args, options, cwd),
+        _ => CommandResult::Error(
+            "Usage: meta project changeset create <id> | checkout <id> | list".to_string(),
+        ),
+    }
+}
+
+fn changeset_path(cwd: &Path, id: &str) -> Result<PathBuf, String> {
+    let valid = !id.is_empty()
+        && !id.starts_with('.')
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(format!(
+            "Invalid changeset id '{id}': use letters, digits, '-', '_' and '.'"
+        ));
+    }
+    Ok(cwd.join(CHANGESET_DIR).join(format!("{id}.json")))
+}
+
+/// Whether HEAD has commits that aren't on origin's default branch (or,
+/// without one, on the branch's upstream)
+fn is_modified(dir: &Path) -> bool {
+    if git::ref_exists(dir, "refs/remotes/origin/HEAD") {
+        return git::output(dir, &["merge-base", "--is-ancestor", "HEAD", "origin/HEAD"]).is_none();
+    }
+    git::ahead_behind(dir).is_none_or(|(ahead, _)| ahead > 0)
+}
+
+fn create(
+    id: &str,
+    args: &[String],
+    options: &ExecuteOptions,
+    provided_projects: &[String],
+    cwd: &Path,
+) -> CommandResult {
+    let path = match changeset_path(cwd, id) {
+        Ok(path) => path,
+        Err(e) => return CommandResult::Error(e),
+    };
+    if path.exists() && !args.iter().any(|a| a == "--force") {
+        return CommandResult::Error(format!(
+            "Changeset '{id}' already exists; pass --force to replace it"
+        ));
+    }
+    let all = args.iter().any(|a| a == "--all");
+    let projects = match workspace_projects(provided_projects, cwd) {
+        Ok(projects) => projects,
+        Err(e) => return CommandResult::Error(e),
+    };
+
+    let mut changeset = Lockfile {
+        version: LOCKFILE_VERSION,
+        ..Default::default()
+    };
+    let mut dirty = Vec::new();
+    for project in &projects {
+        let dir = cwd.join(&project.path);
+        if !git::is_repo_root(&dir) || !(all || is_modified(&dir)) {
+            continue;
+        }
+        if git::is_dirty(&dir) {
+            dirty.push(project.path.clone());
+            continue;
+        }
+        let Some(sha) = git::output(&dir, &["rev-parse", "HEAD"]) else {
+            continue;
+        };
+        changeset.projects.insert(
+            project.path.clone(),
+            LockedProject {
+                url: project.url.clone(),
+                branch: git::current_branch(&dir),
+                sha,
+            },
+        );
+    }
+    if !dirty.is_empty() {
+        return CommandResult::Error(format!(
+            "Commit or stash changes first; {} project(s) have uncommitted changes:\n{}",
+            dirty.len(),
+            dirty.join("\n")
+        ));
+    }
+    if changeset.projects.is_empty() {
+        return CommandResult::Message(
+            "No project has commits beyond its default branch; nothing to record.".to_string(),
+        );
+    }
+
+    let mut lines: Vec<String> = changeset
+        .projects
+        .iter()
+        .map(|(project, locked)| {
+            format!(
+                "  {} {}",
+                style::project(options.plain, project),
+                describe(locked)
+            )
+        })
+        .collect();
+    if options.dry_run {
+        lines.insert(0, format!("Dry run: would record changeset '{id}':"));
+        return CommandResult::Message(lines.join("\n"));
+    }
+    if let Some(parent) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            return CommandResult::Error(format!("Failed to create {CHANGESET_DIR}: {e}"));
+        }
+    }
+    if let Err(e) = changeset.write(&path) {
+        return CommandResult::Error(format!("Failed to write {}: {e}", path.display()));
+    }
+    lines.insert(0, format!("Recorded changeset '{id}':"));
+    lines.push(format!(
+        "Commit {CHANGESET_DIR}/{id}.json to share it; reviewers run 'meta project changeset checkout {id}'."
+    ));
+    CommandResult::Message(lines.join("\n"))
+}
+
+fn checkout(id: &str, args: &[String], options: &ExecuteOptions, cwd: &Path) -> CommandResult {
+    let path = match changeset_path(cwd, id) {
+        Ok(path) => path,
+        Err(e) => return CommandResult::Error(e),
+    };
+    if !path.exists() {
+        return CommandResult::Error(format!("No changeset '{id}' in {CHANGESET_DIR}"));
+    }
+    let changeset = match Lockfile::read(&path) {
+        Ok(changeset) => changeset,
+        Err(e) => return CommandResult::Error(format!("Failed to read {}: {e}", path.display())),
+    };
+
+    let mut problems = Vec::new();
+    for project in changeset.projects.keys() {
+        let dir = cwd.join(project);
+        if !git::is_repo_root(&dir) {
+            problems.push(format!("{project}: not cloned"));
+        } else if git::is_dirty(&dir) {
+            problems.push(format!("{project}: uncommitted changes"));
+        }
+    }
+    if !problems.is_empty() {
+        return CommandResult::Error(format!(
+            "Cannot check out changeset '{id}':\n{}",
+            problems.join("\n")
+        ));
+    }
+    if options.dry_run {
+        let lines: Vec<String> = changeset
+            .projects
+            .iter()
+            .map(|(project, locked)| format!("  {project} {}", describe(locked)))
+            .collect();
+        return CommandResult::Message(format!(
+            "Dry run: would check out changeset '{id}':\n{}",
+            lines.join("\n")
+        ));
+    }
+    let _lock = match workspace_lock::acquire(cwd, "project changeset checkout", args) {
+        Ok(lock) => lock,
+        Err(e) => return CommandResult::Error(e),
+    };
+
+    let mut failures = Vec::new();
+    for (project, locked) in &changeset.projects {
+        match checkout_project(&cwd.join(project), locked) {
+            Ok(detail) => println!(
+                "{} {}: {detail}",
+                style::ok(options.plain),
+                style::project(options.plain, project)
+            ),
+            Err(e) => failures.push(format!("{project}: {e}")),
+        }
+    }
+    if failures.is_empty() {
+        CommandResult::Message(format!(
+            "Checked out changeset '{id}' ({} project(s)).",
+            changeset.projects.len()
+        ))
+    } else {
+        CommandResult::Error(format!(
+            "{} project(s) failed:\n{}",
+            failures.len(),
+            failures.join("\n")
+        ))
+    }
+}
+
+fn describe(locked: &LockedProject) -> String {
+    let sha = &locked.sha[..locked.sha.len().min(12)];
+    match &locked.branch {
+        Some(branch) => format!("{branch} @ {sha}"),
+        None => sha.to_string(),
+    }
+}
+
+/// Put the repo at `dir` on the recorded commit, fetching it if needed
+///
+/// The recorded branch is checked out when it's absent or already at the
+/// commit; a local branch that has moved is left alone and HEAD detached.
+/// A jj repo gets a new change on top of the commit instead.
+fn checkout_project(dir: &Path, locked: &LockedProject) -> anyhow::Result<String> {
+    let commit = format!("{}^{{commit}}", locked.sha);
+    if !git::ref_exists(dir, &commit) {
+        git::run_network(dir, &["fetch", "-q", "origin"])?;
+        if let Some(branch) = &locked.branch {
+            // The commit may only be reachable from the author's branch
+            let _ = git::run_network(dir, &["fetch", "-q", "origin", branch]);
+        }
+        if !git::ref_exists(dir, &commit) {
+            anyhow::bail!("commit {} not found, even after fetching", locked.sha);
+        }
+    }
+    if jj::is_colocated(dir) {
+        jj::new(dir, &locked.sha)?;
+        return Ok(format!("{} (new jj change)", describe(locked)));
+    }
+    let Some(branch) = &locked.branch else {
+        git::run(dir, &["checkout", "-q", "--detach", &locked.sha])?;
+        return Ok(describe(locked));
+    };
+    match git::output(
+        dir,
+        &[
+            "rev-parse",
+            "-q",
+            "--verify",
+            &format!("refs/heads/{branch}"),
+        ],
+    ) {
+        None => git::run(dir, &["checkout", "-q", "-b", branch, &locked.sha])?,
+        Some(sha) if sha == locked.sha => git::run(dir, &["checkout", "-q", branch])?,
+        Some(_) => {
+            git::run(dir, &["checkout", "-q", "--detach", &locked.sha])?;
+            return Ok(format!(
+                "{} (detached; local {branch} has moved)",
+                describe(locked)
+            ));
+        }
+    }
+    Ok(describe(locked))
+}
+
+fn list(cwd: &Path) -> CommandResult {
+    let Ok(entries) = std::fs::read_dir(cwd.join(CHANGESET_DIR)) else {
+        return CommandResult::Message("No changesets recorded.".to_string());
+    };
+    let mut lines: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let id = name.strip_suffix(".json")?.to_string();
+            let count = Lockfile::read(&entry.path()).map_or(0, |c| c.projects.len());
+            Some(format!("{id} ({count} project(s))"))
+        })
+        .collect();
+    if lines.is_empty() {
+        return CommandResult::Message("No changesets recorded.".to_string());
+    }
+    lines.sort();
+    CommandResult::Message(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{commit, run_git, Fixture};
+
+    #[test]
+    fn test_create_and_checkout_changeset() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        fixture.manifest(&[("api", &api), ("web", &web)]);
+        fixture.clone_project("api", &api);
+        fixture.clone_project("web", &web);
+        let api_dir = fixture.workspace().join("api");
+        run_git(&api_dir, &["checkout", "-q", "-b", "feature"]);
+        commit(&api_dir, "feature work");
+
+        match fixture.run("project changeset", &["create", "feat-1"]) {
+            CommandResult::Message(msg) => {
+                assert!(msg.contains("Recorded changeset 'feat-1'"), "{msg}")
+            }
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        let path = fixture.workspace().join(CHANGESET_DIR).join("feat-1.json");
+        let changeset = Lockfile::read(&path).unwrap();
+        assert_eq!(changeset.projects.len(), 1, "web is unmodified");
+        assert_eq!(changeset.projects["api"].branch.as_deref(), Some("feature"));
+        assert!(matches!(
+            fixture.run("project changeset", &["create", "feat-1"]),
+            CommandResult::Error(e) if e.contains("already exists")
+        ));
+
+        std::fs::remove_dir_all(&api_dir).unwrap();
+        fixture.clone_project("api", &api);
+        // The commit only ever existed locally; checkout reports it missing
+        assert!(matches!(
+            fixture.run("project changeset", &["checkout", "feat-1"]),
+            CommandResult::Error(e) if e.contains("not found")
+        ));
+    }
+
+    #[test]
+    fn test_checkout_restores_branch() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        fixture.manifest(&[("api", &api)]);
+        fixture.clone_project("api", &api);
+        let api_dir = fixture.workspace().join("api");
+        run_git(&api_dir, &["checkout", "-q", "-b", "feature"]);
+        commit(&api_dir, "feature work");
+        run_git(&api_dir, &["push", "-q", "origin", "feature"]);
+        fixture.run("project changeset", &["create", "feat-2"]);
+
+        std::fs::remove_dir_all(&api_dir).unwrap();
+        fixture.clone_project("api", &api);
+        match fixture.run("project changeset", &["checkout", "feat-2"]) {
+            CommandResult::Message(msg) => assert!(msg.contains("(1 project(s))"), "{msg}"),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert_eq!(git::current_branch(&api_dir).as_deref(), Some("feature"));
+        match fixture.run("project changeset", &["list"]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "feat-2 (1 project(s))"),
+            _ => panic!("Expected Message result"),
+        }
+    }
+}
@@ -0,0 +1,2462 @@
+//! `meta project check` — workspace consistency findings and remediation
+
+use crate::artifact::{self, ArchiveState};
+use crate::baseline::{self, BASELINE_FILE};
+use crate::deadline;
+use crate::fetch::{self, DEFAULT_FETCH_JOBS, DEFAULT_FETCH_TIMEOUT};
+use crate::jj;
+use crate::manifest;
+use crate::messages;
+use crate::paths::PathDisplay;
+use crate::pool::{default_jobs, parallel_map, parallel_map_grouped};
+use crate::progress;
+use crate::settings::{self, ArchiveSource};
+use crate::state;
+use crate::style;
+use crate::summary::{RowStatus, RunSummary, Span};
+use crate::workspace_lock;
+use crate::{
+    confirm, flag_value, git, parse_jobs, select_unprotected, workspace_projects, ExecuteOptions,
+    WorkspaceProject,
+};
+use meta_plugin_protocol::CommandResult;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Name of the per-workspace incremental check cache
+const CHECK_CACHE_FILE: &str = "check-cache.json";
+
+/// Workspace cache file with the size of each project's last clone
+const CLONE_SIZES_FILE: &str = "clone-sizes.json";
+
+/// How `check --fix` orders the clones of missing projects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CloneOrder {
+    /// Highest `"priority"` in .meta first
+    Priority,
+    /// Largest recorded clone first
+    Size,
+}
+
+impl CloneOrder {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "priority" => Ok(CloneOrder::Priority),
+            "size" => Ok(CloneOrder::Size),
+            _ => Err(format!(
+                "Invalid --clone-order value: {value} (expected priority or size)"
+            )),
+        }
+    }
+}
+
+/// Category of a check finding
+///
+/// Each built-in category has exactly one safe remediation applied by
+/// `--fix`; findings from registered [`CheckRule`]s are reported only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingCategory {
+    /// The project directory does not exist
+    Missing,
+    /// An archive project isn't unpacked from the tarball .meta names
+    Archive,
+    /// The clone's `origin` remote differs from the URL in .meta
+    RemoteMismatch,
+    /// The clone is not on the branch, tag or commit its `ref` pins
+    Pinned,
+    /// The checked-out branch does not track its `origin` counterpart
+    Upstream,
+    /// origin's default branch has no local branch, e.g. after `git branch -D`
+    DeletedBranch,
+    /// The clone was last fetched longer ago than `settings.max_stale_days`
+    Stale,
+    /// The checked-out branch is behind its upstream
+    Behind,
+    /// A registered [`CheckRule`] reported the project
+    Rule,
+}
+
+impl FindingCategory {
+    /// All fixable categories, in the order fixes are applied
+    pub const ALL: [FindingCategory; 8] = [
+        Self::Missing,
+        Self::Archive,
+        Self::RemoteMismatch,
+        Self::Pinned,
+        Self::Upstream,
+        Self::DeletedBranch,
+        Self::Stale,
+        Self::Behind,
+    ];
+
+    fn description(self) -> &'static str {
+        match self {
+            Self::Missing => "missing project(s)",
+            Self::Archive => "archive project(s) not unpacked at their checksum",
+            Self::RemoteMismatch => "project(s) with a mismatched origin remote",
+            Self::Pinned => "project(s) not on their pinned ref",
+            Self::Upstream => "project(s) with a branch not tracking origin",
+            Self::DeletedBranch => "project(s) missing their default branch locally",
+            Self::Stale => "project(s) not fetched recently",
+            Self::Behind => "project(s) behind their upstream",
+            Self::Rule => "project(s) failing a check rule",
+        }
+    }
+
+    fn remediation(self) -> &'static str {
+        match self {
+            Self::Missing => "clone from the .meta URL",
+            Self::Archive => "download, verify and unpack the tarball",
+            Self::RemoteMismatch => "set origin to the .meta URL",
+            Self::Pinned => "check out the pinned ref",
+            Self::Upstream => "set upstream to the origin branch of the same name",
+            Self::DeletedBranch => "re-create the branch from origin",
+            Self::Stale => "fetch from origin",
+            Self::Behind => "fast-forward to the upstream branch",
+            Self::Rule => "no automatic fix",
+        }
+    }
+}
+
+/// A single problem found in the workspace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    pub category: FindingCategory,
+    /// Project path relative to the invocation directory
+    pub project: String,
+    /// What .meta (or convention) expects: a remote URL or upstream ref, or
+    /// a rule's message
+    pub expected: String,
+    /// What the working copy has instead, when it has anything
+    pub actual: Option<String>,
+    /// ID of the [`CheckRule`] behind a `Rule` finding
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+}
+
+// ============================================================================
+// Check Rules
+// ============================================================================
+
+/// An organization-specific check run alongside the built-in ones
+///
+/// Register rules with [`register_check_rule`] before calling
+/// `execute_command`; their findings appear in the same report, summary
+/// and `--summary-file` output. Rules only see cloned projects and are never
+/// auto-fixed.
+pub trait CheckRule: Send + Sync {
+    /// Stable identifier shown in reports, e.g. `branch-protection-file`
+    fn id(&self) -> &str;
+
+    /// Problems found in `project`, each phrased as what the rule expects
+    fn check(&self, project: &RuleProject) -> Vec<String>;
+}
+
+/// The project a [`CheckRule`] is inspecting
+#[derive(Debug, Clone, Copy)]
+pub struct RuleProject<'a> {
+    /// Project path relative to the invocation directory
+    pub path: &'a str,
+    /// Remote URL from .meta
+    pub url: &'a str,
+    /// The clone's working directory
+    pub dir: &'a Path,
+}
+
+static RULES: RwLock<Vec<Arc<dyn CheckRule>>> = RwLock::new(Vec::new());
+
+/// Add `rule` to every subsequent `project check`
+///
+/// Registering a rule with the ID of an existing one replaces it.
+pub fn register_check_rule(rule: impl CheckRule + 'static) {
+    let mut rules = RULES.write().unwrap_or_else(|e| e.into_inner());
+    rules.retain(|r| r.id() != rule.id());
+    rules.push(Arc::new(rule));
+}
+
+/// Run the registered rules over every cloned target, in target order
+fn run_rules(targets: &[WorkspaceProject], cwd: &Path, jobs: usize) -> Vec<Finding> {
+    let rules: Vec<Arc<dyn CheckRule>> = RULES.read().unwrap_or_else(|e| e.into_inner()).clone();
+    if rules.is_empty() {
+        return Vec::new();
+    }
+    parallel_map(targets, jobs, |target| {
+        let dir = cwd.join(&target.path);
+        if !dir.is_dir() {
+            return Vec::new();
+        }
+        let project = RuleProject {
+            path: &target.path,
+            url: &target.url,
+            dir: &dir,
+        };
+        rules
+            .iter()
+            .flat_map(|rule| {
+                rule.check(&project).into_iter().map(|message| Finding {
+                    category: FindingCategory::Rule,
+                    project: target.path.clone(),
+                    expected: message,
+                    actual: None,
+                    rule: Some(rule.id().to_string()),
+                })
+            })
+            .collect::<Vec<_>>()
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Handle `meta project check`
+///
+/// When `provided_projects` is non-empty (--recursive), each of those project
+/// directories is also checked for missing repos declared in its own .meta file.
+pub(crate) fn handle_project_check(
+    args: &[String],
+    options: &ExecuteOptions,
+    provided_projects: &[String],
+    cwd: &Path,
+    summary: &mut RunSummary,
+) -> CommandResult {
+    let fix = args.iter().any(|a| a == "--fix");
+    let fast_forward = args.iter().any(|a| a == "--fast-forward");
+    let yes = args.iter().any(|a| a == "--yes" || a == "-y");
+    let stale_only = args.iter().any(|a| a == "--stale-only");
+    let fetch = stale_only || args.iter().any(|a| a == "--fetch");
+    let incremental = args.iter().any(|a| a == "--incremental");
+    let jobs = match parse_jobs(args) {
+        Ok(jobs) => jobs,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let fetch_timeout = match flag_value(args, "--fetch-timeout") {
+        Some(value) => match value.parse::<u64>() {
+            Ok(secs) => Duration::from_secs(secs),
+            Err(_) => {
+                return CommandResult::Error(format!("Invalid --fetch-timeout value: {value}"))
+            }
+        },
+        None => DEFAULT_FETCH_TIMEOUT,
+    };
+
+    // Not --depth: meta passes its recursion depth through as that
+    let depth = match flag_value(args, "--clone-depth") {
+        Some(value) => match value.parse::<u32>() {
+            Ok(depth) if depth > 0 => Some(depth),
+            _ => return CommandResult::Error(format!("Invalid --clone-depth value: {value}")),
+        },
+        None => None,
+    };
+
+    let filter = flag_value(args, "--filter");
+    if filter.is_some_and(str::is_empty) {
+        return CommandResult::Error("Invalid --filter value: expected a filter spec".to_string());
+    }
+
+    let single_branch = args.iter().any(|a| a == "--single-branch");
+    let reference = flag_value(args, "--reference");
+    let clone_order = match flag_value(args, "--clone-order").map(CloneOrder::parse) {
+        Some(Ok(order)) => Some(order),
+        Some(Err(e)) => return CommandResult::Error(e),
+        None => None,
+    };
+
+    let paths = match PathDisplay::parse(args, cwd) {
+        Ok(paths) => paths,
+        Err(e) => return CommandResult::Error(e),
+    };
+
+    let settings = match settings::load(cwd) {
+        Ok(settings) => settings,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let max_stale_days = settings.max_stale_days;
+    let groups = match settings::concurrency_groups(cwd) {
+        Ok(groups) => groups,
+        Err(e) => return CommandResult::Error(e),
+    };
+    if stale_only && max_stale_days.is_none() {
+        return CommandResult::Error(
+            "--stale-only needs settings.max_stale_days in .meta".to_string(),
+        );
+    }
+
+    let mut targets = match workspace_projects(provided_projects, cwd) {
+        Ok(targets) => targets,
+        Err(e) => return CommandResult::Error(e),
+    };
+    // Flags beat a project's own depth and filter, which beat the settings
+    for target in &mut targets {
+        target.depth = depth.or(target.depth).or(settings.depth);
+        target.filter = filter
+            .map(str::to_string)
+            .or(target.filter.take())
+            .or_else(|| settings.filter.clone());
+        target.single_branch = single_branch
+            .then_some(true)
+            .or(target.single_branch)
+            .or(settings.single_branch);
+        target.reference = reference
+            .or(settings.reference.as_deref())
+            .map(|dir| reference_clone(&cwd.join(dir), &target.path));
+    }
+
+    if fetch {
+        let now = SystemTime::now();
+        let cloned: Vec<String> = targets
+            .iter()
+            .filter(|t| git::is_repo_root(&cwd.join(&t.path)))
+            .filter(|t| {
+                !stale_only || stale_days(&cwd.join(&t.path), max_stale_days, now).is_some()
+            })
+            .map(|t| t.path.clone())
+            .collect();
+        let outcomes = fetch::fetch_projects(
+            &cloned,
+            cwd,
+            jobs.unwrap_or(DEFAULT_FETCH_JOBS),
+            &groups,
+            fetch_timeout,
+            options.plain,
+        );
+        fetch::record_outcomes(summary, &outcomes);
+        let failed = outcomes.iter().filter(|o| o.result.is_err()).count();
+        if failed > 0 {
+            println!(
+                "{} {failed} of {} fetch(es) failed; results for those projects may be stale.",
+                style::warn(options.plain, "WARNING"),
+                outcomes.len()
+            );
+        }
+        println!();
+    }
+    let cache_file = if incremental {
+        state::workspace_cache_file(cwd, CHECK_CACHE_FILE)
+    } else {
+        None
+    };
+    let clone_jobs = jobs.unwrap_or(DEFAULT_FETCH_JOBS);
+    let jobs = jobs.unwrap_or_else(default_jobs);
+    let mut findings = match cache_file {
+        Some(cache_file) => inspect_incremental(&targets, cwd, &cache_file, jobs),
+        None => inspect_all(&targets, cwd, jobs),
+    };
+    // Staleness depends on the clock and rules may look at anything in the
+    // clone, so neither comes from the cache
+    if let Some(max_days) = max_stale_days {
+        findings.extend(stale_findings(&targets, cwd, max_days));
+    }
+    findings.extend(run_rules(&targets, cwd, jobs));
+    match artifact::sources(cwd) {
+        Ok(sources) => findings.extend(archive_findings(&sources, cwd)),
+        Err(e) => return CommandResult::Error(e),
+    }
+
+    if args.iter().any(|a| a == "--write-baseline") {
+        if options.dry_run {
+            return CommandResult::Message(format!(
+                "Dry run: would record {} finding(s) in {BASELINE_FILE}.",
+                findings.len()
+            ));
+        }
+        return match baseline::write(&findings, cwd) {
+            Ok(()) => CommandResult::Message(format!(
+                "Recorded {} finding(s) in {BASELINE_FILE}; later checks report only new ones.",
+                findings.len()
+            )),
+            Err(e) => CommandResult::Error(e),
+        };
+    }
+    let meta_path = manifest::find(cwd);
+    let use_baseline = !args.iter().any(|a| a == "--no-baseline");
+    let (findings, hidden) =
+        match baseline::filter(findings, meta_path.as_deref(), cwd, use_baseline) {
+            Ok(filtered) => filtered,
+            Err(e) => return CommandResult::Error(e),
+        };
+    for expired in &hidden.expired {
+        println!(
+            "{} suppression {expired}; its findings are reported again",
+            style::warn(options.plain, "EXPIRED")
+        );
+    }
+    let mut hidden_note = match (hidden.baselined, hidden.suppressed) {
+        (0, 0) => String::new(),
+        (baselined, suppressed) => format!(
+            "\n({baselined} baselined and {suppressed} suppressed finding(s) not shown; --no-baseline shows baselined ones)"
+        ),
+    };
+    let limited_clones = [
+        (
+            "Shallow clones, history truncated",
+            git::is_shallow as fn(&Path) -> bool,
+        ),
+        (
+            "Partial clones, objects downloaded on demand",
+            git::is_partial,
+        ),
+        (
+            "Sparse checkouts, only some directories present",
+            git::is_sparse,
+        ),
+        (
+            "Clones borrowing objects from a reference",
+            git::has_alternates,
+        ),
+    ];
+    for (label, is_limited) in limited_clones {
+        let listed: Vec<String> = targets
+            .iter()
+            .filter(|t| is_limited(&cwd.join(&t.path)))
+            .map(|t| paths.show(cwd, &t.path))
+            .collect();
+        if !listed.is_empty() {
+            hidden_note.push_str(&format!("\n{label}: {}", listed.join(", ")));
+        }
+    }
+
+    if findings.is_empty() {
+        return CommandResult::Message(messages::text("check.all_present", &[]) + &hidden_note);
+    }
+
+    print_findings(&findings, cwd, &paths, options.plain);
+    for finding in &findings {
+        summary.record(
+            &finding.project,
+            "check",
+            RowStatus::Finding,
+            describe_finding(finding),
+            None,
+        );
+    }
+
+    if fix {
+        let _lock = if options.dry_run {
+            None
+        } else {
+            match workspace_lock::acquire(cwd, "project check --fix", args) {
+                Ok(lock) => Some(lock),
+                Err(e) => return CommandResult::Error(e),
+            }
+        };
+        // Protected projects are still inspected, but only fixed when included;
+        // moving a working tree forward is only done when asked for
+        let fixable = select_unprotected(targets, args);
+        let paths: HashSet<&str> = fixable.iter().map(|t| t.path.as_str()).collect();
+        let mut findings: Vec<Finding> = findings
+            .into_iter()
+            .filter(|f| match f.category {
+                FindingCategory::Archive => true,
+                FindingCategory::Rule => false,
+                FindingCategory::Behind if !fast_forward => false,
+                _ => paths.contains(f.project.as_str()),
+            })
+            .collect();
+        if let Some(order) = clone_order {
+            let sizes = state::workspace_cache_file(cwd, CLONE_SIZES_FILE)
+                .and_then(|file| state::read_json(&file))
+                .unwrap_or_default();
+            order_clones(&mut findings, &fixable, order, &sizes);
+        }
+        apply_fixes(&findings, &fixable, options, yes, clone_jobs, cwd, summary)
+    } else if options.strict {
+        CommandResult::Error(summarize(&findings) + &hidden_note)
+    } else {
+        CommandResult::Message(summarize(&findings) + &hidden_note)
+    }
+}
+
+/// Inspect projects on up to `jobs` threads, keeping findings in target order
+fn inspect_all(targets: &[WorkspaceProject], cwd: &Path, jobs: usize) -> Vec<Finding> {
+    parallel_map(targets, jobs, |target| inspect(target, cwd))
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Inspect a single project and return its findings
+fn inspect(target: &WorkspaceProject, cwd: &Path) -> Vec<Finding> {
+    let dir = cwd.join(&target.path);
+    let mut findings = Vec::new();
+
+    if !dir.is_dir() {
+        findings.push(Finding {
+            category: FindingCategory::Missing,
+            project: target.path.clone(),
+            expected: target.url.clone(),
+            actual: None,
+            rule: None,
+        });
+        return findings;
+    }
+
+    if !git::is_repo_root(&dir) {
+        return findings;
+    }
+
+    if let Some(actual) = git::remote_url(&dir, "origin") {
+        let known = std::iter::once(&target.url).chain(&target.fallbacks);
+        if !known.clone().any(|url| git::urls_match(&actual, url)) {
+            findings.push(Finding {
+                category: FindingCategory::RemoteMismatch,
+                project: target.path.clone(),
+                expected: target.url.clone(),
+                actual: Some(actual),
+                rule: None,
+            });
+        }
+    }
+
+    if let Some(reference) = &target.pinned_ref {
+        if let Some(actual) = off_pinned_ref(&dir, reference) {
+            findings.push(Finding {
+                category: FindingCategory::Pinned,
+                project: target.path.clone(),
+                expected: reference.clone(),
+                actual: Some(actual),
+                rule: None,
+            });
+        }
+    }
+
+    // Only flag tracking when origin actually has a branch of the same name;
+    // local-only topic branches legitimately have no upstream.
+    if let Some(branch) = git::current_branch(&dir) {
+        let expected = format!("origin/{branch}");
+        if git::ref_exists(&dir, &format!("refs/remotes/{expected}")) {
+            let actual = git::upstream(&dir);
+            if actual.as_deref() != Some(expected.as_str()) {
+                findings.push(Finding {
+                    category: FindingCategory::Upstream,
+                    project: target.path.clone(),
+                    expected,
+                    actual,
+                    rule: None,
+                });
+            }
+        }
+    }
+
+    if let Some(expected) = deleted_default_branch(&dir) {
+        findings.push(Finding {
+            category: FindingCategory::DeletedBranch,
+            project: target.path.clone(),
+            expected,
+            actual: None,
+            rule: None,
+        });
+    }
+
+    if let Some(upstream) = git::upstream(&dir) {
+        let behind = git::output(&dir, &["rev-list", "--count", "HEAD..@{u}"])
+            .and_then(|count| count.parse::<usize>().ok())
+            .unwrap_or(0);
+        if behind > 0 {
+            findings.push(Finding {
+                category: FindingCategory::Behind,
+                project: target.path.clone(),
+                expected: upstream,
+                actual: Some(format!("{behind} commit(s) behind")),
+                rule: None,
+            });
+        }
+    }
+
+    findings
+}
+
+/// origin's default branch (e.g. `origin/main`) when the clone at `dir` has
+/// no local branch of that name, though origin still has it
+///
+/// Other remote branches are never checked out by default, so only the
+/// default branch is expected locally; jj keeps no local branches.
+fn deleted_default_branch(dir: &Path) -> Option<String> {
+    if jj::is_colocated(dir) {
+        return None;
+    }
+    let default = git::output(
+        dir,
+        &["symbolic-ref", "-q", "--short", "refs/remotes/origin/HEAD"],
+    )?;
+    let branch = default.strip_prefix("origin/")?;
+    let deleted = !git::ref_exists(dir, &format!("refs/heads/{branch}"))
+        && git::ref_exists(dir, &format!("refs/remotes/{default}"));
+    deleted.then_some(default)
+}
+
+/// What the clone at `dir` has instead of `reference`, or `None` when it's
+/// on it
+///
+/// A ref naming a local or origin branch pins the branch; anything else
+/// (a tag or commit) pins the commit it resolves to.
+fn off_pinned_ref(dir: &Path, reference: &str) -> Option<String> {
+    if jj::is_colocated(dir) {
+        // jj never has a branch checked out, only the commit
+        if jj::is_at(dir, reference) {
+            return None;
+        }
+        let head = git::output(dir, &["rev-parse", "HEAD"]).unwrap_or_default();
+        return Some(format!(
+            "jj working copy on {}",
+            &head[..head.len().min(12)]
+        ));
+    }
+    let branch = git::current_branch(dir);
+    if is_branch(dir, reference) {
+        return match branch {
+            Some(branch) if branch == reference => None,
+            Some(branch) => Some(format!("on branch {branch}")),
+            None => Some("a detached HEAD".to_string()),
+        };
+    }
+    let head = git::output(dir, &["rev-parse", "HEAD"])?;
+    let Some(pinned) = git::output(
+        dir,
+        &[
+            "rev-parse",
+            "-q",
+            "--verify",
+            &format!("{reference}^{{commit}}"),
+        ],
+    ) else {
+        return Some("no such ref locally (fetch first)".to_string());
+    };
+    if head == pinned {
+        return None;
+    }
+    Some(match branch {
+        Some(branch) => format!("on branch {branch}"),
+        None => format!("at {}", &head[..head.len().min(12)]),
+    })
+}
+
+fn is_branch(dir: &Path, reference: &str) -> bool {
+    git::ref_exists(dir, &format!("refs/heads/{reference}"))
+        || git::ref_exists(dir, &format!("refs/remotes/origin/{reference}"))
+}
+
+/// Fetch a pinned tag or commit the shallow or single-branch clone at `dir`
+/// doesn't have
+///
+/// Such a clone only has the tips of some branches, so an older tag or
+/// commit has to be fetched, `depth` commits deep if given, before it can be
+/// checked out.
+fn fetch_missing_ref(dir: &Path, reference: &str, depth: Option<u32>) -> anyhow::Result<()> {
+    let commit = format!("{reference}^{{commit}}");
+    if is_branch(dir, reference)
+        || git::output(dir, &["rev-parse", "-q", "--verify", &commit]).is_some()
+    {
+        return Ok(());
+    }
+    let depth = depth.map(|depth| format!("--depth={depth}"));
+    let fetch = |what: &[&str]| {
+        let mut args = vec!["fetch", "-q"];
+        args.extend(depth.as_deref());
+        args.push("origin");
+        args.extend(what);
+        git::run_network(dir, &args)
+    };
+    fetch(&["tag", reference]).or_else(|_| fetch(&[reference]))
+}
+
+/// The repository under the reference directory `dir` that the clone of
+/// `project` borrows objects from: the clone at `dir/<project>` in a
+/// mirrored workspace, else `dir` itself as a shared object cache
+fn reference_clone(dir: &Path, project: &str) -> PathBuf {
+    let clone = dir.join(project);
+    if clone.exists() {
+        clone
+    } else {
+        dir.to_path_buf()
+    }
+}
+
+/// Check out only `dirs` (and the top-level files) in the sparse clone at
+/// `dir`
+fn set_sparse_paths(dir: &Path, dirs: &[String]) -> anyhow::Result<()> {
+    let mut args = vec!["sparse-checkout", "set", "--"];
+    args.extend(dirs.iter().map(String::as_str));
+    git::run(dir, &args)
+}
+
+/// Whether `reference` looks like an abbreviated or full commit ID rather
+/// than a branch or tag name
+fn is_commit_id(reference: &str) -> bool {
+    (7..=40).contains(&reference.len()) && reference.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Check out the pinned `reference` in the clone at `dir`: a branch (created
+/// to track origin if needed), or else a detached tag or commit; in a jj
+/// repo, a new change on top of it
+fn checkout_pinned_ref(dir: &Path, reference: &str) -> anyhow::Result<()> {
+    if jj::is_colocated(dir) {
+        jj::new(dir, reference)
+    } else if git::ref_exists(dir, &format!("refs/heads/{reference}")) {
+        git::run(dir, &["checkout", "-q", reference])
+    } else if git::ref_exists(dir, &format!("refs/remotes/origin/{reference}")) {
+        let tracking = format!("origin/{reference}");
+        git::run(
+            dir,
+            &["checkout", "-q", "-b", reference, "--track", &tracking],
+        )
+    } else {
+        git::run(dir, &["checkout", "-q", "--detach", reference])
+    }
+}
+
+/// Findings for archive projects that aren't unpacked at their checksum
+fn archive_findings(sources: &[ArchiveSource], cwd: &Path) -> Vec<Finding> {
+    sources
+        .iter()
+        .filter_map(|source| {
+            let actual = match artifact::state(source, cwd) {
+                ArchiveState::Current => return None,
+                ArchiveState::Missing => None,
+                ArchiveState::Outdated(sha256) => Some(format!("unpacked from {sha256}")),
+                ArchiveState::Unmanaged => Some("not unpacked by meta".to_string()),
+            };
+            Some(Finding {
+                category: FindingCategory::Archive,
+                project: source.path.clone(),
+                expected: source.sha256.clone(),
+                actual,
+                rule: None,
+            })
+        })
+        .collect()
+}
+
+/// Findings for cloned targets last fetched more than `max_days` days ago
+fn stale_findings(targets: &[WorkspaceProject], cwd: &Path, max_days: u64) -> Vec<Finding> {
+    let now = SystemTime::now();
+    targets
+        .iter()
+        .filter(|t| git::is_repo_root(&cwd.join(&t.path)))
+        .filter_map(|target| {
+            let age = stale_days(&cwd.join(&target.path), Some(max_days), now)?;
+            Some(Finding {
+                category: FindingCategory::Stale,
+                project: target.path.clone(),
+                expected: format!("a fetch within {max_days} day(s)"),
+                actual: Some(format!("last fetched {age} day(s) ago")),
+                rule: None,
+            })
+        })
+        .collect()
+}
+
+/// Whole days since the clone at `dir` was last fetched, if more than
+/// `max_days`
+///
+/// FETCH_HEAD is rewritten by every fetch; a never-fetched clone counts from
+/// when it was cloned.
+fn stale_days(dir: &Path, max_days: Option<u64>, now: SystemTime) -> Option<u64> {
+    let max_days = max_days?;
+    let git_dir = dir.join(".git");
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let fetched = modified(&git_dir.join("FETCH_HEAD")).or_else(|| modified(&git_dir))?;
+    let days = now.duration_since(fetched).ok()?.as_secs() / 86_400;
+    (days > max_days).then_some(days)
+}
+
+// ============================================================================
+// Incremental Check
+// ============================================================================
+
+/// On-disk cache of per-project findings for `check --incremental`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CheckCache {
+    projects: HashMap<String, CachedProject>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedProject {
+    fingerprint: String,
+    findings: Vec<Finding>,
+}
+
+/// Inspect only projects whose fingerprint changed since the cached run
+fn inspect_incremental(
+    targets: &[WorkspaceProject],
+    cwd: &Path,
+    cache_file: &Path,
+    jobs: usize,
+) -> Vec<Finding> {
+    let mut cached: CheckCache = state::read_json(cache_file).unwrap_or_default();
+    let fingerprints: Vec<Option<String>> = targets.iter().map(|t| fingerprint(t, cwd)).collect();
+
+    let reused: Vec<Option<Vec<Finding>>> = targets
+        .iter()
+        .zip(&fingerprints)
+        .map(|(target, fingerprint)| {
+            let entry = cached.projects.remove(&target.path)?;
+            (fingerprint.as_deref() == Some(entry.fingerprint.as_str())).then_some(entry.findings)
+        })
+        .collect();
+    let stale: Vec<&WorkspaceProject> = targets
+        .iter()
+        .zip(&reused)
+        .filter(|(_, reused)| reused.is_none())
+        .map(|(target, _)| target)
+        .collect();
+    let mut inspected = parallel_map(&stale, jobs, |target| inspect(target, cwd)).into_iter();
+
+    let mut fresh = CheckCache::default();
+    let mut findings = Vec::new();
+    for ((target, fingerprint), reused) in targets.iter().zip(fingerprints).zip(reused) {
+        let project_findings = match reused {
+            Some(findings) => findings,
+            None => inspected.next().unwrap_or_default(),
+        };
+        if let Some(fingerprint) = fingerprint {
+            fresh.projects.insert(
+                target.path.clone(),
+                CachedProject {
+                    fingerprint,
+                    findings: project_findings.clone(),
+                },
+            );
+        }
+        findings.extend(project_findings);
+    }
+
+    // A cache that cannot be written only costs speed on the next run
+    let _ = state::write_json(cache_file, &fresh);
+    findings
+}
+
+/// Cheap summary of everything `inspect` depends on, read without running git
+///
+/// Covers the manifest URL and fallbacks, the pinned ref, the project
+/// directory, HEAD, the repo config (remotes and upstreams), the reflog
+/// (commits, checkouts, resets) and fetched refs. Returns `None` for
+/// projects that are not plain clones, which are always inspected.
+fn fingerprint(target: &WorkspaceProject, cwd: &Path) -> Option<String> {
+    let dir = cwd.join(&target.path);
+    let git_dir = dir.join(".git");
+    if !git_dir.is_dir() {
+        return None;
+    }
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let mtimes: Vec<String> = [
+        dir.clone(),
+        git_dir.join("config"),
+        git_dir.join("logs").join("HEAD"),
+        git_dir.join("FETCH_HEAD"),
+        git_dir.join("packed-refs"),
+    ]
+    .iter()
+    .map(|path| {
+        std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or_else(|| "-".to_string(), |d| d.as_nanos().to_string())
+    })
+    .collect();
+    Some(format!(
+        "{}|{}|{}|{}|{}",
+        target.url,
+        target.fallbacks.join(" "),
+        target.pinned_ref.as_deref().unwrap_or_default(),
+        head.trim(),
+        mtimes.join("|")
+    ))
+}
+
+fn print_findings(findings: &[Finding], cwd: &Path, paths: &PathDisplay, plain: bool) {
+    for finding in findings {
+        let project = paths.show(cwd, &finding.project);
+        match finding.category {
+            FindingCategory::Missing if plain => println!(
+                "MISSING {project}: not cloned (clone from {})",
+                finding.expected
+            ),
+            FindingCategory::Missing => meta_git_lib::print_missing_repo(
+                &project,
+                &finding.expected,
+                &cwd.join(&finding.project),
+            ),
+            FindingCategory::Archive => println!(
+                "{} {}: {} (expected sha256 {})",
+                style::warn(plain, "ARCHIVE"),
+                style::project(plain, &project),
+                finding.actual.as_deref().unwrap_or("not unpacked"),
+                finding.expected
+            ),
+            FindingCategory::RemoteMismatch => println!(
+                "{} {}: origin is {} (expected {})",
+                style::warn(plain, "REMOTE MISMATCH"),
+                style::project(plain, &project),
+                finding.actual.as_deref().unwrap_or_default(),
+                finding.expected
+            ),
+            FindingCategory::Pinned => println!(
+                "{} {}: {} (pinned to {})",
+                style::warn(plain, "PINNED"),
+                style::project(plain, &project),
+                finding.actual.as_deref().unwrap_or_default(),
+                finding.expected
+            ),
+            FindingCategory::Upstream => println!(
+                "{} {}: branch tracks {} (expected {})",
+                style::warn(plain, "UPSTREAM"),
+                style::project(plain, &project),
+                finding.actual.as_deref().unwrap_or("nothing"),
+                finding.expected
+            ),
+            FindingCategory::DeletedBranch => println!(
+                "{} {}: no local branch for {}",
+                style::warn(plain, "DELETED BRANCH"),
+                style::project(plain, &project),
+                finding.expected
+            ),
+            FindingCategory::Stale => println!(
+                "{} {}: {} (expected {})",
+                style::warn(plain, "STALE"),
+                style::project(plain, &project),
+                finding.actual.as_deref().unwrap_or_default(),
+                finding.expected
+            ),
+            FindingCategory::Behind => println!(
+                "{} {}: {} {}",
+                style::warn(plain, "BEHIND"),
+                style::project(plain, &project),
+                finding.actual.as_deref().unwrap_or_default(),
+                finding.expected
+            ),
+            FindingCategory::Rule => println!(
+                "{} {}: [{}] {}",
+                style::warn(plain, "RULE"),
+                style::project(plain, &project),
+                finding.rule.as_deref().unwrap_or_default(),
+                finding.expected
+            ),
+        }
+    }
+    println!();
+}
+
+/// One-line description of a finding for run reports
+fn describe_finding(finding: &Finding) -> String {
+    let what = match (&finding.rule, finding.category) {
+        (Some(rule), _) => format!("rule {rule}"),
+        (None, FindingCategory::Missing) => "missing".to_string(),
+        (None, FindingCategory::Archive) => "archive".to_string(),
+        (None, FindingCategory::RemoteMismatch) => "remote mismatch".to_string(),
+        (None, FindingCategory::Pinned) => "pinned".to_string(),
+        (None, FindingCategory::Upstream) => "upstream".to_string(),
+        (None, FindingCategory::DeletedBranch) => "deleted branch".to_string(),
+        (None, FindingCategory::Stale) => "stale".to_string(),
+        (None, FindingCategory::Behind) => "behind".to_string(),
+        (None, FindingCategory::Rule) => "rule".to_string(),
+    };
+    match &finding.actual {
+        Some(actual) => format!("{what}: {actual} (expected {})", finding.expected),
+        None => format!("{what}: {}", finding.expected),
+    }
+}
+
+fn summarize(findings: &[Finding]) -> String {
+    let count =
+        |category: FindingCategory| findings.iter().filter(|f| f.category == category).count();
+    let missing = count(FindingCategory::Missing);
+    let archives = count(FindingCategory::Archive);
+    let mismatched = count(FindingCategory::RemoteMismatch);
+    let pinned = count(FindingCategory::Pinned);
+    let untracked = count(FindingCategory::Upstream);
+    let deleted = count(FindingCategory::DeletedBranch);
+    let stale = count(FindingCategory::Stale);
+    let behind = count(FindingCategory::Behind);
+    let rules = count(FindingCategory::Rule);
+
+    let mut lines = Vec::new();
+    for (id, count) in [
+        ("check.missing", missing),
+        ("check.archive", archives),
+        ("check.remote_mismatch", mismatched),
+        ("check.pinned", pinned),
+        ("check.upstream", untracked),
+        ("check.deleted_branch", deleted),
+        ("check.stale", stale),
+        ("check.behind", behind),
+        ("check.rules", rules),
+    ] {
+        if count > 0 {
+            lines.push(messages::text(id, &[("count", &count)]));
+        }
+    }
+    if rules + behind < findings.len() {
+        lines.push(messages::text("check.fix_hint", &[]));
+    }
+    lines.join("\n")
+}
+
+// ============================================================================
+// Remediation
+// ============================================================================
+
+/// Apply the remediation for each finding category, asking once per category
+///
+/// Missing projects are cloned `clone_jobs` at a time, within the limits of
+/// their concurrency groups; the other fixes are local and run one by one.
+fn apply_fixes(
+    findings: &[Finding],
+    targets: &[WorkspaceProject],
+    options: &ExecuteOptions,
+    yes: bool,
+    clone_jobs: usize,
+    cwd: &Path,
+    summary: &mut RunSummary,
+) -> CommandResult {
+    let mut fixed = 0;
+    let mut skipped = 0;
+    let mut failures = Vec::new();
+    let targets: HashMap<&str, &WorkspaceProject> =
+        targets.iter().map(|t| (t.path.as_str(), t)).collect();
+    // Already validated when the check started
+    let groups = settings::concurrency_groups(cwd).unwrap_or_default();
+
+    for category in FindingCategory::ALL {
+        let group: Vec<&Finding> = findings.iter().filter(|f| f.category == category).collect();
+        if group.is_empty() {
+            continue;
+        }
+
+        if options.dry_run {
+            for finding in &group {
+                let target = targets.get(finding.project.as_str()).copied();
+                println!(
+                    "[dry-run] {}{}",
+                    describe_fix(finding),
+                    clone_note(finding, target)
+                );
+            }
+            continue;
+        }
+
+        let prompt = format!(
+            "Fix {} {} ({})?",
+            group.len(),
+            category.description(),
+            category.remediation()
+        );
+        if !yes && !confirm(&prompt) {
+            skipped += group.len();
+            for finding in group {
+                summary.record(
+                    &finding.project,
+                    category.remediation(),
+                    RowStatus::Skipped,
+                    "declined",
+                    None,
+                );
+            }
+            continue;
+        }
+
+        // Clones run concurrently; their results are reported in order once
+        // all of them finished
+        let jobs = match category {
+            FindingCategory::Missing | FindingCategory::Archive => clone_jobs,
+            _ => 1,
+        };
+        let completed = AtomicUsize::new(0);
+        if category == FindingCategory::Missing {
+            progress::begin("clone", group.len());
+        }
+        let outcomes = parallel_map_grouped(
+            &group,
+            jobs,
+            &groups,
+            |finding| finding.project.as_str(),
+            |finding| {
+                let started = Instant::now();
+                let target = targets.get(finding.project.as_str()).copied();
+                let result = deadline::check()
+                    .and_then(|()| apply_fix(finding, target, cwd).map_err(|e| e.to_string()));
+                if category == FindingCategory::Missing {
+                    let done = completed.fetch_add(1, Ordering::SeqCst) + 1;
+                    let status = progress::status(&result);
+                    progress::project("clone", &finding.project, status, done, group.len(), None);
+                }
+                (result, Span::since(started))
+            },
+        );
+        if category == FindingCategory::Missing {
+            let cloned: Vec<&str> = group
+                .iter()
+                .zip(&outcomes)
+                .filter(|(_, (result, _))| result.is_ok())
+                .map(|(finding, _)| finding.project.as_str())
+                .collect();
+            record_clone_sizes(cwd, &cloned);
+        }
+        for (finding, (result, span)) in group.iter().zip(outcomes) {
+            let (status, detail) = match &result {
+                Ok(done) => (RowStatus::Ok, done.clone()),
+                Err(e) if e == deadline::CANCELLED => (RowStatus::Skipped, e.clone()),
+                Err(e) => (RowStatus::Failed, e.clone()),
+            };
+            summary.record(
+                &finding.project,
+                category.remediation(),
+                status,
+                detail,
+                Some(span),
+            );
+            match result {
+                Ok(done) => {
+                    println!("{} {done}", style::ok(options.plain));
+                    fixed += 1;
+                }
+                Err(e) => failures.push(format!("{}: {e}", finding.project)),
+            }
+        }
+    }
+
+    if options.dry_run {
+        return CommandResult::Message(messages::text(
+            "check.fix_dry_run",
+            &[("count", &findings.len())],
+        ));
+    }
+
+    if !failures.is_empty() {
+        return CommandResult::Error(format!(
+            "{} fix(es) failed:\n{}",
+            failures.len(),
+            failures.join("\n")
+        ));
+    }
+
+    let mut message = messages::text("check.fix_applied", &[("count", &fixed)]);
+    if skipped > 0 {
+        message.push(' ');
+        message.push_str(&messages::text("check.fix_skipped", &[("count", &skipped)]));
+    }
+    CommandResult::Message(message)
+}
+
+/// Put the missing projects in the order their clones should start
+///
+/// The sort is stable, so ties keep their order; other findings are left
+/// where they are relative to each other.
+fn order_clones(
+    findings: &mut [Finding],
+    targets: &[WorkspaceProject],
+    order: CloneOrder,
+    sizes: &HashMap<String, u64>,
+) {
+    let priorities: HashMap<&str, i64> = targets
+        .iter()
+        .map(|t| (t.path.as_str(), t.priority))
+        .collect();
+    findings.sort_by_cached_key(|finding| {
+        if finding.category != FindingCategory::Missing {
+            return (false, std::cmp::Reverse(0));
+        }
+        let project = finding.project.as_str();
+        match order {
+            CloneOrder::Priority => (
+                false,
+                std::cmp::Reverse(i128::from(priorities.get(project).copied().unwrap_or(0))),
+            ),
+            // Largest first: the longest clones start early instead of
+            // running alone at the end
+            CloneOrder::Size => match sizes.get(project) {
+                Some(&size) => (false, std::cmp::Reverse(i128::from(size))),
+                None => (true, std::cmp::Reverse(0)),
+            },
+        }
+    });
+}
+
+/// Remember how big fresh clones are, for `--clone-order size` next time
+fn record_clone_sizes(cwd: &Path, projects: &[&str]) {
+    let Some(file) = state::workspace_cache_file(cwd, CLONE_SIZES_FILE) else {
+        return;
+    };
+    if projects.is_empty() {
+        return;
+    }
+    let mut sizes: HashMap<String, u64> = state::read_json(&file).unwrap_or_default();
+    for project in projects {
+        let size = crate::dir_size(&cwd.join(project).join(".git"));
+        sizes.insert(project.to_string(), size);
+    }
+    // Only an ordering hint: losing it is harmless
+    let _ = state::write_json(&file, &sizes);
+}
+
+/// `main` for the remote branch `origin/main`
+fn local_branch(remote_branch: &str) -> &str {
+    remote_branch
+        .strip_prefix("origin/")
+        .unwrap_or(remote_branch)
+}
+
+fn describe_fix(finding: &Finding) -> String {
+    match finding.category {
+        FindingCategory::Missing => {
+            format!("git clone {} {}", finding.expected, finding.project)
+        }
+        FindingCategory::Archive => format!(
+            "download and unpack {} (sha256 {})",
+            finding.project, finding.expected
+        ),
+        FindingCategory::RemoteMismatch => format!(
+            "git -C {} remote set-url origin {}",
+            finding.project, finding.expected
+        ),
+        FindingCategory::Pinned => {
+            format!("git -C {} checkout {}", finding.project, finding.expected)
+        }
+        FindingCategory::Upstream => format!(
+            "git -C {} branch --set-upstream-to={}",
+            finding.project, finding.expected
+        ),
+        FindingCategory::DeletedBranch => format!(
+            "git -C {} branch --track {} {}",
+            finding.project,
+            local_branch(&finding.expected),
+            finding.expected
+        ),
+        FindingCategory::Stale => format!("git -C {} fetch --prune", finding.project),
+        FindingCategory::Behind => format!(
+            "git -C {} merge --ff-only {}",
+            finding.project, finding.expected
+        ),
+        FindingCategory::Rule => format!(
+            "{}: {} (no automatic fix)",
+            finding.project, finding.expected
+        ),
+    }
+}
+
+/// Apply the fix for `finding` and describe what was done
+fn apply_fix(
+    finding: &Finding,
+    target: Option<&WorkspaceProject>,
+    cwd: &Path,
+) -> anyhow::Result<String> {
+    match finding.category {
+        FindingCategory::Missing => {
+            let mut done = clone_with_fallbacks(finding, target, cwd)?;
+            done.push_str(&clone_note(finding, target));
+            if let Some(target) = target.filter(|t| !t.jj && !t.sparse.is_empty()) {
+                set_sparse_paths(&cwd.join(&finding.project), &target.sparse)?;
+            }
+            if let Some(reference) = target.and_then(|t| t.pinned_ref.as_deref()) {
+                let dir = cwd.join(&finding.project);
+                let partial_history = |t: &&WorkspaceProject| {
+                    !t.jj && (t.depth.is_some() || t.single_branch == Some(true))
+                };
+                if let Some(target) = target.filter(partial_history) {
+                    fetch_missing_ref(&dir, reference, target.depth)?;
+                }
+                checkout_pinned_ref(&dir, reference)?;
+                done.push_str(&format!(" (checked out {reference})"));
+            }
+            Ok(done)
+        }
+        FindingCategory::Archive => artifact::install(&finding.project, cwd),
+        FindingCategory::RemoteMismatch => git::run(
+            &cwd.join(&finding.project),
+            &["remote", "set-url", "--", "origin", &finding.expected],
+        )
+        .map(|()| describe_fix(finding)),
+        FindingCategory::Pinned => {
+            checkout_pinned_ref(&cwd.join(&finding.project), &finding.expected)
+                .map(|()| describe_fix(finding))
+        }
+        FindingCategory::Upstream => git::run(
+            &cwd.join(&finding.project),
+            &["branch", &format!("--set-upstream-to={}", finding.expected)],
+        )
+        .map(|()| describe_fix(finding)),
+        FindingCategory::DeletedBranch => git::run(
+            &cwd.join(&finding.project),
+            &[
+                "branch",
+                "--track",
+                local_branch(&finding.expected),
+                &finding.expected,
+            ],
+        )
+        .map(|()| describe_fix(finding)),
+        FindingCategory::Stale => git::run_network(
+            &cwd.join(&finding.project),
+            &["fetch", "--prune", "--quiet"],
+        )
+        .map(|()| describe_fix(finding)),
+        FindingCategory::Behind => git::run(
+            &cwd.join(&finding.project),
+            &["merge", "--ff-only", "--quiet", &finding.expected],
+        )
+        .map(|()| describe_fix(finding)),
+        FindingCategory::Rule => anyhow::bail!("check rule findings have no automatic fix"),
+    }
+}
+
+/// How a clone fix for `finding` is made, e.g. ` (depth 1, filter blob:none)`
+fn clone_note(finding: &Finding, target: Option<&WorkspaceProject>) -> String {
+    let Some(target) = target.filter(|_| finding.category == FindingCategory::Missing) else {
+        return String::new();
+    };
+    if target.jj {
+        return " (with jj git clone --colocate)".to_string();
+    }
+    let limits: Vec<String> = [
+        target.submodules.then(|| "with submodules".to_string()),
+        (!target.sparse.is_empty()).then(|| format!("sparse {}", target.sparse.join(" "))),
+        target
+            .reference
+            .as_ref()
+            .map(|reference| format!("reference {}", reference.display())),
+        (target.single_branch == Some(true)).then(|| "single branch".to_string()),
+        target.depth.map(|depth| format!("depth {depth}")),
+        target
+            .filter
+            .as_ref()
+            .map(|filter| format!("filter {filter}")),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if limits.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", limits.join(", "))
+    }
+}
+
+/// Clone from the .meta URL, then from each fallback in order
+///
+/// The clone keeps the URL it came from as `origin`; check accepts any of a
+/// project's URLs there. The target's `depth` limits the history fetched
+/// for each branch, `single_branch` limits the branches to its pinned one
+/// (or the default), its `filter` makes a partial clone, `submodules`
+/// initializes its submodules and `sparse` leaves only the top-level files
+/// checked out (see [`set_sparse_paths`]) and `reference` lends it objects
+/// when it's a repository; a `jj` target is cloned as a colocated jj repo
+/// instead.
+fn clone_with_fallbacks(
+    finding: &Finding,
+    target: Option<&WorkspaceProject>,
+    cwd: &Path,
+) -> anyhow::Result<String> {
+    let fallbacks = target.map(|t| t.fallbacks.as_slice()).unwrap_or_default();
+    let jj = target.is_some_and(|t| t.jj);
+    let depth = target.and_then(|t| t.depth).map(|d| d.to_string());
+    let single_branch = target.is_some_and(|t| t.single_branch == Some(true));
+    // A pinned commit isn't a branch; it's fetched after cloning the default
+    let branch = target
+        .and_then(|t| t.pinned_ref.as_deref())
+        .filter(|reference| single_branch && !is_commit_id(reference));
+    let filter = target
+        .and_then(|t| t.filter.as_ref())
+        .map(|f| format!("--filter={f}"));
+    let reference = target
+        .and_then(|t| t.reference.as_ref())
+        .map(|r| r.to_string_lossy().into_owned());
+    let mut errors = Vec::new();
+    for url in std::iter::once(&finding.expected).chain(fallbacks) {
+        let mut args = vec!["clone"];
+        if let Some(depth) = &depth {
+            args.extend(["--depth", depth]);
+        }
+        if single_branch {
+            args.push("--single-branch");
+            if let Some(branch) = branch {
+                args.extend(["--branch", branch]);
+            }
+        } else if depth.is_some() {
+            // Keep every branch, so pinned and profile branches can be checked out
+            args.push("--no-single-branch");
+        }
+        if let Some(filter) = &filter {
+            args.push(filter);
+        }
+        if target.is_some_and(|t| t.submodules) {
+            args.push("--recurse-submodules");
+        }
+        if target.is_some_and(|t| !t.sparse.is_empty()) {
+            args.push("--sparse");
+        }
+        if let Some(reference) = &reference {
+            args.extend(["--reference-if-able", reference]);
+        }
+        args.extend(["--", url.as_str(), finding.project.as_str()]);
+        let result = if jj {
+            jj::clone(url, &finding.project, cwd)
+        } else {
+            git::run_network(cwd, &args)
+        };
+        match result {
+            Ok(()) if *url == finding.expected => return Ok(describe_fix(finding)),
+            Ok(()) => {
+                return Ok(format!(
+                    "git clone {url} {} (fallback after {} failed)",
+                    finding.project, finding.expected
+                ))
+            }
+            Err(e) => errors.push(format!("{url}: {e}")),
+        }
+    }
+    anyhow::bail!("every clone URL failed:\n{}", errors.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execute_command;
+    use crate::testing::{
+        commit, init_clone, init_upstream, init_workspace, isolate_cache, run_git,
+    };
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_strict_check_fails_on_findings() {
+        let temp_dir = TempDir::new().unwrap();
+        let meta = temp_dir.path().join(".meta");
+        std::fs::write(
+            &meta,
+            r#"{"projects": {"repo1": "git@github.com:org/repo1.git"}}"#,
+        )
+        .unwrap();
+        let run = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+            execute_command(
+                "project check",
+                &args,
+                &ExecuteOptions::default(),
+                &[],
+                temp_dir.path(),
+            )
+        };
+
+        assert!(matches!(run(&[]), CommandResult::Message(_)));
+        assert!(matches!(run(&["--strict"]), CommandResult::Error(_)));
+
+        std::fs::write(
+            &meta,
+            r#"{"projects": {"repo1": "git@github.com:org/repo1.git"}, "settings": {"strict": true}}"#,
+        )
+        .unwrap();
+        match run(&[]) {
+            CommandResult::Error(e) => {
+                assert!(e.contains(&messages::text("check.missing", &[("count", &1)])))
+            }
+            _ => panic!("Expected Error result"),
+        }
+    }
+
+    #[test]
+    fn test_check_reports_remote_mismatch() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            r#"{"projects": {"repo1": "git@github.com:org/repo1.git"}}"#,
+        )
+        .unwrap();
+        init_clone(temp_dir.path(), "repo1", "git@github.com:fork/repo1.git");
+
+        let result = execute_command(
+            "project check",
+            &[],
+            &ExecuteOptions::default(),
+            &[],
+            temp_dir.path(),
+        );
+
+        match result {
+            CommandResult::Message(msg) => assert!(msg.contains("origin remote"), "{msg}"),
+            _ => panic!("Expected Message result"),
+        }
+    }
+
+    struct RequireFile(&'static str);
+
+    impl CheckRule for RequireFile {
+        fn id(&self) -> &str {
+            "require-file"
+        }
+
+        fn check(&self, project: &RuleProject) -> Vec<String> {
+            // Only fire for this test's project; the registry is process-wide
+            if project.path != "ruled" || project.dir.join(self.0).exists() {
+                return Vec::new();
+            }
+            vec![format!("{} must exist", self.0)]
+        }
+    }
+
+    #[test]
+    fn test_registered_rule_findings_are_reported() {
+        isolate_cache();
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            r#"{"projects": {"ruled": "git@github.com:org/ruled.git"}}"#,
+        )
+        .unwrap();
+        init_clone(temp_dir.path(), "ruled", "git@github.com:org/ruled.git");
+        register_check_rule(RequireFile("POLICY.md"));
+
+        let run = || {
+            execute_command(
+                "project check",
+                &["--fix".to_string(), "--yes".to_string()],
+                &ExecuteOptions::default(),
+                &[],
+                temp_dir.path(),
+            )
+        };
+        match run() {
+            CommandResult::Message(msg) => {
+                assert_eq!(msg, messages::text("check.fix_applied", &[("count", &0)]))
+            }
+            _ => panic!("Expected Message result"),
+        }
+
+        let targets = workspace_projects(&[], temp_dir.path()).unwrap();
+        let findings = run_rules(&targets, temp_dir.path(), 1);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule.as_deref(), Some("require-file"));
+        assert_eq!(findings[0].expected, "POLICY.md must exist");
+        assert_eq!(
+            summarize(&findings),
+            messages::text("check.rules", &[("count", &1)])
+        );
+
+        std::fs::write(temp_dir.path().join("ruled").join("POLICY.md"), "").unwrap();
+        assert!(run_rules(&targets, temp_dir.path(), 1).is_empty());
+    }
+
+    #[test]
+    fn test_check_ignores_matching_remote() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            r#"{"projects": {"repo1": "git@github.com:org/repo1.git"}}"#,
+        )
+        .unwrap();
+        init_clone(temp_dir.path(), "repo1", "git@github.com:org/repo1");
+
+        let result = execute_command(
+            "project check",
+            &[],
+            &ExecuteOptions::default(),
+            &[],
+            temp_dir.path(),
+        );
+
+        match result {
+            CommandResult::Message(msg) => assert!(msg.contains("All projects are cloned")),
+            _ => panic!("Expected Message result"),
+        }
+    }
+
+    #[test]
+    fn test_check_fix_sets_remote_url() {
+        isolate_cache();
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            r#"{"projects": {"repo1": "git@github.com:org/repo1.git"}}"#,
+        )
+        .unwrap();
+        init_clone(temp_dir.path(), "repo1", "git@github.com:fork/repo1.git");
+
+        let result = execute_command(
+            "project check",
+            &["--fix".to_string(), "--yes".to_string()],
+            &ExecuteOptions::default(),
+            &[],
+            temp_dir.path(),
+        );
+
+        match result {
+            CommandResult::Message(msg) => assert!(msg.contains("Applied 1 fix"), "{msg}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert_eq!(
+            git::remote_url(&temp_dir.path().join("repo1"), "origin").as_deref(),
+            Some("git@github.com:org/repo1.git")
+        );
+    }
+
+    #[test]
+    fn test_check_fix_clones_from_fallback_url() {
+        isolate_cache();
+        let temp_dir = TempDir::new().unwrap();
+        let mirror = temp_dir.path().join("mirror.git");
+        std::fs::create_dir(&mirror).unwrap();
+        run_git(&mirror, &["init", "-q", "--bare"]);
+        let workspace = temp_dir.path().join("workspace");
+        std::fs::create_dir(&workspace).unwrap();
+        let manifest = serde_json::json!({"projects": {"repo1": {
+            "repo": temp_dir.path().join("unreachable.git").to_string_lossy(),
+            "fallback_urls": [mirror.to_string_lossy()],
+        }}});
+        std::fs::write(workspace.join(".meta"), manifest.to_string()).unwrap();
+        let summary_file = temp_dir.path().join("summary.md");
+
+        let args = [
+            "--fix",
+            "-y",
+            "--summary-file",
+            &summary_file.to_string_lossy(),
+        ]
+        .map(String::from);
+        match execute_command(
+            "project check",
+            &args,
+            &ExecuteOptions::default(),
+            &[],
+            &workspace,
+        ) {
+            CommandResult::Message(msg) => assert!(msg.contains("Applied 1 fix"), "{msg}"),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        let written = std::fs::read_to_string(&summary_file).unwrap();
+        assert!(written.contains("(fallback after"), "{written}");
+
+        // Cloned from the mirror, origin is the mirror: not a mismatch
+        match execute_command(
+            "project check",
+            &[],
+            &ExecuteOptions::default(),
+            &[],
+            &workspace,
+        ) {
+            CommandResult::Message(msg) => {
+                assert_eq!(msg, messages::text("check.all_present", &[]))
+            }
+            _ => panic!("Expected Message result"),
+        }
+    }
+
+    #[test]
+    fn test_check_fix_clones_missing_project() {
+        isolate_cache();
+        let temp_dir = TempDir::new().unwrap();
+        let upstream = temp_dir.path().join("upstream.git");
+        std::fs::create_dir(&upstream).unwrap();
+        run_git(&upstream, &["init", "-q", "--bare"]);
+
+        let workspace = init_workspace(temp_dir.path(), &upstream);
+
+        let result = execute_command(
+            "project check",
+            &["--fix".to_string(), "-y".to_string()],
+            &ExecuteOptions::default(),
+            &[],
+            &workspace,
+        );
+
+        match result {
+            CommandResult::Message(msg) => assert!(msg.contains("Applied 1 fix"), "{msg}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(workspace.join("repo1").join(".git").is_dir());
+
+        // The clone's size is kept for --clone-order size
+        let sizes_file = state::workspace_cache_file(&workspace, CLONE_SIZES_FILE).unwrap();
+        let sizes: HashMap<String, u64> = state::read_json(&sizes_file).unwrap();
+        assert!(sizes["repo1"] > 0);
+    }
+
+    #[test]
+    fn test_clone_order() {
+        let missing = |project: &str| Finding {
+            category: FindingCategory::Missing,
+            project: project.to_string(),
+            expected: String::new(),
+            actual: None,
+            rule: None,
+        };
+        let findings = vec![missing("a"), missing("b"), missing("c"), missing("d")];
+        let order = |order, targets: &[WorkspaceProject], sizes: &HashMap<String, u64>| {
+            let mut findings = findings.clone();
+            order_clones(&mut findings, targets, order, sizes);
+            findings.into_iter().map(|f| f.project).collect::<Vec<_>>()
+        };
+
+        let sizes = HashMap::from([("b".to_string(), 10), ("d".to_string(), 500)]);
+        assert_eq!(order(CloneOrder::Size, &[], &sizes), ["d", "b", "a", "c"]);
+
+        let target = |path: &str, priority| WorkspaceProject {
+            path: path.to_string(),
+            url: format!("git@github.com:org/{path}.git"),
+            protected: false,
+            jj: false,
+            submodules: false,
+            fallbacks: Vec::new(),
+            pinned_ref: None,
+            depth: None,
+            filter: None,
+            single_branch: None,
+            sparse: Vec::new(),
+            priority,
+            reference: None,
+        };
+        let targets = [target("a", -1), target("c", 5)];
+        assert_eq!(
+            order(CloneOrder::Priority, &targets, &HashMap::new()),
+            ["c", "b", "d", "a"]
+        );
+        assert!(CloneOrder::parse("largest").is_err());
+    }
+
+    #[test]
+    fn test_check_fix_restores_upstream_tracking() {
+        isolate_cache();
+        let temp_dir = TempDir::new().unwrap();
+        let upstream = init_upstream(temp_dir.path());
+        let workspace = init_workspace(temp_dir.path(), &upstream);
+        run_git(
+            &workspace,
+            &["clone", "-q", &upstream.to_string_lossy(), "repo1"],
+        );
+        run_git(&workspace.join("repo1"), &["branch", "--unset-upstream"]);
+
+        let result = execute_command(
+            "project check",
+            &[],
+            &ExecuteOptions::default(),
+            &[],
+            &workspace,
+        );
+        match result {
+            CommandResult::Message(msg) => assert!(msg.contains("upstream tracking"), "{msg}"),
+            _ => panic!("Expected Message result"),
+        }
+
+        let result = execute_command(
+            "project check",
+            &["--fix".to_string(), "--yes".to_string()],
+            &ExecuteOptions::default(),
+            &[],
+            &workspace,
+        );
+        match result {
+            CommandResult::Message(msg) => assert!(msg.contains("Applied 1 fix"), "{msg}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert_eq!(
+            git::upstream(&workspace.join("repo1")).as_deref(),
+            Some("origin/main")
+        );
+    }
+
+    #[test]
+    fn test_check_fetch_reports_behind() {
+        isolate_cache();
+        let temp_dir = TempDir::new().unwrap();
+        let upstream = init_upstream(temp_dir.path());
+        let workspace = init_workspace(temp_dir.path(), &upstream);
+        run_git(
+            &workspace,
+            &["clone", "-q", &upstream.to_string_lossy(), "repo1"],
+        );
+
+        // Advance upstream after the clone
+        let source = temp_dir.path().join("source");
+        commit(&source, "second");
+        run_git(&source, &["push", "-q", "../upstream.git", "main"]);
+
+        // Without --fetch the clone doesn't know it is behind
+        let result = execute_command(
+            "project check",
+            &[],
+            &ExecuteOptions::default(),
+            &[],
+            &workspace,
+        );
+        match result {
+            CommandResult::Message(msg) => assert!(msg.contains("All projects are cloned")),
+            _ => panic!("Expected Message result"),
+        }
+
+        let result = execute_command(
+            "project check",
+            &["--fetch".to_string()],
+            &ExecuteOptions::default(),
+            &[],
+            &workspace,
+        );
+        match result {
+            CommandResult::Message(msg) => {
+                assert!(msg.contains("1 project(s) are behind"), "{msg}")
+            }
+            _ => panic!("Expected Message result"),
+        }
+
+        // --fix leaves the working tree alone unless --fast-forward is given
+        let fix = |extra: &[&str]| {
+            let mut args = vec!["--fix".to_string(), "--yes".to_string()];
+            args.extend(extra.iter().map(|a| a.to_string()));
+            execute_command(
+                "project check",
+                &args,
+                &ExecuteOptions::default(),
+                &[],
+                &workspace,
+            )
+        };
+        let behind = || {
+            git::output(
+                &workspace.join("repo1"),
+                &["rev-list", "--count", "HEAD..@{u}"],
+            )
+        };
+        match fix(&[]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Applied 0 fix(es)."),
+            _ => panic!("Expected Message result"),
+        }
+        assert_eq!(behind().as_deref(), Some("1"));
+        match fix(&["--fast-forward"]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Applied 1 fix(es)."),
+            _ => panic!("Expected Message result"),
+        }
+        assert_eq!(behind().as_deref(), Some("0"));
+    }
+
+    #[test]
+    fn test_stale_projects_are_reported_and_fetched() {
+        let fixture = crate::testing::Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        let workspace = fixture.workspace();
+        std::fs::write(
+            workspace.join(".meta"),
+            serde_json::json!({
+                "projects": {"api": api.to_string_lossy(), "web": web.to_string_lossy()},
+                "settings": {"max_stale_days": 7}
+            })
+            .to_string(),
+        )
+        .unwrap();
+        fixture.clone_project("api", &api);
+        fixture.clone_project("web", &web);
+        // Cloned (and never fetched) ten days ago
+        let ten_days_ago = SystemTime::now() - Duration::from_secs(10 * 86_400);
+        std::fs::File::open(workspace.join("api/.git"))
+            .unwrap()
+            .set_modified(ten_days_ago)
+            .unwrap();
+
+        match fixture.run("project check", &[]) {
+            CommandResult::Message(msg) => assert!(
+                msg.contains("1 project(s) haven't been fetched within"),
+                "{msg}"
+            ),
+            _ => panic!("Expected Message result"),
+        }
+        match fixture.run("project check", &["--stale-only"]) {
+            CommandResult::Message(msg) => {
+                assert!(msg.contains("All projects are cloned"), "{msg}")
+            }
+            _ => panic!("Expected Message result"),
+        }
+        assert!(workspace.join("api/.git/FETCH_HEAD").exists());
+        assert!(!workspace.join("web/.git/FETCH_HEAD").exists());
+    }
+
+    #[test]
+    fn test_pinned_refs_are_reported_and_checked_out() {
+        let fixture = crate::testing::Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        run_git(&api, &["tag", "v1.0", "main"]);
+        fixture.push_commit("api", "after v1.0");
+        run_git(&web, &["branch", "release", "main"]);
+        let workspace = fixture.workspace();
+        std::fs::write(
+            workspace.join(".meta"),
+            serde_json::json!({"projects": {
+                "api": {"repo": api.to_string_lossy(), "ref": "v1.0"},
+                "web": {"repo": web.to_string_lossy(), "ref": "release"}
+            }})
+            .to_string(),
+        )
+        .unwrap();
+        fixture.clone_project("api", &api);
+
+        match fixture.run("project check", &[]) {
+            CommandResult::Message(msg) => {
+                assert!(msg.contains("1 project(s) missing"), "{msg}");
+                assert!(
+                    msg.contains("1 project(s) are not on the ref .meta pins them to"),
+                    "{msg}"
+                );
+            }
+            _ => panic!("Expected Message result"),
+        }
+        match fixture.run("project check", &["--fix", "--yes"]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Applied 2 fix(es)."),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        let rev = |dir: &str, rev: &str| git::output(&workspace.join(dir), &["rev-parse", rev]);
+        assert_eq!(rev("api", "HEAD"), rev("api", "v1.0^{commit}"));
+        assert_eq!(
+            git::current_branch(&workspace.join("web")).as_deref(),
+            Some("release")
+        );
+        match fixture.run("project check", &[]) {
+            CommandResult::Message(msg) => {
+                assert!(msg.contains("All projects are cloned"), "{msg}")
+            }
+            _ => panic!("Expected Message result"),
+        }
+    }
+
+    #[test]
+    fn test_shallow_clones() {
+        let fixture = crate::testing::Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        run_git(&api, &["tag", "v1.0", "main"]);
+        fixture.push_commit("api", "after v1.0");
+        fixture.push_commit("web", "second");
+        fixture.push_commit("web", "third");
+        let workspace = fixture.workspace();
+        // Local paths ignore --depth; file:// URLs don't
+        let url = |path: &Path| format!("file://{}", path.display());
+        std::fs::write(
+            workspace.join(".meta"),
+            serde_json::json!({
+                "projects": {
+                    "api": {"repo": url(&api), "ref": "v1.0", "depth": 1},
+                    "web": url(&web)
+                },
+                "settings": {"depth": 2}
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        match fixture.run("project check", &["--fix", "--clone-depth", "0"]) {
+            CommandResult::Error(e) => assert_eq!(e, "Invalid --clone-depth value: 0"),
+            _ => panic!("Expected Error result"),
+        }
+        // meta's recursion --depth is not a clone depth
+        match fixture.run("project check", &["--fix", "--yes", "--depth", "5"]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Applied 2 fix(es)."),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        let count = |dir: &str| {
+            git::output(&workspace.join(dir), &["rev-list", "--count", "HEAD"]).unwrap()
+        };
+        assert_eq!(count("api"), "1");
+        assert_eq!(count("web"), "2");
+        assert_eq!(
+            git::output(&workspace.join("api"), &["rev-parse", "HEAD"]),
+            git::output(&api, &["rev-parse", "v1.0^{commit}"])
+        );
+        match fixture.run("project check", &[]) {
+            CommandResult::Message(msg) => assert!(
+                msg.ends_with("\nShallow clones, history truncated: api, web"),
+                "{msg}"
+            ),
+            _ => panic!("Expected Message result"),
+        }
+    }
+
+    #[test]
+    fn test_single_branch_clones() {
+        let fixture = crate::testing::Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        let docs = fixture.upstream("docs");
+        for upstream in [&api, &web, &docs] {
+            run_git(upstream, &["branch", "release", "main"]);
+            run_git(upstream, &["branch", "topic", "main"]);
+        }
+        let workspace = fixture.workspace();
+        std::fs::write(
+            workspace.join(".meta"),
+            serde_json::json!({
+                "projects": {
+                    "api": {"repo": api.to_string_lossy(), "ref": "release"},
+                    "docs": {"repo": docs.to_string_lossy(), "single_branch": false},
+                    "web": web.to_string_lossy()
+                },
+                "settings": {"single_branch": true}
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        match fixture.run("project check", &["--fix", "--yes"]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Applied 3 fix(es)."),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        let branches = |dir: &str| git::remote_branches(&workspace.join(dir));
+        assert_eq!(branches("api"), vec!["origin/release"]);
+        assert_eq!(
+            git::current_branch(&workspace.join("api")).as_deref(),
+            Some("release")
+        );
+        assert_eq!(branches("web"), vec!["origin/HEAD", "origin/main"]);
+        assert_eq!(
+            branches("docs"),
+            vec![
+                "origin/HEAD",
+                "origin/main",
+                "origin/release",
+                "origin/topic"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_submodule_clones() {
+        let fixture = crate::testing::Fixture::new();
+        let api = fixture.upstream("api");
+        let workspace = fixture.workspace();
+        let write_meta = |submodules: serde_json::Value| {
+            std::fs::write(
+                workspace.join(".meta"),
+                serde_json::json!({"projects": {
+                    "api": {"repo": api.to_string_lossy(), "submodules": submodules}
+                }})
+                .to_string(),
+            )
+            .unwrap();
+        };
+
+        write_meta(true.into());
+        let targets = workspace_projects(&[], &workspace).unwrap();
+        assert!(targets[0].submodules);
+        let missing = &inspect(&targets[0], &workspace)[0];
+        assert_eq!(clone_note(missing, Some(&targets[0])), " (with submodules)");
+        match fixture.run("project check", &["--fix", "--yes"]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Applied 1 fix(es)."),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+
+        write_meta("yes".into());
+        match fixture.run("project check", &[]) {
+            CommandResult::Error(e) => {
+                assert!(e.contains("Invalid submodules for 'api'"), "{e}")
+            }
+            _ => panic!("Expected Error result"),
+        }
+    }
+
+    #[test]
+    fn test_partial_clones() {
+        let fixture = crate::testing::Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        run_git(&api, &["config", "uploadpack.allowFilter", "true"]);
+        let workspace = fixture.workspace();
+        let url = |path: &Path| format!("file://{}", path.display());
+        std::fs::write(
+            workspace.join(".meta"),
+            serde_json::json!({"projects": {
+                "api": {"repo": url(&api), "filter": "blob:none"},
+                "web": url(&web)
+            }})
+            .to_string(),
+        )
+        .unwrap();
+
+        match fixture.run("project check", &["--fix", "--filter="]) {
+            CommandResult::Error(e) => assert!(e.starts_with("Invalid --filter value"), "{e}"),
+            _ => panic!("Expected Error result"),
+        }
+        match fixture.run("project check", &["--fix", "--yes"]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Applied 2 fix(es)."),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(git::is_partial(&workspace.join("api")));
+        assert!(!git::is_partial(&workspace.join("web")));
+        match fixture.run("project check", &[]) {
+            CommandResult::Message(msg) => assert!(
+                msg.ends_with("\nPartial clones, objects downloaded on demand: api"),
+                "{msg}"
+            ),
+            _ => panic!("Expected Message result"),
+        }
+    }
+
+    #[test]
+    fn test_sparse_clones() {
+        let fixture = crate::testing::Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        let source = api.parent().unwrap().parent().unwrap().join("sources/api");
+        for file in ["README", "proto/api.proto", "docs/guide.md"] {
+            let file = source.join(file);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, "").unwrap();
+        }
+        run_git(&source, &["add", "."]);
+        fixture.push_commit("api", "layout");
+        let workspace = fixture.workspace();
+        std::fs::write(
+            workspace.join(".meta"),
+            serde_json::json!({"projects": {
+                "api": {"repo": api.to_string_lossy(), "sparse": ["proto"]},
+                "web": web.to_string_lossy()
+            }})
+            .to_string(),
+        )
+        .unwrap();
+
+        let targets = workspace_projects(&[], &workspace).unwrap();
+        let missing = &inspect(&targets[0], &workspace)[0];
+        assert_eq!(clone_note(missing, Some(&targets[0])), " (sparse proto)");
+        match fixture.run("project check", &["--fix", "--yes"]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Applied 2 fix(es)."),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(workspace.join("api/README").exists());
+        assert!(workspace.join("api/proto/api.proto").exists());
+        assert!(!workspace.join("api/docs").exists());
+        assert!(!git::is_sparse(&workspace.join("web")));
+        match fixture.run("project check", &[]) {
+            CommandResult::Message(msg) => assert!(
+                msg.ends_with("\nSparse checkouts, only some directories present: api"),
+                "{msg}"
+            ),
+            _ => panic!("Expected Message result"),
+        }
+
+        std::fs::write(
+            workspace.join(".meta"),
+            serde_json::json!({"projects": {
+                "api": {"repo": api.to_string_lossy(), "sparse": []}
+            }})
+            .to_string(),
+        )
+        .unwrap();
+        match fixture.run("project check", &[]) {
+            CommandResult::Error(e) => {
+                assert!(e.contains("Invalid sparse for 'api'"), "{e}")
+            }
+            _ => panic!("Expected Error result"),
+        }
+    }
+
+    #[test]
+    fn test_reference_clones() {
+        let fixture = crate::testing::Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        let workspace = fixture.workspace();
+        let cache = workspace.parent().unwrap().join("cache");
+        std::fs::create_dir_all(&cache).unwrap();
+        run_git(&cache, &["clone", "-q", &api.to_string_lossy(), "api"]);
+        std::fs::write(
+            workspace.join(".meta"),
+            serde_json::json!({
+                "projects": {"api": api.to_string_lossy(), "web": web.to_string_lossy()},
+                "settings": {"reference": "../cache"}
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        // web has no clone in the cache, and the cache itself isn't a repo
+        match fixture.run("project check", &["--fix", "--yes"]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Applied 2 fix(es)."),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(git::has_alternates(&workspace.join("api")));
+        assert!(!git::has_alternates(&workspace.join("web")));
+        match fixture.run("project check", &[]) {
+            CommandResult::Message(msg) => assert!(
+                msg.ends_with("\nClones borrowing objects from a reference: api"),
+                "{msg}"
+            ),
+            _ => panic!("Expected Message result"),
+        }
+    }
+
+    #[test]
+    fn test_jj_colocated_clones() {
+        let fixture = crate::testing::Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        run_git(&api, &["branch", "release", "main"]);
+        let workspace = fixture.workspace();
+        std::fs::write(
+            workspace.join(".meta"),
+            serde_json::json!({"projects": {
+                "api": {"repo": api.to_string_lossy(), "ref": "release"},
+                "web": {"repo": web.to_string_lossy(), "vcs": "jj"}
+            }})
+            .to_string(),
+        )
+        .unwrap();
+        fixture.clone_project("api", &api);
+        let api_dir = workspace.join("api");
+        // What jj leaves behind: HEAD detached at the working copy's parent
+        std::fs::create_dir(api_dir.join(".jj")).unwrap();
+        run_git(&api_dir, &["checkout", "-q", "--detach", "origin/release"]);
+
+        let targets = workspace_projects(&[], &workspace).unwrap();
+        assert!(!targets[0].jj && targets[1].jj);
+        assert!(inspect(&targets[0], &workspace).is_empty());
+        commit(&api_dir, "jj working copy");
+        let findings = inspect(&targets[0], &workspace);
+        assert_eq!(findings[0].category, FindingCategory::Pinned);
+        assert!(
+            findings[0]
+                .actual
+                .as_deref()
+                .unwrap()
+                .starts_with("jj working copy on "),
+            "{findings:?}"
+        );
+        let missing = &inspect(&targets[1], &workspace)[0];
+        assert_eq!(
+            clone_note(missing, Some(&targets[1])),
+            " (with jj git clone --colocate)"
+        );
+
+        std::fs::write(
+            workspace.join(".meta"),
+            serde_json::json!({"projects": {
+                "web": {"repo": web.to_string_lossy(), "vcs": "svn"}
+            }})
+            .to_string(),
+        )
+        .unwrap();
+        match fixture.run("project check", &[]) {
+            CommandResult::Error(e) => assert!(e.contains("Invalid vcs for 'web'"), "{e}"),
+            _ => panic!("Expected Error result"),
+        }
+    }
+
+    #[test]
+    fn test_check_invalid_fetch_timeout() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+
+        let result = execute_command(
+            "project check",
+            &["--fetch".to_string(), "--fetch-timeout=soon".to_string()],
+            &ExecuteOptions::default(),
+            &[],
+            temp_dir.path(),
+        );
+        match result {
+            CommandResult::Error(msg) => assert!(msg.contains("--fetch-timeout")),
+            _ => panic!("Expected Error result"),
+        }
+    }
+
+    #[test]
+    fn test_incremental_reuses_cached_findings() {
+        let temp_dir = TempDir::new().unwrap();
+        init_clone(temp_dir.path(), "repo1", "git@github.com:org/repo1.git");
+        let targets = vec![WorkspaceProject {
+            path: "repo1".to_string(),
+            url: "git@github.com:org/repo1.git".to_string(),
+            protected: false,
+            jj: false,
+            submodules: false,
+            fallbacks: Vec::new(),
+            pinned_ref: None,
+            depth: None,
+            filter: None,
+            single_branch: None,
+            sparse: Vec::new(),
+            priority: 0,
+            reference: None,
+        }];
+        let cache_file = temp_dir.path().join("cache").join(CHECK_CACHE_FILE);
+
+        assert!(inspect_incremental(&targets, temp_dir.path(), &cache_file, 2).is_empty());
+
+        // Plant a finding under the current fingerprint: an unchanged project
+        // must be served from the cache rather than re-inspected
+        let mut cache: CheckCache = state::read_json(&cache_file).unwrap();
+        let planted = Finding {
+            category: FindingCategory::Behind,
+            project: "repo1".to_string(),
+            expected: "origin/main".to_string(),
+            actual: Some("1 commit(s) behind".to_string()),
+            rule: None,
+        };
+        cache.projects.get_mut("repo1").unwrap().findings = vec![planted.clone()];
+        state::write_json(&cache_file, &cache).unwrap();
+        assert_eq!(
+            inspect_incremental(&targets, temp_dir.path(), &cache_file, 2),
+            vec![planted]
+        );
+    }
+
+    #[test]
+    fn test_incremental_reinspects_changed_projects() {
+        let temp_dir = TempDir::new().unwrap();
+        init_clone(temp_dir.path(), "repo1", "git@github.com:fork/repo1.git");
+        let targets = vec![WorkspaceProject {
+            path: "repo1".to_string(),
+            url: "git@github.com:org/repo1.git".to_string(),
+            protected: false,
+            jj: false,
+            submodules: false,
+            fallbacks: Vec::new(),
+            pinned_ref: None,
+            depth: None,
+            filter: None,
+            single_branch: None,
+            sparse: Vec::new(),
+            priority: 0,
+            reference: None,
+        }];
+        let cache_file = temp_dir.path().join("cache").join(CHECK_CACHE_FILE);
+
+        let findings = inspect_incremental(&targets, temp_dir.path(), &cache_file, 2);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].category, FindingCategory::RemoteMismatch);
+
+        // Declaring the fork as a fallback changes the manifest side only
+        let mut with_fallback = targets.clone();
+        with_fallback[0].fallbacks = vec!["git@github.com:fork/repo1.git".to_string()];
+        assert!(inspect_incremental(&with_fallback, temp_dir.path(), &cache_file, 2).is_empty());
+
+        run_git(
+            &temp_dir.path().join("repo1"),
+            &[
+                "remote",
+                "set-url",
+                "origin",
+                "git@github.com:org/repo1.git",
+            ],
+        );
+        assert!(inspect_incremental(&targets, temp_dir.path(), &cache_file, 2).is_empty());
+    }
+
+    #[test]
+    fn test_check_parallel_output_is_ordered() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut projects = Vec::new();
+        for i in 0..12 {
+            let name = format!("repo{i:02}");
+            init_clone(temp_dir.path(), &name, "git@github.com:fork/x.git");
+            projects.push(format!(r#""{name}": "git@github.com:org/{name}.git""#));
+        }
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            format!(r#"{{"projects": {{{}}}}}"#, projects.join(", ")),
+        )
+        .unwrap();
+        let targets = workspace_projects(&[], temp_dir.path()).unwrap();
+
+        let sequential = inspect_all(&targets, temp_dir.path(), 1);
+        let parallel = inspect_all(&targets, temp_dir.path(), 6);
+        assert_eq!(sequential.len(), 12);
+        assert_eq!(sequential, parallel);
+    }
+
+    #[test]
+    fn test_check_invalid_jobs() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+
+        let result = execute_command(
+            "project check",
+            &["--jobs".to_string(), "0".to_string()],
+            &ExecuteOptions::default(),
+            &[],
+            temp_dir.path(),
+        );
+        match result {
+            CommandResult::Error(msg) => assert!(msg.contains("--jobs")),
+            _ => panic!("Expected Error result"),
+        }
+    }
+
+    #[test]
+    fn test_check_fix_recreates_deleted_branch() {
+        isolate_cache();
+        let temp_dir = TempDir::new().unwrap();
+        let upstream = init_upstream(temp_dir.path());
+        let workspace = init_workspace(temp_dir.path(), &upstream);
+        run_git(
+            &workspace,
+            &["clone", "-q", &upstream.to_string_lossy(), "repo1"],
+        );
+        let repo = workspace.join("repo1");
+        run_git(&repo, &["checkout", "-q", "-b", "topic"]);
+        run_git(&repo, &["branch", "-q", "-D", "main"]);
+        let run = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+            execute_command(
+                "project check",
+                &args,
+                &ExecuteOptions::default(),
+                &[],
+                &workspace,
+            )
+        };
+
+        match run(&[]) {
+            CommandResult::Message(msg) => assert!(
+                msg.contains(&messages::text("check.deleted_branch", &[("count", &1)])),
+                "{msg}"
+            ),
+            _ => panic!("Expected Message result"),
+        }
+        match run(&["--fix", "--yes"]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Applied 1 fix(es)."),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(git::ref_exists(&repo, "refs/heads/main"));
+        assert_eq!(git::current_branch(&repo).as_deref(), Some("topic"));
+        assert_eq!(
+            git::output(&repo, &["rev-parse", "--abbrev-ref", "main@{u}"]).as_deref(),
+            Some("origin/main")
+        );
+    }
+
+    #[test]
+    fn test_check_fix_dry_run_changes_nothing() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            r#"{"projects": {"missing-repo": "https://github.com/test/repo.git"}}"#,
+        )
+        .unwrap();
+
+        let options = ExecuteOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        let result = execute_command(
+            "project check",
+            &["--fix".to_string()],
+            &options,
+            &[],
+            temp_dir.path(),
+        );
+
+        match result {
+            CommandResult::Message(msg) => assert!(msg.contains("Dry run"), "{msg}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(!temp_dir.path().join("missing-repo").exists());
+    }
+}
@@ -0,0 +1,142 @@
+//! The global `--timeout`: a wall-clock budget for a command's network work
+//!
+//! Fetches and clones stop at the deadline. A git process still running is
+//! killed, and one not yet started is never spawned. The command then
+//! reports what it got done, and the plugin exits with
+//! [`TIMEOUT_EXIT_CODE`] so schedulers can tell a timeout from a failure.
+//!
+//! The deadline belongs to the thread running the command and is handed on
+//! to the workers of [`crate::pool::parallel_map`].
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Exit code of a run cut short by `--timeout` (as coreutils `timeout`)
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// Error of an operation cancelled at the deadline
+pub(crate) const CANCELLED: &str = "cancelled: --timeout reached";
+
+/// When the budget runs out, and whether any work was cut short
+#[derive(Debug, Clone)]
+pub(crate) struct Deadline {
+    at: Instant,
+    timeout: Duration,
+    reached: Arc<AtomicBool>,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Deadline>> = const { RefCell::new(None) };
+}
+
+/// Start the budget of a command on this thread; `None` (or a budget too
+/// large to represent) lifts it
+pub(crate) fn start(timeout: Option<Duration>) {
+    enter(timeout.and_then(|timeout| {
+        Some(Deadline {
+            at: Instant::now().checked_add(timeout)?,
+            timeout,
+            reached: Arc::new(AtomicBool::new(false)),
+        })
+    }));
+}
+
+/// The deadline of this thread, to hand to worker threads
+pub(crate) fn current() -> Option<Deadline> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Adopt a deadline taken from [`current`] on another thread
+pub(crate) fn enter(deadline: Option<Deadline>) {
+    CURRENT.with(|current| *current.borrow_mut() = deadline);
+}
+
+/// Time left, or `None` without a deadline
+pub(crate) fn remaining() -> Option<Duration> {
+    current().map(|d| d.at.saturating_duration_since(Instant::now()))
+}
+
+/// Fail with [`CANCELLED`] once the deadline has passed
+///
+/// Call before starting each network operation.
+pub(crate) fn check() -> Result<(), String> {
+    match current() {
+        Some(deadline) if Instant::now() >= deadline.at => {
+            deadline.reached.store(true, Ordering::SeqCst);
+            Err(CANCELLED.to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether the last command on this thread had work cut short
+pub fn timed_out() -> bool {
+    current().is_some_and(|d| d.reached.load(Ordering::SeqCst))
+}
+
+/// The configured budget, for messages
+pub(crate) fn timeout() -> Option<Duration> {
+    current().map(|d| d.timeout)
+}
+
+/// Parse a `--timeout` value: seconds, or a number with `s`, `m` or `h`
+pub(crate) fn parse(value: &str) -> Result<Duration, String> {
+    let (number, unit) = match value.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+        Some((index, _)) => value.split_at(index),
+        None => (value, "s"),
+    };
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        _ => 0,
+    };
+    let seconds = number
+        .parse::<u64>()
+        .ok()
+        .filter(|n| *n > 0)
+        .and_then(|n| n.checked_mul(multiplier))
+        .filter(|s| *s > 0);
+    match seconds.map(Duration::from_secs) {
+        Some(timeout) if Instant::now().checked_add(timeout).is_some() => Ok(timeout),
+        _ => Err(format!(
+            "Invalid --timeout value '{value}': expected e.g. 90, 30s, 10m or 1h"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse("10m"), Ok(Duration::from_secs(600)));
+        assert_eq!(parse("1h"), Ok(Duration::from_secs(3600)));
+        assert!(parse("0").is_err());
+        assert!(parse("18446744073709551615h").is_err());
+        assert!(parse("18446744073709551615").is_err());
+        assert!(parse("soon").is_err());
+        assert!(parse("5d").is_err());
+    }
+
+    #[test]
+    fn test_deadline_is_shared_with_workers() {
+        start(Some(Duration::ZERO));
+        let deadline = current();
+        std::thread::spawn(move || {
+            enter(deadline);
+            assert_eq!(check(), Err(CANCELLED.to_string()));
+        })
+        .join()
+        .unwrap();
+        assert!(timed_out());
+
+        start(None);
+        assert_eq!(check(), Ok(()));
+        assert!(!timed_out());
+    }
+}
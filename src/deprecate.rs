@@ -0,0 +1,160 @@
+//! `meta project deprecate` — mark a project as on its way out
+//!
+//! The marker lives on the project's .meta entry, so everyone who pulls the
+//! manifest sees it in `meta project list`:
+//!
+//! ```json
+//! "old-api": {
+//!   "repo": "git@github.com:org/old-api.git",
+//!   "deprecated": { "successor": "api", "message": "Merged into api", "since": 1767225600 }
+//! }
+//! ```
+
+use crate::manifest;
+use crate::settings::Deprecation;
+use crate::{flag_value, resolve_project_key, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Handle `meta project deprecate <name> [--successor <other>] [--message <text>] [--undo]`
+pub(crate) fn handle_deprecate(
+    args: &[String],
+    options: &ExecuteOptions,
+    cwd: &Path,
+) -> CommandResult {
+    let Some(name) = positional(args) else {
+        return CommandResult::Error(
+            "Usage: meta project deprecate <name> [--successor <other>] [--message <text>] [--undo]"
+                .to_string(),
+        );
+    };
+    let undo = args.iter().any(|a| a == "--undo");
+    let successor = flag_value(args, "--successor");
+
+    let Some(meta_path) = manifest::find(cwd) else {
+        return CommandResult::Error(format!("No .meta config found in {}", cwd.display()));
+    };
+    let mut document = match manifest::read(&meta_path) {
+        Ok(document) => document,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let Some(projects) = document.get_mut("projects").and_then(|p| p.as_object_mut()) else {
+        return CommandResult::Error("No projects declared in .meta".to_string());
+    };
+    let name = match resolve_project_key(name, projects) {
+        Ok(name) => name,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let successor = match successor
+        .map(|s| resolve_project_key(s, projects))
+        .transpose()
+    {
+        Ok(successor) => successor,
+        Err(e) => return CommandResult::Error(format!("Invalid --successor: {e}")),
+    };
+    if successor.as_deref() == Some(name.as_str()) {
+        return CommandResult::Error(format!("'{name}' cannot succeed itself"));
+    }
+    // Entries are objects after migration
+    let Some(entry) = projects.get_mut(&name).and_then(|e| e.as_object_mut()) else {
+        return CommandResult::Error(format!("Project '{name}' is not in .meta"));
+    };
+
+    let message = if undo {
+        if entry.remove("deprecated").is_none() {
+            return CommandResult::Message(format!("'{name}' is not deprecated."));
+        }
+        format!("'{name}' is no longer deprecated.")
+    } else {
+        let deprecation = Deprecation {
+            message: flag_value(args, "--message").map(str::to_string),
+            successor: successor.clone(),
+            since: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        };
+        let value = match serde_json::to_value(&deprecation) {
+            Ok(value) => value,
+            Err(e) => return CommandResult::Error(format!("Failed to serialize JSON: {e}")),
+        };
+        entry.insert("deprecated".to_string(), value);
+        match successor {
+            Some(successor) => format!("Deprecated '{name}' in favor of '{successor}'."),
+            None => format!("Deprecated '{name}'."),
+        }
+    };
+
+    if options.dry_run {
+        return CommandResult::Message(format!("Dry run: {message}"));
+    }
+    match manifest::write(&meta_path, &document) {
+        Ok(()) => CommandResult::Message(message),
+        Err(e) => CommandResult::Error(e),
+    }
+}
+
+/// The first argument that is neither a flag nor a flag's value
+fn positional(args: &[String]) -> Option<&str> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--successor" | "--message" => {
+                iter.next();
+            }
+            a if a.starts_with('-') => {}
+            a => return Some(a),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_deprecate_and_undo() {
+        let temp_dir = TempDir::new().unwrap();
+        let meta = temp_dir.path().join(".meta");
+        std::fs::write(
+            &meta,
+            r#"{"projects": {"old": "git@github.com:org/old.git", "new": "git@github.com:org/new.git"}}"#,
+        )
+        .unwrap();
+        let options = ExecuteOptions::default();
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+
+        match handle_deprecate(
+            &args(&["--message", "Merged", "old", "--successor", "new"]),
+            &options,
+            temp_dir.path(),
+        ) {
+            CommandResult::Message(msg) => {
+                assert_eq!(msg, "Deprecated 'old' in favor of 'new'.")
+            }
+            _ => panic!("Expected Message result"),
+        }
+        let deprecations = settings::deprecations(&meta).unwrap();
+        assert_eq!(deprecations["old"].successor.as_deref(), Some("new"));
+        assert_eq!(deprecations["old"].message.as_deref(), Some("Merged"));
+        assert!(deprecations["old"].since > 0);
+
+        match handle_deprecate(
+            &args(&["old", "--successor", "gone"]),
+            &options,
+            temp_dir.path(),
+        ) {
+            CommandResult::Error(e) => {
+                assert_eq!(e, "Invalid --successor: Unknown project or alias: gone")
+            }
+            _ => panic!("Expected Error result"),
+        }
+
+        handle_deprecate(&args(&["old", "--undo"]), &options, temp_dir.path());
+        assert!(settings::deprecations(&meta).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,318 @@
+//! `meta project explain` — long-form help for findings and errors
+//!
+//! Every check finding category and the common failure modes have a code
+//! (the finding codes are the `category` values in check's JSON output). A
+//! failing command names the codes that apply to it; `--explain` prints the
+//! full explanations inline, like `rustc --explain`.
+
+use meta_plugin_protocol::CommandResult;
+
+/// Long-form help for one code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Explanation {
+    code: &'static str,
+    title: &'static str,
+    meaning: &'static str,
+    fix: &'static str,
+    silence: &'static str,
+    /// Phrases of the (English) result text that identify this code
+    markers: &'static [&'static str],
+}
+
+const EXPLANATIONS: &[Explanation] = &[
+    Explanation {
+        code: "missing",
+        title: "A project in .meta is not cloned",
+        meaning: "The project's directory does not exist in the workspace, so \
+                  commands that loop over projects skip it.",
+        fix: "Run 'meta project check --fix' to clone it from the .meta URL and \
+              any fallback_urls.",
+        silence: "Remove the project with 'meta project rm <name> --keep-files' \
+                  if the workspace no longer needs it.",
+        markers: &["project(s) missing"],
+    },
+    Explanation {
+        code: "archive",
+        title: "An archive project isn't unpacked at its checksum",
+        meaning: "The project is a \"type\": \"archive\" tarball, and its \
+                  directory is missing, was unpacked from a tarball with \
+                  another sha256, or wasn't unpacked by meta at all.",
+        fix: "Run 'meta project check --fix' to download the url, verify the \
+              sha256 and unpack it; move a directory meta didn't unpack aside \
+              first.",
+        silence: "Remove the entry from .meta, or add {\"code\": \"archive\"} \
+                  to its suppress list.",
+        markers: &["not unpacked at the checksum in .meta"],
+    },
+    Explanation {
+        code: "remote_mismatch",
+        title: "A clone's origin differs from .meta",
+        meaning: "The origin remote of the clone points somewhere other than \
+                  the project's .meta URL or one of its fallback_urls, so \
+                  pulls and pushes go to an unexpected repository.",
+        fix: "Run 'meta project check --fix' to set origin to the .meta URL, or \
+              update .meta if the repository moved.",
+        silence: "Add the URL to the project's fallback_urls in .meta.",
+        markers: &["origin remote that differs from .meta"],
+    },
+    Explanation {
+        code: "pinned",
+        title: "A clone isn't on its pinned ref",
+        meaning: "The project's .meta entry has a \"ref\" (branch, tag or \
+                  commit), and the clone has something else checked out.",
+        fix: "Run 'meta project check --fix' to check out the pinned ref \
+              (fetch first if it isn't in the clone yet).",
+        silence: "Change or remove the project's ref in .meta, or add \
+                  {\"code\": \"pinned\"} to its suppress list.",
+        markers: &["not on the ref .meta pins them to"],
+    },
+    Explanation {
+        code: "upstream",
+        title: "A branch doesn't track its origin counterpart",
+        meaning: "The checked-out branch has no upstream, or tracks a branch \
+                  other than origin/<same name>, so 'behind' checks and pulls \
+                  don't see the shared branch.",
+        fix: "Run 'meta project check --fix' to set the upstream, or \
+              'git branch -u origin/<branch>' in the project.",
+        silence: "Push the branch with 'git push -u origin <branch>', or add \
+                  {\"code\": \"upstream\"} to the project's suppress list in \
+                  .meta.",
+        markers: &["without correct upstream tracking"],
+    },
+    Explanation {
+        code: "deleted_branch",
+        title: "The default branch was deleted locally",
+        meaning: "origin still has its default branch (origin/HEAD), but the \
+                  clone has no local branch of that name, usually after \
+                  'git branch -D'.",
+        fix: "Run 'meta project check --fix' to re-create it tracking \
+              origin, or 'git branch --track <branch> origin/<branch>' in \
+              the project. The checked-out branch is left alone.",
+        silence: "Add {\"code\": \"deleted_branch\"} to the project's \
+                  suppress list in .meta.",
+        markers: &["no local branch for origin's default branch"],
+    },
+    Explanation {
+        code: "behind",
+        title: "A branch is behind its upstream",
+        meaning: "The upstream has commits the checked-out branch doesn't, \
+                  as of the last fetch.",
+        fix: "Run 'meta project check --fix --fast-forward' to fast-forward, \
+              or pull in the project.",
+        silence: "Add {\"code\": \"behind\", \"until\": \"YYYY-MM-DD\"} to the \
+                  project's suppress list in .meta, or record it with \
+                  'check --write-baseline'.",
+        markers: &["are behind their upstream"],
+    },
+    Explanation {
+        code: "stale",
+        title: "A clone hasn't been fetched recently",
+        meaning: "The project's last fetch is older than settings.max_stale_days \
+                  in .meta, so findings about it (and what's built from it) \
+                  may be out of date.",
+        fix: "Run 'meta project check --fetch --stale-only' to fetch just the \
+              stale projects, or 'meta project check --fix'.",
+        silence: "Raise or remove settings.max_stale_days, or add \
+                  {\"code\": \"stale\"} to the project's suppress list in .meta.",
+        markers: &["haven't been fetched within"],
+    },
+    Explanation {
+        code: "rule",
+        title: "A custom check rule reported the project",
+        meaning: "A rule registered by an embedding tool (see CheckRule) found \
+                  a problem; the rule's ID is shown in brackets.",
+        fix: "Follow the rule's message; rules have no automatic fix.",
+        silence: "Add {\"code\": \"<rule ID>\"} to the project's suppress list \
+                  in .meta, optionally with an \"until\" date.",
+        markers: &["from custom check rules"],
+    },
+    Explanation {
+        code: "no_config",
+        title: "No .meta config in this directory",
+        meaning: "The command needs a workspace manifest (.meta, .meta.json, \
+                  .meta.yaml, .meta.yml or .meta.toml) in the current \
+                  directory.",
+        fix: "Run the command from the meta repo's root, or create a .meta \
+              with a \"projects\" object.",
+        silence: "Not applicable.",
+        markers: &["No .meta config found"],
+    },
+    Explanation {
+        code: "workspace_locked",
+        title: "Another command holds the workspace lock",
+        meaning: "Commands that modify projects (reset, prune-remotes, \
+                  check --fix, changeset checkout) take a per-workspace lock \
+                  so two runs can't race on the same repositories.",
+        fix: "Wait for the other command, or re-run with --wait. A lock left \
+              by a crashed process is taken over automatically; if the \
+              holder is unknown, remove the lock file named in the error.",
+        silence: "Pass --wait to queue behind the running command.",
+        markers: &["Workspace is locked by"],
+    },
+    Explanation {
+        code: "manifest_too_new",
+        title: "The .meta manifest is newer than this plugin",
+        meaning: "The manifest's meta_version is higher than this meta-project \
+                  understands; reading it could silently drop settings.",
+        fix: "Upgrade meta-project.",
+        silence: "Not applicable.",
+        markers: &["newer than this plugin supports"],
+    },
+    Explanation {
+        code: "git_requirement",
+        title: "The configured git is unusable or too old",
+        meaning: "The git binary from META_PROJECT_GIT (or git on PATH) is \
+                  unusable or older than settings.git.min_version in .meta, \
+                  checked before every command.",
+        fix: "Install a newer git, point META_PROJECT_GIT at one, or lower \
+              settings.git.min_version if the workspace allows it.",
+        silence: "Remove the git block from the .meta settings.",
+        markers: &["is not usable", "but this workspace requires at least"],
+    },
+    Explanation {
+        code: "uncommitted_changes",
+        title: "A project has uncommitted changes",
+        meaning: "The operation would discard or strand local work, so it \
+                  refuses to touch the project.",
+        fix: "Commit, stash or discard the changes in the project first.",
+        silence: "Where supported, pass the command's override (e.g. \
+                  reset --allow-dirty, rm --keep-files).",
+        markers: &["uncommitted changes"],
+    },
+    Explanation {
+        code: "protected",
+        title: "A project is marked protected",
+        meaning: "Projects with \"protected\": true in .meta are left out of \
+                  destructive or write operations unless explicitly included.",
+        fix: "Confirm at the prompt, or pass --include-protected.",
+        silence: "Remove \"protected\": true from the project in .meta.",
+        markers: &["is protected", "Skipping protected"],
+    },
+    Explanation {
+        code: "credentials_in_url",
+        title: "A URL embeds a password or token",
+        meaning: "Credentials in a remote URL are stored in plain text in \
+                  .git/config and leak into logs and shell history.",
+        fix: "Use a credential helper or SSH, and remove the secret with \
+              'git remote set-url'.",
+        silence: "Not silenced; rotate the exposed credential.",
+        markers: &["contains credentials"],
+    },
+    Explanation {
+        code: "merge_conflict",
+        title: "merge-meta found conflicting changes",
+        meaning: "Both sides of a merge changed the same .meta key \
+                  differently; our side was kept and git reports the file as \
+                  conflicted.",
+        fix: "Edit .meta to the intended value and 'git add' it, or re-run the \
+              merge with 'merge-meta --interactive'.",
+        silence: "Not applicable.",
+        markers: &["conflict(s) in .meta"],
+    },
+];
+
+fn find(code: &str) -> Option<&'static Explanation> {
+    EXPLANATIONS.iter().find(|e| e.code == code)
+}
+
+fn render(explanation: &Explanation) -> String {
+    format!(
+        "{} [{}]\n\nWhat it means:\n  {}\n\nHow to fix it:\n  {}\n\nHow to silence it:\n  {}",
+        explanation.title,
+        explanation.code,
+        explanation.meaning,
+        explanation.fix,
+        explanation.silence
+    )
+}
+
+/// Handle `meta project explain [<code>]`
+pub(crate) fn handle_explain(args: &[String]) -> CommandResult {
+    match args.iter().find(|a| !a.starts_with('-')) {
+        Some(code) => match find(code) {
+            Some(explanation) => CommandResult::Message(render(explanation)),
+            None => CommandResult::Error(format!(
+                "Unknown code '{code}'. Run 'meta project explain' to list codes."
+            )),
+        },
+        None => {
+            let width = EXPLANATIONS.iter().map(|e| e.code.len()).max().unwrap_or(0);
+            let lines: Vec<String> = EXPLANATIONS
+                .iter()
+                .map(|e| format!("  {:<width$}  {}", e.code, e.title))
+                .collect();
+            CommandResult::Message(format!(
+                "Codes (meta project explain <code>):\n{}",
+                lines.join("\n")
+            ))
+        }
+    }
+}
+
+/// Point a failed result at the codes it matches
+///
+/// With `--explain` in `args` the explanations are appended in full;
+/// otherwise a one-line hint names the command to run.
+pub(crate) fn annotate(result: CommandResult, args: &[String]) -> CommandResult {
+    let CommandResult::Error(text) = result else {
+        return result;
+    };
+    let matched: Vec<&Explanation> = EXPLANATIONS
+        .iter()
+        .filter(|e| e.markers.iter().any(|marker| text.contains(marker)))
+        .collect();
+    if matched.is_empty() {
+        return CommandResult::Error(text);
+    }
+    if args.iter().any(|a| a == "--explain") {
+        let explanations: Vec<String> = matched.iter().map(|e| render(e)).collect();
+        return CommandResult::Error(format!("{text}\n\n{}", explanations.join("\n\n")));
+    }
+    let commands: Vec<String> = matched
+        .iter()
+        .map(|e| format!("'meta project explain {}'", e.code))
+        .collect();
+    CommandResult::Error(format!(
+        "{text}\nFor more information, run {} (or re-run with --explain).",
+        commands.join(" or ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::FindingCategory;
+
+    #[test]
+    fn test_every_finding_category_is_explained() {
+        let categories = FindingCategory::ALL
+            .into_iter()
+            .chain([FindingCategory::Rule]);
+        for category in categories {
+            let code = serde_json::to_value(category).unwrap();
+            assert!(find(code.as_str().unwrap()).is_some(), "{code}");
+        }
+    }
+
+    #[test]
+    fn test_annotate_failures() {
+        let error = CommandResult::Error("No .meta config found in /tmp".to_string());
+        match annotate(error, &[]) {
+            CommandResult::Error(e) => assert!(
+                e.ends_with("run 'meta project explain no_config' (or re-run with --explain).")
+            ),
+            _ => panic!("Expected Error result"),
+        }
+
+        let error = CommandResult::Error("No .meta config found in /tmp".to_string());
+        match annotate(error, &["--explain".to_string()]) {
+            CommandResult::Error(e) => assert!(e.contains("How to fix it:"), "{e}"),
+            _ => panic!("Expected Error result"),
+        }
+
+        let other = CommandResult::Error("something else".to_string());
+        assert!(matches!(annotate(other, &[]), CommandResult::Error(e) if e == "something else"));
+        let message = CommandResult::Message("No .meta config found".to_string());
+        assert!(matches!(annotate(message, &[]), CommandResult::Message(_)));
+    }
+}
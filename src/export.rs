@@ -0,0 +1,498 @@
+//! `meta project export` — write a filtered copy of the manifest
+//!
+//! Produces a shadow manifest holding only the selected projects, with
+//! remote URLs optionally rewritten, for sharing part of a workspace with an
+//! external partner. Internal-only keys (the `settings` block and per-project
+//! `fallback_urls`) are dropped.
+//!
+//! `--state-json` instead dumps the resolved workspace model (manifest
+//! entries, paths, clone state, dependencies) as one JSON document for
+//! dashboards and bots.
+//!
+//! `--to-gitmodules` hands the selected projects to git instead: each one is
+//! registered with `git submodule add`, for a team moving off meta. A clone
+//! already at the project's path is adopted as it is; a missing one is
+//! cloned by git.
+
+use crate::pool::{default_jobs, parallel_map};
+use crate::{find_dependents, git, manifest, parse_jobs};
+use crate::{flag_value, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use serde::Serialize;
+use std::path::Path;
+
+/// A `--filter` term; a project is exported when it matches every term
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Filter {
+    Tag(String),
+    Name(String),
+    /// Project path equal to, or under, the given directory
+    Path(String),
+}
+
+impl Filter {
+    fn parse(value: &str) -> Result<Filter, String> {
+        match value.split_once(':') {
+            Some(("tag", tag)) if !tag.is_empty() => Ok(Filter::Tag(tag.to_string())),
+            Some(("name", name)) if !name.is_empty() => Ok(Filter::Name(name.to_string())),
+            Some(("path", path)) if !path.is_empty() => {
+                Ok(Filter::Path(path.trim_end_matches('/').to_string()))
+            }
+            _ => Err(format!(
+                "Invalid --filter '{value}'. Expected tag:NAME, name:NAME or path:DIR"
+            )),
+        }
+    }
+
+    fn matches(&self, name: &str, entry: &serde_json::Value) -> bool {
+        match self {
+            Filter::Tag(tag) => entry
+                .get("tags")
+                .and_then(|t| t.as_array())
+                .is_some_and(|tags| tags.iter().any(|t| t.as_str() == Some(tag))),
+            Filter::Name(wanted) => name == wanted,
+            Filter::Path(dir) => {
+                let path = entry.get("path").and_then(|p| p.as_str()).unwrap_or(name);
+                path == dir || path.starts_with(&format!("{dir}/"))
+            }
+        }
+    }
+}
+
+/// Handle `meta project export [--filter F]... [--rewrite-url FROM=TO]... [--to PATH]`
+pub(crate) fn handle_export(
+    args: &[String],
+    options: &ExecuteOptions,
+    cwd: &Path,
+) -> CommandResult {
+    if args.iter().any(|a| a == "--state-json") {
+        return handle_state_json(args, cwd);
+    }
+    let mut filters = Vec::new();
+    let mut rewrites = Vec::new();
+    for (flag, value) in args.iter().zip(args.iter().skip(1)) {
+        match flag.as_str() {
+            "--filter" => match Filter::parse(value) {
+                Ok(filter) => filters.push(filter),
+                Err(e) => return CommandResult::Error(e),
+            },
+            "--rewrite-url" => match value.split_once('=') {
+                Some((from, to)) if !from.is_empty() => {
+                    rewrites.push((from.to_string(), to.to_string()))
+                }
+                _ => {
+                    return CommandResult::Error(format!(
+                        "Invalid --rewrite-url '{value}'. Expected FROM=TO"
+                    ))
+                }
+            },
+            _ => {}
+        }
+    }
+
+    let Some(meta_path) = manifest::find(cwd) else {
+        return CommandResult::Error(format!("No .meta config found in {}", cwd.display()));
+    };
+    let document = match manifest::read(&meta_path) {
+        Ok(document) => document,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let (exported, count) = shadow_manifest(&document, &filters, &rewrites);
+    if args.iter().any(|a| a == "--to-gitmodules") {
+        return to_gitmodules(&exported, options, cwd);
+    }
+
+    let to = flag_value(args, "--to");
+    let rendered = match manifest::render(Path::new(to.unwrap_or(".meta")), &exported) {
+        Ok(rendered) => rendered,
+        Err(e) => return CommandResult::Error(e),
+    };
+
+    match to {
+        None => CommandResult::Message(rendered.trim_end().to_string()),
+        Some(to) if options.dry_run => {
+            CommandResult::Message(format!("Dry run: would write {count} project(s) to {to}"))
+        }
+        Some(to) => match std::fs::write(cwd.join(to), rendered) {
+            Ok(()) => CommandResult::Message(format!("Exported {count} project(s) to {to}")),
+            Err(e) => CommandResult::Error(format!("Failed to write {to}: {e}")),
+        },
+    }
+}
+
+/// Register the projects of `exported` as submodules of the repo at `cwd`
+fn to_gitmodules(
+    exported: &serde_json::Value,
+    options: &ExecuteOptions,
+    cwd: &Path,
+) -> CommandResult {
+    if !git::is_repo_root(cwd) {
+        return CommandResult::Error(
+            "export --to-gitmodules needs the workspace root to be a git repository".to_string(),
+        );
+    }
+    let existing: Vec<String> = git::output(
+        cwd,
+        &[
+            "config",
+            "-f",
+            ".gitmodules",
+            "--get-regexp",
+            r"^submodule\..*\.path$",
+        ],
+    )
+    .unwrap_or_default()
+    .lines()
+    .filter_map(|line| line.split_once(' ').map(|(_, path)| path.to_string()))
+    .collect();
+
+    let mut added = Vec::new();
+    let mut present = Vec::new();
+    let mut failed = Vec::new();
+    let projects = exported.get("projects").and_then(|p| p.as_object());
+    for (name, entry) in projects.into_iter().flatten() {
+        let Some(url) = entry.get("repo").and_then(|r| r.as_str()) else {
+            continue;
+        };
+        let path = entry.get("path").and_then(|p| p.as_str()).unwrap_or(name);
+        if existing.iter().any(|p| p == path) {
+            present.push(name.as_str());
+            continue;
+        }
+        // --force: meta workspaces usually .gitignore their project paths
+        let add = ["submodule", "add", "--force", "--name", name, url, path];
+        if options.dry_run {
+            println!("[dry-run] git {}", add.join(" "));
+        } else if let Err(e) = git::run_network(cwd, &add) {
+            failed.push(format!("{name}: {e}"));
+            continue;
+        }
+        added.push(name.as_str());
+    }
+
+    let verb = if options.dry_run {
+        "Dry run: would add"
+    } else {
+        "Added"
+    };
+    let mut lines = vec![format!("{verb} {} project(s) as submodules.", added.len())];
+    if !present.is_empty() {
+        lines.push(format!("Already submodules: {}", present.join(", ")));
+    }
+    if !options.dry_run && !added.is_empty() {
+        lines.push("Commit .gitmodules and the new submodule entries to finish.".to_string());
+    }
+    if failed.is_empty() {
+        CommandResult::Message(lines.join("\n"))
+    } else {
+        lines.extend(failed);
+        CommandResult::Error(lines.join("\n"))
+    }
+}
+
+/// `--state-json` output; `version` changes only on incompatible changes
+#[derive(Debug, Serialize)]
+struct WorkspaceState {
+    version: u32,
+    root: String,
+    manifest: String,
+    projects: Vec<ProjectState>,
+    /// Top-level .meta keys of other tools, e.g. the Node `meta` `plugins`
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    extensions: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct ProjectState {
+    name: String,
+    /// Relative to the workspace root
+    path: String,
+    repo: Option<String>,
+    tags: Vec<String>,
+    provides: Vec<String>,
+    depends_on: Vec<String>,
+    /// Projects whose `depends_on` names this one
+    dependents: Vec<String>,
+    /// The project has its own .meta
+    meta: bool,
+    /// The .meta entry as declared (after migration to the current format)
+    entry: serde_json::Value,
+    clone: CloneState,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct CloneState {
+    cloned: bool,
+    branch: Option<String>,
+    head: Option<String>,
+    origin: Option<String>,
+    upstream: Option<String>,
+    ahead: Option<usize>,
+    behind: Option<usize>,
+    dirty: bool,
+}
+
+/// Handle `meta project export --state-json [--to PATH] [--jobs N]`
+fn handle_state_json(args: &[String], cwd: &Path) -> CommandResult {
+    let jobs = match parse_jobs(args) {
+        Ok(jobs) => jobs.unwrap_or_else(default_jobs),
+        Err(e) => return CommandResult::Error(e),
+    };
+    let Some(meta_path) = manifest::find(cwd) else {
+        return CommandResult::Error(format!("No .meta config found in {}", cwd.display()));
+    };
+    let projects = match manifest::projects(&meta_path) {
+        Ok((projects, _ignore)) => projects,
+        Err(e) => return CommandResult::Error(format!("Failed to parse meta config: {e}")),
+    };
+    let document = match manifest::read(&meta_path) {
+        Ok(document) => document,
+        Err(e) => return CommandResult::Error(e),
+    };
+
+    let clones = parallel_map(&projects, jobs, |project| inspect(&cwd.join(&project.path)));
+    let state = WorkspaceState {
+        version: 1,
+        root: cwd.display().to_string(),
+        manifest: meta_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        extensions: manifest::extensions(&document),
+        projects: projects
+            .iter()
+            .zip(clones)
+            .map(|(project, clone)| ProjectState {
+                name: project.name.clone(),
+                path: project.path.clone(),
+                repo: project.repo.clone(),
+                tags: project.tags.clone(),
+                provides: project.provides.clone(),
+                depends_on: project.depends_on.clone(),
+                dependents: find_dependents(&project.name, &projects).unwrap_or_default(),
+                meta: project.meta,
+                entry: document["projects"]
+                    .get(&project.name)
+                    .cloned()
+                    .unwrap_or_default(),
+                clone,
+            })
+            .collect(),
+    };
+    let json = match serde_json::to_string_pretty(&state) {
+        Ok(json) => json,
+        Err(e) => return CommandResult::Error(format!("Failed to serialize state: {e}")),
+    };
+    match flag_value(args, "--to") {
+        None => CommandResult::Message(json),
+        Some(to) => match std::fs::write(cwd.join(to), json + "\n") {
+            Ok(()) => CommandResult::Message(format!(
+                "Exported the state of {} project(s) to {to}",
+                state.projects.len()
+            )),
+            Err(e) => CommandResult::Error(format!("Failed to write {to}: {e}")),
+        },
+    }
+}
+
+fn inspect(dir: &Path) -> CloneState {
+    if !git::is_repo_root(dir) {
+        return CloneState::default();
+    }
+    let (ahead, behind) = git::ahead_behind(dir).unzip();
+    CloneState {
+        cloned: true,
+        branch: git::current_branch(dir),
+        head: git::output(dir, &["rev-parse", "HEAD"]),
+        origin: git::remote_url(dir, "origin"),
+        upstream: git::upstream(dir),
+        ahead,
+        behind,
+        dirty: git::is_dirty(dir),
+    }
+}
+
+/// The manifest limited to projects matching `filters`, with repo URLs
+/// rewritten by the first matching `(from, to)` prefix
+fn shadow_manifest(
+    document: &serde_json::Value,
+    filters: &[Filter],
+    rewrites: &[(String, String)],
+) -> (serde_json::Value, usize) {
+    let rewrite = |url: &str| {
+        rewrites
+            .iter()
+            .find_map(|(from, to)| {
+                url.strip_prefix(from.as_str())
+                    .map(|rest| format!("{to}{rest}"))
+            })
+            .unwrap_or_else(|| url.to_string())
+    };
+
+    let mut projects = serde_json::Map::new();
+    if let Some(declared) = document.get("projects").and_then(|p| p.as_object()) {
+        for (name, entry) in declared {
+            if !filters.iter().all(|f| f.matches(name, entry)) {
+                continue;
+            }
+            let entry = match entry {
+                serde_json::Value::String(url) => serde_json::Value::String(rewrite(url)),
+                serde_json::Value::Object(fields) => {
+                    let mut fields = fields.clone();
+                    fields.remove("fallback_urls");
+                    if let Some(url) = fields.get("repo").and_then(|r| r.as_str()) {
+                        let url = rewrite(url);
+                        fields.insert("repo".to_string(), url.into());
+                    }
+                    serde_json::Value::Object(fields)
+                }
+                other => other.clone(),
+            };
+            projects.insert(name.clone(), entry);
+        }
+    }
+
+    let count = projects.len();
+    let mut exported = match document {
+        serde_json::Value::Object(top) => top.clone(),
+        _ => serde_json::Map::new(),
+    };
+    exported.remove("settings");
+    exported.insert("projects".to_string(), serde_json::Value::Object(projects));
+    (serde_json::Value::Object(exported), count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Fixture;
+    use tempfile::TempDir;
+
+    const MANIFEST: &str = r#"{
+        "projects": {
+            "sdk": {"repo": "git@github.com:acme/sdk.git", "tags": ["oss"],
+                    "fallback_urls": ["https://mirror.internal/sdk.git"]},
+            "docs": {"repo": "git@github.com:acme/docs.git", "path": "web/docs", "tags": ["oss", "web"]},
+            "billing": "git@github.com:acme/billing.git"
+        },
+        "ignore": ["scratch"],
+        "settings": {"aliases": {"ck": "check"}}
+    }"#;
+
+    #[test]
+    fn test_export_filters_and_rewrites() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join(".meta"), MANIFEST).unwrap();
+        let args: Vec<String> = [
+            "--filter",
+            "tag:oss",
+            "--rewrite-url",
+            "git@github.com:acme/=https://github.com/acme/",
+            "--to",
+            ".meta.oss",
+        ]
+        .map(String::from)
+        .to_vec();
+
+        match handle_export(&args, &ExecuteOptions::default(), temp_dir.path()) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Exported 2 project(s) to .meta.oss"),
+            _ => panic!("Expected Message result"),
+        }
+
+        let written: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(temp_dir.path().join(".meta.oss")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            written,
+            serde_json::json!({
+                "meta_version": 2,
+                "projects": {
+                    "sdk": {"repo": "https://github.com/acme/sdk.git", "tags": ["oss"]},
+                    "docs": {"repo": "https://github.com/acme/docs.git", "path": "web/docs",
+                             "tags": ["oss", "web"]}
+                },
+                "ignore": ["scratch"]
+            })
+        );
+    }
+
+    #[test]
+    fn test_state_json() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        fixture.manifest(&[("api", &api), ("web", &web)]);
+        fixture.clone_project("api", &api);
+
+        let state = match fixture.run("project export", &["--state-json"]) {
+            CommandResult::Message(json) => {
+                serde_json::from_str::<serde_json::Value>(&json).unwrap()
+            }
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        };
+        assert_eq!(state["manifest"], ".meta");
+        let projects = state["projects"].as_array().unwrap();
+        let api_state = projects.iter().find(|p| p["name"] == "api").unwrap();
+        assert_eq!(api_state["clone"]["cloned"], true);
+        assert_eq!(api_state["clone"]["branch"], "main");
+        assert_eq!(api_state["clone"]["head"].as_str().unwrap().len(), 40);
+        assert_eq!(api_state["entry"]["repo"], api.to_string_lossy().as_ref());
+        let web_state = projects.iter().find(|p| p["name"] == "web").unwrap();
+        assert_eq!(web_state["clone"]["cloned"], false);
+    }
+
+    #[test]
+    fn test_filters_combine() {
+        let document: serde_json::Value = serde_json::from_str(MANIFEST).unwrap();
+        let filters = [
+            Filter::parse("tag:oss").unwrap(),
+            Filter::parse("path:web/").unwrap(),
+        ];
+        let (exported, count) = shadow_manifest(&document, &filters, &[]);
+        assert_eq!(count, 1);
+        assert!(exported["projects"].get("docs").is_some());
+
+        assert!(Filter::parse("team:core")
+            .unwrap_err()
+            .contains("Invalid --filter 'team:core'"));
+    }
+
+    #[test]
+    fn test_export_to_gitmodules_adopts_clones() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        fixture.manifest(&[("api", &api), ("web", &web)]);
+        fixture.clone_project("api", &api);
+        fixture.clone_project("web", &web);
+        let workspace = fixture.workspace();
+        crate::testing::run_git(&workspace, &["init", "-q", "-b", "main"]);
+
+        match fixture.run(
+            "project export",
+            &["--to-gitmodules", "--filter", "name:api"],
+        ) {
+            CommandResult::Message(msg) => assert!(
+                msg.starts_with("Added 1 project(s) as submodules."),
+                "{msg}"
+            ),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        match fixture.run("project export", &["--to-gitmodules"]) {
+            CommandResult::Message(msg) => {
+                assert!(msg.contains("Already submodules: api"), "{msg}")
+            }
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        let gitmodules = std::fs::read_to_string(workspace.join(".gitmodules")).unwrap();
+        assert!(gitmodules.contains("[submodule \"web\"]"), "{gitmodules}");
+        assert!(
+            gitmodules.contains(&format!("url = {}", api.display())),
+            "{gitmodules}"
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! Parallel `git fetch --prune` across workspace projects
+
+use crate::deadline;
+use crate::git;
+use crate::pool::{parallel_map_grouped, ConcurrencyGroups};
+use crate::progress;
+use crate::style;
+use crate::summary::{RowStatus, RunSummary, Span};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Number of fetches run concurrently
+pub(crate) const DEFAULT_FETCH_JOBS: usize = 8;
+
+/// Per-project fetch timeout when `--fetch-timeout` is not given
+pub(crate) const DEFAULT_FETCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Result of fetching a single project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FetchOutcome {
+    pub project: String,
+    pub result: Result<(), String>,
+    pub span: Span,
+}
+
+/// Record each fetch in a run summary
+pub(crate) fn record_outcomes(summary: &mut RunSummary, outcomes: &[FetchOutcome]) {
+    for outcome in outcomes {
+        let (status, detail) = match &outcome.result {
+            Ok(()) => (RowStatus::Ok, String::new()),
+            Err(e) if e == deadline::CANCELLED => (RowStatus::Skipped, e.clone()),
+            Err(e) => (RowStatus::Failed, e.clone()),
+        };
+        summary.record(
+            &outcome.project,
+            "fetch",
+            status,
+            detail,
+            Some(outcome.span),
+        );
+    }
+}
+
+/// Fetch every project (paths relative to `cwd`) on a bounded worker pool,
+/// keeping concurrency `groups` within their limits
+///
+/// Progress is printed as each fetch completes; outcomes are returned in the
+/// same order as `projects` regardless of completion order.
+pub(crate) fn fetch_projects(
+    projects: &[String],
+    cwd: &Path,
+    jobs: usize,
+    groups: &ConcurrencyGroups,
+    timeout: Duration,
+    plain: bool,
+) -> Vec<FetchOutcome> {
+    let completed = AtomicUsize::new(0);
+    progress::begin("fetch", projects.len());
+    parallel_map_grouped(projects, jobs, groups, String::as_str, |project| {
+        let started = Instant::now();
+        let result = fetch_one(&cwd.join(project), timeout);
+        let done = completed.fetch_add(1, Ordering::SeqCst) + 1;
+        let status = progress::status(&result);
+        progress::project("fetch", project, status, done, projects.len(), None);
+        match &result {
+            Ok(()) => println!("[{done}/{}] {} {project}", projects.len(), style::ok(plain)),
+            Err(e) => println!(
+                "[{done}/{}] {} {project}: {e}",
+                projects.len(),
+                style::failed(plain)
+            ),
+        }
+        FetchOutcome {
+            project: project.clone(),
+            result,
+            span: Span::since(started),
+        }
+    })
+}
+
+/// Run `git fetch --prune` in `dir`, killing it after `timeout`
+pub(crate) fn fetch_one(dir: &Path, timeout: Duration) -> Result<(), String> {
+    git::run_bounded(dir, &["fetch", "--prune", "--quiet"], Some(timeout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Fixture;
+
+    #[test]
+    fn test_fetch_cancelled_at_deadline() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        fixture.clone_project("api", &api);
+        fixture.clone_project("web", &web);
+        let projects = vec!["api".to_string(), "web".to_string()];
+
+        deadline::start(Some(Duration::ZERO));
+        let outcomes = fetch_projects(
+            &projects,
+            &fixture.workspace(),
+            2,
+            &ConcurrencyGroups::default(),
+            DEFAULT_FETCH_TIMEOUT,
+            true,
+        );
+        assert!(outcomes
+            .iter()
+            .all(|o| o.result == Err(deadline::CANCELLED.to_string())));
+        assert!(deadline::timed_out());
+
+        deadline::start(None);
+        let outcomes = fetch_projects(
+            &projects,
+            &fixture.workspace(),
+            2,
+            &ConcurrencyGroups::default(),
+            DEFAULT_FETCH_TIMEOUT,
+            true,
+        );
+        assert!(outcomes.iter().all(|o| o.result.is_ok()), "{outcomes:?}");
+    }
+}
@@ -0,0 +1,359 @@
+//! `meta project fetch-all` — paced, resumable fetches for nightly jobs
+//!
+//! Fetches every cloned project like `check --fetch`, but spreads the
+//! fetches over time so a run across hundreds of repos doesn't hammer a
+//! shared mirror: each host gets a token bucket that refills at `--rate`
+//! fetches per minute and holds up to `--burst` of them.
+//!
+//! Progress is saved in the workspace cache after every fetch. A run that
+//! is interrupted, or stopped by `--timeout`, resumes with the projects it
+//! hasn't fetched yet the next time; a run that gets through every project
+//! clears the record, so the next one starts over.
+
+use crate::fetch::{self, DEFAULT_FETCH_JOBS, DEFAULT_FETCH_TIMEOUT};
+use crate::pool::parallel_map_grouped;
+use crate::summary::{RowStatus, RunSummary, Span};
+use crate::{
+    deadline, flag_value, git, parse_jobs, progress, settings, state, style, workspace_projects,
+    ExecuteOptions,
+};
+use meta_plugin_protocol::CommandResult;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Cache file recording the progress of an unfinished run
+const STATE_FILE: &str = "fetch-all.json";
+
+/// Fetches per minute from one host when `--rate` is not given
+const DEFAULT_RATE: u32 = 30;
+
+/// Fetches one host may start back to back when `--burst` is not given
+const DEFAULT_BURST: u32 = 4;
+
+/// An unfinished run
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RunState {
+    /// When the run started, in seconds since the Unix epoch
+    started: u64,
+    /// Projects fetched successfully so far
+    done: BTreeSet<String>,
+}
+
+/// Token buckets, one per host
+struct Limiter {
+    /// Tokens added per second
+    rate: f64,
+    burst: f64,
+    buckets: Mutex<HashMap<String, (f64, Instant)>>,
+}
+
+impl Limiter {
+    fn new(per_minute: u32, burst: u32) -> Self {
+        Limiter {
+            rate: f64::from(per_minute) / 60.0,
+            burst: f64::from(burst),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a token for `host` and say how long to wait before using it
+    ///
+    /// Tokens are reserved ahead, so concurrent callers for the same host
+    /// queue up one refill interval apart.
+    fn reserve(&self, host: &str, now: Instant) -> Duration {
+        let mut buckets = self.buckets.lock().unwrap();
+        let (tokens, last) = buckets.entry(host.to_string()).or_insert((self.burst, now));
+        let refilled = now.saturating_duration_since(*last).as_secs_f64() * self.rate;
+        *tokens = (*tokens + refilled).min(self.burst) - 1.0;
+        *last = now;
+        if *tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-*tokens / self.rate)
+        }
+    }
+}
+
+/// Handle `meta project fetch-all [--rate N] [--burst N] [--restart]`
+pub(crate) fn handle_fetch_all(
+    args: &[String],
+    options: &ExecuteOptions,
+    provided_projects: &[String],
+    cwd: &Path,
+    summary: &mut RunSummary,
+) -> CommandResult {
+    let count = |flag: &str, default: u32| match flag_value(args, flag) {
+        Some(value) => match value.parse::<u32>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(format!("Invalid {flag} value: {value}")),
+        },
+        None => Ok(default),
+    };
+    let (rate, burst) = match (
+        count("--rate", DEFAULT_RATE),
+        count("--burst", DEFAULT_BURST),
+    ) {
+        (Ok(rate), Ok(burst)) => (rate, burst),
+        (Err(e), _) | (_, Err(e)) => return CommandResult::Error(e),
+    };
+    let jobs = match parse_jobs(args) {
+        Ok(jobs) => jobs.unwrap_or(DEFAULT_FETCH_JOBS),
+        Err(e) => return CommandResult::Error(e),
+    };
+    let fetch_timeout = match flag_value(args, "--fetch-timeout") {
+        Some(value) => match value.parse::<u64>() {
+            Ok(secs) => Duration::from_secs(secs),
+            Err(_) => {
+                return CommandResult::Error(format!("Invalid --fetch-timeout value: {value}"))
+            }
+        },
+        None => DEFAULT_FETCH_TIMEOUT,
+    };
+    let groups = match settings::concurrency_groups(cwd) {
+        Ok(groups) => groups,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let projects = match workspace_projects(provided_projects, cwd) {
+        Ok(projects) => projects,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let Some(state_file) = state::workspace_cache_file(cwd, STATE_FILE) else {
+        return CommandResult::Error(
+            "No cache directory (set HOME or XDG_CACHE_HOME) to record progress in".to_string(),
+        );
+    };
+
+    let restart = args.iter().any(|a| a == "--restart");
+    let previous = if restart {
+        None
+    } else {
+        state::read_json::<RunState>(&state_file)
+    };
+    let resumed = previous.is_some();
+    let run = previous.unwrap_or_else(|| RunState {
+        started: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        done: BTreeSet::new(),
+    });
+    let cloned: Vec<(String, String)> = projects
+        .iter()
+        .filter(|p| git::is_repo_root(&cwd.join(&p.path)))
+        .map(|p| {
+            let url = git::remote_url(&cwd.join(&p.path), "origin").unwrap_or(p.url.clone());
+            (p.path.clone(), host(&url))
+        })
+        .collect();
+    let pending: Vec<&(String, String)> = cloned
+        .iter()
+        .filter(|(path, _)| !run.done.contains(path))
+        .collect();
+    let carried = cloned.len() - pending.len();
+
+    if options.dry_run {
+        let mut hosts: Vec<&str> = pending.iter().map(|(_, host)| host.as_str()).collect();
+        hosts.sort_unstable();
+        hosts.dedup();
+        return CommandResult::Message(format!(
+            "Dry run: would fetch {} project(s) from {} host(s) at up to {rate} per minute per host{}.",
+            pending.len(),
+            hosts.len(),
+            if resumed {
+                format!(", resuming a run that already fetched {carried}")
+            } else {
+                String::new()
+            }
+        ));
+    }
+    if resumed {
+        println!(
+            "Resuming: {carried} project(s) already fetched, {} to go.",
+            pending.len()
+        );
+    }
+
+    let limiter = Limiter::new(rate, burst);
+    let run = Mutex::new(run);
+    let completed = AtomicUsize::new(0);
+    progress::begin("fetch", pending.len());
+    let outcomes = parallel_map_grouped(
+        &pending,
+        jobs,
+        &groups,
+        |(path, _)| path.as_str(),
+        |(path, host)| {
+            let started = Instant::now();
+            let result = pace(&limiter, host)
+                .and_then(|()| fetch::fetch_one(&cwd.join(path), fetch_timeout));
+            if result.is_ok() {
+                let mut run = run.lock().unwrap();
+                run.done.insert(path.clone());
+                // Losing one record only means fetching that project again
+                let _ = state::write_json(&state_file, &*run);
+            }
+            let done = completed.fetch_add(1, Ordering::SeqCst) + 1;
+            progress::project(
+                "fetch",
+                path,
+                progress::status(&result),
+                done,
+                pending.len(),
+                None,
+            );
+            match &result {
+                Ok(()) => println!(
+                    "[{done}/{}] {} {path}",
+                    pending.len(),
+                    style::ok(options.plain)
+                ),
+                Err(e) => println!(
+                    "[{done}/{}] {} {path}: {e}",
+                    pending.len(),
+                    style::failed(options.plain)
+                ),
+            }
+            (result, Span::since(started))
+        },
+    );
+
+    let mut failures = Vec::new();
+    let mut cancelled = 0;
+    for ((path, _), (result, span)) in pending.iter().zip(outcomes) {
+        let (status, detail) = match result {
+            Ok(()) => (RowStatus::Ok, String::new()),
+            Err(e) if e == deadline::CANCELLED => {
+                cancelled += 1;
+                (RowStatus::Skipped, e)
+            }
+            Err(e) => {
+                failures.push(format!("{path}: {e}"));
+                (RowStatus::Failed, e)
+            }
+        };
+        summary.record(path, "fetch", status, detail, Some(span));
+    }
+    let fetched = pending.len() - failures.len() - cancelled;
+
+    if cancelled > 0 {
+        return CommandResult::Error(format!(
+            "Stopped at the --timeout deadline: fetched {fetched}, {cancelled} left; run again to resume."
+        ));
+    }
+    if failures.is_empty() {
+        let _ = std::fs::remove_file(&state_file);
+        return CommandResult::Message(format!(
+            "Fetched {} project(s){}.",
+            fetched + carried,
+            if resumed {
+                format!(", {carried} of them in an earlier run")
+            } else {
+                String::new()
+            }
+        ));
+    }
+    CommandResult::Error(format!(
+        "{} fetch(es) failed; run again to retry them:\n{}",
+        failures.len(),
+        failures.join("\n")
+    ))
+}
+
+/// Wait for a token for `host`, giving up if the wait would pass the deadline
+fn pace(limiter: &Limiter, host: &str) -> Result<(), String> {
+    deadline::check()?;
+    let wait = limiter.reserve(host, Instant::now());
+    if let Some(remaining) = deadline::remaining().filter(|remaining| wait > *remaining) {
+        // Wake at the deadline, so the run counts as timed out
+        std::thread::sleep(remaining);
+        deadline::check()?;
+    }
+    std::thread::sleep(wait);
+    Ok(())
+}
+
+/// The host a remote URL points at; local paths share the host `local`
+pub(crate) fn host(url: &str) -> String {
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest,
+        // scp-like `user@host:path`
+        None if url.contains(':') && !url.starts_with('/') => url,
+        None => return "local".to_string(),
+    };
+    let authority = rest.split(['/', ':']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    if host.is_empty() {
+        "local".to_string()
+    } else {
+        host.to_ascii_lowercase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Fixture;
+
+    #[test]
+    fn test_host() {
+        assert_eq!(host("git@github.com:org/api.git"), "github.com");
+        assert_eq!(host("https://user@GitHub.com/org/api"), "github.com");
+        assert_eq!(host("ssh://git@mirror:2222/org/api"), "mirror");
+        assert_eq!(host("/srv/git/api.git"), "local");
+        assert_eq!(host("file:///srv/git/api.git"), "local");
+    }
+
+    #[test]
+    fn test_limiter_paces_each_host() {
+        let limiter = Limiter::new(60, 2);
+        let now = Instant::now();
+        assert_eq!(limiter.reserve("a", now), Duration::ZERO);
+        assert_eq!(limiter.reserve("a", now), Duration::ZERO);
+        assert_eq!(limiter.reserve("a", now), Duration::from_secs(1));
+        assert_eq!(limiter.reserve("a", now), Duration::from_secs(2));
+        assert_eq!(limiter.reserve("b", now), Duration::ZERO);
+        // Refilled by the time it waited for
+        let later = now + Duration::from_secs(4);
+        assert_eq!(limiter.reserve("a", later), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_fetch_all_resumes() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        fixture.manifest(&[("api", &api), ("web", &web)]);
+        fixture.clone_project("api", &api);
+        fixture.clone_project("web", &web);
+        let workspace = fixture.workspace();
+        let state_file = state::workspace_cache_file(&workspace, STATE_FILE).unwrap();
+
+        match fixture.run("project fetch-all", &["--rate", "0"]) {
+            CommandResult::Error(e) => assert_eq!(e, "Invalid --rate value: 0"),
+            _ => panic!("Expected Error result"),
+        }
+
+        // An earlier run got through api before it was interrupted
+        let interrupted = RunState {
+            started: 1,
+            done: BTreeSet::from(["api".to_string()]),
+        };
+        state::write_json(&state_file, &interrupted).unwrap();
+        match fixture.run("project fetch-all", &[]) {
+            CommandResult::Message(msg) => {
+                assert_eq!(msg, "Fetched 2 project(s), 1 of them in an earlier run.")
+            }
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(!state_file.exists());
+        match fixture.run("project fetch-all", &["--restart"]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Fetched 2 project(s)."),
+            _ => panic!("Expected Message result"),
+        }
+    }
+}
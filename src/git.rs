@@ -0,0 +1,524 @@
+//! Thin wrappers around the `git` executable
+
+use crate::deadline;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+/// The git binary from `META_PROJECT_GIT`; `None` means `git` on PATH
+static EXECUTABLE: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// How network operations may ask for credentials
+static PROMPTING: RwLock<Prompting> = RwLock::new(Prompting {
+    disabled: false,
+    askpass: None,
+});
+
+/// Credential prompting of network operations
+///
+/// Terminal prompts are always off: stdin carries meta's request, and a
+/// prompt would hang the run. Askpass helpers (a GUI dialog, a keychain
+/// bridge) still work unless `--no-prompt` turns them off too.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Prompting {
+    /// `--no-prompt`: no askpass helper, and SSH runs in batch mode
+    pub disabled: bool,
+    /// Helper from `META_PROJECT_ASKPASS`, handed to git as `GIT_ASKPASS`
+    pub askpass: Option<PathBuf>,
+}
+
+/// The git binary the user chose with `META_PROJECT_GIT`, if any
+///
+/// Only the user's environment picks the executable: a .meta is shared
+/// through the repository, so letting it name a program would let any
+/// cloned workspace run code on every command. The path must be absolute.
+pub(crate) fn executable_from_env() -> Result<Option<PathBuf>, String> {
+    parse_program(
+        "META_PROJECT_GIT",
+        std::env::var_os("META_PROJECT_GIT").as_deref(),
+    )
+}
+
+/// The askpass helper the user chose with `META_PROJECT_ASKPASS`, if any
+///
+/// Like the git executable, only the environment can name it.
+pub(crate) fn askpass_from_env() -> Result<Option<PathBuf>, String> {
+    parse_program(
+        "META_PROJECT_ASKPASS",
+        std::env::var_os("META_PROJECT_ASKPASS").as_deref(),
+    )
+}
+
+fn parse_program(
+    variable: &str,
+    value: Option<&std::ffi::OsStr>,
+) -> Result<Option<PathBuf>, String> {
+    match value.filter(|v| !v.is_empty()).map(PathBuf::from) {
+        Some(path) if !path.is_absolute() => Err(format!(
+            "{variable} must be an absolute path, got {}",
+            path.display()
+        )),
+        path => Ok(path),
+    }
+}
+
+/// Set how the network operations of this command may prompt
+pub(crate) fn set_prompting(prompting: Prompting) {
+    *PROMPTING.write().unwrap_or_else(|e| e.into_inner()) = prompting;
+}
+
+/// A `git` command for a network operation, which never prompts on the terminal
+fn network_command() -> Command {
+    let mut command = command();
+    command.env("GIT_TERMINAL_PROMPT", "0").stdin(Stdio::null());
+    let prompting = PROMPTING.read().unwrap_or_else(|e| e.into_inner()).clone();
+    let ssh_configured =
+        std::env::var_os("GIT_SSH_COMMAND").is_some() || std::env::var_os("GIT_SSH").is_some();
+    apply_prompting(&mut command, &prompting, ssh_configured);
+    command
+}
+
+fn apply_prompting(command: &mut Command, prompting: &Prompting, ssh_configured: bool) {
+    if prompting.disabled {
+        // An empty GIT_ASKPASS also hides core.askPass and SSH_ASKPASS from git
+        command.env("GIT_ASKPASS", "").env_remove("SSH_ASKPASS");
+        if !ssh_configured {
+            command.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+        }
+    } else if let Some(askpass) = &prompting.askpass {
+        command.env("GIT_ASKPASS", askpass);
+    }
+}
+
+/// Append guidance to a git error that came from missing credentials
+fn with_auth_hint(error: &str) -> String {
+    const SIGNS: [&str; 5] = [
+        "terminal prompts disabled",
+        "could not read Username",
+        "could not read Password",
+        "Permission denied (publickey",
+        "Host key verification failed",
+    ];
+    if SIGNS.iter().any(|sign| error.contains(sign)) {
+        format!(
+            "{error}\n(credentials are needed and this plugin never prompts on the terminal: \
+             set up a credential helper or SSH key, or set META_PROJECT_ASKPASS to an \
+             askpass program)"
+        )
+    } else {
+        error.to_string()
+    }
+}
+
+/// A `git` command using the configured executable
+pub(crate) fn command() -> Command {
+    let executable = EXECUTABLE.read().unwrap_or_else(|e| e.into_inner());
+    match executable.as_deref() {
+        Some(path) => Command::new(path),
+        None => Command::new("git"),
+    }
+}
+
+/// Select the git executable and enforce a minimum version
+///
+/// Called at command start so a wrong git fails with a clear error instead
+/// of subtly different behavior halfway through a run. The selection only
+/// changes once the requirement is met.
+pub(crate) fn require(executable: Option<&Path>, min_version: Option<&str>) -> Result<(), String> {
+    if executable.is_some() || min_version.is_some() {
+        let name = executable.map_or_else(|| "git".to_string(), |p| p.display().to_string());
+        let found =
+            version_of(executable).ok_or_else(|| format!("git executable {name} is not usable"))?;
+        if let Some(min_version) = min_version {
+            let required = parse_version(min_version)
+                .ok_or_else(|| format!("Invalid git min_version '{min_version}' in settings"))?;
+            if found < required {
+                return Err(format!(
+                    "{name} is version {}, but this workspace requires at least {min_version}",
+                    join_version(&found)
+                ));
+            }
+        }
+    }
+    *EXECUTABLE.write().unwrap_or_else(|e| e.into_inner()) = executable.map(Path::to_path_buf);
+    Ok(())
+}
+
+/// Version of `executable` (or `git` on PATH), e.g. `[2, 39, 2]`
+fn version_of(executable: Option<&Path>) -> Option<Vec<u64>> {
+    let program = executable.unwrap_or(Path::new("git"));
+    let output = Command::new(program).arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // "git version 2.39.2 (Apple Git-143)" or "git version 2.45.1.windows.1"
+    let text = String::from_utf8_lossy(&output.stdout);
+    parse_version(
+        text.trim()
+            .strip_prefix("git version ")?
+            .split(' ')
+            .next()?,
+    )
+}
+
+/// Leading numeric components of a dotted version (`2.45.1.windows.1` → 2.45.1)
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let parts: Vec<u64> = version
+        .split('.')
+        .map_while(|part| part.parse().ok())
+        .collect();
+    (!parts.is_empty()).then_some(parts)
+}
+
+fn join_version(version: &[u64]) -> String {
+    let parts: Vec<String> = version.iter().map(u64::to_string).collect();
+    parts.join(".")
+}
+
+/// Run git in `dir` and return its trimmed stdout, or `None` on failure
+pub(crate) fn output(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = command().args(args).current_dir(dir).output().ok()?;
+
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        None
+    }
+}
+
+/// Run git in `dir`, surfacing stderr in the error when it fails
+pub(crate) fn run(dir: &Path, args: &[&str]) -> anyhow::Result<()> {
+    succeeded(args, command().args(args).current_dir(dir).output()?)
+}
+
+fn succeeded(args: &[&str], output: std::process::Output) -> anyhow::Result<()> {
+    if output.status.success() {
+        Ok(())
+    } else {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+}
+
+/// Run a network operation (fetch, clone) in `dir`
+///
+/// Terminal prompts are disabled either way (see [`Prompting`]), so an
+/// operation that needs interactive auth fails instead of hanging; with a
+/// `--timeout` the operation is also cancelled at the deadline.
+pub(crate) fn run_network(dir: &Path, args: &[&str]) -> anyhow::Result<()> {
+    if deadline::remaining().is_none() {
+        let output = network_command().args(args).current_dir(dir).output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!(
+                "git {} failed: {}",
+                args.join(" "),
+                with_auth_hint(stderr.trim())
+            );
+        }
+        return Ok(());
+    }
+    run_bounded(dir, args, None).map_err(|e| anyhow::anyhow!("git {} failed: {e}", args.join(" ")))
+}
+
+/// Run git in `dir`, killing it after `timeout` or at the `--timeout` deadline
+///
+/// Terminal prompts are disabled: an operation that needs interactive auth
+/// fails instead of hanging the whole run. Errors carry git's stderr,
+/// "timed out after Ns", or [`deadline::CANCELLED`].
+pub(crate) fn run_bounded(
+    dir: &Path,
+    args: &[&str],
+    timeout: Option<Duration>,
+) -> Result<(), String> {
+    deadline::check()?;
+    let limit = match (timeout, deadline::remaining()) {
+        (Some(timeout), Some(remaining)) => Some(timeout.min(remaining)),
+        (timeout, remaining) => timeout.or(remaining),
+    };
+    let mut child = network_command()
+        .args(args)
+        .current_dir(dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run git: {e}"))?;
+
+    // Drained while polling: git blocks once it fills the pipe buffer
+    let stderr = child.stderr.take().map(|mut pipe| {
+        std::thread::spawn(move || {
+            let mut text = String::new();
+            let _ = pipe.read_to_string(&mut text);
+            text
+        })
+    });
+    let stop_at = limit.and_then(|limit| Instant::now().checked_add(limit));
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(_)) => {
+                let text = stderr.and_then(|reader| reader.join().ok());
+                return Err(with_auth_hint(text.unwrap_or_default().trim()));
+            }
+            Ok(None) if stop_at.is_some_and(|at| Instant::now() >= at) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                deadline::check()?;
+                return Err(format!(
+                    "timed out after {}s",
+                    timeout.unwrap_or_default().as_secs()
+                ));
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+/// Get the URL configured for `remote` in the repository at `dir`
+pub(crate) fn remote_url(dir: &Path, remote: &str) -> Option<String> {
+    output(dir, &["config", "--get", &format!("remote.{remote}.url")])
+}
+
+/// Name of the checked-out branch, or `None` when HEAD is detached
+pub(crate) fn current_branch(dir: &Path) -> Option<String> {
+    output(dir, &["symbolic-ref", "--short", "-q", "HEAD"])
+}
+
+/// Upstream of the checked-out branch (e.g. `origin/main`), if configured and present
+pub(crate) fn upstream(dir: &Path) -> Option<String> {
+    output(
+        dir,
+        &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"],
+    )
+}
+
+/// Commits `(ahead, behind)` of HEAD relative to its upstream
+pub(crate) fn ahead_behind(dir: &Path) -> Option<(usize, usize)> {
+    let out = output(dir, &["rev-list", "--left-right", "--count", "HEAD...@{u}"])?;
+    let (ahead, behind) = out.split_once(char::is_whitespace)?;
+    Some((ahead.trim().parse().ok()?, behind.trim().parse().ok()?))
+}
+
+/// Whether a fully-qualified ref (e.g. `refs/remotes/origin/main`) exists
+pub(crate) fn ref_exists(dir: &Path, refname: &str) -> bool {
+    output(dir, &["rev-parse", "--verify", "-q", refname]).is_some()
+}
+
+/// All configured remotes as `(name, url)` pairs
+pub(crate) fn remotes(dir: &Path) -> Vec<(String, String)> {
+    lines(output(
+        dir,
+        &["config", "--get-regexp", r"^remote\..*\.url$"],
+    ))
+    .into_iter()
+    .filter_map(|line| {
+        let (key, url) = line.split_once(' ')?;
+        let name = key.strip_prefix("remote.")?.strip_suffix(".url")?;
+        Some((name.to_string(), url.to_string()))
+    })
+    .collect()
+}
+
+/// Whether the working tree has uncommitted or untracked changes
+pub(crate) fn is_dirty(dir: &Path) -> bool {
+    output(dir, &["status", "--porcelain"]).is_some_and(|out| !out.is_empty())
+}
+
+/// Whether the clone at `dir` has truncated history (cloned with `--depth`)
+pub(crate) fn is_shallow(dir: &Path) -> bool {
+    output(dir, &["rev-parse", "--is-shallow-repository"]).as_deref() == Some("true")
+}
+
+/// Whether the clone at `dir` borrows objects from another repository
+/// through `objects/info/alternates` (cloned with `--reference`)
+pub(crate) fn has_alternates(dir: &Path) -> bool {
+    output(dir, &["rev-parse", "--git-path", "objects/info/alternates"])
+        .is_some_and(|file| dir.join(file).is_file())
+}
+
+/// Whether the clone at `dir` was made with `--filter` and downloads
+/// missing objects from origin on demand
+pub(crate) fn is_partial(dir: &Path) -> bool {
+    output(dir, &["config", "--get", "remote.origin.promisor"]).as_deref() == Some("true")
+}
+
+/// Whether the clone at `dir` has only some paths checked out
+pub(crate) fn is_sparse(dir: &Path) -> bool {
+    output(dir, &["config", "--get", "core.sparseCheckout"]).as_deref() == Some("true")
+}
+
+/// Short names of all remote-tracking branches (e.g. `origin/main`)
+pub(crate) fn remote_branches(dir: &Path) -> Vec<String> {
+    lines(output(
+        dir,
+        &["for-each-ref", "--format=%(refname:short)", "refs/remotes"],
+    ))
+}
+
+/// Local branches whose configured upstream no longer exists
+pub(crate) fn gone_branches(dir: &Path) -> Vec<String> {
+    lines(output(
+        dir,
+        &[
+            "for-each-ref",
+            "--format=%(refname:short) %(upstream:track)",
+            "refs/heads",
+        ],
+    ))
+    .into_iter()
+    .filter_map(|line| line.strip_suffix(" [gone]").map(str::to_string))
+    .collect()
+}
+
+fn lines(output: Option<String>) -> Vec<String> {
+    output
+        .map(|out| out.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Whether `dir` is the root of its own git repository
+///
+/// `git` commands happily walk up into an enclosing repository (usually the
+/// meta repo itself), so project inspection must check for `.git` first.
+pub(crate) fn is_repo_root(dir: &Path) -> bool {
+    dir.join(".git").exists()
+}
+
+/// Compare two remote URLs, ignoring a trailing `.git` or `/`
+pub(crate) fn urls_match(a: &str, b: &str) -> bool {
+    fn normalize(url: &str) -> &str {
+        let url = url.trim().trim_end_matches('/');
+        url.strip_suffix(".git").unwrap_or(url)
+    }
+    normalize(a) == normalize(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_bounded_drains_large_stderr() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        // Far more than a pipe buffer holds, then a failure
+        let noisy = "alias.noisy=!head -c 300000 /dev/zero | tr '\\0' e >&2; exit 1";
+        let started = Instant::now();
+        let error = run_bounded(
+            temp_dir.path(),
+            &["-c", noisy, "noisy"],
+            Some(Duration::from_secs(20)),
+        )
+        .unwrap_err();
+        assert_eq!(error.len(), 300000);
+        assert!(started.elapsed() < Duration::from_secs(20));
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("2.45.1.windows.1"), Some(vec![2, 45, 1]));
+        assert_eq!(parse_version("2.30"), Some(vec![2, 30]));
+        assert_eq!(parse_version("latest"), None);
+        assert!(parse_version("2.9.5") < parse_version("2.30"));
+    }
+
+    #[test]
+    fn test_require_reports_unmet_requirements() {
+        let missing = Path::new("/nonexistent/git");
+        assert_eq!(
+            require(Some(missing), None),
+            Err("git executable /nonexistent/git is not usable".to_string())
+        );
+        assert!(require(None, Some("9999"))
+            .unwrap_err()
+            .contains("but this workspace requires at least 9999"));
+        assert!(require(None, Some("two"))
+            .unwrap_err()
+            .contains("Invalid git min_version"));
+    }
+
+    #[test]
+    fn test_parse_program() {
+        use std::ffi::OsStr;
+        let parse = |value| parse_program("META_PROJECT_GIT", value);
+        assert_eq!(parse(None), Ok(None));
+        assert_eq!(parse(Some(OsStr::new(""))), Ok(None));
+        assert_eq!(
+            parse(Some(OsStr::new("/opt/git/bin/git"))),
+            Ok(Some(PathBuf::from("/opt/git/bin/git")))
+        );
+        assert_eq!(
+            parse_program("META_PROJECT_ASKPASS", Some(OsStr::new("askpass.sh"))),
+            Err("META_PROJECT_ASKPASS must be an absolute path, got askpass.sh".to_string())
+        );
+    }
+
+    #[test]
+    fn test_apply_prompting() {
+        use std::ffi::OsStr;
+        let envs = |prompting: &Prompting, ssh_configured| {
+            let mut command = Command::new("git");
+            apply_prompting(&mut command, prompting, ssh_configured);
+            command
+                .get_envs()
+                .map(|(k, v)| (k.to_os_string(), v.map(OsStr::to_os_string)))
+                .collect::<Vec<_>>()
+        };
+        let askpass = Prompting {
+            disabled: false,
+            askpass: Some(PathBuf::from("/usr/bin/ksshaskpass")),
+        };
+        assert_eq!(
+            envs(&askpass, false),
+            vec![("GIT_ASKPASS".into(), Some("/usr/bin/ksshaskpass".into()))]
+        );
+
+        let no_prompt = Prompting {
+            disabled: true,
+            ..askpass
+        };
+        let disabled = envs(&no_prompt, false);
+        assert!(disabled.contains(&("GIT_ASKPASS".into(), Some("".into()))));
+        assert!(disabled.contains(&("SSH_ASKPASS".into(), None)));
+        assert!(disabled.contains(&(
+            "GIT_SSH_COMMAND".into(),
+            Some("ssh -o BatchMode=yes".into())
+        )));
+        // The user's own SSH command is left alone
+        assert!(!envs(&no_prompt, true)
+            .iter()
+            .any(|(k, _)| k == "GIT_SSH_COMMAND"));
+    }
+
+    #[test]
+    fn test_auth_failures_carry_guidance() {
+        let error = "fatal: could not read Username for 'https://github.com': \
+                     terminal prompts disabled";
+        assert!(with_auth_hint(error).contains("META_PROJECT_ASKPASS"));
+        assert_eq!(
+            with_auth_hint("fatal: repository not found"),
+            "fatal: repository not found"
+        );
+    }
+
+    #[test]
+    fn test_urls_match_ignores_git_suffix() {
+        assert!(urls_match(
+            "git@github.com:org/repo.git",
+            "git@github.com:org/repo"
+        ));
+        assert!(urls_match(
+            "https://github.com/org/repo/",
+            "https://github.com/org/repo.git"
+        ));
+        assert!(!urls_match(
+            "git@github.com:org/repo.git",
+            "git@github.com:fork/repo.git"
+        ));
+    }
+}
@@ -0,0 +1,283 @@
+//! `meta project hook` — install git hooks that run a workspace-wide check
+//!
+//! The command a hook runs comes from `settings.hooks` in .meta, e.g.
+//! `"hooks": {"pre-push": "make lint"}`, and a project's own `hooks` entry
+//! overrides it. The hook runs in the project's root with
+//! `META_PROJECT_NAME` set, and a failing command blocks the push.
+//!
+//! Installed hooks carry a marker line, so reinstalling (after the command
+//! changed) and uninstalling only ever touch hooks written here; an existing
+//! hook from another tool is left alone unless `--force` is given.
+
+use crate::{git, manifest, settings, workspace_projects, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use std::path::{Path, PathBuf};
+
+/// Hooks that can be installed
+const HOOKS: &[&str] = &["pre-push"];
+
+/// Second line of every installed hook script
+const MARKER: &str = "# Installed by meta-project";
+
+/// Handle `meta project hook <hook> install|uninstall [--force]`
+pub(crate) fn handle_hook(
+    args: &[String],
+    options: &ExecuteOptions,
+    provided_projects: &[String],
+    cwd: &Path,
+) -> CommandResult {
+    let usage = "Usage: meta project hook pre-push install|uninstall [--force]";
+    let positionals: Vec<&str> = args
+        .iter()
+        .filter(|a| !a.starts_with('-'))
+        .map(String::as_str)
+        .collect();
+    let (hook, action) = match positionals[..] {
+        [hook, action] => (hook, action),
+        _ => return CommandResult::Error(usage.to_string()),
+    };
+    if !HOOKS.contains(&hook) {
+        return CommandResult::Error(format!(
+            "Unsupported hook '{hook}': expected one of {}",
+            HOOKS.join(", ")
+        ));
+    }
+    let projects = match workspace_projects(provided_projects, cwd) {
+        Ok(projects) => projects,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let cloned: Vec<String> = projects
+        .into_iter()
+        .map(|p| p.path)
+        .filter(|path| git::is_repo_root(&cwd.join(path)))
+        .collect();
+    match action {
+        "install" => install(hook, &cloned, args, options, cwd),
+        "uninstall" => uninstall(hook, &cloned, options, cwd),
+        other => CommandResult::Error(format!(
+            "Unknown hook action '{other}': expected 'install' or 'uninstall'"
+        )),
+    }
+}
+
+fn install(
+    hook: &str,
+    projects: &[String],
+    args: &[String],
+    options: &ExecuteOptions,
+    cwd: &Path,
+) -> CommandResult {
+    let force = args.iter().any(|a| a == "--force");
+    let default = match settings::load(cwd) {
+        Ok(settings) => settings.hooks.get(hook).cloned(),
+        Err(e) => return CommandResult::Error(e),
+    };
+    let overrides = match manifest::find(cwd).map(|meta_path| settings::project_hooks(&meta_path)) {
+        Some(Ok(overrides)) => overrides,
+        Some(Err(e)) => return CommandResult::Error(e),
+        None => Default::default(),
+    };
+
+    let mut installed = Vec::new();
+    let mut unconfigured = Vec::new();
+    let mut foreign = Vec::new();
+    let mut failed = Vec::new();
+    for project in projects {
+        let command = overrides
+            .get(project)
+            .and_then(|hooks| hooks.get(hook))
+            .or(default.as_ref());
+        let Some(command) = command else {
+            unconfigured.push(project.as_str());
+            continue;
+        };
+        let Some(path) = hook_path(&cwd.join(project), hook) else {
+            failed.push(format!("{project}: cannot locate its hooks directory"));
+            continue;
+        };
+        if !force && is_foreign(&path) {
+            foreign.push(project.as_str());
+            continue;
+        }
+        if !options.dry_run {
+            if let Err(e) = write_script(&path, &script(project, command)) {
+                failed.push(format!("{project}: {e}"));
+                continue;
+            }
+        }
+        installed.push(project.as_str());
+    }
+
+    if installed.is_empty() && foreign.is_empty() && failed.is_empty() && !projects.is_empty() {
+        return CommandResult::Error(format!(
+            "No {hook} command configured: set settings.hooks.{hook} (or a project's hooks.{hook}) in .meta"
+        ));
+    }
+    let verb = if options.dry_run {
+        "Dry run: would install"
+    } else {
+        "Installed"
+    };
+    let mut lines = vec![format!(
+        "{verb} the {hook} hook in {} project(s).",
+        installed.len()
+    )];
+    if !unconfigured.is_empty() {
+        lines.push(format!(
+            "No {hook} command for: {}",
+            unconfigured.join(", ")
+        ));
+    }
+    if !foreign.is_empty() {
+        failed.push(format!(
+            "Existing {hook} hook not written by meta-project (pass --force to replace it): {}",
+            foreign.join(", ")
+        ));
+    }
+    if failed.is_empty() {
+        CommandResult::Message(lines.join("\n"))
+    } else {
+        lines.extend(failed);
+        CommandResult::Error(lines.join("\n"))
+    }
+}
+
+fn uninstall(
+    hook: &str,
+    projects: &[String],
+    options: &ExecuteOptions,
+    cwd: &Path,
+) -> CommandResult {
+    let mut removed = 0;
+    let mut failed = Vec::new();
+    for project in projects {
+        let Some(path) = hook_path(&cwd.join(project), hook) else {
+            continue;
+        };
+        if !path.exists() || is_foreign(&path) {
+            continue;
+        }
+        if !options.dry_run {
+            if let Err(e) = std::fs::remove_file(&path) {
+                failed.push(format!("{project}: {e}"));
+                continue;
+            }
+        }
+        removed += 1;
+    }
+    let verb = if options.dry_run {
+        "Dry run: would remove"
+    } else {
+        "Removed"
+    };
+    let summary = format!("{verb} the {hook} hook from {removed} project(s).");
+    if failed.is_empty() {
+        CommandResult::Message(summary)
+    } else {
+        CommandResult::Error(format!("{summary}\n{}", failed.join("\n")))
+    }
+}
+
+/// Where git looks for `hook` in the repo at `dir` (honours core.hooksPath)
+fn hook_path(dir: &Path, hook: &str) -> Option<PathBuf> {
+    let path = git::output(dir, &["rev-parse", "--git-path", &format!("hooks/{hook}")])?;
+    Some(dir.join(path))
+}
+
+/// Whether an existing hook at `path` was written by something else
+fn is_foreign(path: &Path) -> bool {
+    std::fs::read_to_string(path).is_ok_and(|content| !content.contains(MARKER))
+}
+
+/// The hook script running `command` for `project`
+fn script(project: &str, command: &str) -> String {
+    format!(
+        "#!/bin/sh\n{MARKER}; reinstall with 'meta project hook pre-push install'\n\
+         META_PROJECT_NAME='{}'\nexport META_PROJECT_NAME\n{command}\n",
+        project.replace('\'', r"'\''")
+    )
+}
+
+fn write_script(path: &Path, content: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{run_git, Fixture};
+
+    #[test]
+    fn test_pre_push_hook_runs_configured_command() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        std::fs::write(
+            fixture.workspace().join(".meta"),
+            serde_json::json!({
+                "projects": {
+                    "api": {"repo": api.to_string_lossy(), "hooks": {"pre-push": "exit 1"}},
+                    "web": web.to_string_lossy()
+                },
+                "settings": {"hooks": {"pre-push": "test \"$META_PROJECT_NAME\" = web"}}
+            })
+            .to_string(),
+        )
+        .unwrap();
+        fixture.clone_project("api", &api);
+        fixture.clone_project("web", &web);
+        let hook = fixture.workspace().join("web/.git/hooks/pre-push");
+        std::fs::write(&hook, "#!/bin/sh\nexit 0\n").unwrap();
+
+        match fixture.run("project hook", &["pre-push", "install"]) {
+            CommandResult::Error(e) => {
+                assert!(
+                    e.starts_with("Installed the pre-push hook in 1 project(s)."),
+                    "{e}"
+                );
+                assert!(e.contains("(pass --force to replace it): web"), "{e}");
+            }
+            _ => panic!("Expected Error result"),
+        }
+        match fixture.run("project hook", &["pre-push", "install", "--force"]) {
+            CommandResult::Message(msg) => {
+                assert_eq!(msg, "Installed the pre-push hook in 2 project(s).")
+            }
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+
+        // The project override blocks the push; the default lets it through
+        let push = |project: &str| {
+            std::process::Command::new("git")
+                .args(["push", "-q", "origin", "HEAD:refs/heads/topic"])
+                .current_dir(fixture.workspace().join(project))
+                .status()
+                .unwrap()
+                .success()
+        };
+        assert!(!push("api"));
+        assert!(push("web"));
+
+        match fixture.run("project hook", &["pre-push", "uninstall"]) {
+            CommandResult::Message(msg) => {
+                assert_eq!(msg, "Removed the pre-push hook from 2 project(s).")
+            }
+            _ => panic!("Expected Message result"),
+        }
+        assert!(!hook.exists());
+        run_git(
+            &fixture.workspace().join("api"),
+            &["push", "-q", "origin", "HEAD:refs/heads/topic"],
+        );
+    }
+}
@@ -0,0 +1,1161 @@
+//! `meta project import` — add projects declared by another tool's manifest
+//!
+//! Each source (`--from-gitmodules`, `--from-repo-manifest`, `--from-west`,
+//! `--from-tsrc`, `--from-gitman`) yields [`Imported`] projects, which
+//! are merged into .meta the same way: new names are added, names already
+//! in .meta are left as they are. Without a .meta one is created.
+//!
+//! A revision the source pins a project to becomes the entry's `ref`, which
+//! `check` reports on and `check --fix` checks out.
+//!
+//! With `--quarantine`, imported projects go to the `pending` section of
+//! .meta instead, which meta doesn't sync, and are cloned into
+//! `.meta-quarantine/` for review. `meta project approve <name>` moves one
+//! into `projects` (see [`crate::approve`]).
+
+use crate::add::{is_relative_path, validate_url};
+use crate::{flag_value, git, manifest, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Section of .meta holding quarantined projects until they are approved
+pub(crate) const PENDING_KEY: &str = "pending";
+
+/// Where quarantined projects are cloned for review, by name
+pub(crate) const QUARANTINE_DIR: &str = ".meta-quarantine";
+
+/// A project read from a foreign manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Imported {
+    pub name: String,
+    pub path: String,
+    pub url: String,
+    pub tags: Vec<String>,
+    pub revision: Option<String>,
+}
+
+/// Reads the projects of a manifest file
+type Reader = fn(&Path) -> Result<Vec<Imported>, String>;
+
+/// The import flag taking each kind of manifest file
+const MANIFEST_READERS: &[(&str, Reader)] = &[
+    ("--from-repo-manifest", read_repo_manifest),
+    ("--from-west", read_west),
+    ("--from-tsrc", read_tsrc),
+    ("--from-gitman", read_gitman),
+];
+
+/// Handle `meta project import --from-gitmodules [--deinit] [--quarantine]`
+/// and `meta project import --from-<manifest kind> <file> [--quarantine]`
+pub(crate) fn handle_import(
+    args: &[String],
+    options: &ExecuteOptions,
+    cwd: &Path,
+) -> CommandResult {
+    let source = MANIFEST_READERS
+        .iter()
+        .find_map(|(flag, read)| flag_value(args, flag).map(|file| (file, read(&cwd.join(file)))));
+    if let Some((file, projects)) = source {
+        return match projects {
+            Ok(projects) => import(&projects, file, args, options, cwd)
+                .map_or_else(CommandResult::Error, |lines| {
+                    CommandResult::Message(lines.join("\n"))
+                }),
+            Err(e) => CommandResult::Error(e),
+        };
+    }
+    if !args.iter().any(|a| a == "--from-gitmodules") {
+        return CommandResult::Error(
+            "Usage: meta project import --from-gitmodules [--deinit] [--quarantine]\n       \
+             meta project import --from-repo-manifest <file> [--quarantine]\n       \
+             meta project import --from-west|--from-tsrc|--from-gitman <file> [--quarantine]"
+                .to_string(),
+        );
+    }
+    let submodules = match read_gitmodules(cwd) {
+        Ok(submodules) => submodules,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let deinit = args.iter().any(|a| a == "--deinit");
+    if deinit {
+        let dirty: Vec<&str> = submodules
+            .iter()
+            .filter(|s| git::is_dirty(&cwd.join(&s.project.path)))
+            .map(|s| s.project.path.as_str())
+            .collect();
+        if !dirty.is_empty() {
+            return CommandResult::Error(format!(
+                "Refusing to deinit submodule(s) with uncommitted changes: {}",
+                dirty.join(", ")
+            ));
+        }
+    }
+
+    let projects: Vec<Imported> = submodules.iter().map(|s| s.project.clone()).collect();
+    let mut lines = match import(&projects, ".gitmodules", args, options, cwd) {
+        Ok(lines) => lines,
+        Err(e) => return CommandResult::Error(e),
+    };
+    if !deinit {
+        return CommandResult::Message(lines.join("\n"));
+    }
+    if options.dry_run {
+        lines.push(format!(
+            "Dry run: would deinit and remove {} submodule(s).",
+            submodules.len()
+        ));
+        return CommandResult::Message(lines.join("\n"));
+    }
+    for submodule in &submodules {
+        if let Err(e) = remove_submodule(cwd, submodule) {
+            lines.push(format!(
+                "Removing submodule {} failed: {e}",
+                submodule.project.path
+            ));
+            return CommandResult::Error(lines.join("\n"));
+        }
+    }
+    lines.push(format!(
+        "Removed {} submodule(s). Run 'meta project check --fix' to clone them as projects.",
+        submodules.len()
+    ));
+    CommandResult::Message(lines.join("\n"))
+}
+
+/// Merge `projects` into .meta and, with `--quarantine`, clone them for
+/// review; returns the report lines
+fn import(
+    projects: &[Imported],
+    source: &str,
+    args: &[String],
+    options: &ExecuteOptions,
+    cwd: &Path,
+) -> Result<Vec<String>, String> {
+    let quarantine = args.iter().any(|a| a == "--quarantine");
+    let (mut lines, added) = merge(projects, source, quarantine, options, cwd)?;
+    if quarantine && !options.dry_run {
+        lines.extend(clone_for_review(projects, &added, cwd));
+    }
+    Ok(lines)
+}
+
+/// Add `projects` (read from `source`) to the .meta in `cwd`, or to its
+/// pending section with `quarantine`; returns the report lines and the
+/// names added
+pub(crate) fn merge(
+    projects: &[Imported],
+    source: &str,
+    quarantine: bool,
+    options: &ExecuteOptions,
+    cwd: &Path,
+) -> Result<(Vec<String>, Vec<String>), String> {
+    if projects.is_empty() {
+        return Err(format!("No projects found in {source}"));
+    }
+    let meta_path = manifest::find(cwd).unwrap_or_else(|| cwd.join(".meta"));
+    let mut document = if meta_path.exists() {
+        manifest::read(&meta_path)?
+    } else {
+        serde_json::json!({ "meta_version": manifest::CURRENT_VERSION, "projects": {} })
+    };
+    let Some(fields) = document.as_object_mut() else {
+        return Err("Manifest must be an object".to_string());
+    };
+    let known: Vec<String> = ["projects", PENDING_KEY]
+        .iter()
+        .filter_map(|section| fields.get(*section)?.as_object())
+        .flat_map(|entries| entries.keys().cloned())
+        .collect();
+    let section = if quarantine { PENDING_KEY } else { "projects" };
+    let Some(declared) = fields
+        .entry(section)
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+    else {
+        return Err(format!("\"{section}\" in .meta must be an object"));
+    };
+
+    let mut added = Vec::new();
+    let mut skipped = Vec::new();
+    let mut rejected = Vec::new();
+    for project in projects {
+        if let Err(reason) = check_imported(project, cwd) {
+            rejected.push(format!("{}: {reason}", project.name));
+            continue;
+        }
+        if known.contains(&project.name) {
+            skipped.push(project.name.as_str());
+            continue;
+        }
+        let mut entry = Map::new();
+        entry.insert("repo".to_string(), project.url.clone().into());
+        if project.path != project.name {
+            entry.insert("path".to_string(), project.path.clone().into());
+        }
+        if !project.tags.is_empty() {
+            entry.insert("tags".to_string(), project.tags.clone().into());
+        }
+        if let Some(revision) = &project.revision {
+            entry.insert("ref".to_string(), revision.clone().into());
+        }
+        declared.insert(project.name.clone(), Value::Object(entry));
+        added.push(project.name.as_str());
+    }
+
+    let verb = match (options.dry_run, quarantine) {
+        (true, false) => "Dry run: would import",
+        (true, true) => "Dry run: would quarantine",
+        (false, false) => "Imported",
+        (false, true) => "Quarantined",
+    };
+    let mut lines = vec![format!(
+        "{verb} {} project(s) from {source}{}",
+        added.len(),
+        if added.is_empty() {
+            ".".to_string()
+        } else {
+            format!(": {}.", added.join(", "))
+        }
+    )];
+    if !skipped.is_empty() {
+        lines.push(format!("Already in .meta: {}", skipped.join(", ")));
+    }
+    if !rejected.is_empty() {
+        lines.push(format!(
+            "Rejected {} project(s):\n{}",
+            rejected.len(),
+            rejected.join("\n")
+        ));
+    }
+    if !options.dry_run && !added.is_empty() {
+        manifest::write(&meta_path, &document)?;
+    }
+    let added = added.into_iter().map(str::to_string).collect();
+    Ok((lines, added))
+}
+
+/// Apply `add`'s checks to an entry read from a foreign manifest: a URL git
+/// can clone and won't read as an option, and a name and path that stay
+/// inside the workspace (the name also names its quarantine directory)
+fn check_imported(project: &Imported, cwd: &Path) -> Result<(), String> {
+    validate_url(&project.url, cwd)?;
+    for (what, value) in [("name", &project.name), ("path", &project.path)] {
+        if !is_relative_path(value) {
+            return Err(format!(
+                "invalid {what} '{value}': must be relative and stay inside the workspace"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Clone the quarantined projects named in `added` into [`QUARANTINE_DIR`],
+/// returning a line per failure and the next step
+fn clone_for_review(projects: &[Imported], added: &[String], cwd: &Path) -> Vec<String> {
+    if added.is_empty() {
+        return Vec::new();
+    }
+    let mut lines = Vec::new();
+    if let Err(e) = ignore_quarantine(cwd) {
+        lines.push(format!("Failed to update .gitignore: {e}"));
+    }
+    for project in projects.iter().filter(|p| added.contains(&p.name)) {
+        let dir = format!("{QUARANTINE_DIR}/{}", project.name);
+        if cwd.join(&dir).exists() {
+            continue;
+        }
+        if let Err(e) = git::run_network(cwd, &["clone", "-q", "--", &project.url, &dir]) {
+            lines.push(format!("Cloning {} for review failed: {e}", project.name));
+        }
+    }
+    lines.push(format!(
+        "Review them in {QUARANTINE_DIR}/, then run 'meta project approve <name>'."
+    ));
+    lines
+}
+
+/// Make sure the workspace's .gitignore covers [`QUARANTINE_DIR`]
+fn ignore_quarantine(cwd: &Path) -> std::io::Result<()> {
+    let path = cwd.join(".gitignore");
+    let mut content = std::fs::read_to_string(&path).unwrap_or_default();
+    let entry = format!("/{QUARANTINE_DIR}/");
+    if content.lines().any(|line| line.trim() == entry) {
+        return Ok(());
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&entry);
+    content.push('\n');
+    std::fs::write(&path, content)
+}
+
+/// A `[submodule]` section of .gitmodules
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Submodule {
+    /// The section name, which keys the submodule's config and git dir
+    id: String,
+    project: Imported,
+}
+
+/// Name each project after the last component of its path, or the whole
+/// path where two projects would share a name
+fn assign_names<'a>(projects: impl IntoIterator<Item = &'a mut Imported>) {
+    let mut projects: Vec<&mut Imported> = projects.into_iter().collect();
+    let short = |path: &str| path.rsplit('/').next().unwrap_or(path).to_string();
+    let names: Vec<String> = projects.iter().map(|p| short(&p.path)).collect();
+    for (project, name) in projects.iter_mut().zip(&names) {
+        project.name = if names.iter().filter(|n| *n == name).count() > 1 {
+            project.path.clone()
+        } else {
+            name.clone()
+        };
+    }
+}
+
+/// The submodules declared in `cwd/.gitmodules`, sorted by path
+///
+/// Projects are named by [`assign_names`]. Relative URLs are resolved
+/// against the superproject's origin, as git does.
+fn read_gitmodules(cwd: &Path) -> Result<Vec<Submodule>, String> {
+    if !cwd.join(".gitmodules").is_file() {
+        return Err(format!("No .gitmodules found in {}", cwd.display()));
+    }
+    let listing = git::output(
+        cwd,
+        &[
+            "config",
+            "-f",
+            ".gitmodules",
+            "--get-regexp",
+            r"^submodule\..*\.(path|url)$",
+        ],
+    )
+    .unwrap_or_default();
+    let mut sections: Vec<(String, Option<String>, Option<String>)> = Vec::new();
+    for line in listing.lines() {
+        let Some((key, value)) = line.split_once(' ') else {
+            continue;
+        };
+        let Some((id, field)) = key
+            .strip_prefix("submodule.")
+            .and_then(|rest| rest.rsplit_once('.'))
+        else {
+            continue;
+        };
+        let index = match sections.iter().position(|(other, _, _)| other == id) {
+            Some(index) => index,
+            None => {
+                sections.push((id.to_string(), None, None));
+                sections.len() - 1
+            }
+        };
+        match field {
+            "path" => sections[index].1 = Some(value.replace('\\', "/")),
+            _ => sections[index].2 = Some(value.to_string()),
+        }
+    }
+
+    let origin = git::remote_url(cwd, "origin");
+    let mut submodules = Vec::new();
+    for (id, path, url) in sections {
+        let (Some(path), Some(url)) = (path, url) else {
+            return Err(format!(
+                "Submodule '{id}' in .gitmodules needs a path and a url"
+            ));
+        };
+        let url =
+            resolve_url(&url, origin.as_deref()).map_err(|e| format!("Submodule '{id}': {e}"))?;
+        let path = path.trim_end_matches('/').to_string();
+        submodules.push(Submodule {
+            id,
+            project: Imported {
+                name: String::new(),
+                path,
+                url,
+                tags: Vec::new(),
+                revision: None,
+            },
+        });
+    }
+    submodules.sort_by(|a, b| a.project.path.cmp(&b.project.path));
+    assign_names(submodules.iter_mut().map(|s| &mut s.project));
+    Ok(submodules)
+}
+
+/// What a Google `repo` manifest (and its includes) declares
+#[derive(Debug, Default)]
+struct RepoManifest {
+    /// Remote name → fetch base
+    remotes: HashMap<String, String>,
+    default_remote: Option<String>,
+    /// `(name, path, remote, groups)` of each `<project>`
+    projects: Vec<(String, String, Option<String>, Vec<String>)>,
+    removed: Vec<String>,
+}
+
+/// The projects of the `repo` manifest at `path`, sorted by path
+///
+/// A project's URL is its remote's fetch base joined with its name; a
+/// relative fetch base (e.g. `..`) is resolved against the origin of the
+/// repository holding the manifest, as `repo` does. Groups become tags.
+fn read_repo_manifest(path: &Path) -> Result<Vec<Imported>, String> {
+    let mut manifest = RepoManifest::default();
+    collect_repo_manifest(path, &mut manifest, 0)?;
+    let manifest_url = path.parent().and_then(|dir| git::remote_url(dir, "origin"));
+
+    let mut projects = Vec::new();
+    for (name, project_path, remote, groups) in &manifest.projects {
+        if manifest.removed.contains(name) {
+            continue;
+        }
+        let Some(remote) = remote.as_ref().or(manifest.default_remote.as_ref()) else {
+            return Err(format!(
+                "Project '{name}' has no remote and there is no <default remote>"
+            ));
+        };
+        let Some(fetch) = manifest.remotes.get(remote) else {
+            return Err(format!(
+                "Project '{name}' uses undeclared remote '{remote}'"
+            ));
+        };
+        let base = fetch_base(fetch, manifest_url.as_deref())
+            .map_err(|e| format!("Remote '{remote}': {e}"))?;
+        let separator = if base.ends_with(':') { "" } else { "/" };
+        projects.push(Imported {
+            name: String::new(),
+            path: project_path.clone(),
+            url: format!("{base}{separator}{name}"),
+            tags: groups.clone(),
+            revision: None,
+        });
+    }
+    projects.sort_by(|a, b| a.path.cmp(&b.path));
+    assign_names(projects.iter_mut());
+    Ok(projects)
+}
+
+/// Read the manifest at `path` into `manifest`, following `<include>`s
+fn collect_repo_manifest(
+    path: &Path,
+    manifest: &mut RepoManifest,
+    depth: usize,
+) -> Result<(), String> {
+    if depth > 8 {
+        return Err(format!("Too many nested <include>s at {}", path.display()));
+    }
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let document = roxmltree::Document::parse(&content)
+        .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+    let root = document.root_element();
+    if root.tag_name().name() != "manifest" {
+        return Err(format!("{} is not a repo manifest", path.display()));
+    }
+    for node in root.children().filter(|n| n.is_element()) {
+        let attr = |name: &str| node.attribute(name).map(str::to_string);
+        match node.tag_name().name() {
+            "remote" => {
+                if let (Some(name), Some(fetch)) = (attr("name"), attr("fetch")) {
+                    manifest.remotes.insert(name, fetch);
+                }
+            }
+            "default" => {
+                if let Some(remote) = attr("remote") {
+                    manifest.default_remote = Some(remote);
+                }
+            }
+            "project" => {
+                let Some(name) = attr("name") else {
+                    return Err(format!("A <project> in {} has no name", path.display()));
+                };
+                let groups = attr("groups")
+                    .unwrap_or_default()
+                    .split([',', ' '])
+                    .filter(|g| !g.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                let project_path = attr("path").unwrap_or_else(|| name.clone());
+                manifest
+                    .projects
+                    .push((name, project_path, attr("remote"), groups));
+            }
+            "remove-project" => manifest.removed.extend(attr("name")),
+            "include" => {
+                let Some(name) = attr("name") else {
+                    continue;
+                };
+                let included = path.parent().unwrap_or(Path::new(".")).join(name);
+                collect_repo_manifest(&included, manifest, depth + 1)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// A Zephyr `west.yml`; only what's needed to locate the projects
+#[derive(Debug, Deserialize)]
+struct WestFile {
+    manifest: WestManifest,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct WestManifest {
+    defaults: WestDefaults,
+    remotes: Vec<WestRemote>,
+    projects: Vec<WestProject>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct WestDefaults {
+    remote: Option<String>,
+    revision: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WestRemote {
+    name: String,
+    #[serde(rename = "url-base")]
+    url_base: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WestProject {
+    name: String,
+    url: Option<String>,
+    remote: Option<String>,
+    #[serde(rename = "repo-path")]
+    repo_path: Option<String>,
+    revision: Option<String>,
+    path: Option<String>,
+    #[serde(default)]
+    groups: Vec<String>,
+}
+
+/// The projects of the `west.yml` at `path`, in manifest order
+///
+/// West names projects uniquely, so they keep their names. A project's URL
+/// is its `url`, or its remote's `url-base` joined with its `repo-path`
+/// (default: its name). Its revision, or the default revision, is kept;
+/// groups become tags. Projects imported from other manifests (`import:`)
+/// aren't followed.
+fn read_west(path: &Path) -> Result<Vec<Imported>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let west: WestFile = serde_yaml_ng::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+    let manifest = west.manifest;
+
+    let mut projects = Vec::new();
+    for project in manifest.projects {
+        let name = project.name;
+        let url = match project.url {
+            Some(url) => url,
+            None => {
+                let Some(remote) = project.remote.or(manifest.defaults.remote.clone()) else {
+                    return Err(format!(
+                        "Project '{name}' has no url or remote, and there is no default remote"
+                    ));
+                };
+                let Some(base) = manifest.remotes.iter().find(|r| r.name == remote) else {
+                    return Err(format!(
+                        "Project '{name}' uses undeclared remote '{remote}'"
+                    ));
+                };
+                let repo_path = project.repo_path.as_deref().unwrap_or(&name);
+                format!("{}/{repo_path}", base.url_base.trim_end_matches('/'))
+            }
+        };
+        projects.push(Imported {
+            path: project.path.unwrap_or_else(|| name.clone()),
+            name,
+            url,
+            tags: project.groups,
+            revision: project.revision.or(manifest.defaults.revision.clone()),
+        });
+    }
+    Ok(projects)
+}
+
+/// A tsrc `manifest.yml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct TsrcManifest {
+    repos: Vec<TsrcRepo>,
+    groups: BTreeMap<String, TsrcGroup>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TsrcRepo {
+    dest: String,
+    url: Option<String>,
+    #[serde(default)]
+    remotes: Vec<TsrcRemote>,
+    branch: Option<String>,
+    tag: Option<String>,
+    sha1: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TsrcRemote {
+    name: String,
+    url: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct TsrcGroup {
+    repos: Vec<String>,
+}
+
+/// The repos of the tsrc `manifest.yml` at `path`, sorted by path
+///
+/// A repo's URL is its `url`, or else its `origin` (or first) remote. The
+/// groups listing a repo become its tags, and its pin (`sha1`, else `tag`,
+/// else `branch`) is kept as the revision. Projects are named by
+/// [`assign_names`].
+fn read_tsrc(path: &Path) -> Result<Vec<Imported>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let manifest: TsrcManifest = serde_yaml_ng::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+
+    let mut projects = Vec::new();
+    for repo in manifest.repos {
+        let remote = repo
+            .remotes
+            .iter()
+            .find(|r| r.name == "origin")
+            .or(repo.remotes.first());
+        let Some(url) = repo.url.or(remote.map(|r| r.url.clone())) else {
+            return Err(format!("Repo '{}' has no url or remotes", repo.dest));
+        };
+        let tags = manifest
+            .groups
+            .iter()
+            .filter(|(_, group)| group.repos.contains(&repo.dest))
+            .map(|(name, _)| name.clone())
+            .collect();
+        projects.push(Imported {
+            name: String::new(),
+            path: repo.dest.trim_end_matches('/').to_string(),
+            url,
+            tags,
+            revision: repo.sha1.or(repo.tag).or(repo.branch),
+        });
+    }
+    projects.sort_by(|a, b| a.path.cmp(&b.path));
+    assign_names(projects.iter_mut());
+    Ok(projects)
+}
+
+/// A `gitman.yml`
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct GitmanConfig {
+    location: String,
+    sources: Vec<GitmanSource>,
+    groups: Vec<GitmanGroup>,
+}
+
+impl Default for GitmanConfig {
+    fn default() -> Self {
+        GitmanConfig {
+            location: "gitman_sources".to_string(),
+            sources: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GitmanSource {
+    repo: String,
+    name: Option<String>,
+    rev: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitmanGroup {
+    name: String,
+    #[serde(default)]
+    members: Vec<String>,
+}
+
+/// The sources of the `gitman.yml` at `path`, in file order
+///
+/// Sources keep their gitman names (by default the repo's last path
+/// component) and are placed under `location` as gitman places them. The
+/// groups listing a source become its tags, and its `rev` is kept as the
+/// revision.
+fn read_gitman(path: &Path) -> Result<Vec<Imported>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    let config: GitmanConfig = serde_yaml_ng::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+    let location = config.location.trim_end_matches('/');
+
+    let mut projects = Vec::new();
+    for source in config.sources {
+        let name = source.name.unwrap_or_else(|| {
+            let last = source.repo.trim_end_matches('/').rsplit(['/', ':']).next();
+            let last = last.unwrap_or(&source.repo);
+            last.strip_suffix(".git").unwrap_or(last).to_string()
+        });
+        let tags = config
+            .groups
+            .iter()
+            .filter(|group| group.members.contains(&name))
+            .map(|group| group.name.clone())
+            .collect();
+        projects.push(Imported {
+            path: format!("{location}/{name}"),
+            name,
+            url: source.repo,
+            tags,
+            revision: source.rev,
+        });
+    }
+    Ok(projects)
+}
+
+/// The fetch base of a `repo` remote; a relative `fetch` is resolved
+/// against the manifest repository's URL like a relative link
+fn fetch_base(fetch: &str, manifest_url: Option<&str>) -> Result<String, String> {
+    if !fetch.starts_with('.') {
+        return Ok(fetch.trim_end_matches('/').to_string());
+    }
+    let Some(manifest_url) = manifest_url else {
+        return Err(format!(
+            "relative fetch '{fetch}' needs the manifest repository's origin remote"
+        ));
+    };
+    // Relative to the directory holding the manifest repository
+    let parent = manifest_url
+        .trim_end_matches('/')
+        .rsplit_once(['/', ':'])
+        .map_or(manifest_url, |(parent, _)| parent);
+    let relative = format!("{}/", fetch.trim_end_matches('/'));
+    resolve_url(&relative, Some(parent)).map(|url| url.trim_end_matches('/').to_string())
+}
+
+/// `url` with a leading `./` or `../` resolved against `base` (the
+/// superproject's remote URL, taken as a directory)
+fn resolve_url(url: &str, base: Option<&str>) -> Result<String, String> {
+    if !url.starts_with("./") && !url.starts_with("../") {
+        return Ok(url.to_string());
+    }
+    let Some(base) = base else {
+        return Err(format!(
+            "relative URL '{url}' needs the superproject's origin remote"
+        ));
+    };
+    let mut base = base.trim_end_matches('/').to_string();
+    let mut rest = url;
+    loop {
+        if let Some(next) = rest.strip_prefix("./") {
+            rest = next;
+        } else if let Some(next) = rest.strip_prefix("../") {
+            // Drop one component; scp-style `host:repo` splits at the colon
+            let Some(cut) = base.rfind(['/', ':']) else {
+                return Err(format!("relative URL '{url}' leaves origin '{base}'"));
+            };
+            base.truncate(cut + usize::from(base.as_bytes()[cut] == b':'));
+            rest = next;
+        } else {
+            break;
+        }
+    }
+    if base.ends_with(':') {
+        Ok(format!("{base}{rest}"))
+    } else {
+        Ok(format!("{base}/{rest}"))
+    }
+}
+
+/// Deinit `submodule` and remove it from the index and .gitmodules
+fn remove_submodule(cwd: &Path, submodule: &Submodule) -> Result<(), String> {
+    let path = submodule.project.path.as_str();
+    git::run(cwd, &["submodule", "deinit", "-q", "-f", "--", path]).map_err(|e| e.to_string())?;
+    git::run(cwd, &["rm", "-q", "-f", "--", path]).map_err(|e| e.to_string())?;
+    // deinit keeps the submodule's repository under .git/modules
+    if let Some(modules) = git::output(cwd, &["rev-parse", "--git-path", "modules"]) {
+        let git_dir = cwd.join(modules).join(&submodule.id);
+        if git_dir.is_dir() {
+            std::fs::remove_dir_all(&git_dir)
+                .map_err(|e| format!("Failed to remove {}: {e}", git_dir.display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{run_git, Fixture};
+
+    #[test]
+    fn test_resolve_url() {
+        let base = Some("https://github.com/org/super.git");
+        assert_eq!(
+            resolve_url("../api.git", base).unwrap(),
+            "https://github.com/org/api.git"
+        );
+        assert_eq!(
+            resolve_url("./docs", base).unwrap(),
+            "https://github.com/org/super.git/docs"
+        );
+        assert_eq!(
+            resolve_url("../api.git", Some("git@github.com:super.git")).unwrap(),
+            "git@github.com:api.git"
+        );
+        assert_eq!(resolve_url("git@h:a.git", None).unwrap(), "git@h:a.git");
+        assert!(resolve_url("../api.git", None).is_err());
+    }
+
+    #[test]
+    fn test_fetch_base() {
+        let manifest = Some("https://android.googlesource.com/platform/manifest");
+        assert_eq!(
+            fetch_base("..", manifest).unwrap(),
+            "https://android.googlesource.com"
+        );
+        assert_eq!(
+            fetch_base(".", manifest).unwrap(),
+            "https://android.googlesource.com/platform"
+        );
+        assert_eq!(
+            fetch_base("ssh://git.example.com/", None).unwrap(),
+            "ssh://git.example.com"
+        );
+        assert!(fetch_base("..", None).is_err());
+    }
+
+    #[test]
+    fn test_import_from_repo_manifest() {
+        let fixture = Fixture::new();
+        let workspace = fixture.workspace();
+        fixture.manifest(&[]);
+        std::fs::write(
+            workspace.join("default.xml"),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest>
+  <remote name="aosp" fetch="https://android.googlesource.com/" />
+  <remote name="corp" fetch="git@git.corp.example.com:" />
+  <default revision="main" remote="aosp" />
+  <project path="build/make" name="platform/build" groups="pdk,tools" />
+  <project path="vendor/make" name="vendor/build" remote="corp" />
+  <project path="external/old" name="platform/external/old" />
+  <include name="local.xml" />
+</manifest>"#,
+        )
+        .unwrap();
+        std::fs::write(
+            workspace.join("local.xml"),
+            r#"<manifest>
+  <project name="platform/art" />
+  <remove-project name="platform/external/old" />
+</manifest>"#,
+        )
+        .unwrap();
+
+        match fixture.run("project import", &["--from-repo-manifest", "default.xml"]) {
+            CommandResult::Message(msg) => assert_eq!(
+                msg,
+                "Imported 3 project(s) from default.xml: build/make, art, vendor/make."
+            ),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        let document = manifest::read(&workspace.join(".meta")).unwrap();
+        assert_eq!(
+            document["projects"]["build/make"],
+            serde_json::json!({
+                "repo": "https://android.googlesource.com/platform/build",
+                "tags": ["pdk", "tools"]
+            })
+        );
+        assert_eq!(
+            document["projects"]["vendor/make"]["repo"],
+            "git@git.corp.example.com:vendor/build"
+        );
+        assert_eq!(
+            document["projects"]["art"],
+            serde_json::json!({
+                "repo": "https://android.googlesource.com/platform/art",
+                "path": "platform/art"
+            })
+        );
+    }
+
+    #[test]
+    fn test_import_from_west() {
+        let fixture = Fixture::new();
+        let workspace = fixture.workspace();
+        fixture.manifest(&[]);
+        std::fs::write(
+            workspace.join("west.yml"),
+            r#"manifest:
+  defaults:
+    remote: upstream
+    revision: main
+  remotes:
+    - name: upstream
+      url-base: https://github.com/zephyrproject-rtos
+  projects:
+    - name: cmsis
+      revision: 4b96cbb174678dcd3ca86e11e1f24bc5f8726da0
+      path: modules/hal/cmsis
+      groups: [hal]
+    - name: net-tools
+      repo-path: net-tools-fork
+    - name: vendor
+      url: git@git.corp.example.com:vendor.git
+      revision: v1.2.0
+  self:
+    path: zephyr
+"#,
+        )
+        .unwrap();
+
+        match fixture.run("project import", &["--from-west", "west.yml"]) {
+            CommandResult::Message(msg) => assert_eq!(
+                msg,
+                "Imported 3 project(s) from west.yml: cmsis, net-tools, vendor."
+            ),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        let document = manifest::read(&workspace.join(".meta")).unwrap();
+        assert_eq!(
+            document["projects"]["cmsis"],
+            serde_json::json!({
+                "repo": "https://github.com/zephyrproject-rtos/cmsis",
+                "path": "modules/hal/cmsis",
+                "tags": ["hal"],
+                "ref": "4b96cbb174678dcd3ca86e11e1f24bc5f8726da0"
+            })
+        );
+        assert_eq!(
+            document["projects"]["net-tools"],
+            serde_json::json!({
+                "repo": "https://github.com/zephyrproject-rtos/net-tools-fork",
+                "ref": "main"
+            })
+        );
+        assert_eq!(
+            document["projects"]["vendor"]["repo"],
+            "git@git.corp.example.com:vendor.git"
+        );
+        assert_eq!(document["projects"]["vendor"]["ref"], "v1.2.0");
+
+        // The ref is an extension meta reads past
+        let (projects, _) = manifest::projects(&workspace.join(".meta")).unwrap();
+        assert_eq!(projects.len(), 3);
+    }
+
+    #[test]
+    fn test_import_from_tsrc() {
+        let fixture = Fixture::new();
+        let workspace = fixture.workspace();
+        fixture.manifest(&[]);
+        std::fs::write(
+            workspace.join("manifest.yml"),
+            r#"repos:
+  - dest: foo
+    url: git@gitlab.local:proj/foo
+    branch: next
+  - dest: libs/bar
+    remotes:
+      - name: upstream
+        url: https://github.com/org/bar
+      - name: origin
+        url: git@gitlab.local:proj/bar
+    tag: v1.0
+groups:
+  default:
+    repos: [foo, libs/bar]
+  libs:
+    repos: [libs/bar]
+"#,
+        )
+        .unwrap();
+
+        match fixture.run("project import", &["--from-tsrc", "manifest.yml"]) {
+            CommandResult::Message(msg) => {
+                assert_eq!(msg, "Imported 2 project(s) from manifest.yml: foo, bar.")
+            }
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        let document = manifest::read(&workspace.join(".meta")).unwrap();
+        assert_eq!(
+            document["projects"]["foo"],
+            serde_json::json!({
+                "repo": "git@gitlab.local:proj/foo",
+                "tags": ["default"],
+                "ref": "next"
+            })
+        );
+        assert_eq!(
+            document["projects"]["bar"],
+            serde_json::json!({
+                "repo": "git@gitlab.local:proj/bar",
+                "path": "libs/bar",
+                "tags": ["default", "libs"],
+                "ref": "v1.0"
+            })
+        );
+    }
+
+    #[test]
+    fn test_import_rejects_unsafe_entries() {
+        let fixture = Fixture::new();
+        let workspace = fixture.workspace();
+        fixture.manifest(&[]);
+        std::fs::write(
+            workspace.join("manifest.yml"),
+            r#"repos:
+  - dest: foo
+    url: git@gitlab.local:proj/foo
+  - dest: ../../outside
+    url: git@gitlab.local:proj/outside
+  - dest: /tmp/absolute
+    url: git@gitlab.local:proj/absolute
+  - dest: sneaky
+    url: --upload-pack=touch pwned:x
+"#,
+        )
+        .unwrap();
+
+        match fixture.run("project import", &["--from-tsrc", "manifest.yml"]) {
+            CommandResult::Message(msg) => {
+                assert!(
+                    msg.starts_with("Imported 1 project(s) from manifest.yml: foo."),
+                    "{msg}"
+                );
+                assert!(msg.contains("Rejected 3 project(s):"), "{msg}");
+                assert!(msg.contains("invalid path '../../outside'"), "{msg}");
+                assert!(msg.contains("invalid path '/tmp/absolute'"), "{msg}");
+                assert!(
+                    msg.contains("Invalid repository URL '--upload-pack"),
+                    "{msg}"
+                );
+            }
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        let document = manifest::read(&workspace.join(".meta")).unwrap();
+        assert_eq!(document["projects"].as_object().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_import_from_gitman() {
+        let fixture = Fixture::new();
+        let workspace = fixture.workspace();
+        fixture.manifest(&[]);
+        std::fs::write(
+            workspace.join("gitman.yml"),
+            r#"location: deps
+sources:
+  - repo: https://github.com/jacebrowning/gitman-demo
+    name: demo
+    rev: example-branch
+    link: ''
+  - repo: git@github.com:org/tools.git
+    rev: 7bd138fe7359561a8c2ff9d195dff238794ccc04
+groups:
+  - name: minimal
+    members: [demo]
+"#,
+        )
+        .unwrap();
+
+        match fixture.run("project import", &["--from-gitman", "gitman.yml"]) {
+            CommandResult::Message(msg) => {
+                assert_eq!(msg, "Imported 2 project(s) from gitman.yml: demo, tools.")
+            }
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        let document = manifest::read(&workspace.join(".meta")).unwrap();
+        assert_eq!(
+            document["projects"]["demo"],
+            serde_json::json!({
+                "repo": "https://github.com/jacebrowning/gitman-demo",
+                "path": "deps/demo",
+                "tags": ["minimal"],
+                "ref": "example-branch"
+            })
+        );
+        assert_eq!(document["projects"]["tools"]["path"], "deps/tools");
+        assert_eq!(
+            document["projects"]["tools"]["ref"],
+            "7bd138fe7359561a8c2ff9d195dff238794ccc04"
+        );
+    }
+
+    #[test]
+    fn test_import_from_gitmodules_and_deinit() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        let workspace = fixture.workspace();
+        run_git(&workspace, &["init", "-q", "-b", "main"]);
+        for (upstream, path) in [(&api, "libs/api"), (&web, "web")] {
+            run_git(
+                &workspace,
+                &[
+                    "-c",
+                    "protocol.file.allow=always",
+                    "submodule",
+                    "add",
+                    "-q",
+                    &upstream.to_string_lossy(),
+                    path,
+                ],
+            );
+        }
+        run_git(
+            &workspace,
+            &[
+                "-c",
+                "user.name=t",
+                "-c",
+                "user.email=t@t",
+                "commit",
+                "-qm",
+                "subs",
+            ],
+        );
+
+        match fixture.run("project import", &["--from-gitmodules", "--deinit"]) {
+            CommandResult::Message(msg) => assert_eq!(
+                msg,
+                "Imported 2 project(s) from .gitmodules: api, web.\n\
+                 Removed 2 submodule(s). Run 'meta project check --fix' to clone them as projects."
+            ),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        let document = manifest::read(&workspace.join(".meta")).unwrap();
+        assert_eq!(
+            document["projects"]["api"],
+            serde_json::json!({"repo": api.to_string_lossy(), "path": "libs/api"})
+        );
+        assert_eq!(
+            document["projects"]["web"]["repo"],
+            web.to_string_lossy().as_ref()
+        );
+        assert!(!workspace.join("libs/api").exists());
+        assert!(!workspace.join(".git/modules/web").exists());
+        let gitmodules = std::fs::read_to_string(workspace.join(".gitmodules")).unwrap_or_default();
+        assert!(!gitmodules.contains("submodule"), "{gitmodules}");
+    }
+}
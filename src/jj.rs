@@ -0,0 +1,84 @@
+//! Jujutsu (jj) colocated workspaces
+//!
+//! A colocated repo has a `.jj` directory next to `.git`. jj keeps git's
+//! HEAD detached at the parent of its working-copy commit and records every
+//! change in its operation log, so git commands that move HEAD or rewrite
+//! the working tree (`checkout`, `merge`, `reset`) leave jj out of step with
+//! the repo. Commands that would run those go through [`new`] instead; reads
+//! and fetches stay with git, which jj imports on its next command.
+//!
+//! Projects marked `"vcs": "jj"` in .meta are cloned with
+//! `jj git clone --colocate`.
+
+use crate::git;
+use std::path::Path;
+use std::process::Command;
+
+/// Whether the clone at `dir` is managed by jj
+pub(crate) fn is_colocated(dir: &Path) -> bool {
+    dir.join(".jj").is_dir()
+}
+
+/// Clone `url` into `path` (relative to `cwd`) as a colocated jj repo
+pub(crate) fn clone(url: &str, path: &str, cwd: &Path) -> anyhow::Result<()> {
+    run(
+        cwd,
+        &["git", "clone", "--colocate", "--quiet", "--", url, path],
+    )
+}
+
+/// Start a new working-copy change on top of `reference`, the jj way of
+/// checking something out
+///
+/// A branch that only exists on origin is addressed as jj's remote
+/// bookmark `<branch>@origin`.
+pub(crate) fn new(dir: &Path, reference: &str) -> anyhow::Result<()> {
+    let revision = if !git::ref_exists(dir, &format!("refs/heads/{reference}"))
+        && git::ref_exists(dir, &format!("refs/remotes/origin/{reference}"))
+    {
+        format!("{reference}@origin")
+    } else {
+        reference.to_string()
+    };
+    run(dir, &["new", "--quiet", &revision])
+}
+
+/// Whether jj's checked-out commit (git's HEAD) is the commit `reference`
+/// names, whether that's a local or origin branch, a tag or a commit
+pub(crate) fn is_at(dir: &Path, reference: &str) -> bool {
+    let resolve = |name: &str| {
+        git::output(
+            dir,
+            &["rev-parse", "-q", "--verify", &format!("{name}^{{commit}}")],
+        )
+    };
+    let Some(head) = resolve("HEAD") else {
+        return false;
+    };
+    [
+        format!("refs/heads/{reference}"),
+        format!("refs/remotes/origin/{reference}"),
+        reference.to_string(),
+    ]
+    .iter()
+    .find_map(|name| resolve(name))
+        == Some(head)
+}
+
+/// Run jj in `dir`, surfacing stderr in the error when it fails
+fn run(dir: &Path, args: &[&str]) -> anyhow::Result<()> {
+    let output = Command::new("jj")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| anyhow::anyhow!("failed to run jj (is it installed?): {e}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        anyhow::bail!(
+            "jj {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+}
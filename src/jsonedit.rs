@@ -0,0 +1,611 @@
+//! Format-preserving edits to JSON(C) manifests
+//!
+//! Re-serializing a parsed `.meta` loses its key order, indentation and
+//! comments. [`apply`] instead turns the difference between the document on
+//! disk and the one to write into edits of the original text:
+//!
+//! - a changed value is replaced where it stands (objects are edited
+//!   member by member, so siblings stay untouched);
+//! - a removed member is cut out together with its leading comments;
+//! - a new member is appended to its object, in the object's indentation.
+//!
+//! Everything not affected, comments included, is kept byte for byte.
+//!
+//! [`arrange`] reorders the members of a top-level object the same way,
+//! moving each member's text and comments as a whole.
+
+use crate::manifest::strip_jsonc;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// A value in the text: its byte span, and the members if it is an object
+struct Node {
+    start: usize,
+    end: usize,
+    members: Option<Vec<Member>>,
+}
+
+/// An object member; `start` is where its key begins
+struct Member {
+    key: String,
+    start: usize,
+    value: Node,
+}
+
+/// `original` (the text of `old`) edited to hold `new`
+///
+/// Returns `None` when the text can't be edited in place (it isn't a JSON
+/// object, or the result wouldn't parse back to `new`); the caller then
+/// writes the document from scratch.
+pub(crate) fn apply(original: &str, old: &Value, new: &Value) -> Option<String> {
+    if old == new {
+        return Some(original.to_string());
+    }
+    let stripped = strip_jsonc(original);
+    let mut parser = Parser {
+        text: stripped.as_bytes(),
+        pos: 0,
+    };
+    let root = parser.value()?;
+    let first = root.members.as_ref()?.first();
+    let unit = first
+        .map(|member| line_indent(&stripped, member.start))
+        .filter(|indent| !indent.is_empty())
+        .unwrap_or("  ");
+    let editor = Editor {
+        original,
+        stripped: &stripped,
+        unit,
+    };
+    let edited = format!(
+        "{}{}{}",
+        &original[..root.start],
+        editor.value(&root, old, new, "", true)?,
+        &original[root.end..]
+    );
+    let reparsed: Value = serde_json::from_str(&strip_jsonc(&edited)).ok()?;
+    (reparsed == *new).then_some(edited)
+}
+
+/// A run of members, written under a `// --- header ---` comment if it has one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Section {
+    pub header: Option<String>,
+    pub keys: Vec<String>,
+}
+
+/// `original` with the members of its top-level `object` in the order of
+/// `sections`
+///
+/// Header comments from an earlier arrangement are replaced, and blank lines
+/// between members dropped; other comments move with their member. Members
+/// no section names keep their relative order at the end. Returns `None`
+/// when the text can't be rearranged.
+pub(crate) fn arrange(original: &str, object: &str, sections: &[Section]) -> Option<String> {
+    let stripped = strip_jsonc(original);
+    let mut parser = Parser {
+        text: stripped.as_bytes(),
+        pos: 0,
+    };
+    let root = parser.value()?;
+    let node = &root
+        .members
+        .as_ref()?
+        .iter()
+        .find(|m| m.key == object)?
+        .value;
+    let members = node.members.as_ref()?;
+    let Some(first) = members.first() else {
+        return Some(original.to_string());
+    };
+    let inner_start = node.start + 1;
+    let multiline = stripped[inner_start..first.start].contains('\n');
+    let editor = Editor {
+        original,
+        stripped: &stripped,
+        unit: "  ",
+    };
+
+    // (key, comment lines before it, body, comment after its comma)
+    let mut pieces = Vec::new();
+    let mut boundary = inner_start;
+    for member in members {
+        let lead = &original[boundary..member.start];
+        let end = member.value.end;
+        let (after_comma, next_boundary) = editor.separator(end, multiline);
+        // The last member has no comma, but a comment after it on its line
+        // is still its own
+        let (comment, next_boundary) = match after_comma {
+            Some(at) => (&original[at..next_boundary], next_boundary),
+            None => {
+                let line_end = stripped[end..]
+                    .find('\n')
+                    .map_or(stripped.len(), |o| end + o);
+                if multiline && stripped[end..line_end].trim().is_empty() {
+                    (&original[end..line_end], line_end)
+                } else {
+                    ("", next_boundary)
+                }
+            }
+        };
+        boundary = next_boundary;
+        let comments: Vec<&str> = lead
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !is_header(line))
+            .collect();
+        let body = &original[member.start..end];
+        pieces.push((member.key.as_str(), comments, body, comment));
+    }
+    let tail = &original[boundary..node.end - 1];
+    let indent = line_indent(&stripped, first.start);
+
+    let mut ordered: Vec<(Option<&str>, usize)> = Vec::new();
+    for section in sections {
+        let mut header = section.header.as_deref();
+        for key in &section.keys {
+            if let Some(index) = pieces.iter().position(|(k, ..)| k == key) {
+                ordered.push((header.take(), index));
+            }
+        }
+    }
+    for index in 0..pieces.len() {
+        if !ordered.iter().any(|(_, i)| *i == index) {
+            ordered.push((None, index));
+        }
+    }
+
+    let mut text = String::from("{");
+    for (position, (header, index)) in ordered.iter().enumerate() {
+        let (_, comments, body, comment) = &pieces[*index];
+        if multiline {
+            if let Some(header) = header {
+                if position > 0 {
+                    text.push('\n');
+                }
+                text.push_str(&format!("\n{indent}// --- {header} ---"));
+            }
+            for line in comments {
+                text.push_str(&format!("\n{indent}{line}"));
+            }
+            text.push_str(&format!("\n{indent}"));
+        } else if position > 0 {
+            text.push(' ');
+        }
+        text.push_str(body);
+        if position + 1 < ordered.len() {
+            text.push(',');
+        }
+        text.push_str(comment);
+    }
+    text.push_str(tail);
+    text.push('}');
+
+    let arranged = format!("{}{text}{}", &original[..node.start], &original[node.end..]);
+    let before: Value = serde_json::from_str(&stripped).ok()?;
+    let after: Value = serde_json::from_str(&strip_jsonc(&arranged)).ok()?;
+    (before == after).then_some(arranged)
+}
+
+/// Whether a comment line is a section header written by [`arrange`]
+fn is_header(line: &str) -> bool {
+    line.starts_with("// --- ") && line.ends_with(" ---")
+}
+
+struct Editor<'a> {
+    original: &'a str,
+    stripped: &'a str,
+    /// One level of indentation, as the file writes it
+    unit: &'a str,
+}
+
+impl Editor<'_> {
+    /// The text of `node` (holding `old`) changed to `new`; `indent` is the
+    /// indentation of the line the value starts on
+    fn value(
+        &self,
+        node: &Node,
+        old: &Value,
+        new: &Value,
+        indent: &str,
+        multiline: bool,
+    ) -> Option<String> {
+        if old == new {
+            return Some(self.original[node.start..node.end].to_string());
+        }
+        match (&node.members, old, new) {
+            (Some(members), Value::Object(old), Value::Object(new)) => {
+                self.object(node, members, old, new, indent, multiline)
+            }
+            _ => Some(self.fresh(new, indent, multiline)),
+        }
+    }
+
+    fn object(
+        &self,
+        node: &Node,
+        members: &[Member],
+        old: &Map<String, Value>,
+        new: &Map<String, Value>,
+        indent: &str,
+        multiline: bool,
+    ) -> Option<String> {
+        let inner_start = node.start + 1;
+        let inner_end = node.end - 1;
+        let multiline = match members.first() {
+            Some(first) => self.stripped[inner_start..first.start].contains('\n'),
+            None => multiline,
+        };
+        let member_indent = match members.first() {
+            Some(first) => line_indent(self.stripped, first.start).to_string(),
+            None => format!("{indent}{}", self.unit),
+        };
+
+        // Each member owns the text before its key (back to the previous
+        // separator) and a comment after its comma on the same line. A
+        // trailing comma is blanked by strip_jsonc, so it stays in the tail.
+        let mut pieces: Vec<(String, String, String)> = Vec::new();
+        let mut boundary = inner_start;
+        for member in members {
+            let lead = &self.original[boundary..member.start];
+            let (after_comma, next_boundary) = self.separator(member.value.end, multiline);
+            boundary = next_boundary;
+            let Some(new_value) = new.get(&member.key) else {
+                continue;
+            };
+            let old_value = old.get(&member.key)?;
+            let key_indent = line_indent(self.stripped, member.start);
+            let body = format!(
+                "{}{}",
+                &self.original[member.start..member.value.start],
+                self.value(&member.value, old_value, new_value, key_indent, multiline)?
+            );
+            let comment = after_comma
+                .map(|at| self.original[at..boundary].to_string())
+                .unwrap_or_default();
+            pieces.push((lead.to_string(), body, comment));
+        }
+        let mut tail = self.original[boundary..inner_end].to_string();
+
+        let declared: Vec<&str> = members.iter().map(|m| m.key.as_str()).collect();
+        for (key, value) in new
+            .iter()
+            .filter(|(key, _)| !declared.contains(&key.as_str()))
+        {
+            let lead = if multiline {
+                format!("\n{member_indent}")
+            } else if pieces.is_empty() {
+                String::new()
+            } else {
+                " ".to_string()
+            };
+            let body = format!(
+                "{}: {}",
+                serde_json::to_string(key).ok()?,
+                self.fresh(value, &member_indent, multiline)
+            );
+            pieces.push((lead, body, String::new()));
+        }
+        if members.is_empty()
+            && multiline
+            && self.stripped[inner_start..inner_end].trim().is_empty()
+        {
+            tail = format!("\n{indent}");
+        }
+
+        let mut text = String::from("{");
+        let count = pieces.len();
+        for (index, (lead, body, comment)) in pieces.into_iter().enumerate() {
+            text.push_str(&lead);
+            text.push_str(&body);
+            if index + 1 < count {
+                text.push(',');
+            }
+            text.push_str(&comment);
+        }
+        text.push_str(&tail);
+        text.push('}');
+        Some(text)
+    }
+
+    /// After the value ending at `end`: where the text following its comma
+    /// starts (if there is a comma), and where the next member's text starts
+    ///
+    /// In a multi-line object, a comment after the comma on the same line
+    /// stays with the member.
+    fn separator(&self, end: usize, multiline: bool) -> (Option<usize>, usize) {
+        let bytes = self.stripped.as_bytes();
+        let mut pos = end;
+        while pos < bytes.len() && matches!(bytes[pos], b' ' | b'\t' | b'\r' | b'\n') {
+            pos += 1;
+        }
+        if bytes.get(pos) != Some(&b',') {
+            return (None, end);
+        }
+        let after = pos + 1;
+        if !multiline {
+            return (Some(after), after);
+        }
+        let line_end = self.stripped[after..]
+            .find('\n')
+            .map_or(bytes.len(), |offset| after + offset);
+        if self.stripped[after..line_end].trim().is_empty() {
+            (Some(after), line_end)
+        } else {
+            (Some(after), after)
+        }
+    }
+
+    /// `value` serialized for a line indented by `indent`
+    fn fresh(&self, value: &Value, indent: &str, multiline: bool) -> String {
+        if !multiline {
+            return value.to_string();
+        }
+        let mut buffer = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(self.unit.as_bytes());
+        let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+        if value.serialize(&mut serializer).is_err() {
+            return value.to_string();
+        }
+        String::from_utf8_lossy(&buffer).replace('\n', &format!("\n{indent}"))
+    }
+}
+
+/// The whitespace that starts the line containing `pos`
+fn line_indent(text: &str, pos: usize) -> &str {
+    let line_start = text[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let line = &text[line_start..pos];
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Spans of the values in comment-free JSON text
+struct Parser<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.text.len() && self.text[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.skip_whitespace();
+        (self.text.get(self.pos) == Some(&byte)).then(|| self.pos += 1)
+    }
+
+    fn value(&mut self) -> Option<Node> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let members = match self.text.get(start)? {
+            b'{' => Some(self.object()?),
+            b'[' => {
+                self.array()?;
+                None
+            }
+            b'"' => {
+                self.string()?;
+                None
+            }
+            _ => {
+                while self.pos < self.text.len()
+                    && !matches!(self.text[self.pos], b',' | b'}' | b']')
+                    && !self.text[self.pos].is_ascii_whitespace()
+                {
+                    self.pos += 1;
+                }
+                (self.pos > start).then_some(())?;
+                None
+            }
+        };
+        Some(Node {
+            start,
+            end: self.pos,
+            members,
+        })
+    }
+
+    fn object(&mut self) -> Option<Vec<Member>> {
+        self.pos += 1;
+        let mut members = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.text.get(self.pos)? {
+                b'}' => {
+                    self.pos += 1;
+                    return Some(members);
+                }
+                b',' if !members.is_empty() => self.pos += 1,
+                b'"' => {
+                    let start = self.pos;
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    let value = self.value()?;
+                    members.push(Member { key, start, value });
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn array(&mut self) -> Option<()> {
+        self.pos += 1;
+        loop {
+            self.skip_whitespace();
+            match self.text.get(self.pos)? {
+                b']' => {
+                    self.pos += 1;
+                    return Some(());
+                }
+                b',' => self.pos += 1,
+                _ => {
+                    self.value()?;
+                }
+            }
+        }
+    }
+
+    /// Skip a string literal, returning its decoded content
+    fn string(&mut self) -> Option<String> {
+        let start = self.pos;
+        self.pos += 1;
+        while let Some(&byte) = self.text.get(self.pos) {
+            self.pos += 1;
+            match byte {
+                b'\\' => self.pos += 1,
+                b'"' => {
+                    let literal = std::str::from_utf8(&self.text[start..self.pos]).ok()?;
+                    return serde_json::from_str(literal).ok();
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn edit(original: &str, change: impl FnOnce(&mut Value)) -> String {
+        let old: Value = serde_json::from_str(&strip_jsonc(original)).unwrap();
+        let mut new = old.clone();
+        change(&mut new);
+        apply(original, &old, &new).unwrap()
+    }
+
+    #[test]
+    fn test_add_keeps_order_indentation_and_comments() {
+        let original = r#"{
+    // Platform team
+    "projects": {
+        "web": {"repo": "git@x:web.git"},
+        "api": {
+            "repo": "git@x:api.git" // primary
+        }
+    }
+}
+"#;
+        let edited = edit(original, |doc| {
+            doc["projects"]["cli"] = json!({"repo": "git@x:cli.git", "tags": ["tool"]});
+        });
+        assert_eq!(
+            edited,
+            r#"{
+    // Platform team
+    "projects": {
+        "web": {"repo": "git@x:web.git"},
+        "api": {
+            "repo": "git@x:api.git" // primary
+        },
+        "cli": {
+            "repo": "git@x:cli.git",
+            "tags": [
+                "tool"
+            ]
+        }
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_remove_and_change() {
+        let original = r#"{
+  "projects": {
+    // the old one
+    "a": "git@x:a.git", // retired
+    "b": {"repo": "git@x:b.git", "path": "libs/b"},
+    "c": "git@x:c.git"
+  }
+}"#;
+        let edited = edit(original, |doc| {
+            let projects = doc["projects"].as_object_mut().unwrap();
+            projects.remove("a");
+            projects.remove("c");
+            projects["b"]["repo"] = json!("git@y:b.git");
+        });
+        assert_eq!(
+            edited,
+            r#"{
+  "projects": {
+    "b": {"repo": "git@y:b.git", "path": "libs/b"}
+  }
+}"#
+        );
+    }
+
+    #[test]
+    fn test_trailing_commas_and_empty_objects() {
+        let edited = edit("{\"projects\": {},\n\"ignore\": [\"x\",],\n}", |doc| {
+            doc["projects"]["a"] = json!("git@x:a.git");
+        });
+        assert_eq!(
+            edited,
+            "{\"projects\": {\"a\": \"git@x:a.git\"},\n\"ignore\": [\"x\",],\n}"
+        );
+    }
+
+    #[test]
+    fn test_not_an_object() {
+        assert_eq!(apply("[1]", &json!([1]), &json!([2])), None);
+    }
+
+    #[test]
+    fn test_arrange_into_sections() {
+        let original = r#"{
+  "projects": {
+    "web": {"repo": "git@x:web.git", "tags": ["apps"]},
+    // the API
+    "api": "git@x:api.git", // primary
+    "cli": {"repo": "git@x:cli.git", "tags": ["tools"]}
+  },
+  "ignore": []
+}"#;
+        let section = |header: &str, keys: &[&str]| Section {
+            header: Some(header.to_string()),
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+        };
+        let sections = [section("apps", &["web"]), section("tools", &["cli"])];
+        let arranged = arrange(original, "projects", &sections).unwrap();
+        assert_eq!(
+            arranged,
+            r#"{
+  "projects": {
+    // --- apps ---
+    "web": {"repo": "git@x:web.git", "tags": ["apps"]},
+
+    // --- tools ---
+    "cli": {"repo": "git@x:cli.git", "tags": ["tools"]},
+    // the API
+    "api": "git@x:api.git" // primary
+  },
+  "ignore": []
+}"#
+        );
+
+        // Rearranging replaces the old headers
+        let flat = Section {
+            header: None,
+            keys: vec!["api".to_string(), "cli".to_string(), "web".to_string()],
+        };
+        assert_eq!(
+            arrange(&arranged, "projects", &[flat]).unwrap(),
+            r#"{
+  "projects": {
+    // the API
+    "api": "git@x:api.git", // primary
+    "cli": {"repo": "git@x:cli.git", "tags": ["tools"]},
+    "web": {"repo": "git@x:web.git", "tags": ["apps"]}
+  },
+  "ignore": []
+}"#
+        );
+        assert_eq!(arrange("projects: {}", "projects", &[]), None);
+    }
+}
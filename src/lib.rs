@@ -3,17 +3,74 @@
 //! Provides project management commands for meta repositories.
 
 use meta_cli::config::{self, MetaTreeNode, ProjectInfo};
-use serde::Serialize;
-use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::Path;
-use std::process::Command;
-
+use summary::RunSummary;
+
+mod add;
+mod approve;
+mod archive;
+mod artifact;
+mod baseline;
+mod cache;
+mod changeset;
+mod check;
+mod deadline;
+mod deprecate;
+mod explain;
+mod export;
+mod fetch;
+mod fetchall;
+mod git;
+pub mod history;
+mod hook;
+mod import;
+mod jj;
+mod jsonedit;
+pub mod lock;
+mod lockdiff;
+mod manifest;
+mod merge;
+mod messages;
+mod mirror;
+mod mv;
+mod paths;
+mod pool;
+mod profile;
+mod progress;
+mod remotes;
+mod rename;
+mod render;
+mod reset;
+mod restore;
+mod rm;
+mod selection;
+mod selftest;
+mod selfupdate;
+mod settings;
+mod seturl;
+mod state;
+mod stats;
+mod style;
+mod summary;
+mod template;
+#[cfg(any(test, feature = "test-harness"))]
+pub mod testing;
+mod trymerge;
+mod verifylock;
+mod workspace_lock;
+
+pub use check::{register_check_rule, CheckRule, RuleProject};
+pub use deadline::{timed_out, TIMEOUT_EXIT_CODE};
 pub use meta_plugin_protocol::{
     output_execution_plan, CommandResult, ExecutionPlan, PlanResponse, PlannedCommand,
 };
+pub use selfupdate::update_notice;
+pub use settings::Deprecation;
 
 /// Options passed to execute_command
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExecuteOptions {
     pub dry_run: bool,
     pub json_output: bool,
@@ -21,6 +78,12 @@ pub struct ExecuteOptions {
     pub depth: Option<usize>,
     pub verbose: bool,
     pub parallel: bool,
+    /// Words instead of symbols, box drawing and color (`--plain`)
+    #[serde(default)]
+    pub plain: bool,
+    /// Warning-level findings fail the command (`--strict`)
+    #[serde(default)]
+    pub strict: bool,
 }
 
 // ============================================================================
@@ -38,6 +101,9 @@ pub struct ProjectTreeNode {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub tags: Vec<String>,
     pub is_meta: bool,
+    /// Set by `meta project deprecate`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<Deprecation>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub projects: Vec<ProjectTreeNode>,
 }
@@ -50,6 +116,83 @@ pub struct ProjectListOutput {
     pub root: String,
     pub cwd: String,
     pub projects: Vec<ProjectTreeNode>,
+    /// Number of top-level projects before `--offset`/`--limit` (only when paging)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
+}
+
+/// Sort order for the top-level entries of `project list`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListSort {
+    Name,
+    Path,
+    /// Most recent commit first
+    Activity,
+    /// Largest on-disk size first
+    Size,
+}
+
+/// `--sort`, `--offset` and `--limit` for `project list`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ListPage {
+    sort: Option<ListSort>,
+    offset: usize,
+    limit: Option<usize>,
+}
+
+impl ListPage {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let sort = match flag_value(args, "--sort") {
+            None => None,
+            Some("name") => Some(ListSort::Name),
+            Some("path") => Some(ListSort::Path),
+            Some("activity") => Some(ListSort::Activity),
+            Some("size") => Some(ListSort::Size),
+            Some(other) => {
+                return Err(format!(
+                    "Invalid --sort value '{other}': expected name, path, activity or size"
+                ))
+            }
+        };
+        let count = |flag: &str| -> Result<Option<usize>, String> {
+            flag_value(args, flag)
+                .map(|value| {
+                    value
+                        .parse::<usize>()
+                        .map_err(|_| format!("Invalid {flag} value: {value}"))
+                })
+                .transpose()
+        };
+        Ok(ListPage {
+            sort,
+            offset: count("--offset")?.unwrap_or(0),
+            limit: count("--limit")?,
+        })
+    }
+
+    fn is_paged(&self) -> bool {
+        self.offset > 0 || self.limit.is_some()
+    }
+
+    /// Sort and slice top-level nodes (children keep manifest order);
+    /// paths are resolved against `base` for activity and size
+    fn apply(&self, mut nodes: Vec<ProjectTreeNode>, base: &Path) -> Vec<ProjectTreeNode> {
+        match self.sort {
+            None => {}
+            Some(ListSort::Name) => nodes.sort_by(|a, b| a.name.cmp(&b.name)),
+            Some(ListSort::Path) => nodes.sort_by(|a, b| a.path.cmp(&b.path)),
+            Some(ListSort::Activity) => nodes
+                .sort_by_cached_key(|n| std::cmp::Reverse(last_commit_time(&base.join(&n.path)))),
+            Some(ListSort::Size) => {
+                nodes.sort_by_cached_key(|n| std::cmp::Reverse(dir_size(&base.join(&n.path))))
+            }
+        }
+        nodes
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
 }
 
 // ============================================================================
@@ -72,6 +215,153 @@ pub fn execute_command(
         return CommandResult::ShowHelp(None);
     }
 
+    let settings = match settings::load(cwd) {
+        Ok(settings) => settings,
+        Err(e) => return CommandResult::Error(e),
+    };
+    // Built-in subcommands always win over settings.aliases, so an alias can
+    // never shadow (or recursively expand into) another alias
+    let mut command = command.to_string();
+    let mut args = args.to_vec();
+    let subcommand = command.strip_prefix("project ").unwrap_or(&command);
+    if !SUBCOMMANDS.contains(&subcommand) {
+        if let Some(value) = settings.aliases.get(subcommand) {
+            let Some((target, mut expanded)) = settings::expand_alias(value) else {
+                return CommandResult::Error(format!("Alias '{subcommand}' is empty"));
+            };
+            if !SUBCOMMANDS.contains(&target.as_str()) {
+                return CommandResult::Error(format!(
+                    "Alias '{subcommand}' expands to unknown command '{target}'"
+                ));
+            }
+            expanded.extend(args);
+            command = format!("project {target}");
+            args = expanded;
+        }
+    }
+    let subcommand = command.strip_prefix("project ").unwrap_or(&command);
+    match settings.command_defaults(subcommand, &args) {
+        Ok(defaults) => args.extend(defaults),
+        Err(e) => return CommandResult::Error(e),
+    }
+    let args = &args[..];
+
+    let timeout = match flag_value(args, "--timeout")
+        .map(deadline::parse)
+        .transpose()
+    {
+        Ok(timeout) => timeout,
+        Err(e) => return CommandResult::Error(e),
+    };
+    deadline::start(timeout);
+    if let Err(e) = progress::start(flag_value(args, "--progress-json"), cwd) {
+        return CommandResult::Error(e);
+    }
+    if let Err(e) = selection::start(flag_value(args, "--projects-from"), cwd) {
+        return CommandResult::Error(e);
+    }
+
+    let plain = options.plain || args.iter().any(|a| a == "--plain");
+    style::apply(plain);
+
+    let effective_options = ExecuteOptions {
+        plain,
+        strict: options.strict || settings.strict || args.iter().any(|a| a == "--strict"),
+        ..*options
+    };
+    let options = &effective_options;
+    if settings.git.path.is_some() {
+        return CommandResult::Error(
+            "settings.git.path in .meta is not honored: a shared manifest can't choose the \
+             program this plugin runs. Set META_PROJECT_GIT to an absolute path instead."
+                .to_string(),
+        );
+    }
+    let git_path = match git::executable_from_env() {
+        Ok(path) => path,
+        Err(e) => return CommandResult::Error(e),
+    };
+    if let Err(e) = git::require(git_path.as_deref(), settings.git.min_version.as_deref()) {
+        return CommandResult::Error(e);
+    }
+    match git::askpass_from_env() {
+        Ok(askpass) => git::set_prompting(git::Prompting {
+            disabled: args.iter().any(|a| a == "--no-prompt"),
+            askpass,
+        }),
+        Err(e) => return CommandResult::Error(e),
+    }
+
+    let result = dispatch(&command, args, options, provided_projects, cwd);
+    explain::annotate(timeout_note(result), args)
+}
+
+/// Mark a result cut short by `--timeout` as partial
+fn timeout_note(result: CommandResult) -> CommandResult {
+    if !deadline::timed_out() {
+        return result;
+    }
+    let note = format!(
+        "Timed out after {}s: remaining network operations were cancelled; the results above are partial.",
+        deadline::timeout().unwrap_or_default().as_secs()
+    );
+    match result {
+        CommandResult::Message(msg) if msg.is_empty() => CommandResult::Message(note),
+        CommandResult::Message(msg) => CommandResult::Message(format!("{msg}\n{note}")),
+        CommandResult::Error(e) => CommandResult::Error(format!("{e}\n{note}")),
+        other => other,
+    }
+}
+
+/// Built-in `project` subcommands
+const SUBCOMMANDS: &[&str] = &[
+    "list",
+    "ls",
+    "check",
+    "dependents",
+    "reset",
+    "remotes",
+    "prune-remotes",
+    "history-cmd",
+    "rerun",
+    "messages",
+    "self-test",
+    "export",
+    "migrate-manifest",
+    "merge-meta",
+    "deprecate",
+    "stats",
+    "diff-lock",
+    "try-merge",
+    "changeset",
+    "cache",
+    "add",
+    "rm",
+    "explain",
+    "rename",
+    "mv",
+    "set-url",
+    "hook",
+    "import",
+    "approve",
+    "mirror",
+    "archive",
+    "lock",
+    "restore",
+    "verify-lock",
+    "use",
+    "fmt",
+    "fetch-all",
+    "self-update",
+];
+
+fn dispatch(
+    command: &str,
+    args: &[String],
+    options: &ExecuteOptions,
+    provided_projects: &[String],
+    cwd: &Path,
+) -> CommandResult {
     // project dependents reads the dependency graph directly
     if command == "project dependents" {
         return handle_project_dependents(args, options, cwd);
@@ -90,101 +380,184 @@ pub fn execute_command(
         } else {
             ExecuteOptions { ..*options }
         };
-        return handle_project_list(cwd, &effective_options);
+        return handle_project_list(args, cwd, &effective_options);
     }
 
-    // If we have provided projects from meta_cli (e.g., when --recursive is used),
-    // we need to check each project directory for missing repos in their .meta files
-    if !provided_projects.is_empty() {
-        return execute_command_recursive(command, options, provided_projects, cwd);
+    if command == "project check" {
+        let mut summary = RunSummary::new(command);
+        let result =
+            check::handle_project_check(args, options, provided_projects, cwd, &mut summary);
+        return summary::finish(args, &summary, result);
     }
 
-    // Fall back to reading the local meta config
-    let Some((meta_path, _format)) = config::find_meta_config_in(cwd) else {
-        return CommandResult::Error(format!("No .meta config found in {}", cwd.display()));
-    };
-    let projects = match parse_meta_projects(&meta_path) {
-        Ok(projects) => projects,
-        Err(e) => return CommandResult::Error(format!("Failed to parse meta config: {e}")),
-    };
-    let missing = find_missing_projects(&projects, cwd);
+    if command == "project reset" {
+        let mut summary = RunSummary::new(command);
+        let result =
+            reset::handle_project_reset(args, options, provided_projects, cwd, &mut summary);
+        return summary::finish(args, &summary, result);
+    }
 
-    match command {
-        "project check" => {
-            if missing.is_empty() {
-                CommandResult::Message("All projects are cloned and present.".to_string())
-            } else {
-                // Print missing repos (uses visual formatting)
-                print_missing(&missing, cwd);
-                CommandResult::Message(format!(
-                    "{} project(s) missing. Run 'meta git update' to clone them.",
-                    missing.len()
-                ))
-            }
-        }
-        _ => CommandResult::ShowHelp(Some(format!(
-            "unrecognized command '{command}'. Use 'meta git update' to sync projects."
-        ))),
+    if command == "project remotes" {
+        let json_from_args = args.iter().any(|a| a == "--json");
+        let effective_options = ExecuteOptions {
+            json_output: options.json_output || json_from_args,
+            ..*options
+        };
+        return remotes::handle_remotes(&effective_options, provided_projects, cwd);
     }
-}
 
-/// Execute a project command recursively across provided project directories
-///
-/// This handles the case when --recursive is used. Each project directory may have
-/// its own .meta file with additional projects to check/sync.
-fn execute_command_recursive(
-    command: &str,
-    _options: &ExecuteOptions,
-    provided_projects: &[String],
-    cwd: &Path,
-) -> CommandResult {
-    let mut all_missing: Vec<(String, String)> = Vec::new();
-
-    // Check the root meta config first
-    if let Some((root_meta_path, _format)) = config::find_meta_config_in(cwd) {
-        if let Ok(projects) = parse_meta_projects(&root_meta_path) {
-            let missing = find_missing_projects(&projects, cwd);
-            for (name, url) in missing {
-                all_missing.push((name, url));
-            }
-        }
+    if command == "project prune-remotes" {
+        let mut summary = RunSummary::new(command);
+        let result =
+            remotes::handle_prune_remotes(args, options, provided_projects, cwd, &mut summary);
+        return summary::finish(args, &summary, result);
     }
 
-    // Check each provided project directory for its own meta config
-    for project_path in provided_projects {
-        let project_dir = cwd.join(project_path);
-        if let Some((nested_meta_path, _format)) = config::find_meta_config_in(&project_dir) {
-            if let Ok(projects) = parse_meta_projects(&nested_meta_path) {
-                let missing = find_missing_projects(&projects, &project_dir);
-                for (name, url) in missing {
-                    // Use the full path relative to cwd
-                    let full_path = format!("{project_path}/{name}");
-                    all_missing.push((full_path, url));
-                }
-            }
-        }
+    if command == "project try-merge" {
+        let mut summary = RunSummary::new(command);
+        let result =
+            trymerge::handle_try_merge(args, options, provided_projects, cwd, &mut summary);
+        return summary::finish(args, &summary, result);
     }
 
-    match command {
-        "project check" => {
-            if all_missing.is_empty() {
-                CommandResult::Message("All projects are cloned and present.".to_string())
-            } else {
-                // Print all missing repos
-                for (name, url) in &all_missing {
-                    meta_git_lib::print_missing_repo(name, url, &cwd.join(name));
-                }
-                println!();
-                CommandResult::Message(format!(
-                    "{} project(s) missing. Run 'meta git update' to clone them.",
-                    all_missing.len()
-                ))
-            }
-        }
-        _ => CommandResult::ShowHelp(Some(format!(
-            "unrecognized command '{command}'. Use 'meta git update' to sync projects."
-        ))),
+    if command == "project history-cmd" {
+        return history::handle_history(args, options, cwd);
+    }
+
+    if command == "project rerun" {
+        return history::handle_rerun(args, cwd);
+    }
+
+    if command == "project self-test" {
+        return selftest::handle_self_test(args, options);
+    }
+
+    if command == "project self-update" {
+        return selfupdate::handle_self_update(args, options);
     }
+
+    if command == "project export" {
+        return export::handle_export(args, options, cwd);
+    }
+
+    if command == "project migrate-manifest" {
+        return manifest::handle_migrate_manifest(options, cwd);
+    }
+
+    if command == "project merge-meta" {
+        return merge::handle_merge_meta(args, options, cwd);
+    }
+
+    if command == "project deprecate" {
+        return deprecate::handle_deprecate(args, options, cwd);
+    }
+
+    if command == "project stats" {
+        return stats::handle_stats(args, options, cwd);
+    }
+
+    if command == "project fmt" {
+        return manifest::handle_fmt(args, options, cwd);
+    }
+
+    if command == "project fetch-all" {
+        let mut summary = RunSummary::new(command);
+        let result =
+            fetchall::handle_fetch_all(args, options, provided_projects, cwd, &mut summary);
+        return summary::finish(args, &summary, result);
+    }
+
+    if command == "project use" {
+        let mut summary = RunSummary::new(command);
+        let result = profile::handle_use(args, options, provided_projects, cwd, &mut summary);
+        return summary::finish(args, &summary, result);
+    }
+
+    if command == "project restore" {
+        let mut summary = RunSummary::new(command);
+        let result = restore::handle_restore(args, options, provided_projects, cwd, &mut summary);
+        return summary::finish(args, &summary, result);
+    }
+
+    if command == "project lock" {
+        return lock::handle_lock(options, provided_projects, cwd);
+    }
+
+    if command == "project verify-lock" {
+        return verifylock::handle_verify_lock(args, options, cwd);
+    }
+
+    if command == "project diff-lock" {
+        return lockdiff::handle_diff_lock(args, options, provided_projects, cwd);
+    }
+
+    if command == "project changeset" {
+        return changeset::handle_changeset(args, options, provided_projects, cwd);
+    }
+
+    if command == "project cache" {
+        return cache::handle_cache(args, options, cwd);
+    }
+
+    if command == "project add" {
+        return add::handle_add(args, options, cwd);
+    }
+
+    if command == "project rm" {
+        return rm::handle_rm(args, options, cwd);
+    }
+
+    if command == "project explain" {
+        return explain::handle_explain(args);
+    }
+
+    if command == "project rename" {
+        return rename::handle_rename(args, options, cwd);
+    }
+
+    if command == "project mv" {
+        return mv::handle_mv(args, options, cwd);
+    }
+
+    if command == "project set-url" {
+        return seturl::handle_set_url(args, options, cwd);
+    }
+
+    if command == "project hook" {
+        return hook::handle_hook(args, options, provided_projects, cwd);
+    }
+
+    if command == "project import" {
+        return import::handle_import(args, options, cwd);
+    }
+
+    if command == "project approve" {
+        return approve::handle_approve(args, options, cwd);
+    }
+
+    if command == "project archive" {
+        let mut summary = RunSummary::new(command);
+        let result = archive::handle_archive(args, options, provided_projects, cwd, &mut summary);
+        return summary::finish(args, &summary, result);
+    }
+
+    if command == "project mirror" {
+        let mut summary = RunSummary::new(command);
+        let result = mirror::handle_mirror(args, options, cwd, &mut summary);
+        return summary::finish(args, &summary, result);
+    }
+
+    if command == "project messages" {
+        return match serde_json::to_string_pretty(&messages::catalog()) {
+            Ok(json) => CommandResult::Message(json),
+            Err(e) => CommandResult::Error(format!("Failed to serialize JSON: {e}")),
+        };
+    }
+
+    CommandResult::ShowHelp(Some(format!(
+        "unrecognized command '{command}'. Use 'meta project check --fix' to clone missing \
+         projects."
+    )))
 }
 
 // ============================================================================
@@ -192,7 +565,15 @@ fn execute_command_recursive(
 // ============================================================================
 
 /// Handle `meta project list` / `meta project ls`
-fn handle_project_list(cwd: &Path, options: &ExecuteOptions) -> CommandResult {
+fn handle_project_list(args: &[String], cwd: &Path, options: &ExecuteOptions) -> CommandResult {
+    let page = match ListPage::parse(args) {
+        Ok(page) => page,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let paths = match paths::PathDisplay::parse(args, cwd) {
+        Ok(paths) => paths,
+        Err(e) => return CommandResult::Error(e),
+    };
     let max_depth = if options.recursive {
         options.depth
     } else {
@@ -217,13 +598,36 @@ fn handle_project_list(cwd: &Path, options: &ExecuteOptions) -> CommandResult {
         cwd.to_path_buf()
     };
 
-    let tree = match config::walk_meta_tree(&start_dir, max_depth) {
+    let tree = match walk_meta_tree(&start_dir, max_depth) {
         Ok(t) => t,
         Err(e) => return CommandResult::Error(format!("{e}")),
     };
 
-    let root_repo = get_git_remote_url(&start_dir).unwrap_or_default();
-    let project_nodes: Vec<ProjectTreeNode> = tree.iter().map(to_project_tree_node).collect();
+    let root_repo = git::remote_url(&start_dir, "origin").unwrap_or_default();
+    let mut project_nodes: Vec<ProjectTreeNode> = tree.iter().map(to_project_tree_node).collect();
+    annotate_deprecations(&mut project_nodes, &start_dir);
+    let total = project_nodes.len();
+    let mut project_nodes = page.apply(project_nodes, &start_dir);
+
+    if let Some(format) = flag_value(args, "--format") {
+        let template = match template::Template::parse(format) {
+            Ok(template) => template,
+            Err(e) => return CommandResult::Error(e),
+        };
+        let mut lines = Vec::new();
+        render_project_rows(
+            &template,
+            &project_nodes,
+            &start_dir,
+            &paths,
+            "",
+            &mut lines,
+        );
+        return CommandResult::Message(lines.join("\n"));
+    }
+    if paths.is_set() {
+        show_paths(&mut project_nodes, &start_dir, &paths);
+    }
     let abs_cwd = cwd
         .canonicalize()
         .unwrap_or_else(|_| cwd.to_path_buf())
@@ -254,6 +658,7 @@ fn handle_project_list(cwd: &Path, options: &ExecuteOptions) -> CommandResult {
             root: abs_root,
             cwd: abs_cwd,
             projects: project_nodes,
+            total: page.is_paged().then_some(total),
         };
         let json = match serde_json::to_string_pretty(&output) {
             Ok(j) => j,
@@ -263,7 +668,15 @@ fn handle_project_list(cwd: &Path, options: &ExecuteOptions) -> CommandResult {
     } else {
         let mut output = String::new();
         output.push_str(&format!(". ({root_repo})\n"));
-        format_project_tree(&project_nodes, &mut output, "");
+        format_project_tree(&project_nodes, &mut output, "", options.plain);
+        if page.is_paged() {
+            let first = page.offset.min(total);
+            output.push_str(&format!(
+                "Showing {}-{} of {total} project(s)\n",
+                (first + 1).min(total),
+                first + project_nodes.len()
+            ));
+        }
         if output.ends_with('\n') {
             output.pop();
         }
@@ -278,30 +691,145 @@ fn to_project_tree_node(node: &MetaTreeNode) -> ProjectTreeNode {
         repo: node.info.repo.clone(),
         tags: node.info.tags.clone(),
         is_meta: node.is_meta,
+        deprecated: None,
         projects: node.children.iter().map(to_project_tree_node).collect(),
     }
 }
 
-/// Get the git remote origin URL for a directory
-fn get_git_remote_url(dir: &Path) -> Option<String> {
-    let output = Command::new("git")
-        .args(["config", "--get", "remote.origin.url"])
-        .current_dir(dir)
-        .output()
-        .ok()?;
+/// [`config::walk_meta_tree`], also accepting configs only this plugin reads
+/// (`.meta.toml`, YAML in `.meta`) at `start_dir`
+///
+/// Nested meta repos below such a root are expanded the same way, up to
+/// `max_depth` levels (`None` for unlimited).
+fn walk_meta_tree(start_dir: &Path, max_depth: Option<usize>) -> anyhow::Result<Vec<MetaTreeNode>> {
+    fn walk(
+        dir: &Path,
+        depth: usize,
+        visited: &mut HashSet<std::path::PathBuf>,
+    ) -> anyhow::Result<Vec<MetaTreeNode>> {
+        let Some(meta_path) = manifest::find(dir) else {
+            anyhow::bail!("No .meta config found in {}", dir.display());
+        };
+        if manifest::read_by_meta(&meta_path) {
+            return config::walk_meta_tree(dir, Some(depth));
+        }
+        let (projects, _ignore) = manifest::projects(&meta_path)?;
+        Ok(projects
+            .into_iter()
+            .map(|info| {
+                let project_dir = dir.join(&info.path);
+                let is_meta = project_dir.is_dir() && manifest::find(&project_dir).is_some();
+                let canonical = project_dir.canonicalize().unwrap_or(project_dir.clone());
+                let children = if is_meta && depth > 0 && visited.insert(canonical) {
+                    walk(&project_dir, depth - 1, visited).unwrap_or_default()
+                } else {
+                    vec![]
+                };
+                MetaTreeNode {
+                    info,
+                    is_meta,
+                    children,
+                }
+            })
+            .collect())
+    }
 
-    if output.status.success() {
-        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
-    } else {
-        None
+    match manifest::find(start_dir) {
+        Some(meta_path) if !manifest::read_by_meta(&meta_path) => {
+            let mut visited = HashSet::new();
+            visited.insert(start_dir.canonicalize().unwrap_or(start_dir.to_path_buf()));
+            walk(start_dir, max_depth.unwrap_or(usize::MAX), &mut visited)
+        }
+        _ => config::walk_meta_tree(start_dir, max_depth),
+    }
+}
+
+/// Attach `deprecated` markers from the .meta in `dir` (and nested meta repos)
+fn annotate_deprecations(nodes: &mut [ProjectTreeNode], dir: &Path) {
+    let mut deprecations = manifest::find(dir)
+        .and_then(|meta_path| settings::deprecations(&meta_path).ok())
+        .unwrap_or_default();
+    for node in nodes {
+        node.deprecated = deprecations.remove(&node.path);
+        if !node.projects.is_empty() {
+            annotate_deprecations(&mut node.projects, &dir.join(&node.path));
+        }
+    }
+}
+
+/// Rewrite each node's path, stored relative to its meta repo at `dir`, as
+/// `--relative-to` asks; nested projects get their full path too
+fn show_paths(nodes: &mut [ProjectTreeNode], dir: &Path, paths: &paths::PathDisplay) {
+    for node in nodes {
+        let project_dir = dir.join(&node.path);
+        show_paths(&mut node.projects, &project_dir, paths);
+        node.path = paths.show(dir, &node.path);
+    }
+}
+
+/// Render `template` for every node, depth-first, with paths relative to the
+/// listing root unless `--relative-to` says otherwise
+fn render_project_rows(
+    template: &template::Template,
+    nodes: &[ProjectTreeNode],
+    base: &Path,
+    paths: &paths::PathDisplay,
+    prefix: &str,
+    lines: &mut Vec<String>,
+) {
+    for node in nodes {
+        let path = format!("{prefix}{}", node.path);
+        let shown = paths.show(base, &path);
+        let row = template::Row {
+            name: &node.name,
+            path: &shown,
+            repo: node.repo.as_deref(),
+            tags: &node.tags,
+        };
+        lines.push(template.render(&row, &base.join(&path)));
+        render_project_rows(
+            template,
+            &node.projects,
+            base,
+            paths,
+            &format!("{path}/"),
+            lines,
+        );
     }
 }
 
-/// Format a project tree with box-drawing characters
-fn format_project_tree(nodes: &[ProjectTreeNode], output: &mut String, prefix: &str) {
+/// Unix time of the last commit in the repo at `dir`; 0 when not a clone
+fn last_commit_time(dir: &Path) -> u64 {
+    if !git::is_repo_root(dir) {
+        return 0;
+    }
+    git::output(dir, &["log", "-1", "--format=%ct"])
+        .and_then(|out| out.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Total size in bytes of the files under `dir`, not following symlinks
+pub(crate) fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            Ok(t) if t.is_file() => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Format a project tree with box-drawing characters (indentation only when `plain`)
+fn format_project_tree(nodes: &[ProjectTreeNode], output: &mut String, prefix: &str, plain: bool) {
     for (i, node) in nodes.iter().enumerate() {
         let is_last = i == nodes.len() - 1;
-        let connector = if is_last {
+        let connector = if plain {
+            "- "
+        } else if is_last {
             "\u{2514}\u{2500}\u{2500} "
         } else {
             "\u{251c}\u{2500}\u{2500} "
@@ -313,18 +841,34 @@ fn format_project_tree(nodes: &[ProjectTreeNode], output: &mut String, prefix: &
             format!(" [{}]", node.tags.join(", "))
         };
 
+        let deprecated_str = match &node.deprecated {
+            None => String::new(),
+            Some(deprecation) => {
+                let mut notice = " DEPRECATED".to_string();
+                if let Some(successor) = &deprecation.successor {
+                    notice.push_str(&format!(", use {successor}"));
+                }
+                if let Some(message) = &deprecation.message {
+                    notice.push_str(&format!(": {message}"));
+                }
+                style::warn_text(plain, &notice)
+            }
+        };
+
         output.push_str(&format!(
-            "{}{}{} ({}){}\n",
-            prefix, connector, node.name, node.path, tags_str
+            "{}{}{} ({}){}{}\n",
+            prefix, connector, node.name, node.path, tags_str, deprecated_str
         ));
 
         if !node.projects.is_empty() {
-            let child_prefix = if is_last {
+            let child_prefix = if plain {
+                format!("{prefix}  ")
+            } else if is_last {
                 format!("{prefix}    ")
             } else {
                 format!("{prefix}\u{2502}   ")
             };
-            format_project_tree(&node.projects, output, &child_prefix);
+            format_project_tree(&node.projects, output, &child_prefix, plain);
         }
     }
 }
@@ -393,17 +937,17 @@ fn handle_project_dependents(
         nearest_meta_dir.to_path_buf()
     };
 
-    let Some((meta_path, _format)) = config::find_meta_config_in(&start_dir) else {
+    let Some(meta_path) = manifest::find(&start_dir) else {
         return CommandResult::Error(format!("No .meta config found in {}", start_dir.display()));
     };
 
-    let (all_projects, _ignore) = match config::parse_meta_config(&meta_path) {
+    let (all_projects, _ignore) = match manifest::projects(&meta_path) {
         Ok(result) => result,
         Err(e) => return CommandResult::Error(format!("Failed to parse meta config: {e}")),
     };
 
     let Some(dependents) = find_dependents(&project_name, &all_projects) else {
-        return CommandResult::Error(format!("Unknown project or alias: {project_name}"));
+        return CommandResult::Error(unresolved_project_message(&project_name, &all_projects));
     };
 
     if options.json_output {
@@ -429,17 +973,61 @@ fn normalize_token(s: &str) -> String {
     s.replace('-', "_").to_lowercase()
 }
 
-/// Find all projects that depend on the given project.
-///
-/// A project B depends on project A if B's `depends_on` contains:
-/// - A's name directly, OR
-/// - Any of A's `provides` entries
+/// Names whose normalized form starts with the normalized `query`
+pub(crate) fn prefix_matches<'a>(
+    query: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<&'a str> {
+    let query = normalize_token(query);
+    candidates
+        .into_iter()
+        .filter(|candidate| normalize_token(candidate).starts_with(&query))
+        .collect()
+}
+
+/// Up to three candidates within a small edit distance of `query`, closest first
+pub(crate) fn suggest_names<'a>(
+    query: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Vec<&'a str> {
+    let query = normalize_token(query);
+    let threshold = (query.chars().count() / 3).max(2);
+    let mut scored: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .map(|candidate| {
+            (
+                edit_distance(&query, &normalize_token(candidate)),
+                candidate,
+            )
+        })
+        .filter(|(distance, _)| *distance <= threshold)
+        .collect();
+    scored.sort();
+    scored.dedup_by(|a, b| a.1 == b.1);
+    scored.into_iter().take(3).map(|(_, name)| name).collect()
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Resolve a project selector: exact name, then `provides` alias, then unique name prefix
 ///
-/// Token matching is normalized (hyphens ↔ underscores, case-insensitive).
-fn find_dependents(project_name: &str, all_projects: &[ProjectInfo]) -> Option<Vec<String>> {
-    // Resolve the target project by normalized name or alias
-    let normalized_query = normalize_token(project_name);
-    let target = all_projects
+/// Matching is normalized (see [`normalize_token`]).
+fn resolve_project<'a>(query: &str, all_projects: &'a [ProjectInfo]) -> Option<&'a ProjectInfo> {
+    let normalized_query = normalize_token(query);
+    all_projects
         .iter()
         .find(|project| normalize_token(&project.name) == normalized_query)
         .or_else(|| {
@@ -449,7 +1037,82 @@ fn find_dependents(project_name: &str, all_projects: &[ProjectInfo]) -> Option<V
                     .iter()
                     .any(|token| normalize_token(token) == normalized_query)
             })
-        })?;
+        })
+        .or_else(|| {
+            match prefix_matches(query, all_projects.iter().map(|p| p.name.as_str())).as_slice() {
+                [name] => all_projects.iter().find(|p| p.name == *name),
+                _ => None,
+            }
+        })
+}
+
+/// Explain why `query` did not resolve: an ambiguous prefix, or an unknown
+/// name with "did you mean" suggestions
+fn unresolved_project_message(query: &str, all_projects: &[ProjectInfo]) -> String {
+    let names = all_projects.iter().map(|p| p.name.as_str());
+    let mut ambiguous = prefix_matches(query, names.clone());
+    if ambiguous.len() > 1 {
+        ambiguous.sort();
+        return format!(
+            "Ambiguous project '{query}': matches {}",
+            ambiguous.join(", ")
+        );
+    }
+
+    let aliases = all_projects
+        .iter()
+        .flat_map(|p| p.provides.iter().map(String::as_str));
+    let suggestions = suggest_names(query, names.chain(aliases));
+    if suggestions.is_empty() {
+        format!("Unknown project or alias: {query}")
+    } else {
+        format!(
+            "Unknown project or alias: {query}. Did you mean: {}?",
+            suggestions.join(", ")
+        )
+    }
+}
+
+/// The key of the `projects` map of a manifest document that `query`
+/// selects, resolved like [`resolve_project`]; an unresolved query fails with
+/// [`unresolved_project_message`]
+pub(crate) fn resolve_project_key(
+    query: &str,
+    projects: &serde_json::Map<String, serde_json::Value>,
+) -> Result<String, String> {
+    if projects.contains_key(query) {
+        return Ok(query.to_string());
+    }
+    let all_projects: Vec<ProjectInfo> = projects
+        .iter()
+        .map(|(name, entry)| ProjectInfo {
+            name: name.clone(),
+            path: name.clone(),
+            repo: None,
+            tags: vec![],
+            provides: entry
+                .get("provides")
+                .and_then(|p| Vec::<String>::deserialize(p).ok())
+                .unwrap_or_default(),
+            depends_on: vec![],
+            meta: false,
+        })
+        .collect();
+    resolve_project(query, &all_projects)
+        .map(|project| project.name.clone())
+        .ok_or_else(|| unresolved_project_message(query, &all_projects))
+}
+
+/// Find all projects that depend on the given project.
+///
+/// A project B depends on project A if B's `depends_on` contains:
+/// - A's name directly, OR
+/// - Any of A's `provides` entries
+///
+/// Token matching is normalized (hyphens ↔ underscores, case-insensitive), and
+/// the target may be given by any selector [`resolve_project`] accepts.
+fn find_dependents(project_name: &str, all_projects: &[ProjectInfo]) -> Option<Vec<String>> {
+    let target = resolve_project(project_name, all_projects)?;
 
     // Build the set of normalized tokens that the target provides
     let mut provided_tokens: HashSet<String> = HashSet::new();
@@ -486,50 +1149,637 @@ Commands:
   meta project list         List all projects defined in .meta (alias: ls)
   meta project check        Check if all projects in .meta are cloned locally
   meta project dependents   List projects that depend on a given project
+  meta project remotes      List every project's remotes, flagging unexpected ones
+  meta project prune-remotes  Prune deleted remote branches in every project
+  meta project reset        Discard local changes and reset projects (--hard)
+  meta project history-cmd  List previously run project commands
+  meta project rerun [N]    Replay history entry N (default: the latest) after confirming [--yes]
+  meta project messages     Print the check, reset and prune-remotes message catalog (a template for translations)
+  meta project self-test    Run clone/update/clean in a temporary sandbox [--keep]
+  meta project export       Write a filtered copy of .meta (--filter, --to, --state-json, --to-gitmodules)
+  meta project migrate-manifest  Upgrade .meta to the current meta_version
+  meta project merge-meta <ours> <theirs> <base>  Three-way merge of .meta
+  meta project deprecate <name>  Mark a project deprecated (--successor, --message)
+  meta project stats        Summarize the workspace (counts, disk, activity) [--json]
+  meta project lock         Record every project's current commit in meta-lock.json
+  meta project restore --locked  Clone and check out what meta-lock.json records
+  meta project diff-lock <from> [<to>]  What changed between two lockfiles
+  meta project verify-lock  Check .meta, meta-lock.json and the clones agree
+  meta project use [<profile>]  Check out a profile's branches in every project
+  meta project fmt          Put the .meta project entries in settings.sort order
+  meta project fetch-all    Fetch every project at a paced per-host rate, resumably
+  meta project self-update  Replace this plugin with the latest release [--check]
+  meta project try-merge <branch>  Trial-merge a topic branch and report conflicts
+  meta project changeset create|checkout <id>  Record or restore a cross-repo change
+  meta project cache [info|gc]  Show or garbage-collect meta-project's caches
+  meta project add <name> <url>  Add a project to .meta (--path, --tag, --clone)
+  meta project rm <name>    Remove a project from .meta and delete its clone
+  meta project explain [<code>]  Explain a finding or error code in detail
+  meta project rename <old> <new>  Rename a project and move its clone (--path)
+  meta project mv <name> <path>  Move a project's clone to a new path
+  meta project set-url <name> <url>  Change a project's URL in .meta and its clone
+  meta project hook pre-push install|uninstall  Manage a verification hook in every project
+  meta project import --from-<source>  Add projects declared by another tool's manifest
+  meta project approve [<name>]  List quarantined projects, or admit one
+  meta project mirror push --remote URL  Push every project to a backup server
+  meta project archive export --to DIR  Snapshot every project as a git bundle
 
 Options for list:
   --json               Output as JSON
   --recursive, -r      Include nested meta repo children
   --depth N            Maximum recursion depth (default: unlimited)
+  --sort KEY           Order top-level projects by name, path, activity or size
+  --offset N           Skip the first N top-level projects
+  --limit N            Show at most N top-level projects
+  --format TEMPLATE    One line per project, e.g. '{name}\t{branch}\t{behind}'
+                       Fields: name, path, repo, tags, branch, upstream,
+                       ahead, behind, dirty ('{{' and '}}' for literal braces)
+  --relative-to WHERE  Print project paths relative to the workspace root,
+                       the cwd, or absolute (root, cwd or absolute; default:
+                       relative to the .meta that lists them)
+
+Options for check:
+  --fix                Apply safe remediations (clone missing, repair origin
+                       URLs, check out pinned refs, restore upstream
+                       tracking, re-create a deleted default branch from
+                       origin, fetch stale clones)
+  --fast-forward       With --fix, also fast-forward branches that are
+                       behind their upstream (never done otherwise)
+  --yes, -y            Apply fixes without per-category confirmation
+  --fetch              Run 'git fetch --prune' in every project first
+  --fetch-timeout S    Per-project fetch timeout in seconds (default: 60)
+  --stale-only         Fetch only projects last fetched longer ago than
+                       settings.max_stale_days
+  --incremental        Reuse cached results for projects that have not changed
+  --relative-to WHERE  Print project paths as for list (root, cwd or absolute)
+  --jobs N, -j N       Inspect N projects concurrently (default: CPU count;
+                       fetches and --fix clones default to 8)
+  --clone-depth N      Clone missing projects with only the last N commits
+                       of each branch (default: the project's "depth", then
+                       settings.depth)
+  --filter SPEC        Clone missing projects partially, e.g. blob:none to
+                       download file contents on demand (default: the
+                       project's "filter", then settings.filter)
+  --single-branch      Clone missing projects with only their pinned ref's
+                       branch, or the default branch (default: the
+                       project's "single_branch", then settings.single_branch)
+  --reference DIR      Clone missing projects borrowing objects from the
+                       local clone DIR/<path>, or from DIR itself when that
+                       doesn't exist (default: settings.reference)
+  --clone-order ORDER  Start the --fix clones by ORDER instead of by path:
+                       priority (highest "priority": N in .meta first) or
+                       size (largest first, as recorded when each project
+                       was last cloned here; unrecorded ones go last), so
+                       long clones don't hold up the end of the run
+  --write-baseline     Record the current findings in .meta-baseline.json;
+                       later checks report only findings not recorded there
+  --no-baseline        Report baselined findings too
+  A project's "suppress": [{"code": "behind", "until": "YYYY-MM-DD",
+  "reason": "..."}] in .meta hides findings of that code (or rule ID) until
+  the date; expired suppressions are reported.
+  With "settings": {"max_stale_days": N}, clones not fetched in over N days
+  are reported as stale.
+  A project entry's "ref" (branch, tag or commit), e.g. {"repo": "...",
+  "ref": "v1.2.0"}, pins it: clones elsewhere are reported, and --fix checks
+  the ref out, also right after cloning a missing project.
+  A project entry's "depth": N, or "settings": {"depth": N} for all of them,
+  makes --fix clone it shallow; check lists shallow clones, and
+  'git fetch --unshallow' in one restores its full history. "filter" works
+  the same way for partial clones, which check lists too, and so does
+  "single_branch": true. An entry's "submodules": true clones it with
+  --recurse-submodules, and "sparse": ["dir", ...] clones it with --sparse
+  and checks out only those directories; check lists sparse checkouts.
+  Clones made with a reference (git clone --reference-if-able) share its
+  objects, so it must not be deleted; 'git repack -a -d' in a clone and
+  removing .git/objects/info/alternates make it standalone.
+  An entry {"type": "archive", "url": "...", "sha256": "..."} is a tarball
+  rather than a clone: --fix downloads it with curl, verifies the checksum
+  and unpacks it into the path; check reports it until the unpacked version
+  matches the sha256.
+  Clones with a .jj directory are colocated jj repos: pins are checked by
+  commit and fixed with 'jj new', never 'git checkout'. An entry's
+  "vcs": "jj" makes --fix clone it with 'jj git clone --colocate'.
+
+Options for remotes:
+  --json               Output as JSON
+
+Options for prune-remotes:
+  --delete-gone        Also delete local branches whose upstream is gone
+                       (branches with unmerged commits are kept)
+  --force              With --delete-gone, delete unmerged branches too
+  --yes, -y            Delete without confirmation
+
+Options for reset:
+  --hard               Required: discard local changes
+  --to lock|branch     Reset to the meta-lock.json SHA or to origin/<branch>
+  --clean              Also remove untracked files
+  --yes, -y            Skip the typed confirmation (refuses dirty projects)
+  --allow-dirty        With --yes, discard uncommitted changes too
+
+Output:
+  --plain              Words instead of symbols, tree lines and color
+                       (screen readers, dumb terminals; implied by TERM=dumb)
+
+Explanations:
+  Failures name the codes that apply to them (e.g. workspace_locked; check
+  findings use their category). --explain prints the full explanation of
+  each: what it means, how to fix it and how to silence it.
+
+Strict mode (check, remotes):
+  --strict             Exit non-zero on warning-level findings (missing or
+                       behind projects, rule violations, remotes not in .meta,
+                       credentials in URLs); "strict": true in the .meta
+                       settings block makes it the default
+
+Run artifacts (check, reset, prune-remotes, try-merge):
+  --summary-file PATH  Append a Markdown report of per-project operations and
+                       durations to PATH (e.g. $GITHUB_STEP_SUMMARY)
+  --report-file PATH   Write the same report to PATH as HTML (a self-contained
+                       page with sortable tables), Markdown, JSON or text,
+                       chosen by the .html, .md, .json or other extension
+  --trace-file PATH    Write a Chrome trace (Perfetto, chrome://tracing) of
+                       every timed per-project operation to PATH
+
+Timeout (any command):
+  --timeout DURATION   Wall-clock budget for network operations (fetches,
+                       clones), e.g. 90, 30s, 10m or 1h. At the deadline,
+                       running operations are killed and pending ones
+                       cancelled; the partial results are reported and the
+                       exit code is 124
+
+Credentials (any command):
+  Fetches, clones and pushes never prompt on the terminal: stdin carries
+  meta's request, so a prompt would hang the run. One that needs credentials
+  fails with guidance instead. META_PROJECT_ASKPASS=/absolute/path/to/program
+  hands git an askpass helper (GIT_ASKPASS) for this plugin only.
+  --no-prompt          Also turn off askpass helpers and run SSH in batch
+                       mode (no passphrase or host key questions), so
+                       missing credentials fail at once; for CI
+
+Progress events (fetches, mirror, archive):
+  --progress-json PATH Write a JSON line per phase start and finished project
+                       (phase, project, status, done, total, percent, bytes)
+                       to PATH, or to stderr for '-', for GUIs and IDEs
+
+Project selection (commands that act on the declared projects):
+  --projects-from F    Act only on the projects listed in file F, one path
+                       or name per line, e.g. 'list --format' output saved
+                       through grep (stdin carries meta's request, so '-'
+                       is not accepted)
+
+Workspace lock (reset, prune-remotes, check --fix, changeset checkout):
+  --wait               Wait for another running command to release the lock
+  --no-wait            Fail immediately if the workspace is locked (default)
+
+Protected projects:
+  Projects marked "protected": true in .meta are skipped by reset,
+  prune-remotes and check --fix unless confirmed at the prompt or
+  --include-protected is passed (--yes alone never includes them).
+
+Git executable:
+  META_PROJECT_GIT=/absolute/path/to/git picks the git binary this plugin
+  runs; "settings": {"git": {"min_version": "2.38"}} in .meta sets the lowest
+  acceptable version. Every command checks them first and fails with a
+  clear error when unmet. The binary comes only from your environment: a
+  .meta is shared through the repository, so it can't name a program to run.
+
+Fallback URLs:
+  A project's "fallback_urls": [...] in .meta are tried in order when check
+  --fix can't clone from its repo URL; the URL used is recorded in the run
+  summary, and check accepts any of them as origin.
+
+Options for export:
+  --filter F           Keep projects matching F: tag:NAME, name:NAME or
+                       path:DIR (repeatable; all filters must match)
+  --rewrite-url A=B    Replace the URL prefix A with B (repeatable)
+  --to PATH            Write to PATH (YAML for .yaml/.yml) instead of stdout
+  The settings block and per-project fallback_urls are never exported.
+  --state-json         Instead dump the resolved workspace as JSON: each
+                       project's .meta entry, path, tags, dependencies and
+                       dependents, and clone state (branch, HEAD, origin,
+                       upstream, ahead/behind, dirty); with --to, to a file
+  --to-gitmodules      Instead register the (filtered, rewritten) projects as
+                       submodules of the workspace repo with 'git submodule
+                       add'; existing clones are adopted, missing ones cloned
+
+Merge driver (merge-meta):
+  Merges .meta semantically: projects added on either side are kept, and
+  only a field both sides changed differently conflicts. Register it with
+    git config merge.meta.driver "meta project merge-meta %A %B %O"
+    echo ".meta merge=meta" >> .gitattributes
+  --interactive, -i    Resolve remaining conflicts at the terminal, showing who
+                       last changed each side and whether conflicting URLs
+                       are reachable
+
+Options for deprecate:
+  --successor NAME     Project that replaces it (must be in .meta)
+  --message TEXT       Why, or what to do instead
+  --undo               Remove the deprecation marker
+  Deprecated projects are flagged in list output.
+
+Options for diff-lock:
+  <to>                 Second lockfile (default: the projects' current HEADs)
+  --shortlog N         Commit subjects listed per project (default: 10)
+  --json               Output as JSON
+  Commits missing from a local clone are reported; fetch first to list them.
+
+Options for verify-lock:
+  --json               Output the issues as JSON
+  Fails when a project isn't locked, a locked project isn't in .meta, the
+  URLs differ, or a clone lacks or hasn't checked out its locked commit.
+  Nothing is fetched, so it's cheap enough to gate CI builds.
+
+Options for try-merge:
+  --into BRANCH        Branch to merge into (default: origin's default branch)
+  --jobs N, -j N       Projects to trial-merge in parallel
+  --json               Output as JSON
+  Each merge runs in a temporary worktree; checkouts are left untouched.
+  Projects without the branch are skipped. Exits non-zero on any conflict.
+
+Options for changeset:
+  create <id>          Record the branch and commit of every project with
+                       commits beyond origin's default branch
+  checkout <id>        Check out each recorded project (fetching if needed)
+  list                 List recorded changesets
+  --all                With create, record every cloned project
+  --force              With create, replace an existing changeset
+  Changesets live in .meta-changesets/<id>.json; commit the file to share it.
+
+Options for cache gc:
+  --max-age DAYS       Remove entries unused for longer (default: 90)
+  --max-size SIZE      Then remove the least recently used entries until the
+                       cache fits, e.g. 500M or 2G (default: 1G)
+  --dry-run            List what would be removed
+  The current workspace's entry and locked entries are always kept.
+
+Options for add:
+  --path DIR           Where to clone it (default: the name)
+  --tag TAG            Tag the project; repeatable or comma-separated
+  --clone              Clone it right away
+  The URL must be clonable (scheme://host/..., user@host:path or a local
+  directory) and free of credentials; name and path must be unused.
+
+Options for rm:
+  --keep-files         Only remove the .meta entry; leave the working copy
+  --include-protected  Allow removing a protected project
+  A clone with uncommitted changes or unpushed commits is never deleted.
+
+Options for rename:
+  --path DIR           Move the clone here (default: follows the name, unless
+                       the project has an explicit path)
+  --dry-run            Show what would change
+  The clone is moved, .gitignore entries and successor links are updated, and
+  .meta is rewritten; if a step fails, the earlier ones are undone.
+
+Options for mv:
+  --dry-run            Show what would change
+  The clone is moved (parent directories are created), the .meta path and
+  .gitignore entry are updated; if a step fails, the earlier ones are undone.
+
+Options for set-url:
+  --dry-run            Show what would change
+  The URL is validated like in add. The clone's origin is updated first and
+  restored if .meta can't be written, so the two never drift apart.
+
+Options for hook:
+  --force              Replace an existing hook not written by meta-project
+  --dry-run            Show what would change
+  The hook runs settings.hooks.pre-push from .meta (a project's own
+  hooks.pre-push overrides it) in the project, with META_PROJECT_NAME set:
+    "settings": { "hooks": { "pre-push": "make lint" } }
+
+Options for import:
+  --from-gitmodules    Read the submodules in .gitmodules (relative URLs are
+                       resolved against origin)
+  --deinit             Then deinit the submodules and remove them from the
+                       index; 'meta project check --fix' clones them again
+  --from-repo-manifest FILE  Read the <project>s of a Google repo manifest
+                       (following <include>s); groups become tags
+  --from-west FILE     Read the projects of a Zephyr west.yml; groups become
+                       tags and pinned revisions are kept as "revision"
+  --from-tsrc FILE     Read the repos of a tsrc manifest.yml; groups become
+                       tags and sha1/tag/branch pins are kept as "revision"
+  --from-gitman FILE   Read the sources of a gitman.yml (placed under its
+                       location); groups become tags, rev is kept as "revision"
+  --quarantine         Put the projects in the "pending" section of .meta
+                       (not synced) and clone them into .meta-quarantine/ for
+                       review; 'meta project approve <name>' admits one
+  --dry-run            Show what would change
+  Projects already in .meta are left as they are; without a .meta one is created.
+
+Options for mirror:
+  --remote URL         Destination URL template; {name} and {path} are replaced,
+                       otherwise <URL>/<path>.git is used
+  --ref BRANCH         Push only this branch of origin (repeatable, globs allowed);
+                       tags are always pushed
+  -j, --jobs N         Push N projects at a time
+  --dry-run            Print the pushes without running them
+  Destination repositories must already exist on the server.
+
+Options for archive:
+  --to DIR             New or empty directory to write the archive to: a git
+                       bundle and metadata.json per project under projects/,
+                       a copy of .meta, manifest.json and SHA256SUMS
+  -j, --jobs N         Bundle N projects at a time
+  --dry-run            Print the bundles without writing them
+  Object storage URLs aren't written to; upload the directory afterwards.
+
+Options for restore:
+  --locked             Required: restore the commits recorded in meta-lock.json
+  --dry-run            Show what would be cloned and checked out
+  Missing projects are cloned; the others get the recorded commit checked
+  out (and the recorded branch moved to it). Projects with uncommitted
+  changes, or whose recorded branch has commits the lock lacks, are
+  refused, and a recorded commit not found locally or on origin fails the
+  command before any clone is changed. --projects-from limits it.
+
+Options for use:
+  <profile>            A profile from the .meta settings.profiles block;
+                       without it, list the profiles (* marks the active one)
+  --dry-run            Show the checkouts without switching
+  A profile gives a "branch" for every project and/or per-project
+  "projects" overrides; projects it doesn't name are left alone. Branches
+  missing locally are created from origin (fetch first). Projects with
+  uncommitted changes stop the switch before any project is touched.
+
+Options for fmt:
+  --sort POLICY        alphabetical, path or tag (default: settings.sort)
+  --check              Fail instead of writing when the order is off
+  With "settings": {"sort": "..."}, every command that writes .meta keeps
+  the order too. "tag" groups projects by their first tag under
+  "// --- tag ---" comment headers, untagged ones last. Ordering applies to
+  JSON manifests; YAML and TOML ones are always written in name order.
+
+Options for fetch-all:
+  --rate N             Fetches per minute from any one host (default: 30)
+  --burst N            Fetches one host may start back to back (default: 4)
+  --jobs N, -j N       Fetch N projects concurrently (default: 8)
+  --fetch-timeout S    Per-project fetch timeout in seconds (default: 60)
+  --restart            Start over instead of resuming an unfinished run
+  --dry-run            Show how many projects and hosts a run would cover
+  Meant for nightly jobs on shared mirrors. Progress is saved after every
+  fetch; a run that is interrupted, stopped by --timeout or has failures is
+  resumed by the next one, which fetches only the projects left.
+
+Options for self-update:
+  --check              Only report whether a newer release is out
+  --dry-run            Show what would be replaced
+  The release feed is the project's latest GitHub release, or the URL in
+  META_PROJECT_RELEASE_FEED. The release's SHA256SUMS must carry an SSH
+  signature (SHA256SUMS.sig) by the release key built into this binary, and
+  the platform's binary must match it, before it replaces this one; checking
+  needs ssh-keygen. With "settings": {"update_check": true}, other commands
+  print a notice on stderr when a newer release is out, checking at most
+  once a day; never for --json, off a terminal, under CI or with
+  META_PROJECT_NO_UPDATE_CHECK=1.
+
+Options for history-cmd:
+  --limit N            Show only the N most recent entries
+  --json               Output as JSON
+
+Translations:
+  The result messages of check, reset and prune-remotes are read from
+  ~/.config/meta-project/locales/<lang>.json (a map of message ID to text;
+  see 'meta project messages'); other commands' output is English only. The
+  language comes from META_PROJECT_LANG, LC_ALL, LC_MESSAGES or LANG.
+
+Aliases:
+  Define shortcuts in the .meta "settings" block; built-in commands take precedence:
+    "settings": { "aliases": { "ck": "check --fetch" } }
+  Then 'meta project ck -j 4' runs 'meta project check --fetch -j 4'.
+
+Per-command defaults:
+  Default flags for a command (aliases included) travel with the repo in "settings":
+    "settings": { "commands": { "check": { "fetch": true, "jobs": 8 } } }
+  true adds the flag, a string or number adds it with that value and a list
+  repeats it. Flags given on the command line take precedence. Only the
+  entry of the command being run is read, so entries for other plugins'
+  commands sharing the .meta (update, exec, ...) are left alone.
+
+Concurrency groups:
+  Projects sharing a resource (an LFS store, a license server) can name a
+  "group"; fetches, mirror pushes and archive bundles then run at most that
+  group's limit of them at once, within the overall --jobs (default limit: 1):
+    "api": { "repo": "...", "group": "lfs" },
+    "settings": { "concurrency": { "lfs": 2 } }
+  "host_concurrency" limits the projects of one remote host the same way,
+  whatever their group, e.g. to stay under a server's connection cap:
+    "settings": { "host_concurrency": { "git.corp.example": 4 } }
+  Bandwidth isn't capped: git has no setting for it.
+
+Profiles:
+  Named branch sets in the settings block; 'meta project use <profile>'
+  checks them out and remembers the active profile:
+    "settings": { "profiles": {
+      "release-2.x": { "branch": "release/2.x", "projects": { "api": "maint/2.x" } }
+    } }
 
 Examples:
   meta project dependents meta_git_lib          # Who depends on meta_git_lib?
   meta project dependents meta_git_lib --json   # JSON output
+  meta project check --fix --yes                # Remediate all findings
 
-To clone missing projects, use: meta git update
+To clone missing projects, use: meta project check --fix
 "#
 }
 
-fn parse_meta_projects(meta_path: &Path) -> anyhow::Result<HashMap<String, String>> {
-    let (projects, _ignore) = config::parse_meta_config(meta_path)?;
-    let mut map = HashMap::new();
-    for p in projects {
+fn parse_meta_projects(meta_path: &Path) -> anyhow::Result<Vec<WorkspaceProject>> {
+    let (projects, _ignore) = manifest::projects(meta_path)?;
+    let mut keys = settings::project_keys(meta_path).map_err(anyhow::Error::msg)?;
+    Ok(projects
+        .into_iter()
         // Skip projects without a repo URL (cannot clone)
-        if let Some(repo) = p.repo {
-            map.insert(p.path.clone(), repo);
+        .filter_map(|p| {
+            let url = p.repo?;
+            let keys = keys.remove(&p.path).unwrap_or_default();
+            Some(WorkspaceProject {
+                protected: keys.protected,
+                jj: keys.jj,
+                submodules: keys.submodules,
+                fallbacks: keys.fallbacks,
+                pinned_ref: keys.pinned_ref,
+                depth: keys.depth,
+                filter: keys.filter,
+                single_branch: keys.single_branch,
+                sparse: keys.sparse,
+                priority: keys.priority,
+                reference: None,
+                path: p.path,
+                url,
+            })
+        })
+        .collect())
+}
+
+/// A project declared in .meta: its path relative to cwd and its remote URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct WorkspaceProject {
+    pub path: String,
+    pub url: String,
+    /// Marked `"protected": true`; see [`select_unprotected`]
+    pub protected: bool,
+    /// Marked `"vcs": "jj"`: cloned as a colocated jj repo
+    pub jj: bool,
+    /// Marked `"submodules": true`: cloned with `--recurse-submodules`
+    pub submodules: bool,
+    /// Mirror URLs to clone from when `url` fails, in order
+    pub fallbacks: Vec<String>,
+    /// Branch, tag or commit from the entry's `ref` key
+    pub pinned_ref: Option<String>,
+    /// Shallow clone depth from the entry's `depth` key
+    pub depth: Option<u32>,
+    /// Partial clone filter from the entry's `filter` key
+    pub filter: Option<String>,
+    /// The entry's `single_branch`: clone only the default or pinned branch
+    pub single_branch: Option<bool>,
+    /// Directories from the entry's `sparse` key: cloned with `--sparse`
+    /// and only these checked out (empty for a full checkout)
+    pub sparse: Vec<String>,
+    /// The entry's `priority`, for `check --fix --clone-order priority`
+    pub priority: i64,
+    /// Local repository to borrow objects from when cloning; set from
+    /// `--reference` or `settings.reference` by check
+    pub reference: Option<std::path::PathBuf>,
+}
+
+/// Gather the cloneable projects of the workspace, sorted by path
+///
+/// Reads the .meta config in `cwd`. When `provided_projects` is non-empty
+/// (--recursive), each of those project directories is also searched for its
+/// own .meta config, whose projects are reported relative to `cwd`.
+pub(crate) fn workspace_projects(
+    provided_projects: &[String],
+    cwd: &Path,
+) -> Result<Vec<WorkspaceProject>, String> {
+    let mut projects = Vec::new();
+
+    if provided_projects.is_empty() {
+        let Some(meta_path) = manifest::find(cwd) else {
+            return Err(format!("No .meta config found in {}", cwd.display()));
+        };
+        let declared = parse_meta_projects(&meta_path)
+            .map_err(|e| format!("Failed to parse meta config: {e}"))?;
+        projects.extend(declared);
+    } else {
+        // Read the root meta config first
+        if let Some(root_meta_path) = manifest::find(cwd) {
+            if let Ok(declared) = parse_meta_projects(&root_meta_path) {
+                projects.extend(declared);
+            }
+        }
+
+        // Then each provided project directory's own meta config
+        for project_path in provided_projects {
+            let project_dir = cwd.join(project_path);
+            if let Some(nested_meta_path) = manifest::find(&project_dir) {
+                if let Ok(declared) = parse_meta_projects(&nested_meta_path) {
+                    for project in declared {
+                        // Use the full path relative to cwd
+                        let path = format!("{project_path}/{}", project.path);
+                        projects.push(WorkspaceProject { path, ..project });
+                    }
+                }
+            }
         }
     }
-    Ok(map)
+
+    projects.sort_by(|a, b| a.path.cmp(&b.path));
+    projects.dedup();
+    selection::retain(&mut projects)?;
+    Ok(projects)
 }
 
-fn find_missing_projects(
-    projects: &HashMap<String, String>,
-    base_dir: &Path,
-) -> Vec<(String, String)> {
-    projects
+/// Drop protected projects from a destructive or write operation
+///
+/// They stay in with `--include-protected`, or when the user confirms at the
+/// prompt; `--yes` never includes them implicitly.
+pub(crate) fn select_unprotected(
+    projects: Vec<WorkspaceProject>,
+    args: &[String],
+) -> Vec<WorkspaceProject> {
+    if args.iter().any(|a| a == "--include-protected") {
+        return projects;
+    }
+    if !projects.iter().any(|p| p.protected) {
+        return projects;
+    }
+
+    let names: Vec<&str> = projects
         .iter()
-        .filter(|(name, _)| !base_dir.join(name).is_dir())
-        .map(|(name, url)| (name.clone(), url.clone()))
-        .collect()
+        .filter(|p| p.protected)
+        .map(|p| p.path.as_str())
+        .collect();
+    let names = names.join(", ");
+    let yes = args.iter().any(|a| a == "--yes" || a == "-y");
+    if !yes && confirm(&format!("Include protected project(s) {names}?")) {
+        return projects;
+    }
+
+    println!(
+        "{} Skipping protected project(s): {names} (pass --include-protected to include them)",
+        style::warn(args.iter().any(|a| a == "--plain"), "PROTECTED")
+    );
+    projects.into_iter().filter(|p| !p.protected).collect()
 }
 
-fn print_missing(missing: &[(String, String)], cwd: &Path) {
-    if !missing.is_empty() {
-        for (name, url) in missing {
-            meta_git_lib::print_missing_repo(name, url, &cwd.join(name));
+/// Value of a `--flag value` or `--flag=value` argument
+pub(crate) fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == flag {
+            return iter.next().map(String::as_str);
+        }
+        if let Some(value) = arg.strip_prefix(flag).and_then(|v| v.strip_prefix('=')) {
+            return Some(value);
         }
-        println!();
     }
+    None
+}
+
+/// Parse `--jobs N` / `-j N`; `None` when not given
+pub(crate) fn parse_jobs(args: &[String]) -> Result<Option<usize>, String> {
+    let Some(value) = flag_value(args, "--jobs").or_else(|| flag_value(args, "-j")) else {
+        return Ok(None);
+    };
+    match value.parse::<usize>() {
+        Ok(jobs) if jobs > 0 => Ok(Some(jobs)),
+        _ => Err(format!("Invalid --jobs value: {value}")),
+    }
+}
+
+/// Ask a yes/no question on the controlling terminal
+///
+/// Plugins receive their request on stdin, so the prompt goes through
+/// `/dev/tty`. Returns `false` when no terminal is available, so destructive
+/// operations never proceed unattended without `--yes`.
+pub(crate) fn confirm(prompt: &str) -> bool {
+    prompt_tty(&format!("{prompt} [y/N] "))
+        .is_some_and(|answer| matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Require the user to type `expected` before a destructive operation
+pub(crate) fn confirm_typed(prompt: &str, expected: &str) -> bool {
+    prompt_tty(&format!("{prompt} Type '{expected}' to continue: "))
+        .is_some_and(|answer| answer == expected)
+}
+
+/// Write `prompt` to the controlling terminal and read back one trimmed line
+pub(crate) fn prompt_tty(prompt: &str) -> Option<String> {
+    use std::io::{BufRead, BufReader, Write};
+
+    let Ok(mut tty) = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+    else {
+        println!("{prompt}skipped (no terminal; pass --yes to apply)");
+        return None;
+    };
+    let _ = write!(tty, "{prompt}");
+    let _ = tty.flush();
+    let mut answer = String::new();
+    BufReader::new(tty).read_line(&mut answer).ok()?;
+    Some(answer.trim().to_string())
 }
 
 #[cfg(test)]
@@ -566,6 +1816,118 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_alias_expands_to_subcommand() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            r#"{"projects": {"a": "git@github.com:org/a.git"}, "settings": {"aliases": {"deps": "dependents --json", "bad": "frobnicate", "list": "check"}}}"#,
+        )
+        .unwrap();
+        let options = ExecuteOptions::default();
+
+        // Alias arguments come before the ones given on the command line
+        let args = vec!["a".to_string()];
+        match execute_command("project deps", &args, &options, &[], temp_dir.path()) {
+            CommandResult::Message(msg) => assert_eq!(msg, "[]"),
+            _ => panic!("Expected Message result"),
+        }
+
+        match execute_command("project bad", &[], &options, &[], temp_dir.path()) {
+            CommandResult::Error(msg) => assert!(msg.contains("unknown command 'frobnicate'")),
+            _ => panic!("Expected Error result"),
+        }
+
+        // Built-ins cannot be shadowed
+        match execute_command("project list", &[], &options, &[], temp_dir.path()) {
+            CommandResult::Message(msg) => assert!(msg.contains("── a (a)")),
+            _ => panic!("Expected Message result"),
+        }
+    }
+
+    #[test]
+    fn test_command_defaults_apply_after_aliases() {
+        let temp_dir = TempDir::new().unwrap();
+        let meta = temp_dir.path().join(".meta");
+        std::fs::write(
+            &meta,
+            r#"{"projects": {"a": "git@github.com:org/a.git"},
+                "settings": {"aliases": {"deps": "dependents"}, "commands": {"dependents": {"json": true}}}}"#,
+        )
+        .unwrap();
+        let options = ExecuteOptions::default();
+        let args = vec!["a".to_string()];
+        match execute_command("project deps", &args, &options, &[], temp_dir.path()) {
+            CommandResult::Message(msg) => assert_eq!(msg, "[]"),
+            _ => panic!("Expected Message result"),
+        }
+
+        // Entries for commands this plugin doesn't have belong to other
+        // plugins sharing the .meta, and don't affect this one
+        std::fs::write(
+            &meta,
+            r#"{"projects": {}, "settings": {"commands": {"sync": {"jobs": 8, "depth": 1}, "update": {"x": {}}}}}"#,
+        )
+        .unwrap();
+        match execute_command("project list", &[], &options, &[], temp_dir.path()) {
+            CommandResult::Message(_) => {}
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+    }
+
+    #[test]
+    fn test_manifest_cannot_choose_git_executable() {
+        let temp_dir = TempDir::new().unwrap();
+        let script = temp_dir.path().join("git.sh");
+        std::fs::write(&script, "#!/bin/sh\ntouch ran\necho 'git version 9.9.9'\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            r#"{"projects": {}, "settings": {"git": {"path": "git.sh"}}}"#,
+        )
+        .unwrap();
+
+        match execute_command(
+            "project list",
+            &[],
+            &ExecuteOptions::default(),
+            &[],
+            temp_dir.path(),
+        ) {
+            CommandResult::Error(msg) => assert!(msg.contains("META_PROJECT_GIT"), "{msg}"),
+            _ => panic!("Expected Error result"),
+        }
+        assert!(!temp_dir.path().join("ran").exists());
+    }
+
+    #[test]
+    fn test_git_requirement_checked_before_dispatch() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            r#"{"projects": {}, "settings": {"git": {"min_version": "9999.0"}}}"#,
+        )
+        .unwrap();
+
+        match execute_command(
+            "project list",
+            &[],
+            &ExecuteOptions::default(),
+            &[],
+            temp_dir.path(),
+        ) {
+            CommandResult::Error(msg) => {
+                assert!(msg.contains("requires at least 9999.0"), "{msg}")
+            }
+            _ => panic!("Expected Error result"),
+        }
+    }
+
     #[test]
     fn test_project_check_all_present() {
         let temp_dir = TempDir::new().unwrap();
@@ -596,12 +1958,12 @@ mod tests {
         let options = ExecuteOptions::default();
         let result = execute_command("project sync", &[], &options, &[], temp_dir.path());
 
-        // sync command should now return an error directing users to meta git update
+        // sync command should now return an error directing users to check --fix
         match result {
             CommandResult::ShowHelp(Some(msg)) => {
-                assert!(msg.contains("meta git update"));
+                assert!(msg.contains("meta project check --fix"), "{msg}");
             }
-            _ => panic!("Expected ShowHelp result directing to meta git update"),
+            _ => panic!("Expected ShowHelp result directing to check --fix"),
         }
     }
 
@@ -610,7 +1972,7 @@ mod tests {
         let help = get_help_text();
         assert!(help.contains("project check"));
         assert!(help.contains("project list"));
-        assert!(help.contains("meta git update")); // Points to the right command
+        assert!(help.contains("meta project check --fix")); // Points to the right command
         assert!(!help.contains("project sync")); // sync is removed
     }
 
@@ -660,6 +2022,91 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_project_list_toml() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(".meta.toml"),
+            r#"
+# Services owned by the platform team
+[projects]
+repo1 = "git@github.com:org/repo1.git"
+
+[projects.repo2]
+repo = "git@github.com:org/repo2.git"
+path = "libs/repo2"
+tags = ["lib"]
+"#,
+        )
+        .unwrap();
+
+        let options = ExecuteOptions {
+            json_output: true,
+            ..Default::default()
+        };
+        let result = execute_command("project list", &[], &options, &[], temp_dir.path());
+        match result {
+            CommandResult::Message(msg) => {
+                let parsed: serde_json::Value = serde_json::from_str(&msg).unwrap();
+                let projects = parsed["projects"].as_array().unwrap();
+                assert_eq!(projects.len(), 2);
+                assert_eq!(projects[1]["path"], "libs/repo2");
+                assert_eq!(projects[1]["tags"], serde_json::json!(["lib"]));
+            }
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+
+        // Edits are written back as TOML
+        let result = execute_command(
+            "project add",
+            &[
+                "repo3".to_string(),
+                "git@github.com:org/repo3.git".to_string(),
+            ],
+            &ExecuteOptions::default(),
+            &[],
+            temp_dir.path(),
+        );
+        assert!(matches!(result, CommandResult::Message(_)));
+        let written = std::fs::read_to_string(temp_dir.path().join(".meta.toml")).unwrap();
+        assert!(written.contains("[projects.repo3]"), "{written}");
+        let (projects, _ignore) = manifest::projects(&temp_dir.path().join(".meta.toml")).unwrap();
+        assert_eq!(projects.len(), 3);
+    }
+
+    #[test]
+    fn test_project_list_jsonc() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            r#"{
+  // Services owned by the platform team
+  "projects": {
+    "repo1": "git@github.com:org/repo1.git",
+    "repo2": {"repo": "git@github.com:org/repo2.git", "tags": ["lib",],},
+  },
+}
+"#,
+        )
+        .unwrap();
+
+        let options = ExecuteOptions {
+            json_output: true,
+            ..Default::default()
+        };
+        match execute_command("project list", &[], &options, &[], temp_dir.path()) {
+            CommandResult::Message(msg) => {
+                let parsed: serde_json::Value = serde_json::from_str(&msg).unwrap();
+                let projects = parsed["projects"].as_array().unwrap();
+                assert_eq!(projects.len(), 2);
+                assert_eq!(projects[1]["tags"], serde_json::json!(["lib"]));
+            }
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+    }
+
     #[test]
     fn test_project_list_json() {
         let temp_dir = TempDir::new().unwrap();
@@ -690,6 +2137,144 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_project_list_sort_and_page() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            r#"{"projects": {
+                "a": {"repo": "git@github.com:org/a.git", "path": "z/a"},
+                "b": {"repo": "git@github.com:org/b.git", "path": "y/b"},
+                "c": {"repo": "git@github.com:org/c.git", "path": "x/c"}
+            }}"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("y/b")).unwrap();
+        std::fs::write(temp_dir.path().join("y/b/big.bin"), vec![0u8; 4096]).unwrap();
+
+        let args: Vec<String> = ["--sort", "path", "--offset", "1", "--limit", "1"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let options = ExecuteOptions::default();
+        match execute_command("project list", &args, &options, &[], temp_dir.path()) {
+            CommandResult::Message(msg) => {
+                assert!(msg.contains("b (y/b)"), "{msg}");
+                assert!(!msg.contains("c (x/c)"), "{msg}");
+                assert!(msg.ends_with("Showing 2-2 of 3 project(s)"), "{msg}");
+            }
+            _ => panic!("Expected Message result"),
+        }
+
+        let args: Vec<String> = ["--sort=size", "--limit=1", "--json"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        match execute_command("project list", &args, &options, &[], temp_dir.path()) {
+            CommandResult::Message(msg) => {
+                let parsed: serde_json::Value = serde_json::from_str(&msg).unwrap();
+                assert_eq!(parsed["total"], 3);
+                assert_eq!(parsed["projects"][0]["name"], "b");
+            }
+            _ => panic!("Expected Message result"),
+        }
+
+        let args = vec!["--sort".to_string(), "age".to_string()];
+        match execute_command("project list", &args, &options, &[], temp_dir.path()) {
+            CommandResult::Error(msg) => assert!(msg.contains("Invalid --sort value 'age'")),
+            _ => panic!("Expected Error result"),
+        }
+    }
+
+    #[test]
+    fn test_project_list_format() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            r#"{"projects": {
+                "a": {"repo": "git@github.com:org/a.git", "tags": ["x", "y"]},
+                "b": {"repo": "git@github.com:org/b.git", "path": "libs/b"}
+            }}"#,
+        )
+        .unwrap();
+
+        let args = vec![
+            "--format".to_string(),
+            r"{name}\t{path}\t{tags}".to_string(),
+        ];
+        let options = ExecuteOptions::default();
+        match execute_command("project list", &args, &options, &[], temp_dir.path()) {
+            CommandResult::Message(msg) => assert_eq!(msg, "a\ta\tx,y\nb\tlibs/b\t"),
+            _ => panic!("Expected Message result"),
+        }
+    }
+
+    #[test]
+    fn test_project_list_relative_to() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let libs = root.join("libs");
+        std::fs::create_dir_all(&libs).unwrap();
+        std::fs::write(
+            root.join(".meta"),
+            r#"{"projects": {"libs": "git@github.com:org/libs.git"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            libs.join(".meta"),
+            r#"{"projects": {"api": "git@github.com:org/api.git"}}"#,
+        )
+        .unwrap();
+        let list = |relative_to: &str| {
+            let args: Vec<String> = ["--format", "{path}", "--relative-to", relative_to]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+            execute_command(
+                "project list",
+                &args,
+                &ExecuteOptions::default(),
+                &[],
+                &libs,
+            )
+        };
+
+        for (relative_to, expected) in [
+            ("root", "libs/api".to_string()),
+            ("cwd", "api".to_string()),
+            ("absolute", libs.join("api").to_string_lossy().into_owned()),
+        ] {
+            match list(relative_to) {
+                CommandResult::Message(msg) => assert_eq!(msg, expected),
+                _ => panic!("Expected Message result"),
+            }
+        }
+        match list("home") {
+            CommandResult::Error(e) => assert!(e.contains("Invalid --relative-to value 'home'")),
+            _ => panic!("Expected Error result"),
+        }
+    }
+
+    #[test]
+    fn test_project_list_plain() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            r#"{"projects": {"repo1": "git@github.com:org/repo1.git", "repo2": "git@github.com:org/repo2.git"}}"#,
+        )
+        .unwrap();
+
+        let args = vec!["--plain".to_string()];
+        let options = ExecuteOptions::default();
+        match execute_command("project list", &args, &options, &[], temp_dir.path()) {
+            CommandResult::Message(msg) => {
+                assert!(msg.contains("\n- repo1 (repo1)\n- repo2 (repo2)"), "{msg}");
+                assert!(!msg.contains('\u{2500}'));
+            }
+            _ => panic!("Expected Message result"),
+        }
+    }
+
     #[test]
     fn test_project_ls_alias() {
         let temp_dir = TempDir::new().unwrap();
@@ -762,6 +2347,7 @@ mod tests {
                 repo: Some("git@github.com:org/api.git".to_string()),
                 tags: vec!["backend".to_string()],
                 is_meta: false,
+                deprecated: None,
                 projects: vec![],
             },
             ProjectTreeNode {
@@ -770,16 +2356,21 @@ mod tests {
                 repo: Some("git@github.com:org/frontend.git".to_string()),
                 tags: vec![],
                 is_meta: false,
+                deprecated: Some(Deprecation {
+                    successor: Some("web".to_string()),
+                    ..Default::default()
+                }),
                 projects: vec![],
             },
         ];
 
         let mut output = String::new();
-        format_project_tree(&nodes, &mut output, "");
+        format_project_tree(&nodes, &mut output, "", false);
         assert!(output.contains("api"));
         assert!(output.contains("services/api"));
         assert!(output.contains("[backend]"));
         assert!(output.contains("frontend"));
+        assert!(output.contains(" DEPRECATED, use web"));
         // Last item uses └──
         assert!(output.contains("\u{2514}\u{2500}\u{2500}"));
         // Non-last item uses ├──
@@ -1064,6 +2655,49 @@ mod tests {
         assert_eq!(find_dependents("nonexistent", &projects), None);
     }
 
+    #[test]
+    fn test_find_dependents_by_unique_prefix() {
+        let projects = vec![
+            make_project("loop_lib", &[], &[]),
+            make_project("meta_cli", &[], &["loop_lib"]),
+            make_project("meta_git_cli", &[], &[]),
+        ];
+        assert_eq!(
+            find_dependents("loop", &projects),
+            Some(vec!["meta_cli".to_string()])
+        );
+        // "meta" is a prefix of two projects, so it doesn't resolve
+        assert_eq!(find_dependents("meta", &projects), None);
+    }
+
+    #[test]
+    fn test_suggest_names() {
+        let names = ["meta_cli", "meta_git_cli", "loop_lib"];
+        assert_eq!(suggest_names("meta-clu", names), vec!["meta_cli"]);
+        assert_eq!(suggest_names("lop_lib", names), vec!["loop_lib"]);
+        assert!(suggest_names("something_else", names).is_empty());
+    }
+
+    #[test]
+    fn test_unresolved_project_message() {
+        let projects = vec![
+            make_project("meta_cli", &[], &[]),
+            make_project("meta_git_cli", &[], &[]),
+        ];
+        assert_eq!(
+            unresolved_project_message("meta_clu", &projects),
+            "Unknown project or alias: meta_clu. Did you mean: meta_cli?"
+        );
+        assert_eq!(
+            unresolved_project_message("meta", &projects),
+            "Ambiguous project 'meta': matches meta_cli, meta_git_cli"
+        );
+        assert_eq!(
+            unresolved_project_message("zzz", &projects),
+            "Unknown project or alias: zzz"
+        );
+    }
+
     #[test]
     fn test_project_dependents_command() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,210 @@
+//! `meta-lock.json`: the recorded commit of every project in a workspace
+//!
+//! `meta project lock` writes it next to .meta; `reset --to lock`,
+//! `restore --locked`, `diff-lock` and `verify-lock` read it.
+
+use crate::{git, workspace_projects, ExecuteOptions, WorkspaceProject};
+use meta_plugin_protocol::CommandResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// File name of the lockfile, stored next to the .meta config
+pub const LOCKFILE_NAME: &str = "meta-lock.json";
+
+/// Current lockfile format version
+pub const LOCKFILE_VERSION: u32 = 1;
+
+/// Contents of `meta-lock.json`, keyed by project path
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub version: u32,
+    #[serde(default)]
+    pub projects: BTreeMap<String, LockedProject>,
+}
+
+/// The recorded state of a single project
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedProject {
+    pub url: String,
+    /// Branch that was checked out; absent when HEAD was detached
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    pub sha: String,
+}
+
+impl Lockfile {
+    /// Read and validate a lockfile
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let lockfile: Lockfile = serde_json::from_str(&content)?;
+        if lockfile.version > LOCKFILE_VERSION {
+            anyhow::bail!(
+                "{} has version {}, but this meta-project only understands version {}",
+                path.display(),
+                lockfile.version,
+                LOCKFILE_VERSION
+            );
+        }
+        Ok(lockfile)
+    }
+
+    /// Write the lockfile as pretty-printed JSON
+    pub(crate) fn write(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    /// The current HEAD of every cloned project in `projects`
+    pub(crate) fn snapshot(projects: &[WorkspaceProject], cwd: &Path) -> Self {
+        let projects = projects
+            .iter()
+            .filter_map(|project| {
+                let dir = cwd.join(&project.path);
+                if !git::is_repo_root(&dir) {
+                    return None;
+                }
+                let sha = git::output(&dir, &["rev-parse", "HEAD"])?;
+                let locked = LockedProject {
+                    url: project.url.clone(),
+                    branch: git::current_branch(&dir),
+                    sha,
+                };
+                Some((project.path.clone(), locked))
+            })
+            .collect();
+        Lockfile {
+            version: LOCKFILE_VERSION,
+            projects,
+        }
+    }
+}
+
+/// Handle `meta project lock`: record the current HEAD of every cloned project
+pub(crate) fn handle_lock(
+    options: &ExecuteOptions,
+    provided_projects: &[String],
+    cwd: &Path,
+) -> CommandResult {
+    let projects = match workspace_projects(provided_projects, cwd) {
+        Ok(projects) => projects,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let lockfile = Lockfile::snapshot(&projects, cwd);
+    let path = cwd.join(LOCKFILE_NAME);
+    let count = lockfile.projects.len();
+
+    let mut lines = Vec::new();
+    if Lockfile::read(&path).is_ok_and(|existing| existing == lockfile) {
+        lines.push(format!(
+            "{LOCKFILE_NAME} is up to date ({count} project(s))."
+        ));
+    } else if options.dry_run {
+        lines.push(format!(
+            "Dry run: would lock {count} project(s) in {LOCKFILE_NAME}."
+        ));
+    } else {
+        if let Err(e) = lockfile.write(&path) {
+            return CommandResult::Error(format!("Failed to write {LOCKFILE_NAME}: {e}"));
+        }
+        lines.push(format!("Locked {count} project(s) in {LOCKFILE_NAME}."));
+    }
+
+    let uncloned: Vec<&str> = projects
+        .iter()
+        .filter(|p| !lockfile.projects.contains_key(&p.path))
+        .map(|p| p.path.as_str())
+        .collect();
+    if !uncloned.is_empty() {
+        lines.push(format!("Not cloned, not recorded: {}", uncloned.join(", ")));
+    }
+    let dirty: Vec<&str> = lockfile
+        .projects
+        .keys()
+        .filter(|path| git::is_dirty(&cwd.join(path)))
+        .map(String::as_str)
+        .collect();
+    if !dirty.is_empty() {
+        lines.push(format!(
+            "Uncommitted changes aren't recorded: {}",
+            dirty.join(", ")
+        ));
+    }
+    CommandResult::Message(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{commit, Fixture};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lock_records_current_heads() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        fixture.manifest(&[("api", &api), ("web", &web)]);
+        fixture.clone_project("api", &api);
+        let workspace = fixture.workspace();
+        commit(&workspace.join("api"), "local change");
+
+        match fixture.run("project lock", &[]) {
+            CommandResult::Message(msg) => assert_eq!(
+                msg,
+                "Locked 1 project(s) in meta-lock.json.\nNot cloned, not recorded: web"
+            ),
+            _ => panic!("Expected Message result"),
+        }
+        let lockfile = Lockfile::read(&workspace.join(LOCKFILE_NAME)).unwrap();
+        let locked = &lockfile.projects["api"];
+        assert_eq!(locked.url, api.to_string_lossy());
+        assert_eq!(locked.branch.as_deref(), Some("main"));
+        assert_eq!(
+            Some(locked.sha.clone()),
+            git::output(&workspace.join("api"), &["rev-parse", "HEAD"])
+        );
+
+        fixture.clone_project("web", &web);
+        std::fs::write(workspace.join("web/notes.txt"), "draft").unwrap();
+        match fixture.run("project lock", &[]) {
+            CommandResult::Message(msg) => assert_eq!(
+                msg,
+                "Locked 2 project(s) in meta-lock.json.\nUncommitted changes aren't recorded: web"
+            ),
+            _ => panic!("Expected Message result"),
+        }
+        match fixture.run("project lock", &[]) {
+            CommandResult::Message(msg) => {
+                assert!(msg.starts_with("meta-lock.json is up to date (2 project(s))."))
+            }
+            _ => panic!("Expected Message result"),
+        }
+    }
+
+    #[test]
+    fn test_read_lockfile() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(LOCKFILE_NAME);
+        std::fs::write(
+            &path,
+            r#"{"version": 1, "projects": {"repo1": {"url": "git@github.com:org/repo1.git", "branch": "main", "sha": "abc123"}}}"#,
+        )
+        .unwrap();
+
+        let lockfile = Lockfile::read(&path).unwrap();
+        let locked = &lockfile.projects["repo1"];
+        assert_eq!(locked.branch.as_deref(), Some("main"));
+        assert_eq!(locked.sha, "abc123");
+    }
+
+    #[test]
+    fn test_read_lockfile_rejects_newer_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(LOCKFILE_NAME);
+        std::fs::write(&path, r#"{"version": 99, "projects": {}}"#).unwrap();
+
+        let err = Lockfile::read(&path).unwrap_err();
+        assert!(err.to_string().contains("version 99"));
+    }
+}
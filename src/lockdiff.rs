@@ -0,0 +1,282 @@
+//! `meta project diff-lock` — what changed between two lockfiles
+//!
+//! Compares two lockfiles, or one lockfile against the workspace's current
+//! HEADs, and lists each changed project's commit range with the subjects of
+//! the commits in it, e.g. for "what changed between release A and B".
+
+use crate::lock::Lockfile;
+use crate::{git, workspace_projects, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Commit subjects listed per project before the rest are summarized
+const DEFAULT_SHORTLOG: usize = 10;
+
+/// How a project differs between the two sides
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+enum Change {
+    Added {
+        sha: String,
+    },
+    Removed {
+        sha: String,
+    },
+    Changed {
+        from: String,
+        to: String,
+        /// Commits in `from..to`; `None` when they aren't available locally
+        #[serde(skip_serializing_if = "Option::is_none")]
+        commits: Option<Vec<String>>,
+        /// Commits in `to..from`, i.e. the change went backwards
+        #[serde(skip_serializing_if = "is_zero")]
+        reverted: usize,
+    },
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+#[derive(Debug, Serialize)]
+struct ProjectDiff {
+    project: String,
+    #[serde(flatten)]
+    change: Change,
+}
+
+/// Handle `meta project diff-lock <from> [<to>] [--json] [--shortlog N]`
+///
+/// Without `<to>`, the workspace's current HEADs are the other side.
+pub(crate) fn handle_diff_lock(
+    args: &[String],
+    options: &ExecuteOptions,
+    provided_projects: &[String],
+    cwd: &Path,
+) -> CommandResult {
+    let mut files = Vec::new();
+    let mut shortlog = DEFAULT_SHORTLOG;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--shortlog" => match iter.next().map(|v| v.parse()) {
+                Some(Ok(n)) => shortlog = n,
+                _ => return CommandResult::Error("--shortlog requires a number".to_string()),
+            },
+            a if a.starts_with('-') => {}
+            a => files.push(a),
+        }
+    }
+    let read = |file: &str| {
+        Lockfile::read(&cwd.join(file)).map_err(|e| format!("Failed to read {file}: {e}"))
+    };
+    let (from, to) = match files[..] {
+        [from] => {
+            let projects = match workspace_projects(provided_projects, cwd) {
+                Ok(projects) => projects,
+                Err(e) => return CommandResult::Error(e),
+            };
+            (read(from), Ok(Lockfile::snapshot(&projects, cwd)))
+        }
+        [from, to] => (read(from), read(to)),
+        _ => {
+            return CommandResult::Error("Usage: meta project diff-lock <from> [<to>]".to_string())
+        }
+    };
+    let (from, to) = match (from, to) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(e), _) | (_, Err(e)) => return CommandResult::Error(e),
+    };
+
+    let diffs = diff(&from, &to, cwd);
+    if options.json_output || args.iter().any(|a| a == "--json") {
+        return match serde_json::to_string_pretty(&diffs) {
+            Ok(json) => CommandResult::Message(json),
+            Err(e) => CommandResult::Error(format!("Failed to serialize JSON: {e}")),
+        };
+    }
+    if diffs.is_empty() {
+        return CommandResult::Message("No differences.".to_string());
+    }
+    CommandResult::Message(render(&diffs, shortlog))
+}
+
+fn diff(from: &Lockfile, to: &Lockfile, cwd: &Path) -> Vec<ProjectDiff> {
+    let paths: BTreeSet<&String> = from.projects.keys().chain(to.projects.keys()).collect();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let change = match (from.projects.get(path), to.projects.get(path)) {
+                (None, Some(new)) => Change::Added {
+                    sha: new.sha.clone(),
+                },
+                (Some(old), None) => Change::Removed {
+                    sha: old.sha.clone(),
+                },
+                (Some(old), Some(new)) if old.sha != new.sha => {
+                    let dir = cwd.join(path);
+                    let log = |range: &str| {
+                        git::output(&dir, &["log", "--format=%h %s", range])
+                            .map(|out| out.lines().map(str::to_string).collect::<Vec<_>>())
+                    };
+                    Change::Changed {
+                        commits: log(&format!("{}..{}", old.sha, new.sha)),
+                        reverted: log(&format!("{}..{}", new.sha, old.sha))
+                            .map_or(0, |commits| commits.len()),
+                        from: old.sha.clone(),
+                        to: new.sha.clone(),
+                    }
+                }
+                _ => return None,
+            };
+            Some(ProjectDiff {
+                project: path.clone(),
+                change,
+            })
+        })
+        .collect()
+}
+
+fn short(sha: &str) -> &str {
+    &sha[..sha.len().min(12)]
+}
+
+fn render(diffs: &[ProjectDiff], shortlog: usize) -> String {
+    let mut lines = Vec::new();
+    for diff in diffs {
+        match &diff.change {
+            Change::Added { sha } => {
+                lines.push(format!("+ {} (added at {})", diff.project, short(sha)))
+            }
+            Change::Removed { sha } => {
+                lines.push(format!("- {} (removed, was {})", diff.project, short(sha)))
+            }
+            Change::Changed {
+                from,
+                to,
+                commits,
+                reverted,
+            } => {
+                let range = format!("{}..{}", short(from), short(to));
+                match commits {
+                    None => lines.push(format!(
+                        "~ {} {range} (commits not available locally; fetch first)",
+                        diff.project
+                    )),
+                    Some(commits) => {
+                        let mut header =
+                            format!("~ {} {range} ({} commit(s)", diff.project, commits.len());
+                        if *reverted > 0 {
+                            header.push_str(&format!(", {reverted} reverted"));
+                        }
+                        header.push(')');
+                        lines.push(header);
+                        for commit in commits.iter().take(shortlog) {
+                            lines.push(format!("    {commit}"));
+                        }
+                        if commits.len() > shortlog {
+                            lines.push(format!("    ... and {} more", commits.len() - shortlog));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock::LockedProject;
+    use crate::testing::{commit, run_git};
+    use tempfile::TempDir;
+
+    fn head(dir: &Path) -> String {
+        git::output(dir, &["rev-parse", "HEAD"]).unwrap()
+    }
+
+    fn lockfile(entries: &[(&str, &str)]) -> Lockfile {
+        Lockfile {
+            version: 1,
+            projects: entries
+                .iter()
+                .map(|(path, sha)| {
+                    let locked = LockedProject {
+                        url: format!("git@github.com:org/{path}.git"),
+                        branch: None,
+                        sha: sha.to_string(),
+                    };
+                    (path.to_string(), locked)
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_diff_lists_commit_ranges() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path().join("app");
+        std::fs::create_dir(&repo).unwrap();
+        run_git(&repo, &["init", "-q"]);
+        commit(&repo, "release A");
+        let a = head(&repo);
+        commit(&repo, "fix login");
+        commit(&repo, "add search");
+        let b = head(&repo);
+
+        let from = lockfile(&[("app", &a), ("old", "1111")]);
+        let to = lockfile(&[("app", &b), ("new", "2222")]);
+        let diffs = diff(&from, &to, temp_dir.path());
+        let text = render(&diffs, 1);
+        assert!(text.contains("~ app "), "{text}");
+        assert!(text.contains("(2 commit(s))"), "{text}");
+        assert!(text.contains(" add search"), "{text}");
+        assert!(text.contains("... and 1 more"), "{text}");
+        assert!(text.contains("+ new (added at 2222)"));
+        assert!(text.contains("- old (removed, was 1111)"));
+
+        let rolled_back = diff(&to, &from, temp_dir.path());
+        let app = rolled_back.iter().find(|d| d.project == "app").unwrap();
+        assert!(matches!(
+            &app.change,
+            Change::Changed { commits: Some(c), reverted: 2, .. } if c.is_empty()
+        ));
+    }
+
+    #[test]
+    fn test_diff_against_workspace_heads() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            r#"{"projects": {"app": "git@github.com:org/app.git"}}"#,
+        )
+        .unwrap();
+        let repo = temp_dir.path().join("app");
+        std::fs::create_dir(&repo).unwrap();
+        run_git(&repo, &["init", "-q"]);
+        commit(&repo, "initial");
+        let locked = lockfile(&[("app", &head(&repo))]);
+        std::fs::write(
+            temp_dir.path().join("release.json"),
+            serde_json::to_string(&locked).unwrap(),
+        )
+        .unwrap();
+
+        let args = vec!["release.json".to_string()];
+        let options = ExecuteOptions::default();
+        match handle_diff_lock(&args, &options, &[], temp_dir.path()) {
+            CommandResult::Message(msg) => assert_eq!(msg, "No differences."),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+
+        commit(&repo, "after release");
+        match handle_diff_lock(&args, &options, &[], temp_dir.path()) {
+            CommandResult::Message(msg) => assert!(msg.contains("after release"), "{msg}"),
+            _ => panic!("Expected Message result"),
+        }
+    }
+}
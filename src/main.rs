@@ -14,12 +14,151 @@ fn main() {
     );
     help_commands.insert(
         "check".to_string(),
-        "Verify all projects are cloned and consistent".to_string(),
+        "Verify all projects are cloned and consistent (--fix to remediate)".to_string(),
     );
     help_commands.insert(
         "dependents".to_string(),
         "List projects that depend on a given project".to_string(),
     );
+    help_commands.insert(
+        "reset".to_string(),
+        "Reset projects to the lockfile or remote branch (--hard --to lock|branch)".to_string(),
+    );
+    help_commands.insert(
+        "remotes".to_string(),
+        "List remotes of every project, flagging non-manifest and credentialed URLs".to_string(),
+    );
+    help_commands.insert(
+        "prune-remotes".to_string(),
+        "Fetch with --prune everywhere and report deleted remote branches".to_string(),
+    );
+    help_commands.insert(
+        "history-cmd".to_string(),
+        "List previously run project commands".to_string(),
+    );
+    help_commands.insert(
+        "rerun".to_string(),
+        "Replay a command from history-cmd by number".to_string(),
+    );
+    help_commands.insert(
+        "self-test".to_string(),
+        "Validate git and the plugin against a temporary sandbox workspace".to_string(),
+    );
+    help_commands.insert(
+        "export".to_string(),
+        "Write a filtered, URL-rewritten copy of .meta for sharing".to_string(),
+    );
+    help_commands.insert(
+        "migrate-manifest".to_string(),
+        "Rewrite .meta in the current manifest layout (meta_version)".to_string(),
+    );
+    help_commands.insert(
+        "merge-meta".to_string(),
+        "Three-way merge of .meta, usable as a git merge driver".to_string(),
+    );
+    help_commands.insert(
+        "deprecate".to_string(),
+        "Mark a project deprecated, optionally naming its successor".to_string(),
+    );
+    help_commands.insert(
+        "stats".to_string(),
+        "Summarize projects by tag and ecosystem, disk usage and recent activity".to_string(),
+    );
+    help_commands.insert(
+        "diff-lock".to_string(),
+        "List per-project commit ranges between two lockfiles, or a lockfile and HEAD".to_string(),
+    );
+    help_commands.insert(
+        "verify-lock".to_string(),
+        "Check that .meta, meta-lock.json and the clones agree, without fetching".to_string(),
+    );
+    help_commands.insert(
+        "try-merge".to_string(),
+        "Trial-merge a topic branch in every project and report which would conflict".to_string(),
+    );
+    help_commands.insert(
+        "changeset".to_string(),
+        "Record or check out a named set of branches and commits across projects".to_string(),
+    );
+    help_commands.insert(
+        "cache".to_string(),
+        "Show the cache size, or remove stale entries with 'gc' (age and size limits)".to_string(),
+    );
+    help_commands.insert(
+        "add".to_string(),
+        "Add a project to .meta, optionally cloning it".to_string(),
+    );
+    help_commands.insert(
+        "rm".to_string(),
+        "Remove a project from .meta and delete its working copy (--keep-files to keep it)"
+            .to_string(),
+    );
+    help_commands.insert(
+        "explain".to_string(),
+        "Explain a finding or error code: what it means, how to fix and silence it".to_string(),
+    );
+    help_commands.insert(
+        "rename".to_string(),
+        "Rename a project in .meta and move its working copy, rolling back on failure".to_string(),
+    );
+    help_commands.insert(
+        "mv".to_string(),
+        "Move a project's working copy to a new path, updating .meta and .gitignore".to_string(),
+    );
+    help_commands.insert(
+        "set-url".to_string(),
+        "Change a project's URL in .meta and repoint the origin of its clone".to_string(),
+    );
+    help_commands.insert(
+        "hook".to_string(),
+        "Install or remove a git hook running the .meta verification command in every project"
+            .to_string(),
+    );
+    help_commands.insert(
+        "import".to_string(),
+        "Add projects declared in .gitmodules or a repo, west, tsrc or gitman manifest to .meta"
+            .to_string(),
+    );
+    help_commands.insert(
+        "approve".to_string(),
+        "Move a quarantined project from the pending section into the workspace".to_string(),
+    );
+    help_commands.insert(
+        "mirror".to_string(),
+        "Push every project's branches and tags to a backup server".to_string(),
+    );
+    help_commands.insert(
+        "archive".to_string(),
+        "Export a checksummed snapshot of every project as git bundles".to_string(),
+    );
+    help_commands.insert(
+        "lock".to_string(),
+        "Record each project's current commit, branch and URL in meta-lock.json".to_string(),
+    );
+    help_commands.insert(
+        "restore".to_string(),
+        "Clone missing projects and check out the commits recorded in meta-lock.json".to_string(),
+    );
+    help_commands.insert(
+        "use".to_string(),
+        "Switch every project to the branches a profile pins".to_string(),
+    );
+    help_commands.insert(
+        "fmt".to_string(),
+        "Reorder the .meta project entries by the settings.sort policy".to_string(),
+    );
+    help_commands.insert(
+        "fetch-all".to_string(),
+        "Fetch every project at a per-host rate limit, resuming interrupted runs".to_string(),
+    );
+    help_commands.insert(
+        "self-update".to_string(),
+        "Download, verify and install the latest release of this plugin".to_string(),
+    );
+    help_commands.insert(
+        "messages".to_string(),
+        "Print the check, reset and prune-remotes messages as a translation template".to_string(),
+    );
 
     run_plugin(PluginDefinition {
         info: PluginInfo {
@@ -30,6 +169,40 @@ fn main() {
                 "project ls".to_string(),
                 "project check".to_string(),
                 "project dependents".to_string(),
+                "project reset".to_string(),
+                "project remotes".to_string(),
+                "project prune-remotes".to_string(),
+                "project history-cmd".to_string(),
+                "project rerun".to_string(),
+                "project messages".to_string(),
+                "project self-test".to_string(),
+                "project export".to_string(),
+                "project migrate-manifest".to_string(),
+                "project merge-meta".to_string(),
+                "project deprecate".to_string(),
+                "project stats".to_string(),
+                "project diff-lock".to_string(),
+                "project try-merge".to_string(),
+                "project changeset".to_string(),
+                "project cache".to_string(),
+                "project add".to_string(),
+                "project rm".to_string(),
+                "project explain".to_string(),
+                "project rename".to_string(),
+                "project mv".to_string(),
+                "project set-url".to_string(),
+                "project hook".to_string(),
+                "project import".to_string(),
+                "project approve".to_string(),
+                "project mirror".to_string(),
+                "project archive".to_string(),
+                "project lock".to_string(),
+                "project restore".to_string(),
+                "project verify-lock".to_string(),
+                "project use".to_string(),
+                "project fmt".to_string(),
+                "project fetch-all".to_string(),
+                "project self-update".to_string(),
             ],
             description: Some("Project inspection for meta repositories".to_string()),
             help: Some(PluginHelp {
@@ -41,8 +214,10 @@ fn main() {
                     "meta project list --json".to_string(),
                     "meta project list --recursive".to_string(),
                     "meta project check".to_string(),
+                    "meta project check --fetch".to_string(),
+                    "meta project check --fix --yes".to_string(),
                 ],
-                note: Some("To clone missing projects, use: meta git update".to_string()),
+                note: Some("To clone missing projects, use: meta project check --fix".to_string()),
             }),
         },
         execute,
@@ -66,13 +241,38 @@ fn execute(request: PluginRequest) -> CommandResult {
         depth: request.options.depth,
         verbose: request.options.verbose,
         parallel: request.options.parallel,
+        plain: std::env::var("TERM").is_ok_and(|term| term == "dumb"),
+        strict: false,
     };
 
-    meta_project_cli::execute_command(
+    meta_project_cli::history::record(
+        &cwd,
+        &request.command,
+        &request.args,
+        &options,
+        &request.projects,
+    );
+
+    let result = meta_project_cli::execute_command(
         &request.command,
         &request.args,
         &options,
         &request.projects,
         &cwd,
-    )
+    );
+    if request.command != "project self-update" {
+        if let Some(notice) = meta_project_cli::update_notice(&cwd, &options) {
+            eprintln!("{notice}");
+        }
+    }
+    // run_plugin only knows exit code 1; a timeout gets its own
+    if meta_project_cli::timed_out() {
+        match result {
+            CommandResult::Message(msg) => println!("{msg}"),
+            CommandResult::Error(e) => eprintln!("Error: {e}"),
+            _ => {}
+        }
+        std::process::exit(meta_project_cli::TIMEOUT_EXIT_CODE);
+    }
+    result
 }
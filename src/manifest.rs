@@ -0,0 +1,868 @@
+//! Reading, versioning and migrating the raw .meta document
+//!
+//! The manifest carries a `meta_version`; a file without one is version 1.
+//! Older layouts are upgraded in memory on every read, one migration step at
+//! a time, and `meta project migrate-manifest` writes the upgraded layout
+//! back. A manifest newer than this plugin understands is an error rather
+//! than being misread.
+//!
+//! | Version | Layout                                                       |
+//! |---------|--------------------------------------------------------------|
+//! | 1       | project entries are URL strings or objects                   |
+//! | 2       | every project entry is an object with a `repo` key           |
+//!
+//! Besides the JSON and YAML files meta itself reads, a `.meta.toml` is
+//! accepted; it is only used when no other config is present. The format
+//! follows the extension, except that a `.meta` or `.meta.json` whose content
+//! doesn't start with `{` is read (and written back) as YAML. JSON may carry
+//! `//` and `/* */` comments and trailing commas (JSONC).
+//!
+//! Edits to an existing JSON manifest keep its layout: only the changed
+//! parts of the text are rewritten (see [`crate::jsonedit`]), so key order,
+//! indentation and comments survive `add`, `rm`, `set-url` and friends.
+//! YAML and TOML manifests are re-serialized.
+//!
+//! With `settings.sort`, every write also puts the entries of a JSON
+//! manifest's `projects` in that order (see [`SortPolicy`]), and
+//! `meta project fmt` applies it to the file as it stands.
+//!
+//! Writes go to a temp file that is renamed over the manifest, so a crash
+//! never leaves it half written. The replaced content is kept in `.meta.bak`
+//! (named after the manifest), with older copies rotated to `.meta.bak.1`
+//! and `.meta.bak.2`.
+//!
+//! Top-level keys other than the ones this plugin interprets belong to other
+//! tools and are carried through edits untouched. A manifest with a `plugins`
+//! key is shared with the Node `meta` CLI, which only understands project
+//! entries that are URL strings, so entries that are nothing but a `repo`
+//! are written back in that form.
+
+use crate::jsonedit::{self, Section};
+use crate::settings::SortPolicy;
+use crate::{flag_value, ExecuteOptions};
+use anyhow::Context;
+use meta_cli::config::{self, MetaConfig, ProjectEntry, ProjectInfo};
+use meta_plugin_protocol::CommandResult;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The TOML manifest, looked for after meta's own config names
+pub(crate) const TOML_FILE: &str = ".meta.toml";
+
+/// Copies of earlier manifest contents kept by [`write`]
+const BACKUPS: usize = 3;
+
+/// Manifest version written by this plugin
+pub(crate) const CURRENT_VERSION: u64 = 2;
+
+/// A described step that upgrades a manifest by one version
+type Migration = (&'static str, fn(&mut Map<String, Value>));
+
+/// Upgrade from version N to N + 1: `MIGRATIONS[N - 1]`
+///
+/// Append-only: a released migration must never change, or manifests
+/// migrated by older plugins would disagree with freshly migrated ones.
+const MIGRATIONS: &[Migration] = &[("project entries become objects", v1_to_v2)];
+
+fn v1_to_v2(document: &mut Map<String, Value>) {
+    let Some(Value::Object(projects)) = document.get_mut("projects") else {
+        return;
+    };
+    for entry in projects.values_mut() {
+        if let Value::String(url) = entry {
+            *entry = serde_json::json!({ "repo": url });
+        }
+    }
+}
+
+/// Top-level keys this plugin (or meta itself) interprets
+const OWN_KEYS: &[&str] = &["meta_version", "projects", "pending", "ignore", "settings"];
+
+/// Top-level keys written by other tools, e.g. the Node `meta` `plugins` list
+pub(crate) fn extensions(document: &Value) -> Map<String, Value> {
+    match document {
+        Value::Object(fields) => fields
+            .iter()
+            .filter(|(key, _)| !OWN_KEYS.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+        _ => Map::new(),
+    }
+}
+
+/// Whether the Node `meta` CLI reads this manifest too
+fn is_shared_with_node(document: &Value) -> bool {
+    document.get("plugins").is_some()
+}
+
+/// The `meta_version` of a parsed manifest (1 when absent)
+pub(crate) fn version(document: &Value) -> Result<u64, String> {
+    match document.get("meta_version") {
+        None => Ok(1),
+        Some(value) => value
+            .as_u64()
+            .filter(|v| *v >= 1)
+            .ok_or_else(|| format!("Invalid meta_version {value}: expected a positive integer")),
+    }
+}
+
+/// Upgrade `document` to [`CURRENT_VERSION`], returning the steps applied
+pub(crate) fn migrate(document: &mut Value) -> Result<Vec<&'static str>, String> {
+    let from = version(document)?;
+    if from > CURRENT_VERSION {
+        return Err(format!(
+            "Manifest meta_version {from} is newer than this plugin supports \
+             ({CURRENT_VERSION}); upgrade meta-project"
+        ));
+    }
+    let Value::Object(fields) = document else {
+        return Err("Manifest must be an object".to_string());
+    };
+
+    let mut applied = Vec::new();
+    for (description, step) in &MIGRATIONS[(from - 1) as usize..] {
+        step(fields);
+        applied.push(*description);
+    }
+    if !applied.is_empty() {
+        fields.insert("meta_version".to_string(), CURRENT_VERSION.into());
+    }
+    Ok(applied)
+}
+
+/// The .meta config in `dir` itself (parents are not searched)
+pub(crate) fn find(dir: &Path) -> Option<PathBuf> {
+    config::find_meta_config_in(dir)
+        .map(|(path, _format)| path)
+        .or_else(|| Some(dir.join(TOML_FILE)).filter(|path| path.is_file()))
+}
+
+/// Serialization format of a manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+/// The format of the manifest at `meta_path` holding `content`
+fn detect(meta_path: &Path, content: &str) -> Format {
+    let path = meta_path.to_string_lossy();
+    if path.ends_with(".yaml") || path.ends_with(".yml") {
+        Format::Yaml
+    } else if path.ends_with(".toml") {
+        Format::Toml
+    } else if content.trim().is_empty() || strip_jsonc(content).trim_start().starts_with('{') {
+        Format::Json
+    } else {
+        Format::Yaml
+    }
+}
+
+/// The format of the file at `meta_path`, or the one its name implies
+fn format_of(meta_path: &Path) -> Format {
+    detect(
+        meta_path,
+        &std::fs::read_to_string(meta_path).unwrap_or_default(),
+    )
+}
+
+/// `content` with JSONC comments and trailing commas blanked out
+///
+/// Only what lies outside string literals is touched. Every byte keeps its
+/// offset (blanked characters become spaces, line breaks stay), so parse
+/// errors point at the right line and spans map back onto `content`.
+pub(crate) fn strip_jsonc(content: &str) -> String {
+    let blank = |out: &mut String, c: char| match c {
+        '\n' => out.push('\n'),
+        c => out.extend(std::iter::repeat_n(' ', c.len_utf8())),
+    };
+    let mut out = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => {
+                out.push(' ');
+                while let Some(next) = chars.next_if(|&next| next != '\n') {
+                    blank(&mut out, next);
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                out.push_str("  ");
+                let mut last = ' ';
+                for next in chars.by_ref() {
+                    blank(&mut out, next);
+                    if last == '*' && next == '/' {
+                        break;
+                    }
+                    last = next;
+                }
+            }
+            ('}' | ']', _) => {
+                let trimmed = out.trim_end().len();
+                if out[..trimmed].ends_with(',') {
+                    out.replace_range(trimmed - 1..trimmed, " ");
+                }
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Whether meta's own parser reads the config at `meta_path` (strict JSON,
+/// or YAML with a YAML extension)
+pub(crate) fn read_by_meta(meta_path: &Path) -> bool {
+    let content = std::fs::read_to_string(meta_path).unwrap_or_default();
+    match detect(meta_path, &content) {
+        Format::Json => strip_jsonc(&content) == content,
+        Format::Yaml => {
+            let path = meta_path.to_string_lossy();
+            path.ends_with(".yaml") || path.ends_with(".yml")
+        }
+        Format::Toml => false,
+    }
+}
+
+/// The projects (sorted by name) and ignore list of the config at `meta_path`
+///
+/// meta's parser handles strict JSON and YAML by extension; JSONC, TOML and
+/// YAML in a `.meta` file are normalized the same way.
+pub(crate) fn projects(meta_path: &Path) -> anyhow::Result<(Vec<ProjectInfo>, Vec<String>)> {
+    if read_by_meta(meta_path) {
+        return config::parse_meta_config(meta_path);
+    }
+    let content = std::fs::read_to_string(meta_path)
+        .with_context(|| format!("Failed to read meta config file: '{}'", meta_path.display()))?;
+    let parsed: MetaConfig = match detect(meta_path, &content) {
+        Format::Json => serde_json::from_str(&strip_jsonc(&content)).with_context(|| {
+            format!("Failed to parse JSON config file: {}", meta_path.display())
+        })?,
+        Format::Yaml => serde_yaml_ng::from_str(&content).with_context(|| {
+            format!("Failed to parse YAML config file: {}", meta_path.display())
+        })?,
+        Format::Toml => toml::from_str(&content).with_context(|| {
+            format!("Failed to parse TOML config file: {}", meta_path.display())
+        })?,
+    };
+    let mut projects: Vec<ProjectInfo> = parsed
+        .projects
+        .into_iter()
+        .map(|(name, entry)| match entry {
+            ProjectEntry::Simple(url) => ProjectInfo {
+                path: name.clone(),
+                name,
+                repo: Some(url),
+                tags: vec![],
+                provides: vec![],
+                depends_on: vec![],
+                meta: false,
+            },
+            ProjectEntry::Extended {
+                repo,
+                path,
+                tags,
+                provides,
+                depends_on,
+                meta,
+            } => ProjectInfo {
+                path: path.unwrap_or_else(|| name.clone()).replace('\\', "/"),
+                name,
+                repo,
+                tags,
+                provides,
+                depends_on,
+                meta,
+            },
+        })
+        .collect();
+    projects.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok((projects, parsed.ignore))
+}
+
+/// Read a .meta config (JSON, YAML or TOML) as it is on disk
+fn read_raw(meta_path: &Path) -> Result<Value, String> {
+    let content = std::fs::read_to_string(meta_path)
+        .map_err(|e| format!("Failed to read {}: {e}", meta_path.display()))?;
+    match detect(meta_path, &content) {
+        Format::Json => serde_json::from_str(&strip_jsonc(&content)).map_err(|e| e.to_string()),
+        Format::Yaml => serde_yaml_ng::from_str(&content).map_err(|e| e.to_string()),
+        Format::Toml => toml::from_str(&content).map_err(|e| e.to_string()),
+    }
+    .map_err(|e| format!("Failed to parse meta config: {e}"))
+}
+
+/// Read a .meta config, upgraded to the current layout
+pub(crate) fn read(meta_path: &Path) -> Result<Value, String> {
+    let mut document = read_raw(meta_path)?;
+    migrate(&mut document)?;
+    // A manifest shared with Node meta keeps string entries at any version
+    if let Value::Object(fields) = &mut document {
+        v1_to_v2(fields);
+    }
+    Ok(document)
+}
+
+/// Serialize `document` in the format of the file at `meta_path` (or, for a
+/// new file, the format its name implies)
+pub(crate) fn render(meta_path: &Path, document: &Value) -> Result<String, String> {
+    let document = for_disk(document);
+    match format_of(meta_path) {
+        Format::Json => serde_json::to_string_pretty(&document)
+            .map(|json| json + "\n")
+            .map_err(|e| e.to_string()),
+        Format::Yaml => serde_yaml_ng::to_string(&document).map_err(|e| e.to_string()),
+        Format::Toml => toml::to_string(&document).map_err(|e| e.to_string()),
+    }
+    .map_err(|e| format!("Failed to serialize manifest: {e}"))
+}
+
+/// `document` as it is stored: collapsed for manifests shared with Node meta
+fn for_disk(document: &Value) -> std::borrow::Cow<'_, Value> {
+    if is_shared_with_node(document) {
+        std::borrow::Cow::Owned(collapse_entries(document))
+    } else {
+        std::borrow::Cow::Borrowed(document)
+    }
+}
+
+/// The existing JSON text at `meta_path` edited in place to hold `document`
+fn edit_in_place(meta_path: &Path, document: &Value) -> Option<String> {
+    let original = std::fs::read_to_string(meta_path).ok()?;
+    if detect(meta_path, &original) != Format::Json || original.trim().is_empty() {
+        return None;
+    }
+    let on_disk: Value = serde_json::from_str(&strip_jsonc(&original)).ok()?;
+    crate::jsonedit::apply(&original, &on_disk, &for_disk(document))
+}
+
+/// `document` with `{"repo": URL}` project entries turned back into URLs
+fn collapse_entries(document: &Value) -> Value {
+    let mut document = document.clone();
+    if let Some(Value::Object(projects)) = document.get_mut("projects") {
+        for entry in projects.values_mut() {
+            let url = match entry {
+                Value::Object(fields) if fields.len() == 1 => fields.get("repo").cloned(),
+                _ => None,
+            };
+            if let Some(url @ Value::String(_)) = url {
+                *entry = url;
+            }
+        }
+    }
+    document
+}
+
+/// Write `document` to `meta_path`
+///
+/// An existing JSON manifest is edited in place; anything else (or JSON the
+/// edit layer can't handle) is written in the format of [`render`]. The
+/// file is replaced atomically, after backing up its current content.
+pub(crate) fn write(meta_path: &Path, document: &Value) -> Result<(), String> {
+    let rendered = output(meta_path, document, sort_policy(document))?;
+    let previous = std::fs::read(meta_path).ok();
+    if previous.as_deref() == Some(rendered.as_bytes()) {
+        return Ok(());
+    }
+    replace(meta_path, rendered.as_bytes(), previous.is_some())
+        .map_err(|e| format!("Failed to write {}: {e}", meta_path.display()))
+}
+
+/// The text [`write`] puts at `meta_path` for `document`, with the projects
+/// in `policy` order
+fn output(
+    meta_path: &Path,
+    document: &Value,
+    policy: Option<SortPolicy>,
+) -> Result<String, String> {
+    let rendered = match edit_in_place(meta_path, document) {
+        Some(edited) => edited,
+        None => render(meta_path, document)?,
+    };
+    let Some(policy) = policy else {
+        return Ok(rendered);
+    };
+    // YAML and TOML come out of render in name order already
+    Ok(jsonedit::arrange(&rendered, "projects", &sections(document, policy)).unwrap_or(rendered))
+}
+
+/// `settings.sort` of `document`; an invalid value leaves the order alone
+fn sort_policy(document: &Value) -> Option<SortPolicy> {
+    SortPolicy::deserialize(document.pointer("/settings/sort")?).ok()
+}
+
+/// The project names of `document`, grouped and ordered by `policy`
+fn sections(document: &Value, policy: SortPolicy) -> Vec<Section> {
+    let projects = document
+        .get("projects")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten();
+    let mut entries: Vec<(&String, &str, Option<&str>)> = projects
+        .map(|(name, entry)| {
+            let path = entry.get("path").and_then(Value::as_str).unwrap_or(name);
+            let tag = entry
+                .get("tags")
+                .and_then(|tags| tags.get(0))
+                .and_then(Value::as_str);
+            (name, path, tag)
+        })
+        .collect();
+    let section = |header: Option<&str>, entries: Vec<&(&String, &str, Option<&str>)>| Section {
+        header: header.map(str::to_string),
+        keys: entries.iter().map(|(name, ..)| name.to_string()).collect(),
+    };
+    match policy {
+        SortPolicy::Alphabetical => vec![section(None, entries.iter().collect())],
+        SortPolicy::Path => {
+            entries.sort_by_key(|&(name, path, _)| (path, name));
+            vec![section(None, entries.iter().collect())]
+        }
+        SortPolicy::Tag => {
+            // Untagged projects sort after every tag
+            let mut groups: BTreeMap<(bool, &str), Vec<_>> = BTreeMap::new();
+            for entry in &entries {
+                let key = (entry.2.is_none(), entry.2.unwrap_or("untagged"));
+                groups.entry(key).or_default().push(entry);
+            }
+            groups
+                .into_iter()
+                .map(|((_, header), entries)| section(Some(header), entries))
+                .collect()
+        }
+    }
+}
+
+/// `meta_path` with `suffix` appended to its file name
+fn sibling(meta_path: &Path, suffix: &str) -> PathBuf {
+    let name = meta_path.file_name().unwrap_or_default().to_string_lossy();
+    meta_path.with_file_name(format!("{name}{suffix}"))
+}
+
+/// Write `content` to a temp file, back up the current manifest and rename
+/// the temp file over it
+fn replace(meta_path: &Path, content: &[u8], back_up: bool) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let tmp = sibling(meta_path, ".tmp");
+    let written = std::fs::File::create(&tmp).and_then(|mut file| {
+        file.write_all(content)?;
+        file.sync_all()
+    });
+    let result = written
+        .and_then(|()| {
+            if back_up {
+                rotate_backups(meta_path)
+            } else {
+                Ok(())
+            }
+        })
+        .and_then(|()| std::fs::rename(&tmp, meta_path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Shift `.bak` → `.bak.1` → `.bak.2` and copy the manifest to `.bak`
+fn rotate_backups(meta_path: &Path) -> std::io::Result<()> {
+    let backup = |index: usize| match index {
+        0 => sibling(meta_path, ".bak"),
+        n => sibling(meta_path, &format!(".bak.{n}")),
+    };
+    for index in (1..BACKUPS).rev() {
+        if backup(index - 1).exists() {
+            std::fs::rename(backup(index - 1), backup(index))?;
+        }
+    }
+    std::fs::copy(meta_path, backup(0)).map(|_| ())
+}
+
+/// Handle `meta project migrate-manifest`
+pub(crate) fn handle_migrate_manifest(options: &ExecuteOptions, cwd: &Path) -> CommandResult {
+    let Some(meta_path) = find(cwd) else {
+        return CommandResult::Error(format!("No .meta config found in {}", cwd.display()));
+    };
+    let mut document = match read_raw(&meta_path) {
+        Ok(document) => document,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let from = match version(&document) {
+        Ok(from) => from,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let applied = match migrate(&mut document) {
+        Ok(applied) => applied,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let name = meta_path.file_name().unwrap_or_default().to_string_lossy();
+    if applied.is_empty() {
+        return CommandResult::Message(format!(
+            "{name} is already at meta_version {CURRENT_VERSION}."
+        ));
+    }
+
+    let steps: Vec<String> = applied.iter().map(|step| format!("  - {step}")).collect();
+    if options.dry_run {
+        return CommandResult::Message(format!(
+            "Dry run: would migrate {name} from meta_version {from} to {CURRENT_VERSION}:\n{}",
+            steps.join("\n")
+        ));
+    }
+    if let Err(e) = write(&meta_path, &document) {
+        return CommandResult::Error(e);
+    }
+    CommandResult::Message(format!(
+        "Migrated {name} from meta_version {from} to {CURRENT_VERSION}:\n{}",
+        steps.join("\n")
+    ))
+}
+
+/// Handle `meta project fmt [--sort <policy>] [--check]`
+pub(crate) fn handle_fmt(args: &[String], options: &ExecuteOptions, cwd: &Path) -> CommandResult {
+    let Some(meta_path) = find(cwd) else {
+        return CommandResult::Error(format!("No .meta config found in {}", cwd.display()));
+    };
+    let document = match read_raw(&meta_path) {
+        Ok(document) => document,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let policy = match flag_value(args, "--sort") {
+        Some(value) => match SortPolicy::parse(value) {
+            Some(policy) => policy,
+            None => {
+                return CommandResult::Error(format!(
+                    "Invalid --sort value '{value}': expected alphabetical, path or tag"
+                ))
+            }
+        },
+        None => match sort_policy(&document) {
+            Some(policy) => policy,
+            None => return CommandResult::Error(
+                "No sort policy: set settings.sort in .meta or pass --sort alphabetical|path|tag"
+                    .to_string(),
+            ),
+        },
+    };
+    let formatted = match output(&meta_path, &document, Some(policy)) {
+        Ok(formatted) => formatted,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let name = meta_path.file_name().unwrap_or_default().to_string_lossy();
+    let current = std::fs::read_to_string(&meta_path).unwrap_or_default();
+    if formatted == current {
+        return CommandResult::Message(format!("{name} is already in {} order.", policy.name()));
+    }
+    if args.iter().any(|a| a == "--check") {
+        return CommandResult::Error(format!(
+            "{name} is not in {} order; run 'meta project fmt' to fix it.",
+            policy.name()
+        ));
+    }
+    if options.dry_run {
+        return CommandResult::Message(format!(
+            "Dry run: would put {name} in {} order.",
+            policy.name()
+        ));
+    }
+    match replace(&meta_path, formatted.as_bytes(), true) {
+        Ok(()) => CommandResult::Message(format!("Put {name} in {} order.", policy.name())),
+        Err(e) => CommandResult::Error(format!("Failed to write {}: {e}", meta_path.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_migrate_v1_strings_to_objects() {
+        let mut document = serde_json::json!({"projects": {
+            "a": "git@github.com:org/a.git",
+            "b": {"repo": "git@github.com:org/b.git", "path": "libs/b"}
+        }});
+        assert_eq!(
+            migrate(&mut document),
+            Ok(vec!["project entries become objects"])
+        );
+        assert_eq!(
+            document,
+            serde_json::json!({"meta_version": 2, "projects": {
+                "a": {"repo": "git@github.com:org/a.git"},
+                "b": {"repo": "git@github.com:org/b.git", "path": "libs/b"}
+            }})
+        );
+        assert_eq!(migrate(&mut document), Ok(vec![]));
+        assert_eq!(MIGRATIONS.len() as u64, CURRENT_VERSION - 1);
+    }
+
+    #[test]
+    fn test_write_keeps_rotating_backups() {
+        let temp_dir = TempDir::new().unwrap();
+        let meta = temp_dir.path().join(".meta");
+        let version = |n: u64| serde_json::json!({"projects": {}, "meta_version": 2, "n": n});
+        write(&meta, &version(0)).unwrap();
+        assert!(!temp_dir.path().join(".meta.bak").exists());
+
+        for n in 1..=4 {
+            write(&meta, &version(n)).unwrap();
+        }
+        let backup = |name: &str| read_raw(&temp_dir.path().join(name)).unwrap()["n"].clone();
+        assert_eq!(backup(".meta.bak"), 3);
+        assert_eq!(backup(".meta.bak.1"), 2);
+        assert_eq!(backup(".meta.bak.2"), 1);
+        assert!(!temp_dir.path().join(".meta.bak.3").exists());
+        assert!(!temp_dir.path().join(".meta.tmp").exists());
+
+        // An unchanged write doesn't rotate
+        write(&meta, &version(4)).unwrap();
+        assert_eq!(backup(".meta.bak"), 3);
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            detect(Path::new(".meta"), "{\"projects\": {}}"),
+            Format::Json
+        );
+        assert_eq!(detect(Path::new(".meta"), ""), Format::Json);
+        assert_eq!(
+            detect(Path::new(".meta"), "projects:\n  a: x\n"),
+            Format::Yaml
+        );
+        assert_eq!(
+            detect(Path::new(".meta.json"), "# team\nprojects: {}"),
+            Format::Yaml
+        );
+        assert_eq!(detect(Path::new(".meta.yml"), "{}"), Format::Yaml);
+        assert_eq!(detect(Path::new(".meta.toml"), "[projects]"), Format::Toml);
+    }
+
+    #[test]
+    fn test_strip_jsonc() {
+        let content = "{\n  // team repos\n  \"projects\": {\"a\": \"http://x/a.git\",},\n  /* old\n */ \"ignore\": [\"b\",],\n}";
+        let stripped = strip_jsonc(content);
+        assert_eq!(stripped.len(), content.len());
+        assert_eq!(stripped.lines().count(), content.lines().count());
+        assert_eq!(
+            serde_json::from_str::<Value>(&stripped).unwrap(),
+            serde_json::json!({"projects": {"a": "http://x/a.git"}, "ignore": ["b"]})
+        );
+        assert_eq!(strip_jsonc(r#"{"a": "//,]"}"#), r#"{"a": "//,]"}"#);
+    }
+
+    #[test]
+    fn test_jsonc_manifest() {
+        let temp_dir = TempDir::new().unwrap();
+        let meta = temp_dir.path().join(".meta");
+        std::fs::write(
+            &meta,
+            "// Workspace\n{\"projects\": {\n  \"a\": \"git@github.com:org/a.git\", // core\n}}\n",
+        )
+        .unwrap();
+        assert!(!read_by_meta(&meta));
+        let (found, _) = projects(&meta).unwrap();
+        assert_eq!(found[0].repo.as_deref(), Some("git@github.com:org/a.git"));
+        let mut document = read(&meta).unwrap();
+        assert_eq!(
+            document["projects"]["a"]["repo"],
+            "git@github.com:org/a.git"
+        );
+
+        // Writing edits the text in place, so the comments survive
+        document["projects"]["b"] = serde_json::json!({"repo": "git@github.com:org/b.git"});
+        write(&meta, &document).unwrap();
+        let written = std::fs::read_to_string(&meta).unwrap();
+        assert!(written.starts_with("// Workspace\n{"), "{written}");
+        assert!(written.contains("// core"), "{written}");
+        assert_eq!(projects(&meta).unwrap().0.len(), 2);
+    }
+
+    #[test]
+    fn test_yaml_in_meta_stays_yaml() {
+        let temp_dir = TempDir::new().unwrap();
+        let meta = temp_dir.path().join(".meta");
+        std::fs::write(&meta, "projects:\n  a: git@github.com:org/a.git\n").unwrap();
+
+        let (found, _) = projects(&meta).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].repo.as_deref(), Some("git@github.com:org/a.git"));
+
+        let mut document = read(&meta).unwrap();
+        document["projects"]["b"] = serde_json::json!({"repo": "git@github.com:org/b.git"});
+        write(&meta, &document).unwrap();
+        assert_eq!(format_of(&meta), Format::Yaml);
+        let (found, _) = projects(&meta).unwrap();
+        assert_eq!(found.len(), 2);
+    }
+
+    #[test]
+    fn test_node_manifest_round_trips() {
+        let temp_dir = TempDir::new().unwrap();
+        let meta = temp_dir.path().join(".meta");
+        std::fs::write(
+            &meta,
+            r#"{"projects": {"a": "git@github.com:org/a.git"}, "plugins": ["meta-npm"], "x-team": "core"}"#,
+        )
+        .unwrap();
+
+        let mut document = read(&meta).unwrap();
+        assert_eq!(
+            document["projects"]["a"]["repo"],
+            "git@github.com:org/a.git"
+        );
+        assert_eq!(
+            Value::Object(extensions(&document)),
+            serde_json::json!({"plugins": ["meta-npm"], "x-team": "core"})
+        );
+        document["projects"]["b"] =
+            serde_json::json!({"repo": "git@github.com:org/b.git", "tags": ["x"]});
+        write(&meta, &document).unwrap();
+
+        assert_eq!(
+            read_raw(&meta).unwrap(),
+            serde_json::json!({
+                "meta_version": 2,
+                "projects": {
+                    "a": "git@github.com:org/a.git",
+                    "b": {"repo": "git@github.com:org/b.git", "tags": ["x"]}
+                },
+                "plugins": ["meta-npm"],
+                "x-team": "core"
+            })
+        );
+        assert_eq!(
+            read(&meta).unwrap()["projects"]["a"]["repo"],
+            "git@github.com:org/a.git"
+        );
+    }
+
+    #[test]
+    fn test_newer_manifest_is_rejected() {
+        let mut document = serde_json::json!({"meta_version": 99, "projects": {}});
+        assert!(migrate(&mut document)
+            .unwrap_err()
+            .contains("newer than this plugin supports"));
+        let mut document = serde_json::json!({"meta_version": "2"});
+        assert!(migrate(&mut document)
+            .unwrap_err()
+            .contains("Invalid meta_version"));
+    }
+
+    #[test]
+    fn test_migrate_manifest_rewrites_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let meta = temp_dir.path().join(".meta");
+        std::fs::write(&meta, r#"{"projects": {"a": "git@github.com:org/a.git"}}"#).unwrap();
+
+        let dry_run = ExecuteOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            handle_migrate_manifest(&dry_run, temp_dir.path()),
+            CommandResult::Message(msg) if msg.starts_with("Dry run")
+        ));
+        assert_eq!(version(&read_raw(&meta).unwrap()), Ok(1));
+
+        match handle_migrate_manifest(&ExecuteOptions::default(), temp_dir.path()) {
+            CommandResult::Message(msg) => {
+                assert!(msg.starts_with("Migrated .meta from meta_version 1 to 2"))
+            }
+            _ => panic!("Expected Message result"),
+        }
+        assert_eq!(version(&read_raw(&meta).unwrap()), Ok(2));
+        match handle_migrate_manifest(&ExecuteOptions::default(), temp_dir.path()) {
+            CommandResult::Message(msg) => assert!(msg.contains("already at meta_version 2")),
+            _ => panic!("Expected Message result"),
+        }
+    }
+
+    #[test]
+    fn test_sort_policy_on_write_and_fmt() {
+        let temp_dir = TempDir::new().unwrap();
+        let meta = temp_dir.path().join(".meta");
+        std::fs::write(
+            &meta,
+            r#"{
+  "projects": {
+    "zeta": {"repo": "git@x:zeta.git", "path": "a/zeta"},
+    "alpha": {"repo": "git@x:alpha.git", "path": "b/alpha", "tags": ["libs"]}
+  }
+}
+"#,
+        )
+        .unwrap();
+        let run = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+            handle_fmt(&args, &ExecuteOptions::default(), temp_dir.path())
+        };
+        assert!(matches!(run(&[]), CommandResult::Error(e) if e.starts_with("No sort policy")));
+        assert!(
+            matches!(run(&["--sort", "path"]), CommandResult::Message(msg) if msg == ".meta is already in path order.")
+        );
+        assert!(matches!(
+            run(&["--sort", "alphabetical", "--check"]),
+            CommandResult::Error(_)
+        ));
+
+        // With settings.sort, every write keeps the order
+        let mut document = read(&meta).unwrap();
+        document["settings"] = serde_json::json!({"sort": "tag"});
+        document["projects"]["beta"] =
+            serde_json::json!({"repo": "git@x:beta.git", "tags": ["libs"]});
+        write(&meta, &document).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&meta).unwrap(),
+            r#"{
+  "projects": {
+    // --- libs ---
+    "alpha": {"repo": "git@x:alpha.git", "path": "b/alpha", "tags": ["libs"]},
+    "beta": {
+      "repo": "git@x:beta.git",
+      "tags": [
+        "libs"
+      ]
+    },
+
+    // --- untagged ---
+    "zeta": {"repo": "git@x:zeta.git", "path": "a/zeta"}
+  },
+  "meta_version": 2,
+  "settings": {
+    "sort": "tag"
+  }
+}
+"#
+        );
+        assert!(
+            matches!(run(&["--check"]), CommandResult::Message(msg) if msg == ".meta is already in tag order.")
+        );
+        match run(&["--sort", "alphabetical"]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Put .meta in alphabetical order."),
+            _ => panic!("Expected Message result"),
+        }
+        let content = std::fs::read_to_string(&meta).unwrap();
+        assert!(!content.contains("// ---"), "{content}");
+        assert!(content.find("\"beta\"") < content.find("\"zeta\""));
+    }
+}
@@ -0,0 +1,395 @@
+//! `meta project merge-meta` — semantic three-way merge of .meta
+//!
+//! Meant to run as a git merge driver, so concurrent additions of different
+//! projects (or edits to different fields of one project) merge cleanly
+//! instead of conflicting on neighbouring JSON lines:
+//!
+//! ```text
+//! git config merge.meta.driver "meta project merge-meta %A %B %O"
+//! echo ".meta merge=meta" >> .gitattributes
+//! ```
+//!
+//! The merged manifest replaces `<ours>`. A field both sides changed
+//! differently is a conflict: `<ours>` keeps our value and the command fails,
+//! so git reports the file as conflicted. With `--interactive` each conflict
+//! is shown with who last changed the manifest on each side and, for URLs,
+//! whether they are reachable, and the chosen value is written instead.
+
+use crate::manifest;
+use crate::{git, prompt_tty, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// A field both sides changed differently
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Conflict {
+    /// Object keys leading to the field, e.g. `["projects", "api", "repo"]`
+    pub keys: Vec<String>,
+    pub base: Option<Value>,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+}
+
+impl Conflict {
+    /// Dotted location, e.g. `projects.api.repo`
+    fn path(&self) -> String {
+        self.keys.join(".")
+    }
+}
+
+/// Handle `meta project merge-meta <ours> <theirs> <base>`
+pub(crate) fn handle_merge_meta(
+    args: &[String],
+    options: &ExecuteOptions,
+    cwd: &Path,
+) -> CommandResult {
+    let files: Vec<&String> = args.iter().filter(|a| !a.starts_with('-')).collect();
+    let [ours_path, theirs_path, base_path] = files[..] else {
+        return CommandResult::Error(
+            "Usage: meta project merge-meta <ours> <theirs> <base>".to_string(),
+        );
+    };
+    let ours_path = cwd.join(ours_path);
+
+    let read = |path: &Path| -> Result<(Value, bool), String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let (mut document, yaml) =
+            parse(&content).map_err(|e| format!("Failed to parse {}: {e}", path.display()))?;
+        manifest::migrate(&mut document)?;
+        Ok((document, yaml))
+    };
+    let sides = read(&ours_path).and_then(|ours| {
+        Ok((
+            ours,
+            read(&cwd.join(theirs_path))?.0,
+            read(&cwd.join(base_path))?.0,
+        ))
+    });
+    let ((ours, yaml), theirs, base) = match sides {
+        Ok(sides) => sides,
+        Err(e) => return CommandResult::Error(e),
+    };
+
+    let (mut merged, mut conflicts) = merge(&base, &ours, &theirs);
+    if !conflicts.is_empty() && args.iter().any(|a| a == "--interactive" || a == "-i") {
+        conflicts = resolve_interactively(&mut merged, conflicts, cwd);
+    }
+    let rendered = if yaml {
+        serde_yaml_ng::to_string(&merged).map_err(|e| e.to_string())
+    } else {
+        serde_json::to_string_pretty(&merged)
+            .map(|json| json + "\n")
+            .map_err(|e| e.to_string())
+    };
+    let rendered = match rendered {
+        Ok(rendered) => rendered,
+        Err(e) => return CommandResult::Error(format!("Failed to serialize manifest: {e}")),
+    };
+
+    if !options.dry_run {
+        if let Err(e) = std::fs::write(&ours_path, rendered) {
+            return CommandResult::Error(format!("Failed to write {}: {e}", ours_path.display()));
+        }
+    }
+    if conflicts.is_empty() {
+        return CommandResult::Message("Merged .meta without conflicts.".to_string());
+    }
+    let lines: Vec<String> = conflicts.iter().map(describe).collect();
+    CommandResult::Error(format!(
+        "{} conflict(s) in .meta (our side kept):\n{}",
+        conflicts.len(),
+        lines.join("\n")
+    ))
+}
+
+/// Parse a manifest of unknown format: git hands the driver temp files
+/// without the original extension. Returns whether it was YAML.
+fn parse(content: &str) -> Result<(Value, bool), String> {
+    match serde_json::from_str(&manifest::strip_jsonc(content)) {
+        Ok(document) => Ok((document, false)),
+        Err(json_error) => serde_yaml_ng::from_str(content)
+            .map(|document| (document, true))
+            .map_err(|_| json_error.to_string()),
+    }
+}
+
+fn show(value: &Option<Value>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "(removed)".to_string(),
+    }
+}
+
+fn describe(conflict: &Conflict) -> String {
+    format!(
+        "  {}: ours {}, theirs {} (base {})",
+        conflict.path(),
+        show(&conflict.ours),
+        show(&conflict.theirs),
+        show(&conflict.base)
+    )
+}
+
+/// Three-way merge of two manifests descended from `base`
+///
+/// Objects merge key by key, so the project sets are unioned and only a
+/// field changed differently on both sides conflicts; conflicts keep ours.
+pub(crate) fn merge(base: &Value, ours: &Value, theirs: &Value) -> (Value, Vec<Conflict>) {
+    let mut conflicts = Vec::new();
+    let merged = merge_value(
+        &mut Vec::new(),
+        Some(base),
+        Some(ours),
+        Some(theirs),
+        &mut conflicts,
+    );
+    (merged.unwrap_or(Value::Null), conflicts)
+}
+
+fn merge_value(
+    keys: &mut Vec<String>,
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    conflicts: &mut Vec<Conflict>,
+) -> Option<Value> {
+    if ours == theirs || base == theirs {
+        return ours.cloned();
+    }
+    if base == ours {
+        return theirs.cloned();
+    }
+    if let (Some(Value::Object(o)), Some(Value::Object(t))) = (ours, theirs) {
+        let empty = Map::new();
+        let b = match base {
+            Some(Value::Object(b)) => b,
+            _ => &empty,
+        };
+        let mut merged = o.clone();
+        for key in o.keys().chain(t.keys().filter(|k| !o.contains_key(*k))) {
+            keys.push(key.clone());
+            match merge_value(keys, b.get(key), o.get(key), t.get(key), conflicts) {
+                Some(value) => merged.insert(key.clone(), value),
+                None => merged.remove(key),
+            };
+            keys.pop();
+        }
+        return Some(Value::Object(merged));
+    }
+    conflicts.push(Conflict {
+        keys: keys.clone(),
+        base: base.cloned(),
+        ours: ours.cloned(),
+        theirs: theirs.cloned(),
+    });
+    ours.cloned()
+}
+
+// ============================================================================
+// Interactive Resolution
+// ============================================================================
+
+/// Side of a conflict to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Choice {
+    Ours,
+    Theirs,
+    Base,
+}
+
+fn parse_choice(answer: &str) -> Option<Choice> {
+    match answer.to_lowercase().as_str() {
+        "o" | "ours" => Some(Choice::Ours),
+        "t" | "theirs" => Some(Choice::Theirs),
+        "b" | "base" => Some(Choice::Base),
+        _ => None,
+    }
+}
+
+/// Ask about each conflict on the terminal; returns the ones left unresolved
+fn resolve_interactively(
+    merged: &mut Value,
+    conflicts: Vec<Conflict>,
+    cwd: &Path,
+) -> Vec<Conflict> {
+    let file = manifest::find(cwd)
+        .and_then(|path| Some(path.file_name()?.to_string_lossy().to_string()))
+        .unwrap_or_else(|| ".meta".to_string());
+    let last_change = |rev: &str| {
+        git::output(cwd, &["log", "-1", "--format=%an, %ar", rev, "--", &file])
+            .filter(|line| !line.is_empty())
+    };
+    let ours_by = last_change("HEAD");
+    let theirs_by = last_change("MERGE_HEAD");
+
+    let total = conflicts.len();
+    let mut unresolved = Vec::new();
+    for (i, conflict) in conflicts.into_iter().enumerate() {
+        println!("Conflict {} of {total}: {}", i + 1, conflict.path());
+        for (label, value, by) in [
+            ("base", &conflict.base, None),
+            ("ours", &conflict.ours, ours_by.as_deref()),
+            ("theirs", &conflict.theirs, theirs_by.as_deref()),
+        ] {
+            let mut line = format!("  {label:<7}{}", show(value));
+            if let Some(by) = by {
+                line.push_str(&format!("  (last changed by {by})"));
+            }
+            if let Some(Value::String(url)) = value {
+                if conflict.keys.last().is_some_and(|k| k == "repo") {
+                    let state = if url_reachable(url, cwd) {
+                        "reachable"
+                    } else {
+                        "not reachable"
+                    };
+                    line.push_str(&format!(" [{state}]"));
+                }
+            }
+            println!("{line}");
+        }
+
+        let choice = prompt_tty("Keep [o]urs, [t]heirs or [b]ase (anything else skips)? ")
+            .and_then(|answer| parse_choice(&answer));
+        let value = match choice {
+            Some(Choice::Ours) => conflict.ours.clone(),
+            Some(Choice::Theirs) => conflict.theirs.clone(),
+            Some(Choice::Base) => conflict.base.clone(),
+            None => {
+                unresolved.push(conflict);
+                continue;
+            }
+        };
+        set_path(merged, &conflict.keys, value);
+    }
+    unresolved
+}
+
+/// Whether `url` answers `git ls-remote`, without prompting for credentials
+fn url_reachable(url: &str, cwd: &Path) -> bool {
+    git::run_bounded(
+        cwd,
+        &["ls-remote", "--quiet", "--exit-code", url, "HEAD"],
+        None,
+    )
+    .is_ok()
+}
+
+/// Set (or with `None`, remove) the field at `keys`
+fn set_path(document: &mut Value, keys: &[String], value: Option<Value>) {
+    let Some((last, parents)) = keys.split_last() else {
+        return;
+    };
+    let mut current = document;
+    for key in parents {
+        let Some(next) = current.get_mut(key) else {
+            return;
+        };
+        current = next;
+    }
+    if let Value::Object(fields) = current {
+        match value {
+            Some(value) => fields.insert(last.clone(), value),
+            None => fields.remove(last),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::init_upstream;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_merge_unions_projects_and_fields() {
+        let base = json!({"projects": {"a": {"repo": "git@x:a.git"}, "b": "git@x:b.git"}});
+        let ours = json!({"projects": {
+            "a": {"repo": "git@x:a.git", "tags": ["web"]},
+            "b": "git@x:b.git",
+            "c": "git@x:c.git"
+        }});
+        let theirs = json!({"projects": {
+            "a": {"repo": "git@x:a-renamed.git"},
+            "d": "git@x:d.git"
+        }});
+
+        let (merged, conflicts) = merge(&base, &ours, &theirs);
+        assert!(conflicts.is_empty(), "{conflicts:?}");
+        assert_eq!(
+            merged,
+            json!({"projects": {
+                "a": {"repo": "git@x:a-renamed.git", "tags": ["web"]},
+                "c": "git@x:c.git",
+                "d": "git@x:d.git"
+            }})
+        );
+    }
+
+    #[test]
+    fn test_merge_reports_diverging_fields() {
+        let base = json!({"projects": {"a": {"repo": "git@x:a.git"}}});
+        let ours = json!({"projects": {"a": {"repo": "git@x:ours.git"}}});
+        let theirs = json!({"projects": {"a": {"repo": "git@x:theirs.git"}}});
+
+        let (merged, conflicts) = merge(&base, &ours, &theirs);
+        assert_eq!(merged, ours);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].path(), "projects.a.repo");
+        assert_eq!(conflicts[0].theirs, Some(json!("git@x:theirs.git")));
+    }
+
+    #[test]
+    fn test_resolution_helpers() {
+        assert_eq!(parse_choice("T"), Some(Choice::Theirs));
+        assert_eq!(parse_choice("ours"), Some(Choice::Ours));
+        assert_eq!(parse_choice(""), None);
+
+        let mut document = json!({"projects": {"a": {"repo": "x", "branch": "dev"}}});
+        let keys = |path: &str| path.split('.').map(String::from).collect::<Vec<_>>();
+        set_path(&mut document, &keys("projects.a.repo"), Some(json!("y")));
+        set_path(&mut document, &keys("projects.a.branch"), None);
+        assert_eq!(document, json!({"projects": {"a": {"repo": "y"}}}));
+
+        let temp_dir = TempDir::new().unwrap();
+        let upstream = init_upstream(temp_dir.path());
+        assert!(url_reachable(&upstream.to_string_lossy(), temp_dir.path()));
+        assert!(!url_reachable(
+            &temp_dir.path().join("missing.git").to_string_lossy(),
+            temp_dir.path()
+        ));
+    }
+
+    #[test]
+    fn test_merge_meta_writes_ours() {
+        let temp_dir = TempDir::new().unwrap();
+        let write = |name: &str, content: &str| std::fs::write(temp_dir.path().join(name), content);
+        // A v1 string entry and its v2 object form are the same project
+        write("base", r#"{"projects": {"a": "git@x:a.git"}}"#).unwrap();
+        write(
+            "ours",
+            r#"{"projects": {"a": "git@x:a.git", "b": "git@x:b.git"}}"#,
+        )
+        .unwrap();
+        write(
+            "theirs",
+            r#"{"meta_version": 2, "projects": {"a": {"repo": "git@x:a.git"}, "c": {"repo": "git@x:c.git"}}}"#,
+        )
+        .unwrap();
+
+        let args = ["ours", "theirs", "base"].map(String::from);
+        match handle_merge_meta(&args, &ExecuteOptions::default(), temp_dir.path()) {
+            CommandResult::Message(_) => {}
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        let merged: Value =
+            serde_json::from_str(&std::fs::read_to_string(temp_dir.path().join("ours")).unwrap())
+                .unwrap();
+        let projects = merged["projects"].as_object().unwrap();
+        assert_eq!(projects.len(), 3);
+        assert_eq!(merged["meta_version"], 2);
+    }
+}
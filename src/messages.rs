@@ -0,0 +1,192 @@
+//! User-facing message catalog
+//!
+//! The result messages of `check`, `reset` and `prune-remotes` are looked up
+//! by a stable ID so they can be translated; other commands' output is
+//! English only. English is built in; other languages are JSON objects mapping IDs to
+//! templates, read from `$XDG_CONFIG_HOME/meta-project/locales/<lang>.json`
+//! (or `~/.config/...`). The language comes from `META_PROJECT_LANG`, then the
+//! usual `LC_ALL`, `LC_MESSAGES` and `LANG`. Missing IDs fall back to English.
+//!
+//! Templates use `{name}` placeholders. `meta project messages` prints the
+//! English catalog as a starting point for a translation.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Built-in English templates, by message ID
+///
+/// IDs are part of the interface: translations and scripts key on them, so
+/// existing IDs must not be renamed or given different placeholders.
+const ENGLISH: &[(&str, &str)] = &[
+    ("check.all_present", "All projects are cloned and present."),
+    (
+        "check.missing",
+        "{count} project(s) missing. Run 'meta project check --fix' to clone them.",
+    ),
+    (
+        "check.archive",
+        "{count} archive project(s) are not unpacked at the checksum in .meta.",
+    ),
+    (
+        "check.remote_mismatch",
+        "{count} project(s) have an origin remote that differs from .meta.",
+    ),
+    (
+        "check.pinned",
+        "{count} project(s) are not on the ref .meta pins them to.",
+    ),
+    (
+        "check.upstream",
+        "{count} project(s) have a branch without correct upstream tracking.",
+    ),
+    (
+        "check.deleted_branch",
+        "{count} project(s) have no local branch for origin's default branch.",
+    ),
+    (
+        "check.behind",
+        "{count} project(s) are behind their upstream.",
+    ),
+    (
+        "check.stale",
+        "{count} project(s) haven't been fetched within settings.max_stale_days.",
+    ),
+    ("check.rules", "{count} finding(s) from custom check rules."),
+    (
+        "check.fix_hint",
+        "Run 'meta project check --fix' to apply safe remediations.",
+    ),
+    (
+        "check.fix_dry_run",
+        "Dry run: {count} fix(es) would be applied.",
+    ),
+    ("check.fix_applied", "Applied {count} fix(es)."),
+    ("check.fix_skipped", "Skipped {count}."),
+    ("reset.none", "No cloned projects to reset."),
+    (
+        "reset.dry_run",
+        "Dry run: {count} project(s) would be reset.",
+    ),
+    ("reset.cancelled", "Reset cancelled."),
+    ("reset.done", "Reset {count} project(s)."),
+    (
+        "prune.pruned",
+        "Pruned {count} remote-tracking branch(es) across {projects} project(s).",
+    ),
+    ("prune.deleted", "Deleted {count} local branch(es)."),
+    ("prune.kept", "Left local branches untouched."),
+    (
+        "prune.unmerged",
+        "Kept {count} branch(es) with unmerged commits ({branches}); re-run with --force to delete them.",
+    ),
+];
+
+/// The message for `id` in the active language, with `{name}` placeholders filled
+pub(crate) fn text(id: &str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+    let template = translations()
+        .get(id)
+        .map(String::as_str)
+        .or_else(|| english(id))
+        .unwrap_or(id);
+    fill(template, args)
+}
+
+/// The built-in English catalog, for `meta project messages`
+pub(crate) fn catalog() -> BTreeMap<&'static str, &'static str> {
+    ENGLISH.iter().copied().collect()
+}
+
+fn english(id: &str) -> Option<&'static str> {
+    ENGLISH.iter().find(|(key, _)| *key == id).map(|(_, t)| *t)
+}
+
+fn fill(template: &str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+    let mut out = template.to_string();
+    for (name, value) in args {
+        out = out.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    out
+}
+
+fn translations() -> &'static HashMap<String, String> {
+    static TRANSLATIONS: OnceLock<HashMap<String, String>> = OnceLock::new();
+    TRANSLATIONS.get_or_init(|| {
+        language(|var| std::env::var(var).ok())
+            .and_then(|lang| {
+                let path = locales_dir()?.join(format!("{lang}.json"));
+                let content = std::fs::read_to_string(path).ok()?;
+                serde_json::from_str(&content).ok()
+            })
+            .unwrap_or_default()
+    })
+}
+
+/// The requested language code (`de` for `de_DE.UTF-8`); `None` means English
+fn language(var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let value = ["META_PROJECT_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| var(name))
+        .find(|value| !value.is_empty())?;
+    let lang = value
+        .split(['_', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    match lang.as_str() {
+        "" | "c" | "posix" | "en" => None,
+        _ => Some(lang),
+    }
+}
+
+fn locales_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("meta-project").join("locales"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_placeholders() {
+        assert_eq!(
+            fill(
+                english("prune.pruned").unwrap(),
+                &[("count", &3), ("projects", &2)]
+            ),
+            "Pruned 3 remote-tracking branch(es) across 2 project(s)."
+        );
+    }
+
+    #[test]
+    fn test_language_selection() {
+        let env = |pairs: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                pairs
+                    .iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        assert_eq!(
+            language(env(&[("LANG", "de_DE.UTF-8")])),
+            Some("de".to_string())
+        );
+        assert_eq!(
+            language(env(&[("META_PROJECT_LANG", "fr"), ("LANG", "de_DE.UTF-8")])),
+            Some("fr".to_string())
+        );
+        assert_eq!(language(env(&[("LC_ALL", ""), ("LANG", "C.UTF-8")])), None);
+        assert_eq!(language(env(&[("LANG", "en_US.UTF-8")])), None);
+        assert_eq!(language(env(&[])), None);
+    }
+
+    #[test]
+    fn test_catalog_ids_are_unique() {
+        assert_eq!(catalog().len(), ENGLISH.len());
+    }
+}
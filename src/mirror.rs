@@ -0,0 +1,238 @@
+//! `meta project mirror push` — replicate every project to a backup server
+//!
+//! Each cloned project is pushed to the URL its `--remote` template gives
+//! it: origin's branches (as of the last fetch) become branches there, and
+//! tags are copied. Destination repositories must already exist; this
+//! plugin has no forge API to create them.
+
+use crate::pool::{default_jobs, parallel_map_grouped};
+use crate::summary::{RowStatus, RunSummary, Span};
+use crate::{
+    deadline, flag_value, git, manifest, parse_jobs, progress, settings, style, ExecuteOptions,
+};
+use meta_plugin_protocol::CommandResult;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// Handle `meta project mirror push --remote <template> [--ref <branch>]... [-j N]`
+pub(crate) fn handle_mirror(
+    args: &[String],
+    options: &ExecuteOptions,
+    cwd: &Path,
+    summary: &mut RunSummary,
+) -> CommandResult {
+    let usage =
+        "Usage: meta project mirror push --remote <url-template> [--ref <branch>]... [-j N]";
+    if args.first().map(String::as_str) != Some("push") {
+        return CommandResult::Error(usage.to_string());
+    }
+    let Some(template) = flag_value(args, "--remote") else {
+        return CommandResult::Error(usage.to_string());
+    };
+    let jobs = match parse_jobs(args) {
+        Ok(jobs) => jobs.unwrap_or_else(default_jobs),
+        Err(e) => return CommandResult::Error(e),
+    };
+    let branches = branch_patterns(args);
+    let groups = match settings::concurrency_groups(cwd) {
+        Ok(groups) => groups,
+        Err(e) => return CommandResult::Error(e),
+    };
+
+    let Some(meta_path) = manifest::find(cwd) else {
+        return CommandResult::Error(format!("No .meta config found in {}", cwd.display()));
+    };
+    let projects = match manifest::projects(&meta_path) {
+        Ok((projects, _)) => projects,
+        Err(e) => return CommandResult::Error(format!("Failed to parse meta config: {e}")),
+    };
+    let targets: Vec<(String, String)> = projects
+        .into_iter()
+        .filter(|p| git::is_repo_root(&cwd.join(&p.path)))
+        .map(|p| {
+            let url = destination(template, &p.name, &p.path);
+            (p.path, url)
+        })
+        .collect();
+
+    if options.dry_run {
+        for (path, url) in &targets {
+            let refspecs = refspecs(&cwd.join(path), &branches);
+            println!("[dry-run] git -C {path} push {url} {}", refspecs.join(" "));
+        }
+        return CommandResult::Message(format!(
+            "Dry run: would mirror {} project(s).",
+            targets.len()
+        ));
+    }
+
+    let completed = AtomicUsize::new(0);
+    progress::begin("mirror", targets.len());
+    let outcomes = parallel_map_grouped(
+        &targets,
+        jobs,
+        &groups,
+        |(path, _)| path,
+        |(path, url)| {
+            let started = Instant::now();
+            let refspecs = refspecs(&cwd.join(path), &branches);
+            let mut push = vec!["push", "--quiet", url.as_str()];
+            push.extend(refspecs.iter().map(String::as_str));
+            let result = deadline::check()
+                .and_then(|()| git::run_network(&cwd.join(path), &push).map_err(|e| e.to_string()));
+            let done = completed.fetch_add(1, Ordering::SeqCst) + 1;
+            let status = progress::status(&result);
+            progress::project("mirror", path, status, done, targets.len(), None);
+            match &result {
+                Ok(()) => println!(
+                    "[{done}/{}] {} {path}",
+                    targets.len(),
+                    style::ok(options.plain)
+                ),
+                Err(e) => println!(
+                    "[{done}/{}] {} {path}: {e}",
+                    targets.len(),
+                    style::failed(options.plain)
+                ),
+            }
+            (result, Span::since(started))
+        },
+    );
+
+    let mut failed = Vec::new();
+    for ((path, url), (result, span)) in targets.iter().zip(outcomes) {
+        let (status, detail) = match result {
+            Ok(()) => (RowStatus::Ok, String::new()),
+            Err(e) if e == deadline::CANCELLED => (RowStatus::Skipped, e),
+            Err(e) => {
+                failed.push(format!("{path} → {url}: {e}"));
+                (RowStatus::Failed, e)
+            }
+        };
+        summary.record(path, "mirror", status, detail, Some(span));
+    }
+    let mirrored = targets.len() - failed.len();
+    let message = format!("Mirrored {mirrored} of {} project(s).", targets.len());
+    if failed.is_empty() {
+        CommandResult::Message(message)
+    } else {
+        CommandResult::Error(format!(
+            "{message}\n{}\nMissing destination repositories have to be created on the server first.",
+            failed.join("\n")
+        ))
+    }
+}
+
+/// The push URL of a project: `{name}` and `{path}` in `template` are
+/// replaced, and a template without either is a base URL for `<path>.git`
+fn destination(template: &str, name: &str, path: &str) -> String {
+    if template.contains("{name}") || template.contains("{path}") {
+        template.replace("{name}", name).replace("{path}", path)
+    } else {
+        format!("{}/{path}.git", template.trim_end_matches('/'))
+    }
+}
+
+/// The `--ref` values; none means every branch
+fn branch_patterns(args: &[String]) -> Vec<String> {
+    args.iter()
+        .zip(args.iter().skip(1))
+        .filter(|(flag, _)| *flag == "--ref")
+        .map(|(_, value)| value.clone())
+        .collect()
+}
+
+/// Origin's branches in the repo at `dir` that match `patterns` (globs
+/// allowed), each pushed as a branch, plus all tags
+///
+/// Branches are listed rather than pushed with a wildcard so that the
+/// `origin/HEAD` symref doesn't turn into a branch named `HEAD`.
+fn refspecs(dir: &Path, patterns: &[String]) -> Vec<String> {
+    let mut list = vec!["for-each-ref", "--format=%(refname:strip=3)"];
+    let globs: Vec<String> = patterns
+        .iter()
+        .map(|pattern| format!("refs/remotes/origin/{pattern}"))
+        .collect();
+    if globs.is_empty() {
+        list.push("refs/remotes/origin/");
+    } else {
+        list.extend(globs.iter().map(String::as_str));
+    }
+    let mut refspecs: Vec<String> = git::output(dir, &list)
+        .unwrap_or_default()
+        .lines()
+        .filter(|branch| !branch.is_empty() && *branch != "HEAD")
+        .map(|branch| format!("+refs/remotes/origin/{branch}:refs/heads/{branch}"))
+        .collect();
+    refspecs.push("+refs/tags/*:refs/tags/*".to_string());
+    refspecs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{run_git, Fixture};
+
+    #[test]
+    fn test_destination() {
+        assert_eq!(
+            destination("git@backup:org/{name}.git", "api", "services/api"),
+            "git@backup:org/api.git"
+        );
+        assert_eq!(
+            destination("https://backup.example.com/org/", "api", "services/api"),
+            "https://backup.example.com/org/services/api.git"
+        );
+    }
+
+    #[test]
+    fn test_mirror_push() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        run_git(&web, &["branch", "topic", "main"]);
+        fixture.manifest(&[("api", &api), ("web", &web)]);
+        fixture.clone_project("api", &api);
+        fixture.clone_project("web", &web);
+        let backup = api.parent().unwrap().join("backup");
+        std::fs::create_dir_all(&backup).unwrap();
+        run_git(&backup, &["init", "-q", "--bare", "api.git"]);
+        let template = format!("{}/{{name}}.git", backup.display());
+
+        match fixture.run("project mirror", &["push", "--remote", &template]) {
+            CommandResult::Error(e) => {
+                assert!(e.starts_with("Mirrored 1 of 2 project(s)."), "{e}");
+                assert!(e.contains("web →"), "{e}");
+            }
+            _ => panic!("Expected Error result"),
+        }
+        let has = |repo: &str, branch: &str| {
+            std::process::Command::new("git")
+                .args([
+                    "rev-parse",
+                    "--verify",
+                    "-q",
+                    &format!("refs/heads/{branch}"),
+                ])
+                .current_dir(backup.join(repo))
+                .status()
+                .unwrap()
+                .success()
+        };
+        assert!(has("api.git", "main"));
+        assert!(!has("api.git", "HEAD"));
+
+        run_git(&backup, &["init", "-q", "--bare", "web.git"]);
+        match fixture.run(
+            "project mirror",
+            &["push", "--remote", &template, "--ref", "main"],
+        ) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Mirrored 2 of 2 project(s)."),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(has("web.git", "main"));
+        assert!(!has("web.git", "topic"));
+    }
+}
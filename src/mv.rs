@@ -0,0 +1,100 @@
+//! `meta project mv` — move a project's working copy to a new path
+
+use crate::rename::{check_destination, relocate, set_path};
+use crate::{manifest, resolve_project_key, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use std::path::Path;
+
+/// Handle `meta project mv <name> <new-path>`
+///
+/// Moves the clone (if there is one), updates the project's `path` and
+/// .gitignore entry, and rolls everything back if a step fails.
+pub(crate) fn handle_mv(args: &[String], options: &ExecuteOptions, cwd: &Path) -> CommandResult {
+    let positionals: Vec<&String> = args.iter().filter(|a| !a.starts_with('-')).collect();
+    let [name, new_path] = positionals[..] else {
+        return CommandResult::Error("Usage: meta project mv <name> <new-path>".to_string());
+    };
+    let new_path = new_path.trim_end_matches('/').replace('\\', "/");
+
+    let Some(meta_path) = manifest::find(cwd) else {
+        return CommandResult::Error(format!("No .meta config found in {}", cwd.display()));
+    };
+    let mut document = match manifest::read(&meta_path) {
+        Ok(document) => document,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let Some(projects) = document.get_mut("projects").and_then(|p| p.as_object_mut()) else {
+        return CommandResult::Error("No projects declared in .meta".to_string());
+    };
+    let name = match resolve_project_key(name, projects) {
+        Ok(name) => name,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let Some(mut entry) = projects.remove(&name) else {
+        return CommandResult::Error(format!("Project '{name}' is not in .meta"));
+    };
+    let old_path = entry
+        .get("path")
+        .and_then(|p| p.as_str())
+        .unwrap_or(&name)
+        .to_string();
+    if new_path == old_path {
+        return CommandResult::Message(format!("'{name}' is already at {old_path}."));
+    }
+    if let Err(e) = check_destination(projects, cwd, &old_path, &new_path) {
+        return CommandResult::Error(e);
+    }
+    set_path(&mut entry, &name, &new_path);
+    projects.insert(name.clone(), entry);
+
+    let summary = format!("Moved '{name}' from {old_path} to {new_path}.");
+    if options.dry_run {
+        return CommandResult::Message(format!("Dry run: {summary}"));
+    }
+    relocate(cwd, &meta_path, &document, &old_path, &new_path)
+        .map_or_else(CommandResult::Error, |()| CommandResult::Message(summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Fixture;
+
+    #[test]
+    fn test_mv_relocates_clone() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        fixture.manifest(&[("api", &api), ("web", &web)]);
+        fixture.clone_project("api", &api);
+        std::fs::write(fixture.workspace().join(".gitignore"), "/api/\n").unwrap();
+
+        // A unique prefix selects the project, like any other selector
+        match fixture.run("project mv", &["ap", "services/api"]) {
+            CommandResult::Message(msg) => {
+                assert_eq!(msg, "Moved 'api' from api to services/api.")
+            }
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(fixture.workspace().join("services/api/.git").exists());
+        assert!(!fixture.workspace().join("api").exists());
+        assert_eq!(
+            std::fs::read_to_string(fixture.workspace().join(".gitignore")).unwrap(),
+            "/services/api/\n"
+        );
+        let document = manifest::read(&fixture.workspace().join(".meta")).unwrap();
+        assert_eq!(document["projects"]["api"]["path"], "services/api");
+
+        match fixture.run("project mv", &["web", "services/api"]) {
+            CommandResult::Error(e) => assert!(e.contains("'api' already uses path"), "{e}"),
+            _ => panic!("Expected Error result"),
+        }
+
+        // Moving back to the name makes the path implicit again
+        fixture.run("project mv", &["api", "api"]);
+        let document = manifest::read(&fixture.workspace().join(".meta")).unwrap();
+        assert!(document["projects"]["api"].get("path").is_none());
+        assert!(fixture.workspace().join("api/.git").exists());
+    }
+}
@@ -0,0 +1,124 @@
+//! `--relative-to root|cwd|absolute` — how list and check print project paths
+//!
+//! Project paths are stored relative to the .meta that declares them, so
+//! without the flag a command prints them relative to the directory it read
+//! .meta from. `root` prints them relative to the workspace root (the
+//! outermost meta repo), `cwd` relative to where the command was run and
+//! `absolute` in full, so the same project prints the same way wherever the
+//! command is invoked from.
+
+use meta_cli::config;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RelativeTo {
+    Root,
+    Cwd,
+    Absolute,
+}
+
+/// Renders project paths for one command run
+#[derive(Debug, Clone)]
+pub(crate) struct PathDisplay {
+    /// `None` prints paths as stored
+    mode: Option<RelativeTo>,
+    root: PathBuf,
+    cwd: PathBuf,
+}
+
+impl PathDisplay {
+    /// The `--relative-to` of `args` for a command run in `cwd`
+    pub(crate) fn parse(args: &[String], cwd: &Path) -> Result<Self, String> {
+        let mode = match crate::flag_value(args, "--relative-to") {
+            None => None,
+            Some("root") => Some(RelativeTo::Root),
+            Some("cwd") => Some(RelativeTo::Cwd),
+            Some("absolute") => Some(RelativeTo::Absolute),
+            Some(other) => {
+                return Err(format!(
+                    "Invalid --relative-to value '{other}': expected root, cwd or absolute"
+                ))
+            }
+        };
+        let cwd = absolute(cwd);
+        let root = config::find_meta_config(&cwd, None)
+            .and_then(|(config_path, _)| config_path.parent().map(Path::to_path_buf))
+            .map(|nearest| config::find_root_meta_dir(&nearest))
+            .map(|root| absolute(&root))
+            .unwrap_or_else(|| cwd.clone());
+        Ok(PathDisplay { mode, root, cwd })
+    }
+
+    /// Whether `--relative-to` was given
+    pub(crate) fn is_set(&self) -> bool {
+        self.mode.is_some()
+    }
+
+    /// `path`, stored relative to the directory `base`, as the flag asks
+    pub(crate) fn show(&self, base: &Path, path: &str) -> String {
+        let full = || normalize(&absolute(base).join(path));
+        let shown = match self.mode {
+            None => return path.to_string(),
+            Some(RelativeTo::Absolute) => full(),
+            Some(RelativeTo::Root) => relative(&full(), &self.root),
+            Some(RelativeTo::Cwd) => relative(&full(), &self.cwd),
+        };
+        shown.to_string_lossy().into_owned()
+    }
+}
+
+fn absolute(dir: &Path) -> PathBuf {
+    dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+}
+
+/// `path` with `.` and `..` components folded away, without touching the
+/// filesystem (the project may not be cloned yet)
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// `path` relative to `from`, both absolute; `.` when they're the same
+fn relative(path: &Path, from: &Path) -> PathBuf {
+    let path: Vec<_> = path.components().collect();
+    let from: Vec<_> = from.components().collect();
+    let common = path.iter().zip(&from).take_while(|(a, b)| a == b).count();
+    let relative: PathBuf = std::iter::repeat_n(Component::ParentDir, from.len() - common)
+        .chain(path[common..].iter().copied())
+        .collect();
+    if relative.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        relative
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative() {
+        let rel = |path: &str, from: &str| {
+            relative(Path::new(path), Path::new(from))
+                .to_string_lossy()
+                .into_owned()
+        };
+        assert_eq!(rel("/ws/api", "/ws"), "api");
+        assert_eq!(rel("/ws/api", "/ws/libs"), "../api");
+        assert_eq!(rel("/ws", "/ws"), ".");
+        assert_eq!(
+            normalize(Path::new("/ws/libs/../api/./src")),
+            Path::new("/ws/api/src")
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! Bounded worker pool for per-project work
+//!
+//! Besides the overall `--jobs` limit, projects can be put in named
+//! [`ConcurrencyGroups`] (a shared LFS store, a license server) that have a
+//! lower limit of their own, and remote hosts can limit how many of their
+//! projects run at once.
+
+use crate::{deadline, progress};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Condvar, Mutex};
+
+/// Default worker count for local (CPU/disk bound) work
+pub(crate) fn default_jobs() -> usize {
+    std::thread::available_parallelism().map_or(4, |n| n.get())
+}
+
+/// Projects sharing a resource, and how many of each group may run at once
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ConcurrencyGroups {
+    /// Project path → group name
+    members: HashMap<String, String>,
+    /// Group name → limit; a group without one runs a project at a time
+    limits: HashMap<String, usize>,
+    /// Project path → `host <name>`, for projects on a host with a limit
+    hosts: HashMap<String, String>,
+}
+
+impl ConcurrencyGroups {
+    pub(crate) fn new(members: HashMap<String, String>, limits: HashMap<String, usize>) -> Self {
+        ConcurrencyGroups {
+            members,
+            limits,
+            hosts: HashMap::new(),
+        }
+    }
+
+    /// Also limit the projects of each host in `limits`; `hosts` maps
+    /// project paths to the host of their remote
+    pub(crate) fn with_hosts(
+        mut self,
+        hosts: HashMap<String, String>,
+        limits: &BTreeMap<String, usize>,
+    ) -> Self {
+        for (project, host) in hosts {
+            if let Some(&limit) = limits.get(&host) {
+                // Host names have no spaces, so these never clash with groups
+                let key = format!("host {host}");
+                self.limits.insert(key.clone(), limit);
+                self.hosts.insert(project, key);
+            }
+        }
+        self
+    }
+
+    /// The group and host slots `project` needs, with their limits
+    fn slots(&self, project: &str) -> Vec<(&str, usize)> {
+        [self.members.get(project), self.hosts.get(project)]
+            .into_iter()
+            .flatten()
+            .map(|key| {
+                let limit = self.limits.get(key).copied().unwrap_or(1).max(1);
+                (key.as_str(), limit)
+            })
+            .collect()
+    }
+}
+
+/// Apply `f` to every item on at most `jobs` threads
+///
+/// Results are returned in input order regardless of completion order, so
+/// output built from them is deterministic. Workers run under the caller's
+/// `--timeout` deadline and report to its `--progress-json` sink.
+pub(crate) fn parallel_map<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    parallel_map_grouped(items, jobs, &ConcurrencyGroups::default(), |_| "", f)
+}
+
+/// [`parallel_map`], also keeping each of `groups` within its own limit;
+/// `project` gives an item's project path
+///
+/// Items start in input order, except that one whose group or host is full
+/// waits while later items go ahead.
+pub(crate) fn parallel_map_grouped<T, R, P, F>(
+    items: &[T],
+    jobs: usize,
+    groups: &ConcurrencyGroups,
+    project: P,
+    f: F,
+) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    P: Fn(&T) -> &str + Sync,
+    F: Fn(&T) -> R + Sync,
+{
+    if jobs <= 1 || items.len() <= 1 {
+        return items.iter().map(&f).collect();
+    }
+
+    let deadline = deadline::current();
+    let sink = progress::current();
+    let results: Mutex<Vec<Option<R>>> = Mutex::new((0..items.len()).map(|_| None).collect());
+    let slots: Vec<Vec<(&str, usize)>> = items.iter().map(|i| groups.slots(project(i))).collect();
+    // Indexes not started yet, and how many of each group are running
+    let queue: Mutex<(Vec<usize>, HashMap<&str, usize>)> =
+        Mutex::new(((0..items.len()).collect(), HashMap::new()));
+    let released = Condvar::new();
+
+    let take = || {
+        let mut state = queue.lock().unwrap();
+        loop {
+            let (pending, running) = &mut *state;
+            if pending.is_empty() {
+                return None;
+            }
+            let ready = pending.iter().position(|&index| {
+                slots[index]
+                    .iter()
+                    .all(|&(group, limit)| running.get(group).copied().unwrap_or(0) < limit)
+            });
+            if let Some(position) = ready {
+                let index = pending.remove(position);
+                for &(group, _) in &slots[index] {
+                    *running.entry(group).or_default() += 1;
+                }
+                return Some(index);
+            }
+            state = released.wait(state).unwrap();
+        }
+    };
+    let release = |index: usize| {
+        if !slots[index].is_empty() {
+            let mut state = queue.lock().unwrap();
+            for &(group, _) in &slots[index] {
+                if let Some(count) = state.1.get_mut(group) {
+                    *count -= 1;
+                }
+            }
+            released.notify_all();
+        }
+    };
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.min(items.len()) {
+            scope.spawn(|| {
+                deadline::enter(deadline.clone());
+                progress::enter(sink.clone());
+                while let Some(index) = take() {
+                    let result = f(&items[index]);
+                    release(index);
+                    results.lock().unwrap()[index] = Some(result);
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every item is processed exactly once"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallel_map_preserves_order() {
+        let items: Vec<u64> = (0..50).collect();
+        let results = parallel_map(&items, 8, |n| {
+            // Finish later items first to shake out ordering bugs
+            std::thread::sleep(std::time::Duration::from_millis(50 - n));
+            n * 2
+        });
+        assert_eq!(results, items.iter().map(|n| n * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_groups_have_their_own_limit() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let items: Vec<String> = (0..12).map(|n| format!("p{n}")).collect();
+        // Even projects share a resource that allows two at a time
+        let members = items
+            .iter()
+            .step_by(2)
+            .map(|p| (p.clone(), "lfs".to_string()))
+            .collect();
+        let groups = ConcurrencyGroups::new(members, HashMap::from([("lfs".to_string(), 2)]));
+        let (running, peak, total) = (
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+        );
+        let results = parallel_map_grouped(
+            &items,
+            8,
+            &groups,
+            |p| p.as_str(),
+            |p| {
+                let grouped = !groups.slots(p).is_empty();
+                if grouped {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                }
+                total.fetch_add(1, Ordering::SeqCst);
+                std::thread::sleep(std::time::Duration::from_millis(20));
+                if grouped {
+                    running.fetch_sub(1, Ordering::SeqCst);
+                }
+                p.to_uppercase()
+            },
+        );
+        assert_eq!(results[3], "P3");
+        assert_eq!(total.load(Ordering::SeqCst), 12);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_hosts_and_groups_both_apply() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let items: Vec<String> = (0..12).map(|n| format!("p{n}")).collect();
+        // All on one host allowing three; a third also in a group of one
+        let hosts = items
+            .iter()
+            .map(|p| (p.clone(), "git.corp".to_string()))
+            .collect();
+        let members = items
+            .iter()
+            .step_by(3)
+            .map(|p| (p.clone(), "lfs".to_string()))
+            .collect();
+        let groups = ConcurrencyGroups::new(members, HashMap::new()).with_hosts(
+            hosts,
+            &BTreeMap::from([("git.corp".to_string(), 3), ("other".to_string(), 1)]),
+        );
+        assert_eq!(groups.slots("p0"), vec![("lfs", 1), ("host git.corp", 3)]);
+        assert_eq!(groups.slots("p1"), vec![("host git.corp", 3)]);
+
+        let (running, peak, running_lfs, peak_lfs) = (
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+            AtomicUsize::new(0),
+        );
+        parallel_map_grouped(
+            &items,
+            8,
+            &groups,
+            |p| p.as_str(),
+            |p| {
+                let lfs = groups.slots(p).len() == 2;
+                peak.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                if lfs {
+                    let now = running_lfs.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak_lfs.fetch_max(now, Ordering::SeqCst);
+                }
+                std::thread::sleep(std::time::Duration::from_millis(20));
+                if lfs {
+                    running_lfs.fetch_sub(1, Ordering::SeqCst);
+                }
+                running.fetch_sub(1, Ordering::SeqCst);
+            },
+        );
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+        assert_eq!(peak_lfs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_parallel_map_single_job() {
+        let items = vec!["a", "b"];
+        assert_eq!(
+            parallel_map(&items, 1, |s| s.to_uppercase()),
+            vec!["A", "B"]
+        );
+    }
+}
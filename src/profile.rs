@@ -0,0 +1,258 @@
+//! `meta project use <profile>` — switch every project to a profile's branches
+//!
+//! Profiles live in the .meta `settings.profiles` block and pin a branch per
+//! project, e.g. a `release-2.x` profile pinning the maintenance branches.
+//! The active profile is remembered in the workspace's cache directory.
+
+use crate::settings;
+use crate::summary::{RowStatus, RunSummary, Span};
+use crate::{git, jj, state, style, workspace_lock, workspace_projects, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Cache file recording the active profile
+const PROFILE_FILE: &str = "profile.json";
+
+/// The profile last switched to with `meta project use`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ActiveProfile {
+    pub name: String,
+    /// When it was switched to, in seconds since the Unix epoch
+    pub since: u64,
+}
+
+/// The workspace's active profile, if one was switched to
+pub(crate) fn active(cwd: &Path) -> Option<ActiveProfile> {
+    state::read_json(&state::workspace_cache_file(cwd, PROFILE_FILE)?)
+}
+
+/// Handle `meta project use [<profile>]`
+///
+/// Without a profile, lists the defined profiles and marks the active one.
+pub(crate) fn handle_use(
+    args: &[String],
+    options: &ExecuteOptions,
+    provided_projects: &[String],
+    cwd: &Path,
+    summary: &mut RunSummary,
+) -> CommandResult {
+    let profiles = match settings::load(cwd) {
+        Ok(settings) => settings.profiles,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let current = active(cwd).map(|a| a.name);
+    let Some(name) = args.iter().find(|a| !a.starts_with('-')) else {
+        if profiles.is_empty() {
+            return CommandResult::Message(
+                "No profiles defined; add them to the .meta settings.profiles block.".to_string(),
+            );
+        }
+        let lines: Vec<String> = profiles
+            .keys()
+            .map(|name| {
+                let marker = if current.as_ref() == Some(name) {
+                    "*"
+                } else {
+                    " "
+                };
+                format!("{marker} {name}")
+            })
+            .collect();
+        return CommandResult::Message(lines.join("\n"));
+    };
+    let Some(profile) = profiles.get(name) else {
+        let known: Vec<&str> = profiles.keys().map(String::as_str).collect();
+        return CommandResult::Error(format!(
+            "Unknown profile '{name}'; defined: {}",
+            if known.is_empty() {
+                "none".to_string()
+            } else {
+                known.join(", ")
+            }
+        ));
+    };
+    let projects = match workspace_projects(provided_projects, cwd) {
+        Ok(projects) => projects,
+        Err(e) => return CommandResult::Error(e),
+    };
+
+    let mut switches = Vec::new();
+    let mut problems = Vec::new();
+    let mut on_branch = 0;
+    for project in &projects {
+        let dir = cwd.join(&project.path);
+        let Some(branch) = profile.branch_for(&project.path) else {
+            continue;
+        };
+        if !git::is_repo_root(&dir) {
+            continue;
+        }
+        // jj keeps HEAD detached, and its working copy is a commit of its own
+        let jj = jj::is_colocated(&dir);
+        if jj && jj::is_at(&dir, branch) || git::current_branch(&dir).as_deref() == Some(branch) {
+            on_branch += 1;
+            continue;
+        }
+        if !jj && git::is_dirty(&dir) {
+            problems.push(format!(
+                "{}: has uncommitted changes (commit or stash them first)",
+                project.path
+            ));
+        } else if git::ref_exists(&dir, &format!("refs/heads/{branch}")) {
+            switches.push((project.path.as_str(), branch, false));
+        } else if git::ref_exists(&dir, &format!("refs/remotes/origin/{branch}")) {
+            switches.push((project.path.as_str(), branch, true));
+        } else {
+            problems.push(format!(
+                "{}: no branch '{branch}' locally or on origin (fetch first)",
+                project.path
+            ));
+        }
+    }
+    if !problems.is_empty() {
+        return CommandResult::Error(format!(
+            "Cannot switch to profile '{name}':\n{}",
+            problems.join("\n")
+        ));
+    }
+
+    if options.dry_run {
+        for (path, branch, track) in &switches {
+            if jj::is_colocated(&cwd.join(path)) {
+                println!("[dry-run] jj -R {path} new {branch}");
+            } else if *track {
+                println!("[dry-run] git -C {path} checkout -b {branch} --track origin/{branch}");
+            } else {
+                println!("[dry-run] git -C {path} checkout {branch}");
+            }
+        }
+        return CommandResult::Message(format!(
+            "Dry run: would switch {} project(s) to profile '{name}'; {on_branch} already on it.",
+            switches.len()
+        ));
+    }
+
+    let _lock = match workspace_lock::acquire(cwd, "project use", args) {
+        Ok(lock) => lock,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let mut failures = Vec::new();
+    for (path, branch, track) in &switches {
+        let started = Instant::now();
+        let dir = cwd.join(path);
+        let tracking = format!("origin/{branch}");
+        let result = if jj::is_colocated(&dir) {
+            jj::new(&dir, branch)
+        } else if *track {
+            git::run(
+                &dir,
+                &["checkout", "-q", "-b", branch, "--track", &tracking],
+            )
+        } else {
+            git::run(&dir, &["checkout", "-q", branch])
+        };
+        let (status, detail) = match result {
+            Ok(()) => {
+                println!("{} {path} on {branch}", style::ok(options.plain));
+                (RowStatus::Ok, branch.to_string())
+            }
+            Err(e) => {
+                failures.push(format!("{path}: {e}"));
+                (RowStatus::Failed, e.to_string())
+            }
+        };
+        summary.record(path, "use", status, detail, Some(Span::since(started)));
+    }
+    if !failures.is_empty() {
+        return CommandResult::Error(format!(
+            "Failed to switch {} project(s) to profile '{name}':\n{}",
+            failures.len(),
+            failures.join("\n")
+        ));
+    }
+
+    if let Some(path) = state::workspace_cache_file(cwd, PROFILE_FILE) {
+        let since = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let active = ActiveProfile {
+            name: name.clone(),
+            since,
+        };
+        if let Err(e) = state::write_json(&path, &active) {
+            return CommandResult::Error(format!("Failed to record the active profile: {e}"));
+        }
+    }
+    CommandResult::Message(format!(
+        "Switched {} project(s) to profile '{name}'; {on_branch} already on it.",
+        switches.len()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{run_git, Fixture};
+
+    #[test]
+    fn test_use_profile() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        run_git(&api, &["branch", "maint/2.x", "main"]);
+        run_git(&web, &["branch", "release/2.x", "main"]);
+        let workspace = fixture.workspace();
+        let meta = serde_json::json!({
+            "projects": { "api": api.to_string_lossy(), "web": web.to_string_lossy() },
+            "settings": { "profiles": {
+                "main": { "branch": "main" },
+                "release-2.x": { "branch": "release/2.x", "projects": { "api": "maint/2.x" } }
+            }}
+        });
+        std::fs::write(workspace.join(".meta"), meta.to_string()).unwrap();
+        fixture.clone_project("api", &api);
+        fixture.clone_project("web", &web);
+
+        match fixture.run("project use", &["release-2.x"]) {
+            CommandResult::Message(msg) => assert_eq!(
+                msg,
+                "Switched 2 project(s) to profile 'release-2.x'; 0 already on it."
+            ),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert_eq!(
+            git::current_branch(&workspace.join("api")).as_deref(),
+            Some("maint/2.x")
+        );
+        assert_eq!(
+            git::current_branch(&workspace.join("web")).as_deref(),
+            Some("release/2.x")
+        );
+        assert_eq!(active(&workspace).unwrap().name, "release-2.x");
+        match fixture.run("project use", &[]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "  main\n* release-2.x"),
+            _ => panic!("Expected Message result"),
+        }
+
+        std::fs::write(workspace.join("web/dirty.txt"), "wip").unwrap();
+        run_git(&workspace.join("web"), &["add", "dirty.txt"]);
+        match fixture.run("project use", &["main"]) {
+            CommandResult::Error(e) => assert!(e.contains("web: has uncommitted changes"), "{e}"),
+            _ => panic!("Expected Error result"),
+        }
+        assert_eq!(
+            git::current_branch(&workspace.join("api")).as_deref(),
+            Some("maint/2.x")
+        );
+        match fixture.run("project use", &["nightly"]) {
+            CommandResult::Error(e) => {
+                assert_eq!(e, "Unknown profile 'nightly'; defined: main, release-2.x")
+            }
+            _ => panic!("Expected Error result"),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! The global `--progress-json`: machine-readable progress for wrappers
+//!
+//! Long per-project operations (fetches, mirror pushes, archive bundles)
+//! write one JSON object per line as each phase starts and each project
+//! finishes, so an IDE extension or GUI can draw a progress bar:
+//!
+//! ```text
+//! {"phase":"fetch","total":12,"done":0,"percent":0}
+//! {"phase":"fetch","project":"api","status":"ok","total":12,"done":1,"percent":8}
+//! {"phase":"archive","project":"web","status":"ok","total":3,"done":2,"percent":66,"bytes":48213}
+//! ```
+//!
+//! Events go to the file given (truncated first), or to stderr for `-`, so
+//! they never mix with the human-readable output on stdout. Like the
+//! deadline, the sink belongs to the thread running the command and is
+//! handed on to the workers of [`crate::pool::parallel_map`].
+
+use serde::Serialize;
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Where events are written
+pub(crate) type Sink = Arc<Mutex<Box<dyn Write + Send>>>;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Sink>> = const { RefCell::new(None) };
+}
+
+/// One progress event
+#[derive(Debug, Serialize)]
+struct Event<'a> {
+    phase: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<&'a str>,
+    total: usize,
+    done: usize,
+    percent: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes: Option<u64>,
+}
+
+/// Send this thread's events to `target` (a path relative to `cwd`, or `-`
+/// for stderr); `None` turns them off
+pub(crate) fn start(target: Option<&str>, cwd: &Path) -> Result<(), String> {
+    let sink: Option<Box<dyn Write + Send>> = match target {
+        None => None,
+        Some("-") => Some(Box::new(std::io::stderr())),
+        Some(path) => {
+            let file = std::fs::File::create(cwd.join(path))
+                .map_err(|e| format!("Failed to open --progress-json file {path}: {e}"))?;
+            Some(Box::new(file))
+        }
+    };
+    enter(sink.map(|sink| Arc::new(Mutex::new(sink))));
+    Ok(())
+}
+
+/// The sink of this thread, to hand to worker threads
+pub(crate) fn current() -> Option<Sink> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Adopt a sink taken from [`current`] on another thread
+pub(crate) fn enter(sink: Option<Sink>) {
+    CURRENT.with(|current| *current.borrow_mut() = sink);
+}
+
+/// A phase over `total` projects is starting
+pub(crate) fn begin(phase: &str, total: usize) {
+    emit(&Event {
+        phase,
+        project: None,
+        status: None,
+        total,
+        done: 0,
+        percent: if total == 0 { 100 } else { 0 },
+        bytes: None,
+    });
+}
+
+/// `project` finished with `status` ("ok", "failed" or "skipped"), the
+/// `done`th of `total`; `bytes` is what it transferred or wrote, if known
+pub(crate) fn project(
+    phase: &str,
+    project: &str,
+    status: &str,
+    done: usize,
+    total: usize,
+    bytes: Option<u64>,
+) {
+    emit(&Event {
+        phase,
+        project: Some(project),
+        status: Some(status),
+        total,
+        done,
+        percent: (done * 100).checked_div(total).unwrap_or(100),
+        bytes,
+    });
+}
+
+/// The event status of a per-project result
+pub(crate) fn status<T>(result: &Result<T, String>) -> &'static str {
+    match result {
+        Ok(_) => "ok",
+        Err(e) if e == crate::deadline::CANCELLED => "skipped",
+        Err(_) => "failed",
+    }
+}
+
+fn emit(event: &Event) {
+    let Some(sink) = current() else {
+        return;
+    };
+    let Ok(line) = serde_json::to_string(event) else {
+        return;
+    };
+    let mut sink = sink.lock().unwrap_or_else(|e| e.into_inner());
+    // A wrapper that stopped reading must not fail the command
+    let _ = writeln!(sink, "{line}").and_then(|()| sink.flush());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pool::parallel_map;
+
+    #[test]
+    fn test_events_from_workers() {
+        let dir = tempfile::TempDir::new().unwrap();
+        start(Some("progress.jsonl"), dir.path()).unwrap();
+        let items = ["a", "b", "c"];
+        begin("fetch", items.len());
+        let done = std::sync::atomic::AtomicUsize::new(0);
+        parallel_map(&items, 3, |item| {
+            let done = done.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            project("fetch", item, "ok", done, items.len(), None);
+        });
+        start(None, dir.path()).unwrap();
+
+        let content = std::fs::read_to_string(dir.path().join("progress.jsonl")).unwrap();
+        let events: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), 4);
+        assert_eq!(
+            events[0],
+            serde_json::json!({"phase": "fetch", "total": 3, "done": 0, "percent": 0})
+        );
+        assert!(events.iter().any(|e| e["percent"] == 100));
+        assert!(events[1..].iter().all(|e| e["status"] == "ok"));
+    }
+}
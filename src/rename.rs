@@ -0,0 +1,270 @@
+//! `meta project rename` — rename a project in .meta and move its clone
+//!
+//! The steps (move the working copy, update .gitignore, write .meta) run in
+//! that order, and a failing step undoes the ones before it, so the
+//! workspace is never left half renamed. `meta project mv` moves a project
+//! the same way.
+
+use crate::add::is_relative_path;
+use crate::{flag_value, manifest, resolve_project_key, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Handle `meta project rename <old> <new> [--path <dir>]`
+///
+/// A project without an explicit `path` lives at its name, so renaming it
+/// moves the directory too; `--path` moves it somewhere else.
+pub(crate) fn handle_rename(
+    args: &[String],
+    options: &ExecuteOptions,
+    cwd: &Path,
+) -> CommandResult {
+    let (old, new) = match positionals(args)[..] {
+        [old, new] => (old, new),
+        _ => {
+            return CommandResult::Error(
+                "Usage: meta project rename <old> <new> [--path <dir>]".to_string(),
+            )
+        }
+    };
+    let Some(meta_path) = manifest::find(cwd) else {
+        return CommandResult::Error(format!("No .meta config found in {}", cwd.display()));
+    };
+    let mut document = match manifest::read(&meta_path) {
+        Ok(document) => document,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let Some(projects) = document.get_mut("projects").and_then(|p| p.as_object_mut()) else {
+        return CommandResult::Error("No projects declared in .meta".to_string());
+    };
+    let resolved = match resolve_project_key(old, projects) {
+        Ok(name) => name,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let old = resolved.as_str();
+    if projects.contains_key(new) {
+        return CommandResult::Error(format!("A project named '{new}' is already in .meta"));
+    }
+    let Some(mut entry) = projects.remove(old) else {
+        return CommandResult::Error(format!("Project '{old}' is not in .meta"));
+    };
+
+    let explicit_path = entry
+        .get("path")
+        .and_then(|p| p.as_str())
+        .map(str::to_string);
+    let old_path = explicit_path.clone().unwrap_or_else(|| old.to_string());
+    let new_path = match flag_value(args, "--path") {
+        Some(path) => path.replace('\\', "/"),
+        None => explicit_path.unwrap_or_else(|| new.to_string()),
+    };
+    if let Err(e) = check_destination(projects, cwd, &old_path, &new_path) {
+        return CommandResult::Error(e);
+    }
+    set_path(&mut entry, new, &new_path);
+    projects.insert(new.to_string(), entry);
+    // Successor links name projects, so they follow the rename
+    for other in projects.values_mut() {
+        if let Some(successor) = other.pointer_mut("/deprecated/successor") {
+            if successor.as_str() == Some(old) {
+                *successor = Value::String(new.to_string());
+            }
+        }
+    }
+
+    let moves = new_path != old_path;
+    let summary = if moves {
+        format!("Renamed '{old}' to '{new}' and moved {old_path} to {new_path}.")
+    } else {
+        format!("Renamed '{old}' to '{new}'.")
+    };
+    if options.dry_run {
+        return CommandResult::Message(format!("Dry run: {summary}"));
+    }
+
+    relocate(cwd, &meta_path, &document, &old_path, &new_path)
+        .map_or_else(CommandResult::Error, |()| CommandResult::Message(summary))
+}
+
+/// Fail unless the project at `old_path` (already taken out of `projects`)
+/// can move to `new_path`
+pub(crate) fn check_destination(
+    projects: &Map<String, Value>,
+    cwd: &Path,
+    old_path: &str,
+    new_path: &str,
+) -> Result<(), String> {
+    if !is_relative_path(new_path) {
+        return Err(format!(
+            "Invalid project path '{new_path}': must be relative and stay inside the workspace"
+        ));
+    }
+    let taken = projects.iter().find(|(other, entry)| {
+        entry.get("path").and_then(|p| p.as_str()).unwrap_or(other) == new_path
+    });
+    if let Some((other, _)) = taken {
+        return Err(format!("Project '{other}' already uses path '{new_path}'"));
+    }
+    if new_path != old_path && cwd.join(new_path).exists() {
+        return Err(format!("Cannot move {old_path}: {new_path} already exists"));
+    }
+    Ok(())
+}
+
+/// Point the entry of project `name` at `path`; a path equal to the name
+/// is left implicit
+pub(crate) fn set_path(entry: &mut Value, name: &str, path: &str) {
+    if let Some(fields) = entry.as_object_mut() {
+        if path == name {
+            fields.remove("path");
+        } else {
+            fields.insert("path".to_string(), path.into());
+        }
+    }
+}
+
+/// Move the working copy from `old_path` to `new_path`, update .gitignore
+/// and write `document` to `meta_path`, undoing earlier steps on failure
+pub(crate) fn relocate(
+    cwd: &Path,
+    meta_path: &Path,
+    document: &Value,
+    old_path: &str,
+    new_path: &str,
+) -> Result<(), String> {
+    let moves = new_path != old_path;
+    let old_dir = cwd.join(old_path);
+    let new_dir = cwd.join(new_path);
+    let moved = moves && old_dir.exists();
+    if moved {
+        if let Some(parent) = new_dir.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+        }
+        std::fs::rename(&old_dir, &new_dir)
+            .map_err(|e| format!("Failed to move {old_path} to {new_path}: {e}"))?;
+    }
+    let rollback_move = || {
+        if moved {
+            let _ = std::fs::rename(&new_dir, &old_dir);
+        }
+    };
+
+    let gitignore = cwd.join(".gitignore");
+    let original_ignore = std::fs::read_to_string(&gitignore).ok();
+    if let Some(content) = original_ignore.as_deref().filter(|_| moves) {
+        let updated = rename_ignore_entries(content, old_path, new_path);
+        if updated != content {
+            if let Err(e) = std::fs::write(&gitignore, updated) {
+                rollback_move();
+                return Err(format!("Failed to update .gitignore: {e}"));
+            }
+        }
+    }
+
+    if let Err(e) = manifest::write(meta_path, document) {
+        rollback_move();
+        if let Some(content) = original_ignore {
+            let _ = std::fs::write(&gitignore, content);
+        }
+        return Err(format!("{e}; the change was rolled back"));
+    }
+    Ok(())
+}
+
+/// Arguments that are neither flags nor a flag's value
+fn positionals(args: &[String]) -> Vec<&str> {
+    let mut positionals = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--path" => {
+                iter.next();
+            }
+            a if a.starts_with('-') => {}
+            a => positionals.push(a),
+        }
+    }
+    positionals
+}
+
+/// Rewrite .gitignore lines that ignore exactly `old` (`old`, `/old`,
+/// `old/` or `/old/`), keeping their anchoring and trailing slash
+fn rename_ignore_entries(content: &str, old: &str, new: &str) -> String {
+    let lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            let anchored = line.starts_with('/');
+            let dir_only = line.ends_with('/');
+            let bare = line.trim_start_matches('/').trim_end_matches('/');
+            if bare != old {
+                return line.to_string();
+            }
+            format!(
+                "{}{new}{}",
+                if anchored { "/" } else { "" },
+                if dir_only { "/" } else { "" }
+            )
+        })
+        .collect();
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Fixture;
+
+    #[test]
+    fn test_rename_ignore_entries() {
+        assert_eq!(
+            rename_ignore_entries("/api\nweb/\napi-docs\n/api/\n", "api", "core"),
+            "/core\nweb/\napi-docs\n/core/\n"
+        );
+    }
+
+    #[test]
+    fn test_rename_moves_clone_and_updates_references() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let old = fixture.upstream("old");
+        fixture.manifest(&[("api", &api), ("old", &old)]);
+        fixture.clone_project("api", &api);
+        std::fs::write(fixture.workspace().join(".gitignore"), "api/\nold/\n").unwrap();
+        fixture.run("project deprecate", &["old", "--successor", "api"]);
+
+        match fixture.run("project rename", &["api", "core"]) {
+            CommandResult::Message(msg) => {
+                assert_eq!(msg, "Renamed 'api' to 'core' and moved api to core.")
+            }
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(fixture.workspace().join("core/.git").exists());
+        assert!(!fixture.workspace().join("api").exists());
+        assert_eq!(
+            std::fs::read_to_string(fixture.workspace().join(".gitignore")).unwrap(),
+            "core/\nold/\n"
+        );
+        let document = manifest::read(&fixture.workspace().join(".meta")).unwrap();
+        assert!(document["projects"].get("api").is_none());
+        assert_eq!(
+            document["projects"]["core"]["repo"],
+            api.to_string_lossy().as_ref()
+        );
+        assert_eq!(
+            document["projects"]["old"]["deprecated"]["successor"],
+            "core"
+        );
+
+        match fixture.run("project rename", &["core", "old"]) {
+            CommandResult::Error(e) => assert!(e.contains("already in .meta"), "{e}"),
+            _ => panic!("Expected Error result"),
+        }
+    }
+}
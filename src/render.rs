@@ -0,0 +1,282 @@
+//! Report renderers: one tabular report, rendered as text, JSON, Markdown or HTML
+//!
+//! The HTML renderer produces a single self-contained page (inline style and
+//! script, no external assets) whose tables sort by clicking a column header,
+//! so it can be attached to a CI run as-is.
+
+use std::path::Path;
+
+/// A titled table with optional notes above it and command output below
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Report {
+    pub title: String,
+    /// Short paragraphs shown above the table
+    pub notes: Vec<String>,
+    pub columns: Vec<&'static str>,
+    pub rows: Vec<Vec<String>>,
+    /// Command output, shown verbatim below the table
+    pub output: Option<String>,
+}
+
+/// Turns a [`Report`] into a document
+pub(crate) trait Renderer {
+    fn render(&self, report: &Report) -> String;
+}
+
+/// Renderer for a report file, chosen by extension (`.html`, `.md`, `.json`;
+/// anything else is plain text)
+pub(crate) fn for_path(path: &Path) -> Box<dyn Renderer> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "html" | "htm" => Box::new(Html),
+        "md" | "markdown" => Box::new(Markdown),
+        "json" => Box::new(Json),
+        _ => Box::new(Text),
+    }
+}
+
+// ============================================================================
+// Text
+// ============================================================================
+
+/// Aligned columns for terminals and log files
+pub(crate) struct Text;
+
+impl Renderer for Text {
+    fn render(&self, report: &Report) -> String {
+        let mut out = format!("{}\n\n", report.title);
+        for note in &report.notes {
+            out.push_str(note);
+            out.push_str("\n\n");
+        }
+        if !report.rows.is_empty() {
+            let widths: Vec<usize> = (0..report.columns.len())
+                .map(|i| {
+                    report
+                        .rows
+                        .iter()
+                        .map(|row| row.get(i).map_or(0, |c| c.chars().count()))
+                        .chain([report.columns[i].len()])
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect();
+            let line = |cells: Vec<&str>| {
+                let padded: Vec<String> = cells
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| format!("{cell:<width$}"))
+                    .collect();
+                format!("{}\n", padded.join("  ").trim_end())
+            };
+            out.push_str(&line(report.columns.clone()));
+            for row in &report.rows {
+                let cells: Vec<String> = row.iter().map(|c| c.replace('\n', " ")).collect();
+                out.push_str(&line(cells.iter().map(String::as_str).collect()));
+            }
+            out.push('\n');
+        }
+        if let Some(output) = report.output.as_deref().map(str::trim) {
+            if !output.is_empty() {
+                out.push_str(output);
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+// ============================================================================
+// JSON
+// ============================================================================
+
+/// Rows become objects keyed by column name
+pub(crate) struct Json;
+
+impl Renderer for Json {
+    fn render(&self, report: &Report) -> String {
+        let rows: Vec<serde_json::Value> = report
+            .rows
+            .iter()
+            .map(|row| {
+                let fields: serde_json::Map<String, serde_json::Value> = report
+                    .columns
+                    .iter()
+                    .zip(row)
+                    .map(|(column, cell)| (column.to_lowercase(), cell.clone().into()))
+                    .collect();
+                serde_json::Value::Object(fields)
+            })
+            .collect();
+        let value = serde_json::json!({
+            "title": report.title,
+            "notes": report.notes,
+            "rows": rows,
+            "output": report.output,
+        });
+        serde_json::to_string_pretty(&value).unwrap_or_default() + "\n"
+    }
+}
+
+// ============================================================================
+// Markdown
+// ============================================================================
+
+/// GitHub-flavored Markdown, e.g. for `$GITHUB_STEP_SUMMARY`
+///
+/// Project names are code spans and durations are right-aligned.
+pub(crate) struct Markdown;
+
+impl Renderer for Markdown {
+    fn render(&self, report: &Report) -> String {
+        let mut md = format!("## {}\n\n", report.title);
+        for note in &report.notes {
+            md.push_str(note);
+            md.push_str("\n\n");
+        }
+        if !report.rows.is_empty() {
+            md.push_str(&format!("| {} |\n", report.columns.join(" | ")));
+            let rule: Vec<&str> = report
+                .columns
+                .iter()
+                .map(|c| if *c == "Duration" { "---:" } else { "---" })
+                .collect();
+            md.push_str(&format!("|{}|\n", rule.join("|")));
+            for row in &report.rows {
+                let cells: Vec<String> = report
+                    .columns
+                    .iter()
+                    .zip(row)
+                    .map(|(column, cell)| match *column {
+                        "Project" => format!("`{}`", escape_cell(cell)),
+                        _ => escape_cell(cell),
+                    })
+                    .collect();
+                md.push_str(&format!("| {} |\n", cells.join(" | ")));
+            }
+            md.push('\n');
+        }
+        if let Some(output) = report.output.as_deref().map(str::trim) {
+            if !output.is_empty() {
+                md.push_str("```\n");
+                md.push_str(output);
+                md.push_str("\n```\n");
+            }
+        }
+        md
+    }
+}
+
+/// Keep a cell on one table row
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', "<br>")
+}
+
+// ============================================================================
+// HTML
+// ============================================================================
+
+/// A single self-contained page with sortable tables
+pub(crate) struct Html;
+
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin:1em 0}\
+th,td{border:1px solid #ccc;padding:.3em .6em;text-align:left;vertical-align:top}\
+th{background:#f3f3f3;cursor:pointer;user-select:none}\
+th[aria-sort=ascending]::after{content:' \\25B2'}\
+th[aria-sort=descending]::after{content:' \\25BC'}\
+pre{background:#f6f6f6;padding:1em;overflow:auto}";
+
+const HTML_SCRIPT: &str = "document.querySelectorAll('table').forEach(function(t){\
+t.querySelectorAll('th').forEach(function(th,i){th.addEventListener('click',function(){\
+var asc=th.getAttribute('aria-sort')!=='ascending';\
+t.querySelectorAll('th').forEach(function(h){h.removeAttribute('aria-sort')});\
+th.setAttribute('aria-sort',asc?'ascending':'descending');\
+var body=t.tBodies[0];var rows=Array.prototype.slice.call(body.rows);\
+rows.sort(function(a,b){var x=a.cells[i].textContent,y=b.cells[i].textContent;\
+var c=x.localeCompare(y,undefined,{numeric:true});return asc?c:-c});\
+rows.forEach(function(r){body.appendChild(r)})})})});";
+
+impl Renderer for Html {
+    fn render(&self, report: &Report) -> String {
+        let title = escape_html(&report.title);
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n"
+        );
+        for note in &report.notes {
+            html.push_str(&format!("<p>{}</p>\n", escape_html(note)));
+        }
+        if !report.rows.is_empty() {
+            html.push_str("<table>\n<thead><tr>");
+            for column in &report.columns {
+                html.push_str(&format!("<th>{}</th>", escape_html(column)));
+            }
+            html.push_str("</tr></thead>\n<tbody>\n");
+            for row in &report.rows {
+                html.push_str("<tr>");
+                for cell in row {
+                    html.push_str(&format!(
+                        "<td>{}</td>",
+                        escape_html(cell).replace('\n', "<br>")
+                    ));
+                }
+                html.push_str("</tr>\n");
+            }
+            html.push_str("</tbody>\n</table>\n");
+        }
+        if let Some(output) = report.output.as_deref().map(str::trim) {
+            if !output.is_empty() {
+                html.push_str(&format!("<pre>{}</pre>\n", escape_html(output)));
+            }
+        }
+        html.push_str(&format!(
+            "<script>{HTML_SCRIPT}</script>\n</body>\n</html>\n"
+        ));
+        html
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        Report {
+            title: "meta project check".to_string(),
+            notes: vec!["1 operation(s), 0 failed".to_string()],
+            columns: vec!["Project", "Detail"],
+            rows: vec![vec!["a<b>".to_string(), "x | y".to_string()]],
+            output: Some("done\n".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_renderer_for_path() {
+        let html = for_path(Path::new("report.HTML")).render(&report());
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<td>a&lt;b&gt;</td>"));
+        assert!(html.contains("<script>"));
+        assert!(!html.contains("src=\""), "page must be self-contained");
+
+        let md = for_path(Path::new("report.md")).render(&report());
+        assert!(md.contains("| `a<b>` | x \\| y |"), "{md}");
+
+        let json: serde_json::Value =
+            serde_json::from_str(&for_path(Path::new("report.json")).render(&report())).unwrap();
+        assert_eq!(json["rows"][0]["project"], "a<b>");
+
+        let text = for_path(Path::new("report.txt")).render(&report());
+        assert!(text.contains("Project  Detail\na<b>     x | y\n"), "{text}");
+    }
+}
@@ -0,0 +1,433 @@
+//! `meta project reset --hard` — return every project to a pristine state
+
+use crate::lock::{Lockfile, LOCKFILE_NAME};
+use crate::messages;
+use crate::style;
+use crate::summary::{RowStatus, RunSummary, Span};
+use crate::workspace_lock;
+use crate::{
+    confirm_typed, flag_value, git, jj, select_unprotected, workspace_projects, ExecuteOptions,
+};
+use colored::Colorize;
+use meta_plugin_protocol::CommandResult;
+use std::path::Path;
+use std::time::Instant;
+
+/// What `--to` resets each project to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResetTarget {
+    /// The SHA (and branch) recorded in meta-lock.json
+    Lock,
+    /// `origin/<branch>` for the checked-out branch
+    Branch,
+}
+
+/// A single project's planned reset
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ResetStep {
+    project: String,
+    /// Branch to (re)create at `commit`; `None` detaches HEAD
+    branch: Option<String>,
+    commit: String,
+    dirty: bool,
+}
+
+/// Handle `meta project reset --hard --to lock|branch`
+pub(crate) fn handle_project_reset(
+    args: &[String],
+    options: &ExecuteOptions,
+    provided_projects: &[String],
+    cwd: &Path,
+    summary: &mut RunSummary,
+) -> CommandResult {
+    if !args.iter().any(|a| a == "--hard") {
+        return CommandResult::ShowHelp(Some(
+            "project reset discards local changes and requires --hard".to_string(),
+        ));
+    }
+    let target = match flag_value(args, "--to") {
+        Some("lock") => ResetTarget::Lock,
+        Some("branch") => ResetTarget::Branch,
+        Some(other) => {
+            return CommandResult::Error(format!(
+                "Invalid --to value '{other}': expected 'lock' or 'branch'"
+            ))
+        }
+        None => {
+            return CommandResult::ShowHelp(Some(
+                "Usage: meta project reset --hard --to lock|branch".to_string(),
+            ))
+        }
+    };
+    let yes = args.iter().any(|a| a == "--yes" || a == "-y");
+    let allow_dirty = args.iter().any(|a| a == "--allow-dirty");
+    let clean = args.iter().any(|a| a == "--clean");
+
+    let projects = match workspace_projects(provided_projects, cwd) {
+        Ok(projects) => select_unprotected(projects, args),
+        Err(e) => return CommandResult::Error(e),
+    };
+    let lockfile = if target == ResetTarget::Lock {
+        match Lockfile::read(&cwd.join(LOCKFILE_NAME)) {
+            Ok(lockfile) => Some(lockfile),
+            Err(e) => return CommandResult::Error(format!("Failed to read {LOCKFILE_NAME}: {e}")),
+        }
+    } else {
+        None
+    };
+
+    let mut steps = Vec::new();
+    let mut problems = Vec::new();
+    for project in &projects {
+        let dir = cwd.join(&project.path);
+        if !git::is_repo_root(&dir) {
+            continue;
+        }
+        if jj::is_colocated(&dir) {
+            println!(
+                "{} {}: managed by jj, not reset ('jj new <commit>' keeps its changes)",
+                style::warn(options.plain, "SKIPPED"),
+                style::project(options.plain, &project.path)
+            );
+            continue;
+        }
+        let planned = match &lockfile {
+            Some(lockfile) => match lockfile.projects.get(&project.path) {
+                Some(locked) => Ok((locked.branch.clone(), locked.sha.clone())),
+                None => Err(format!("not recorded in {LOCKFILE_NAME}")),
+            },
+            None => remote_branch_target(&dir),
+        };
+        match planned {
+            Ok((branch, commit)) => steps.push(ResetStep {
+                project: project.path.clone(),
+                branch,
+                commit,
+                dirty: git::is_dirty(&dir),
+            }),
+            Err(e) => problems.push(format!("{}: {e}", project.path)),
+        }
+    }
+
+    if !problems.is_empty() {
+        return CommandResult::Error(format!(
+            "Cannot plan reset for {} project(s):\n{}",
+            problems.len(),
+            problems.join("\n")
+        ));
+    }
+    if steps.is_empty() {
+        return CommandResult::Message(messages::text("reset.none", &[]));
+    }
+
+    for step in &steps {
+        let dirty = if step.dirty && options.plain {
+            " DIRTY (uncommitted changes will be lost)".to_string()
+        } else if step.dirty {
+            format!(" {}", "(uncommitted changes will be lost)".red())
+        } else {
+            String::new()
+        };
+        println!(
+            "{}: reset to {}{dirty}",
+            style::project(options.plain, &step.project),
+            describe_target(step)
+        );
+    }
+    println!();
+
+    if options.dry_run {
+        return CommandResult::Message(messages::text("reset.dry_run", &[("count", &steps.len())]));
+    }
+
+    let _lock = match workspace_lock::acquire(cwd, "project reset", args) {
+        Ok(lock) => lock,
+        Err(e) => return CommandResult::Error(e),
+    };
+
+    let dirty = steps.iter().filter(|s| s.dirty).count();
+    if yes {
+        if dirty > 0 && !allow_dirty {
+            return CommandResult::Error(format!(
+                "{dirty} project(s) have uncommitted changes. Pass --allow-dirty with --yes to discard them."
+            ));
+        }
+    } else if !confirm_typed(
+        &format!("This discards local changes in {} project(s).", steps.len()),
+        "reset",
+    ) {
+        return CommandResult::Message(messages::text("reset.cancelled", &[]));
+    }
+
+    let mut failures = Vec::new();
+    for step in &steps {
+        let started = Instant::now();
+        match apply_step(step, clean, cwd) {
+            Ok(()) => {
+                println!("{} {}", style::ok(options.plain), step.project);
+                summary.record(
+                    &step.project,
+                    "reset",
+                    RowStatus::Ok,
+                    describe_target(step),
+                    Some(Span::since(started)),
+                );
+            }
+            Err(e) => {
+                summary.record(
+                    &step.project,
+                    "reset",
+                    RowStatus::Failed,
+                    e.to_string(),
+                    Some(Span::since(started)),
+                );
+                failures.push(format!("{}: {e}", step.project));
+            }
+        }
+    }
+
+    if failures.is_empty() {
+        CommandResult::Message(messages::text("reset.done", &[("count", &steps.len())]))
+    } else {
+        CommandResult::Error(format!(
+            "{} reset(s) failed:\n{}",
+            failures.len(),
+            failures.join("\n")
+        ))
+    }
+}
+
+/// `origin/<branch>` for the checked-out branch of the repo at `dir`
+fn remote_branch_target(dir: &Path) -> Result<(Option<String>, String), String> {
+    let branch = git::current_branch(dir).ok_or("HEAD is detached; no branch to reset to")?;
+    let remote = format!("origin/{branch}");
+    if !git::ref_exists(dir, &format!("refs/remotes/{remote}")) {
+        return Err(format!("{remote} does not exist"));
+    }
+    Ok((Some(branch), remote))
+}
+
+fn describe_target(step: &ResetStep) -> String {
+    match &step.branch {
+        Some(branch) if branch != &step.commit => format!("{} on {branch}", step.commit),
+        _ => step.commit.clone(),
+    }
+}
+
+fn apply_step(step: &ResetStep, clean: bool, cwd: &Path) -> anyhow::Result<()> {
+    let dir = cwd.join(&step.project);
+    match &step.branch {
+        Some(branch) => git::run(&dir, &["checkout", "-q", "-f", "-B", branch, &step.commit])?,
+        None => git::run(&dir, &["checkout", "-q", "-f", "--detach", &step.commit])?,
+    }
+    git::run(&dir, &["reset", "-q", "--hard", &step.commit])?;
+    if clean {
+        git::run(&dir, &["clean", "-q", "-fd"])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execute_command;
+    use crate::testing::{commit, init_upstream, init_workspace, isolate_cache, run_git};
+    use tempfile::TempDir;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_reset_requires_hard() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+
+        let result = execute_command(
+            "project reset",
+            &args(&["--to", "branch"]),
+            &ExecuteOptions::default(),
+            &[],
+            temp_dir.path(),
+        );
+        match result {
+            CommandResult::ShowHelp(Some(msg)) => assert!(msg.contains("--hard")),
+            _ => panic!("Expected ShowHelp result"),
+        }
+    }
+
+    #[test]
+    fn test_reset_to_branch_refuses_dirty_without_allow_dirty() {
+        isolate_cache();
+        let temp_dir = TempDir::new().unwrap();
+        let upstream = init_upstream(temp_dir.path());
+        let workspace = init_workspace(temp_dir.path(), &upstream);
+        run_git(
+            &workspace,
+            &["clone", "-q", &upstream.to_string_lossy(), "repo1"],
+        );
+        std::fs::write(workspace.join("repo1").join("scratch.txt"), "wip").unwrap();
+
+        let result = execute_command(
+            "project reset",
+            &args(&["--hard", "--to", "branch", "--yes"]),
+            &ExecuteOptions::default(),
+            &[],
+            &workspace,
+        );
+        match result {
+            CommandResult::Error(msg) => assert!(msg.contains("--allow-dirty"), "{msg}"),
+            _ => panic!("Expected Error result"),
+        }
+        assert!(workspace.join("repo1").join("scratch.txt").exists());
+    }
+
+    #[test]
+    fn test_reset_skips_protected_projects() {
+        isolate_cache();
+        let temp_dir = TempDir::new().unwrap();
+        let upstream = init_upstream(temp_dir.path());
+        let workspace = init_workspace(temp_dir.path(), &upstream);
+        std::fs::write(
+            workspace.join(".meta"),
+            format!(
+                r#"{{"projects": {{"repo1": {{"repo": "{}", "protected": true}}}}}}"#,
+                upstream.display()
+            ),
+        )
+        .unwrap();
+        run_git(
+            &workspace,
+            &["clone", "-q", &upstream.to_string_lossy(), "repo1"],
+        );
+        let repo = workspace.join("repo1");
+        commit(&repo, "local work");
+        let local_head = git::output(&repo, &["rev-parse", "HEAD"]).unwrap();
+
+        let result = execute_command(
+            "project reset",
+            &args(&["--hard", "--to", "branch", "--yes"]),
+            &ExecuteOptions::default(),
+            &[],
+            &workspace,
+        );
+        match result {
+            CommandResult::Message(msg) => assert!(msg.contains("No cloned projects"), "{msg}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert_eq!(git::output(&repo, &["rev-parse", "HEAD"]), Some(local_head));
+
+        let result = execute_command(
+            "project reset",
+            &args(&["--hard", "--to", "branch", "--yes", "--include-protected"]),
+            &ExecuteOptions::default(),
+            &[],
+            &workspace,
+        );
+        match result {
+            CommandResult::Message(msg) => assert!(msg.contains("Reset 1 project"), "{msg}"),
+            _ => panic!("Expected Message result"),
+        }
+    }
+
+    #[test]
+    fn test_reset_to_branch_discards_local_commits() {
+        isolate_cache();
+        let temp_dir = TempDir::new().unwrap();
+        let upstream = init_upstream(temp_dir.path());
+        let workspace = init_workspace(temp_dir.path(), &upstream);
+        run_git(
+            &workspace,
+            &["clone", "-q", &upstream.to_string_lossy(), "repo1"],
+        );
+        let repo = workspace.join("repo1");
+        let remote_head = git::output(&repo, &["rev-parse", "origin/main"]).unwrap();
+        commit(&repo, "local work");
+        std::fs::write(repo.join("scratch.txt"), "wip").unwrap();
+
+        let result = execute_command(
+            "project reset",
+            &args(&["--hard", "--to=branch", "--yes", "--allow-dirty", "--clean"]),
+            &ExecuteOptions::default(),
+            &[],
+            &workspace,
+        );
+        match result {
+            CommandResult::Message(msg) => assert!(msg.contains("Reset 1 project"), "{msg}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert_eq!(
+            git::output(&repo, &["rev-parse", "HEAD"]).unwrap(),
+            remote_head
+        );
+        assert!(!repo.join("scratch.txt").exists());
+    }
+
+    #[test]
+    fn test_reset_to_lock_requires_entry() {
+        let temp_dir = TempDir::new().unwrap();
+        let upstream = init_upstream(temp_dir.path());
+        let workspace = init_workspace(temp_dir.path(), &upstream);
+        run_git(
+            &workspace,
+            &["clone", "-q", &upstream.to_string_lossy(), "repo1"],
+        );
+        std::fs::write(
+            workspace.join(LOCKFILE_NAME),
+            r#"{"version": 1, "projects": {}}"#,
+        )
+        .unwrap();
+
+        let result = execute_command(
+            "project reset",
+            &args(&["--hard", "--to", "lock", "--yes"]),
+            &ExecuteOptions::default(),
+            &[],
+            &workspace,
+        );
+        match result {
+            CommandResult::Error(msg) => assert!(msg.contains("not recorded"), "{msg}"),
+            _ => panic!("Expected Error result"),
+        }
+    }
+
+    #[test]
+    fn test_reset_to_lock_checks_out_recorded_sha() {
+        isolate_cache();
+        let temp_dir = TempDir::new().unwrap();
+        let upstream = init_upstream(temp_dir.path());
+        let workspace = init_workspace(temp_dir.path(), &upstream);
+        run_git(
+            &workspace,
+            &["clone", "-q", &upstream.to_string_lossy(), "repo1"],
+        );
+        let repo = workspace.join("repo1");
+        let locked_sha = git::output(&repo, &["rev-parse", "HEAD"]).unwrap();
+        commit(&repo, "after lock");
+        std::fs::write(
+            workspace.join(LOCKFILE_NAME),
+            format!(
+                r#"{{"version": 1, "projects": {{"repo1": {{"url": "{}", "branch": "main", "sha": "{locked_sha}"}}}}}}"#,
+                upstream.display()
+            ),
+        )
+        .unwrap();
+
+        let result = execute_command(
+            "project reset",
+            &args(&["--hard", "--to", "lock", "--yes"]),
+            &ExecuteOptions::default(),
+            &[],
+            &workspace,
+        );
+        match result {
+            CommandResult::Message(msg) => assert!(msg.contains("Reset 1 project"), "{msg}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert_eq!(
+            git::output(&repo, &["rev-parse", "HEAD"]).unwrap(),
+            locked_sha
+        );
+        assert_eq!(git::current_branch(&repo).as_deref(), Some("main"));
+    }
+}
@@ -0,0 +1,367 @@
+//! `meta project restore --locked` — bring the workspace to meta-lock.json
+//!
+//! Unlike `reset --hard --to lock`, this never discards work: it clones the
+//! locked projects that are missing and checks out the recorded commit (and
+//! branch) in the others, but refuses projects with uncommitted changes and
+//! local branches holding commits the lock doesn't contain, which moving the
+//! branch would orphan. A recorded commit that isn't in a clone is fetched from origin; one that
+//! can't be found there either fails the command before any existing clone
+//! is touched.
+
+use crate::lock::{LockedProject, Lockfile, LOCKFILE_NAME};
+use crate::style;
+use crate::summary::{RowStatus, RunSummary, Span};
+use crate::workspace_lock;
+use crate::{git, jj, workspace_projects, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use std::path::Path;
+use std::time::Instant;
+
+/// What restoring one locked project takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Clone,
+    Checkout,
+}
+
+/// Handle `meta project restore --locked`
+pub(crate) fn handle_restore(
+    args: &[String],
+    options: &ExecuteOptions,
+    provided_projects: &[String],
+    cwd: &Path,
+    summary: &mut RunSummary,
+) -> CommandResult {
+    if !args.iter().any(|a| a == "--locked") {
+        return CommandResult::ShowHelp(Some("Usage: meta project restore --locked".to_string()));
+    }
+    let lockfile = match Lockfile::read(&cwd.join(LOCKFILE_NAME)) {
+        Ok(lockfile) => lockfile,
+        Err(e) => return CommandResult::Error(format!("Failed to read {LOCKFILE_NAME}: {e}")),
+    };
+    let declared = match workspace_projects(provided_projects, cwd) {
+        Ok(projects) => projects,
+        Err(e) => return CommandResult::Error(e),
+    };
+
+    let mut steps = Vec::new();
+    let mut current = 0;
+    let mut problems = Vec::new();
+    // Only the selected projects (all declared ones by default) are restored
+    let selected = lockfile
+        .projects
+        .iter()
+        .filter(|(path, _)| declared.iter().any(|p| &p.path == *path));
+    for (path, locked) in selected {
+        let dir = cwd.join(path);
+        if !git::is_repo_root(&dir) {
+            if dir.exists() {
+                problems.push(format!("{path}: exists but is not a git clone"));
+            } else {
+                steps.push((path, locked, Action::Clone));
+            }
+        } else if is_at(&dir, locked) {
+            current += 1;
+        } else if !jj::is_colocated(&dir) && git::is_dirty(&dir) {
+            problems.push(format!(
+                "{path}: has uncommitted changes (commit or stash them, or use 'reset --hard --to lock')"
+            ));
+        } else if let Some(problem) = unlocked_commits(&dir, locked) {
+            problems.push(format!("{path}: {problem}"));
+        } else {
+            steps.push((path, locked, Action::Checkout));
+        }
+    }
+    if !problems.is_empty() {
+        return CommandResult::Error(format!(
+            "Cannot restore {} project(s):\n{}",
+            problems.len(),
+            problems.join("\n")
+        ));
+    }
+    let unlocked: Vec<&str> = declared
+        .iter()
+        .filter(|p| !lockfile.projects.contains_key(&p.path))
+        .map(|p| p.path.as_str())
+        .collect();
+    let unlocked_note = if unlocked.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "\nNot in {LOCKFILE_NAME}, left as they are: {}",
+            unlocked.join(", ")
+        )
+    };
+
+    if options.dry_run {
+        for (path, locked, action) in &steps {
+            match action {
+                Action::Clone => println!("[dry-run] git clone {} {path}", locked.url),
+                Action::Checkout => println!("[dry-run] git -C {path} checkout {}", target(locked)),
+            }
+        }
+        return CommandResult::Message(format!(
+            "Dry run: would restore {} project(s); {current} already match {LOCKFILE_NAME}.{unlocked_note}",
+            steps.len()
+        ));
+    }
+    if steps.is_empty() {
+        return CommandResult::Message(format!(
+            "All {current} locked project(s) already match {LOCKFILE_NAME}.{unlocked_note}"
+        ));
+    }
+
+    let _lock = match workspace_lock::acquire(cwd, "project restore", args) {
+        Ok(lock) => lock,
+        Err(e) => return CommandResult::Error(e),
+    };
+
+    // Every commit has to be there before any clone changes; a fetched one
+    // gets the branch check it couldn't have while planning
+    let unreachable: Vec<String> = steps
+        .iter()
+        .filter(|(_, _, action)| *action == Action::Checkout)
+        .filter_map(|(path, locked, _)| {
+            let dir = cwd.join(path);
+            ensure_commit(&dir, &locked.sha)
+                .err()
+                .or_else(|| unlocked_commits(&dir, locked))
+                .map(|e| format!("{path}: {e}"))
+        })
+        .collect();
+    if !unreachable.is_empty() {
+        return CommandResult::Error(format!(
+            "Cannot restore {} project(s); nothing was changed:\n{}",
+            unreachable.len(),
+            unreachable.join("\n")
+        ));
+    }
+
+    let mut failures = Vec::new();
+    for (path, locked, action) in &steps {
+        let started = Instant::now();
+        let dir = cwd.join(path);
+        let result = match action {
+            Action::Clone => git::run_network(cwd, &["clone", "-q", "--", &locked.url, path])
+                .map_err(|e| e.to_string())
+                .and_then(|()| ensure_commit(&dir, &locked.sha)),
+            Action::Checkout => Ok(()),
+        }
+        .and_then(|()| checkout(&dir, locked));
+        let (status, detail) = match &result {
+            Ok(()) => {
+                println!("{} {path} at {}", style::ok(options.plain), target(locked));
+                (RowStatus::Ok, target(locked))
+            }
+            Err(e) => {
+                failures.push(format!("{path}: {e}"));
+                (RowStatus::Failed, e.clone())
+            }
+        };
+        summary.record(path, "restore", status, detail, Some(Span::since(started)));
+    }
+
+    if failures.is_empty() {
+        CommandResult::Message(format!(
+            "Restored {} project(s) to {LOCKFILE_NAME}; {current} already matched.{unlocked_note}",
+            steps.len()
+        ))
+    } else {
+        CommandResult::Error(format!(
+            "{} restore(s) failed:\n{}",
+            failures.len(),
+            failures.join("\n")
+        ))
+    }
+}
+
+/// Whether the clone at `dir` has `locked` checked out
+///
+/// jj never has a branch checked out, so only the commit counts there.
+fn is_at(dir: &Path, locked: &LockedProject) -> bool {
+    git::output(dir, &["rev-parse", "HEAD"]).as_deref() == Some(locked.sha.as_str())
+        && (jj::is_colocated(dir) || git::current_branch(dir) == locked.branch)
+}
+
+/// Why moving the recorded branch to the recorded commit would orphan
+/// commits, if it would: the local branch has commits the commit lacks
+///
+/// `None` when there is no such branch, in jj repos (which never move a
+/// branch) and while the commit isn't in the clone yet.
+fn unlocked_commits(dir: &Path, locked: &LockedProject) -> Option<String> {
+    let branch = locked.branch.as_deref()?;
+    let commit = format!("{}^{{commit}}", locked.sha);
+    if jj::is_colocated(dir)
+        || !git::ref_exists(dir, &format!("refs/heads/{branch}"))
+        || git::output(dir, &["cat-file", "-e", &commit]).is_none()
+        || git::run(dir, &["merge-base", "--is-ancestor", branch, &locked.sha]).is_ok()
+    {
+        return None;
+    }
+    Some(format!(
+        "{branch} has commits not in {LOCKFILE_NAME} (push them or move them to another branch, or use 'reset --hard --to lock')"
+    ))
+}
+
+/// Make sure `sha` is in the clone at `dir`, fetching origin if it isn't
+fn ensure_commit(dir: &Path, sha: &str) -> Result<(), String> {
+    let commit = format!("{sha}^{{commit}}");
+    let present = || git::output(dir, &["cat-file", "-e", &commit]).is_some();
+    if present() {
+        return Ok(());
+    }
+    git::run_network(dir, &["fetch", "-q", "origin"]).map_err(|e| e.to_string())?;
+    if present() {
+        Ok(())
+    } else {
+        Err(format!("commit {sha} is not reachable from origin"))
+    }
+}
+
+/// Check out the recorded commit, moving the recorded branch to it; in a jj
+/// repo, start a new change on top of the commit
+fn checkout(dir: &Path, locked: &LockedProject) -> Result<(), String> {
+    let result = match &locked.branch {
+        _ if jj::is_colocated(dir) => jj::new(dir, &locked.sha),
+        Some(branch) => git::run(dir, &["checkout", "-q", "-B", branch, &locked.sha]),
+        None => git::run(dir, &["checkout", "-q", "--detach", &locked.sha]),
+    };
+    result.map_err(|e| e.to_string())
+}
+
+fn target(locked: &LockedProject) -> String {
+    match &locked.branch {
+        Some(branch) => format!("{} on {branch}", locked.sha),
+        None => locked.sha.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{commit, run_git, Fixture};
+
+    #[test]
+    fn test_restore_locked() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        fixture.manifest(&[("api", &api), ("web", &web)]);
+        fixture.clone_project("api", &api);
+        fixture.clone_project("web", &web);
+        let workspace = fixture.workspace();
+        match fixture.run("project lock", &[]) {
+            CommandResult::Message(msg) => assert!(msg.starts_with("Locked 2"), "{msg}"),
+            _ => panic!("Expected Message result"),
+        }
+        let locked_api = git::output(&workspace.join("api"), &["rev-parse", "HEAD"]);
+
+        // api moves on to a feature branch, web disappears
+        run_git(&workspace.join("api"), &["checkout", "-q", "-b", "feature"]);
+        commit(&workspace.join("api"), "after the lock");
+        let feature = git::output(&workspace.join("api"), &["rev-parse", "HEAD"]);
+        std::fs::remove_dir_all(workspace.join("web")).unwrap();
+
+        match fixture.run("project restore", &["--locked"]) {
+            CommandResult::Message(msg) => assert_eq!(
+                msg,
+                "Restored 2 project(s) to meta-lock.json; 0 already matched."
+            ),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert_eq!(
+            git::output(&workspace.join("api"), &["rev-parse", "HEAD"]),
+            locked_api
+        );
+        assert_eq!(
+            git::output(&workspace.join("api"), &["rev-parse", "feature"]),
+            feature
+        );
+        assert!(workspace.join("web/.git").exists());
+        match fixture.run("project restore", &["--locked"]) {
+            CommandResult::Message(msg) => {
+                assert!(
+                    msg.starts_with("All 2 locked project(s) already match"),
+                    "{msg}"
+                )
+            }
+            _ => panic!("Expected Message result"),
+        }
+
+        // A commit that never reached origin fails before anything changes
+        commit(&workspace.join("api"), "local only");
+        match fixture.run("project lock", &[]) {
+            CommandResult::Message(_) => {}
+            _ => panic!("Expected Message result"),
+        }
+        run_git(&workspace.join("api"), &["reset", "-q", "--hard", "HEAD~1"]);
+        run_git(
+            &workspace.join("api"),
+            &["reflog", "expire", "--expire=now", "--all"],
+        );
+        run_git(&workspace.join("api"), &["gc", "-q", "--prune=now"]);
+        run_git(&workspace.join("web"), &["checkout", "-q", "--detach"]);
+        match fixture.run("project restore", &["--locked"]) {
+            CommandResult::Error(e) => {
+                assert!(e.starts_with("Cannot restore 1 project(s)"), "{e}");
+                assert!(e.contains("api: commit"), "{e}");
+            }
+            _ => panic!("Expected Error result"),
+        }
+        assert_eq!(git::current_branch(&workspace.join("web")), None);
+    }
+
+    #[test]
+    fn test_restore_keeps_commits_the_lock_lacks() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        fixture.manifest(&[("api", &api), ("web", &web)]);
+        fixture.clone_project("api", &api);
+        fixture.clone_project("web", &web);
+        let workspace = fixture.workspace();
+        match fixture.run("project lock", &[]) {
+            CommandResult::Message(_) => {}
+            _ => panic!("Expected Message result"),
+        }
+        commit(&workspace.join("api"), "unpushed work");
+        let unpushed = git::output(&workspace.join("api"), &["rev-parse", "HEAD"]);
+
+        match fixture.run("project restore", &["--locked"]) {
+            CommandResult::Error(e) => {
+                assert!(e.starts_with("Cannot restore 1 project(s)"), "{e}");
+                assert!(
+                    e.contains("api: main has commits not in meta-lock.json"),
+                    "{e}"
+                );
+                assert!(e.contains("reset --hard --to lock"), "{e}");
+            }
+            _ => panic!("Expected Error result"),
+        }
+        assert_eq!(
+            git::output(&workspace.join("api"), &["rev-parse", "main"]),
+            unpushed
+        );
+
+        // Outside the selection, api is left alone and web is restored
+        std::fs::remove_dir_all(workspace.join("web")).unwrap();
+        std::fs::write(workspace.join("selected.txt"), "web\n").unwrap();
+        match fixture.run(
+            "project restore",
+            &["--locked", "--projects-from", "selected.txt"],
+        ) {
+            CommandResult::Message(msg) => assert_eq!(
+                msg,
+                "Restored 1 project(s) to meta-lock.json; 0 already matched."
+            ),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(workspace.join("web/.git").exists());
+        assert_eq!(
+            git::output(&workspace.join("api"), &["rev-parse", "main"]),
+            unpushed
+        );
+    }
+}
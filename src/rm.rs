@@ -0,0 +1,172 @@
+//! `meta project rm` — remove a project from .meta and the workspace
+
+use crate::{confirm, git, manifest, resolve_project_key, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use std::path::Path;
+
+/// Handle `meta project rm <name> [--keep-files] [--include-protected]`
+///
+/// The working copy is deleted too unless `--keep-files` is given; one with
+/// uncommitted changes or unpushed commits is never deleted.
+pub(crate) fn handle_rm(args: &[String], options: &ExecuteOptions, cwd: &Path) -> CommandResult {
+    let Some(name) = args.iter().find(|a| !a.starts_with('-')) else {
+        return CommandResult::Error(
+            "Usage: meta project rm <name> [--keep-files] [--include-protected]".to_string(),
+        );
+    };
+    let keep_files = args.iter().any(|a| a == "--keep-files");
+
+    let Some(meta_path) = manifest::find(cwd) else {
+        return CommandResult::Error(format!("No .meta config found in {}", cwd.display()));
+    };
+    let mut document = match manifest::read(&meta_path) {
+        Ok(document) => document,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let Some(projects) = document.get_mut("projects").and_then(|p| p.as_object_mut()) else {
+        return CommandResult::Error("No projects declared in .meta".to_string());
+    };
+    let name = match resolve_project_key(name, projects) {
+        Ok(name) => name,
+        Err(e) => return CommandResult::Error(e),
+    };
+    let entry = &projects[&name];
+    let path = entry
+        .get("path")
+        .and_then(|p| p.as_str())
+        .unwrap_or(&name)
+        .to_string();
+    let protected = entry.get("protected").and_then(|v| v.as_bool()) == Some(true);
+    if protected
+        && !args.iter().any(|a| a == "--include-protected")
+        && (args.iter().any(|a| a == "--yes" || a == "-y")
+            || !confirm(&format!("'{name}' is protected. Remove it anyway?")))
+    {
+        return CommandResult::Error(format!(
+            "'{name}' is protected; pass --include-protected to remove it"
+        ));
+    }
+
+    let dir = cwd.join(&path);
+    let delete = !keep_files && dir.exists();
+    if delete {
+        if let Err(e) = ensure_disposable(&dir) {
+            return CommandResult::Error(format!(
+                "Refusing to delete {path}: {e}. Pass --keep-files to only remove it from .meta."
+            ));
+        }
+    }
+    projects.remove(name.as_str());
+
+    let removed_files = if delete {
+        format!(" and deleted {path}")
+    } else {
+        String::new()
+    };
+    if options.dry_run {
+        return CommandResult::Message(format!(
+            "Dry run: would remove '{name}' from .meta{removed_files}."
+        ));
+    }
+    if let Err(e) = manifest::write(&meta_path, &document) {
+        return CommandResult::Error(e);
+    }
+    if delete {
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            return CommandResult::Error(format!(
+                "Removed '{name}' from .meta, but deleting {path} failed: {e}"
+            ));
+        }
+    }
+    CommandResult::Message(format!("Removed '{name}' from .meta{removed_files}."))
+}
+
+/// Fail unless deleting the repo at `dir` loses nothing that isn't upstream
+fn ensure_disposable(dir: &Path) -> Result<(), String> {
+    if !git::is_repo_root(dir) {
+        return Err("it is not a git clone".to_string());
+    }
+    if git::is_dirty(dir) {
+        return Err("it has uncommitted changes".to_string());
+    }
+    let unpushed = git::output(
+        dir,
+        &[
+            "rev-list",
+            "--count",
+            "HEAD",
+            "--branches",
+            "--not",
+            "--remotes",
+        ],
+    )
+    .and_then(|count| count.parse::<usize>().ok());
+    match unpushed {
+        Some(0) => Ok(()),
+        Some(count) => Err(format!("it has {count} unpushed commit(s)")),
+        None => Err("its commits could not be checked".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{commit, Fixture};
+
+    #[test]
+    fn test_rm_refuses_dirty_and_unpushed() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        fixture.manifest(&[("api", &api), ("web", &web)]);
+        fixture.clone_project("api", &api);
+        let api_dir = fixture.workspace().join("api");
+
+        std::fs::write(api_dir.join("notes.txt"), "wip").unwrap();
+        match fixture.run("project rm", &["api"]) {
+            CommandResult::Error(e) => assert!(e.contains("uncommitted changes"), "{e}"),
+            _ => panic!("Expected Error result"),
+        }
+        std::fs::remove_file(api_dir.join("notes.txt")).unwrap();
+        commit(&api_dir, "local only");
+        match fixture.run("project rm", &["api"]) {
+            CommandResult::Error(e) => assert!(e.contains("1 unpushed commit(s)"), "{e}"),
+            _ => panic!("Expected Error result"),
+        }
+
+        match fixture.run("project rm", &["api", "--keep-files"]) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Removed 'api' from .meta."),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(api_dir.exists());
+        let document = manifest::read(&fixture.workspace().join(".meta")).unwrap();
+        assert!(document["projects"].get("api").is_none());
+    }
+
+    #[test]
+    fn test_rm_deletes_clean_clone() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        fixture.manifest(&[("api", &api)]);
+        fixture.clone_project("api", &api);
+
+        match fixture.run("project rm", &["apu"]) {
+            CommandResult::Error(e) => {
+                assert_eq!(e, "Unknown project or alias: apu. Did you mean: api?")
+            }
+            _ => panic!("Expected Error result"),
+        }
+        match fixture.run("project rm", &["api"]) {
+            CommandResult::Message(msg) => {
+                assert_eq!(msg, "Removed 'api' from .meta and deleted api.")
+            }
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(!fixture.workspace().join("api").exists());
+        match fixture.run("project rm", &["api"]) {
+            CommandResult::Error(e) => assert_eq!(e, "Unknown project or alias: api"),
+            _ => panic!("Expected Error result"),
+        }
+    }
+}
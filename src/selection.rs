@@ -0,0 +1,129 @@
+//! The global `--projects-from <file>`: limit a command to listed projects
+//!
+//! The file names one project per line, by path or by the last component of
+//! its path, so other commands' output can be saved and fed back in:
+//!
+//! ```text
+//! meta project list --format '{path}\t{behind}' | awk '$2 > 0 {print $1}' > behind.txt
+//! meta project reset --hard --to branch --projects-from behind.txt
+//! ```
+//!
+//! Stdin is not an option: it carries meta's request to the plugin, so a
+//! pipe into `meta` never reaches the plugin. Blank lines and lines starting with `#` are skipped. Every command that
+//! works through the workspace's declared projects sees only the listed
+//! ones; a name that matches no project is an error rather than a silent
+//! no-op. Like the deadline, the selection belongs to the thread running
+//! the command.
+
+use crate::WorkspaceProject;
+use std::cell::RefCell;
+use std::path::Path;
+
+thread_local! {
+    static CURRENT: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Limit this thread's command to the projects listed in the file `source`
+/// (relative to `cwd`); `None` selects every project
+pub(crate) fn start(source: Option<&str>, cwd: &Path) -> Result<(), String> {
+    let text = match source {
+        None => {
+            CURRENT.with(|current| *current.borrow_mut() = None);
+            return Ok(());
+        }
+        Some("-") => {
+            return Err(
+                "--projects-from can't read stdin, which carries meta's request; \
+                 write the list to a file and pass its path"
+                    .to_string(),
+            )
+        }
+        Some(path) => std::fs::read_to_string(cwd.join(path))
+            .map_err(|e| format!("Failed to read --projects-from file {path}: {e}"))?,
+    };
+    let names = parse(&text);
+    if names.is_empty() {
+        return Err(format!(
+            "--projects-from {} lists no projects",
+            source.unwrap_or_default()
+        ));
+    }
+    CURRENT.with(|current| *current.borrow_mut() = Some(names));
+    Ok(())
+}
+
+fn parse(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.trim_end_matches('/').to_string())
+        .collect()
+}
+
+/// Keep only the selected projects, failing on a name that matches none
+pub(crate) fn retain(projects: &mut Vec<WorkspaceProject>) -> Result<(), String> {
+    let Some(names) = CURRENT.with(|current| current.borrow().clone()) else {
+        return Ok(());
+    };
+    let matches = |project: &WorkspaceProject, name: &str| {
+        project.path == name || Path::new(&project.path).file_name() == Some(name.as_ref())
+    };
+    let unknown: Vec<&str> = names
+        .iter()
+        .filter(|name| !projects.iter().any(|p| matches(p, name)))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        return Err(format!(
+            "--projects-from lists unknown project(s): {}",
+            unknown.join(", ")
+        ));
+    }
+    projects.retain(|p| names.iter().any(|name| matches(p, name)));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Fixture;
+    use meta_plugin_protocol::CommandResult;
+
+    #[test]
+    fn test_projects_from_file() {
+        assert_eq!(
+            parse("api\n\n# frontends\n  libs/web/ \n"),
+            vec!["api", "libs/web"]
+        );
+
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        let workspace = fixture.workspace();
+        fixture.manifest(&[("api", &api), ("libs/web", &web)]);
+        std::fs::write(workspace.join("selected.txt"), "web\n").unwrap();
+
+        match fixture.run(
+            "project check",
+            &["--fix", "--yes", "--projects-from", "selected.txt"],
+        ) {
+            CommandResult::Message(msg) => assert_eq!(msg, "Applied 1 fix(es)."),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+        assert!(workspace.join("libs/web/.git").is_dir());
+        assert!(!workspace.join("api").exists());
+
+        std::fs::write(workspace.join("selected.txt"), "web\ndocs\n").unwrap();
+        match fixture.run("project check", &["--projects-from", "selected.txt"]) {
+            CommandResult::Error(e) => {
+                assert_eq!(e, "--projects-from lists unknown project(s): docs")
+            }
+            _ => panic!("Expected Error result"),
+        }
+        match fixture.run("project check", &["--projects-from", "-"]) {
+            CommandResult::Error(e) => assert!(e.contains("can't read stdin"), "{e}"),
+            _ => panic!("Expected Error result"),
+        }
+    }
+}
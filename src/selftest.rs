@@ -0,0 +1,264 @@
+//! `meta project self-test` — exercise the plugin end to end in a sandbox
+//!
+//! Builds a throwaway workspace under the system temp directory with a local
+//! bare upstream and a generated .meta, then drives the real commands against
+//! it: clone (check --fix), update (check --fetch --fix --fast-forward),
+//! and clean (reset --hard --clean). Nothing outside the sandbox is touched,
+//! and no network access is needed.
+
+use crate::{execute_command, git, messages, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Sandbox directory, removed on drop (kept with `--keep` for inspection)
+struct Sandbox {
+    root: PathBuf,
+    keep: bool,
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = std::fs::remove_dir_all(&self.root);
+        }
+    }
+}
+
+/// Handle `meta project self-test [--keep]`
+pub(crate) fn handle_self_test(args: &[String], options: &ExecuteOptions) -> CommandResult {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let sandbox = Sandbox {
+        root: std::env::temp_dir().join(format!(
+            "meta-project-self-test-{}-{nanos}",
+            std::process::id()
+        )),
+        keep: args.iter().any(|a| a == "--keep"),
+    };
+    if let Err(e) = std::fs::create_dir_all(&sandbox.root) {
+        return CommandResult::Error(format!("Failed to create sandbox: {e}"));
+    }
+
+    let results = run_steps(&sandbox.root, options);
+    let failed = results.iter().filter(|(_, r)| r.is_err()).count();
+
+    let mut lines: Vec<String> = results
+        .iter()
+        .map(|(name, result)| match result {
+            Ok(detail) if detail.is_empty() => format!("PASS  {name}"),
+            Ok(detail) => format!("PASS  {name} ({detail})"),
+            Err(e) => format!("FAIL  {name}: {e}"),
+        })
+        .collect();
+    if sandbox.keep {
+        lines.push(format!("Sandbox kept at {}", sandbox.root.display()));
+    }
+
+    if failed == 0 {
+        lines.push(format!("Self-test passed: {} step(s).", results.len()));
+        CommandResult::Message(lines.join("\n"))
+    } else {
+        lines.push(format!(
+            "Self-test failed: {failed} of {} step(s).",
+            results.len()
+        ));
+        CommandResult::Error(lines.join("\n"))
+    }
+}
+
+type StepResult = Result<String, String>;
+
+/// A named check; later steps rely on the sandbox state earlier ones leave
+type Step<'a> = (&'static str, Box<dyn Fn() -> StepResult + 'a>);
+
+/// Run every step in order; a failed step stops the remaining ones
+fn run_steps(root: &Path, options: &ExecuteOptions) -> Vec<(&'static str, StepResult)> {
+    let workspace = root.join("workspace");
+    let source = root.join("source");
+    let repo = workspace.join("app");
+    // Nested commands get --yes and no dry-run: the sandbox is disposable
+    let options = ExecuteOptions {
+        dry_run: false,
+        json_output: false,
+        recursive: false,
+        ..*options
+    };
+
+    let steps: Vec<Step> = vec![
+        (
+            "git is installed",
+            Box::new(|| git::output(root, &["--version"]).ok_or("git not found on PATH".into())),
+        ),
+        (
+            "create upstream and manifest",
+            Box::new(|| create_fixture(root, &source, &workspace)),
+        ),
+        (
+            "check reports the missing project",
+            Box::new(|| {
+                let expected = messages::text("check.missing", &[("count", &1)]);
+                match run(&workspace, &options, &["check"]) {
+                    Ok(msg) if msg.contains(&expected) => Ok(String::new()),
+                    other => Err(format!("expected a missing finding, got {other:?}")),
+                }
+            }),
+        ),
+        (
+            "clone with check --fix",
+            Box::new(|| {
+                run(&workspace, &options, &["check", "--fix", "--yes"])?;
+                expect_head(&repo, &source)
+            }),
+        ),
+        (
+            "check passes on a fresh clone",
+            Box::new(|| {
+                let msg = run(&workspace, &options, &["check"])?;
+                if msg == messages::text("check.all_present", &[]) {
+                    Ok(String::new())
+                } else {
+                    Err(format!("unexpected findings: {msg}"))
+                }
+            }),
+        ),
+        (
+            "update with check --fetch --fix --fast-forward",
+            Box::new(|| {
+                sandbox_commit(&source, "second")?;
+                git::run(&source, &["push", "-q", "origin", "main"]).map_err(|e| e.to_string())?;
+                run(
+                    &workspace,
+                    &options,
+                    &["check", "--fetch", "--fix", "--fast-forward", "--yes"],
+                )?;
+                expect_head(&repo, &source)
+            }),
+        ),
+        (
+            "clean with reset --hard --clean",
+            Box::new(|| {
+                std::fs::write(repo.join("scratch.txt"), "self-test").map_err(|e| e.to_string())?;
+                run(
+                    &workspace,
+                    &options,
+                    &[
+                        "reset",
+                        "--hard",
+                        "--to",
+                        "branch",
+                        "--clean",
+                        "--yes",
+                        "--allow-dirty",
+                    ],
+                )?;
+                if repo.join("scratch.txt").exists() {
+                    return Err("untracked file survived reset --clean".to_string());
+                }
+                Ok(String::new())
+            }),
+        ),
+    ];
+
+    let mut results = Vec::new();
+    for (name, step) in steps {
+        let result = step();
+        let stop = result.is_err();
+        results.push((name, result));
+        if stop {
+            break;
+        }
+    }
+    results
+}
+
+/// `source` (non-bare, main) pushed to `upstream.git`, and a workspace listing it as `app`
+fn create_fixture(root: &Path, source: &Path, workspace: &Path) -> StepResult {
+    let io = |e: std::io::Error| e.to_string();
+    std::fs::create_dir_all(source).map_err(io)?;
+    std::fs::create_dir_all(workspace).map_err(io)?;
+    let git = |dir: &Path, args: &[&str]| git::run(dir, args).map_err(|e| e.to_string());
+
+    git(source, &["init", "-q"])?;
+    git(source, &["symbolic-ref", "HEAD", "refs/heads/main"])?;
+    sandbox_commit(source, "initial")?;
+    git(root, &["clone", "-q", "--bare", "source", "upstream.git"])?;
+    let upstream = root.join("upstream.git");
+    git(
+        source,
+        &["remote", "add", "origin", &upstream.to_string_lossy()],
+    )?;
+
+    let manifest = serde_json::json!({ "projects": { "app": upstream.to_string_lossy() } });
+    std::fs::write(workspace.join(".meta"), manifest.to_string()).map_err(io)?;
+    Ok(root.display().to_string())
+}
+
+fn sandbox_commit(dir: &Path, message: &str) -> Result<(), String> {
+    git::run(
+        dir,
+        &[
+            "-c",
+            "user.name=meta self-test",
+            "-c",
+            "user.email=self-test@localhost",
+            "-c",
+            "commit.gpgsign=false",
+            "commit",
+            "-q",
+            "--allow-empty",
+            "-m",
+            message,
+        ],
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Run a `project` subcommand in the sandbox workspace
+fn run(workspace: &Path, options: &ExecuteOptions, args: &[&str]) -> Result<String, String> {
+    let (command, rest) = args.split_first().expect("subcommand");
+    let rest: Vec<String> = rest.iter().map(|a| a.to_string()).collect();
+    match execute_command(
+        &format!("project {command}"),
+        &rest,
+        options,
+        &[],
+        workspace,
+    ) {
+        CommandResult::Message(msg) => Ok(msg),
+        CommandResult::Error(e) => Err(e),
+        _ => Err(format!("project {command} returned no result")),
+    }
+}
+
+/// The clone's HEAD matches the source repo's HEAD
+fn expect_head(repo: &Path, source: &Path) -> StepResult {
+    let actual = git::output(repo, &["rev-parse", "HEAD"]).ok_or("clone has no HEAD")?;
+    let expected = git::output(source, &["rev-parse", "HEAD"]).ok_or("source has no HEAD")?;
+    if actual == expected {
+        Ok(actual[..actual.len().min(12)].to_string())
+    } else {
+        Err(format!("clone is at {actual}, expected {expected}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::isolate_cache;
+
+    #[test]
+    fn test_self_test_passes() {
+        isolate_cache();
+        match handle_self_test(&[], &ExecuteOptions::default()) {
+            CommandResult::Message(msg) => {
+                assert!(msg.ends_with("Self-test passed: 7 step(s)."), "{msg}");
+                assert!(!msg.contains("FAIL"), "{msg}");
+            }
+            CommandResult::Error(e) => panic!("self-test failed:\n{e}"),
+            _ => panic!("Expected Message result"),
+        }
+    }
+}
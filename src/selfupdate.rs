@@ -0,0 +1,461 @@
+//! `meta project self-update` — keep the plugin binary current
+//!
+//! The release feed is the latest GitHub release of the repository the
+//! plugin is built from, or the URL in `META_PROJECT_RELEASE_FEED` (say, an
+//! internal mirror serving the same JSON). A release carries one binary per
+//! platform, named `meta-project-<arch>-<os>`, a `SHA256SUMS` file covering
+//! them and `SHA256SUMS.sig`, an SSH signature of that file (`ssh-keygen -Y
+//! sign -n meta-project-release`). Downloads go through `curl`. The
+//! signature is verified with `ssh-keygen -Y verify` against the release key
+//! built into the binary (`META_PROJECT_RELEASE_KEY` at build time, an
+//! `ssh-ed25519 ...` public key), then the binary against `SHA256SUMS`, and
+//! only then is it swapped for the running executable. A build without the
+//! key refuses to self-update.
+//!
+//! With `"settings": {"update_check": true}`, the plugin also looks at the
+//! feed after each command, at most once a day, and prints a notice on
+//! stderr when a newer version is out. The notice is skipped for `--json`
+//! output, when stderr isn't a terminal, under CI and with
+//! `META_PROJECT_NO_UPDATE_CHECK=1`.
+
+use crate::{settings, state, style, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Environment variable overriding the release feed URL
+const FEED_ENV: &str = "META_PROJECT_RELEASE_FEED";
+
+/// Environment variable that turns the passive update notice off
+const NO_CHECK_ENV: &str = "META_PROJECT_NO_UPDATE_CHECK";
+
+/// Cache file remembering the last look at the feed
+const CHECK_FILE: &str = "update-check.json";
+
+/// How long the passive check trusts its last look, in seconds
+const CHECK_INTERVAL: u64 = 24 * 60 * 60;
+
+/// The version of this binary
+const CURRENT: &str = env!("CARGO_PKG_VERSION");
+
+/// Public key that signs releases, baked in by the release build
+const RELEASE_KEY: Option<&str> = option_env!("META_PROJECT_RELEASE_KEY");
+
+/// Signer identity and namespace of release signatures
+const SIGNER: &str = "meta-project-release";
+
+/// A release as the GitHub releases API describes it
+#[derive(Debug, Clone, Deserialize)]
+struct Release {
+    tag_name: String,
+    #[serde(default)]
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    fn asset(&self, name: &str) -> Option<&Asset> {
+        self.assets.iter().find(|a| a.name == name)
+    }
+}
+
+/// The last passive look at the feed
+#[derive(Debug, Serialize, Deserialize)]
+struct LastCheck {
+    /// Seconds since the Unix epoch
+    checked: u64,
+    latest: String,
+}
+
+/// Handle `meta project self-update [--check]`
+pub(crate) fn handle_self_update(args: &[String], options: &ExecuteOptions) -> CommandResult {
+    let release = match fetch_release(&feed_url(), 30) {
+        Ok(release) => release,
+        Err(e) => return CommandResult::Error(format!("Failed to read the release feed: {e}")),
+    };
+    if !is_newer(release.version(), CURRENT) {
+        return CommandResult::Message(format!(
+            "meta-project {CURRENT} is up to date (latest: {}).",
+            release.version()
+        ));
+    }
+    if args.iter().any(|a| a == "--check") {
+        return CommandResult::Message(format!(
+            "meta-project {} is available (this is {CURRENT}); run 'meta project self-update'.",
+            release.version()
+        ));
+    }
+    let Some(key) = RELEASE_KEY else {
+        return CommandResult::Error(format!(
+            "This build has no release signing key, so a download can't be verified; \
+             install meta-project {} from the release page instead.",
+            release.version()
+        ));
+    };
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => return CommandResult::Error(format!("Cannot locate this executable: {e}")),
+    };
+    if options.dry_run {
+        return CommandResult::Message(format!(
+            "Dry run: would replace {} ({CURRENT}) with {} {}.",
+            exe.display(),
+            asset_name(),
+            release.version()
+        ));
+    }
+    match install(&release, &exe, key) {
+        Ok(()) => CommandResult::Message(format!(
+            "{} Updated meta-project {CURRENT} → {}.",
+            style::ok(options.plain),
+            release.version()
+        )),
+        Err(e) => CommandResult::Error(format!("Self-update failed; nothing was replaced: {e}")),
+    }
+}
+
+/// A "new version available" line, when the feed has one and the notice
+/// is turned on
+///
+/// Only with `settings.update_check`, for a person at a terminal: never for
+/// `--json`, with stderr redirected or under CI. Looks at the feed at most
+/// once per [`CHECK_INTERVAL`], with a short timeout; any failure just means
+/// no notice.
+pub fn update_notice(cwd: &Path, options: &ExecuteOptions) -> Option<String> {
+    let set = |name: &str| std::env::var_os(name).is_some_and(|v| !v.is_empty() && v != "0");
+    if options.json_output || set(NO_CHECK_ENV) || set("CI") || !std::io::stderr().is_terminal() {
+        return None;
+    }
+    if settings::load(cwd).ok()?.update_check != Some(true) {
+        return None;
+    }
+    let file = state::cache_dir()?.join(CHECK_FILE);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let latest = match state::read_json::<LastCheck>(&file) {
+        Some(last) if now.saturating_sub(last.checked) < CHECK_INTERVAL => last.latest,
+        _ => {
+            let latest = fetch_release(&feed_url(), 3).ok()?.version().to_string();
+            let last = LastCheck {
+                checked: now,
+                latest,
+            };
+            let _ = state::write_json(&file, &last);
+            last.latest
+        }
+    };
+    is_newer(&latest, CURRENT).then(|| {
+        format!(
+            "meta-project {latest} is available (this is {CURRENT}); run 'meta project self-update'."
+        )
+    })
+}
+
+fn feed_url() -> String {
+    if let Some(feed) = std::env::var_os(FEED_ENV).filter(|f| !f.is_empty()) {
+        return feed.to_string_lossy().into_owned();
+    }
+    let repository = env!("CARGO_PKG_REPOSITORY").trim_end_matches('/');
+    let slug = repository
+        .strip_prefix("https://github.com/")
+        .unwrap_or(repository);
+    format!("https://api.github.com/repos/{slug}/releases/latest")
+}
+
+/// The release asset holding this platform's binary
+fn asset_name() -> String {
+    let name = format!(
+        "meta-project-{}-{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS
+    );
+    if cfg!(windows) {
+        name + ".exe"
+    } else {
+        name
+    }
+}
+
+fn fetch_release(feed: &str, timeout_secs: u64) -> Result<Release, String> {
+    let timeout = timeout_secs.to_string();
+    let output = Command::new("curl")
+        .args(["-fsSL", "--max-time", &timeout, feed])
+        .output()
+        .map_err(|e| format!("failed to run curl: {e}"))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("unexpected feed format: {e}"))
+}
+
+/// Download this platform's binary from `release`, verify it and put it in
+/// place of `exe`
+///
+/// `SHA256SUMS` must carry a signature by `key`, and the binary must match
+/// it. The download lands next to `exe` and is renamed over it, so a failure
+/// at any step leaves the old binary alone.
+fn install(release: &Release, exe: &Path, key: &str) -> Result<(), String> {
+    let name = asset_name();
+    let asset = |name: &str| {
+        release
+            .asset(name)
+            .ok_or_else(|| format!("release {} has no {name}", release.tag_name))
+    };
+    let binary = asset(&name)?;
+    let sums = asset("SHA256SUMS")?;
+    let signature = asset("SHA256SUMS.sig")?;
+
+    let download = sibling(exe, "download");
+    let sums_file = sibling(exe, "sha256sums");
+    let signature_file = sibling(exe, "sha256sums.sig");
+    let signers_file = sibling(exe, "allowed-signers");
+    let result = (|| {
+        curl(&sums.browser_download_url, &sums_file)?;
+        curl(&signature.browser_download_url, &signature_file)?;
+        std::fs::write(&signers_file, format!("{SIGNER} {}\n", key.trim()))
+            .map_err(|e| e.to_string())?;
+        verify_signature(&sums_file, &signature_file, &signers_file)?;
+        let listing = std::fs::read_to_string(&sums_file).map_err(|e| e.to_string())?;
+        let expected = listing
+            .lines()
+            .filter_map(|line| line.split_once(char::is_whitespace))
+            .find(|(_, file)| file.trim().trim_start_matches('*') == name)
+            .map(|(sum, _)| sum.to_ascii_lowercase())
+            .ok_or_else(|| format!("SHA256SUMS doesn't list {name}"))?;
+        curl(&binary.browser_download_url, &download)?;
+        let actual = sha256(&download)?;
+        if actual != expected {
+            return Err(format!(
+                "checksum mismatch for {name}: got {actual}, SHA256SUMS says {expected}"
+            ));
+        }
+        make_executable(&download)?;
+        replace(&download, exe)
+    })();
+    for scratch in [&download, &sums_file, &signature_file, &signers_file] {
+        let _ = std::fs::remove_file(scratch);
+    }
+    result
+}
+
+/// Check that `signature` is the release key's (listed in `signers`)
+/// signature of `file`
+fn verify_signature(file: &Path, signature: &Path, signers: &Path) -> Result<(), String> {
+    let input = std::fs::File::open(file).map_err(|e| e.to_string())?;
+    let output = Command::new("ssh-keygen")
+        .args(["-Y", "verify", "-I", SIGNER, "-n", SIGNER, "-f"])
+        .arg(signers)
+        .arg("-s")
+        .arg(signature)
+        .stdin(input)
+        .output()
+        .map_err(|e| format!("failed to run ssh-keygen to verify the release: {e}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "SHA256SUMS is not signed by the release key: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// `<exe>.<suffix>`, a scratch file next to the executable
+fn sibling(exe: &Path, suffix: &str) -> PathBuf {
+    let mut name = exe.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{suffix}"));
+    exe.with_file_name(name)
+}
+
+fn curl(url: &str, to: &Path) -> Result<(), String> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "-o"])
+        .arg(to)
+        .arg(url)
+        .output()
+        .map_err(|e| format!("failed to run curl: {e}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "downloading {url} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+fn sha256(file: &Path) -> Result<String, String> {
+    let mut reader = std::fs::File::open(file).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut reader, &mut hasher).map_err(|e| e.to_string())?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+#[cfg(unix)]
+fn make_executable(file: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(file, std::fs::Permissions::from_mode(0o755))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_file: &Path) -> Result<(), String> {
+    Ok(())
+}
+
+/// Put `new` in place of `exe`; a running executable can be renamed but not
+/// overwritten on Windows, so it's moved aside first there
+fn replace(new: &Path, exe: &Path) -> Result<(), String> {
+    if cfg!(windows) {
+        let old = sibling(exe, "old");
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(exe, &old).map_err(|e| e.to_string())?;
+    }
+    std::fs::rename(new, exe).map_err(|e| e.to_string())
+}
+
+/// Whether version `latest` is newer than `current`, comparing the numeric
+/// components (`0.3.0` > `0.2.22`); anything after a `-` is ignored
+fn is_newer(latest: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .split('-')
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map_while(|part| part.parse().ok())
+            .collect()
+    };
+    parse(latest) > parse(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("0.3.0", "0.2.22"));
+        assert!(is_newer("0.2.22", "0.2.9"));
+        assert!(!is_newer("0.2.22", "0.2.22"));
+        assert!(!is_newer("0.2.22-rc1", "0.2.22"));
+        assert!(!is_newer("nightly", "0.2.22"));
+    }
+
+    #[test]
+    fn test_install_verifies_signature_and_checksum() {
+        let dir = TempDir::new().unwrap();
+        let exe = dir.path().join("meta-project");
+        std::fs::write(&exe, "old").unwrap();
+        let new = dir.path().join("new");
+        std::fs::write(&new, "new").unwrap();
+        let url = |path: &Path| format!("file://{}", path.display());
+        let sums = dir.path().join("SHA256SUMS");
+        let signature = dir.path().join("SHA256SUMS.sig");
+        let release = Release {
+            tag_name: "v9.0.0".to_string(),
+            assets: [
+                (asset_name(), &new),
+                ("SHA256SUMS".to_string(), &sums),
+                ("SHA256SUMS.sig".to_string(), &signature),
+            ]
+            .into_iter()
+            .map(|(name, path)| Asset {
+                name,
+                browser_download_url: url(path),
+            })
+            .collect(),
+        };
+        let keygen = |args: &[&str]| {
+            let status = Command::new("ssh-keygen")
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "ssh-keygen {args:?}");
+        };
+        for name in ["release", "other"] {
+            keygen(&["-q", "-t", "ed25519", "-N", "", "-C", "", "-f", name]);
+        }
+        let public =
+            |name: &str| std::fs::read_to_string(dir.path().join(format!("{name}.pub"))).unwrap();
+        let sign = |sums_text: String| {
+            std::fs::write(&sums, sums_text).unwrap();
+            let _ = std::fs::remove_file(&signature);
+            keygen(&[
+                "-Y",
+                "sign",
+                "-q",
+                "-f",
+                "release",
+                "-n",
+                SIGNER,
+                "SHA256SUMS",
+            ]);
+        };
+        let old_untouched = || assert_eq!(std::fs::read_to_string(&exe).unwrap(), "old");
+
+        sign(format!("{}  {}\n", "0".repeat(64), asset_name()));
+        let e = install(&release, &exe, &public("release")).unwrap_err();
+        assert!(e.starts_with("checksum mismatch"), "{e}");
+        old_untouched();
+
+        sign(format!("{}  {}\n", sha256(&new).unwrap(), asset_name()));
+        let e = install(&release, &exe, &public("other")).unwrap_err();
+        assert!(
+            e.starts_with("SHA256SUMS is not signed by the release key"),
+            "{e}"
+        );
+        old_untouched();
+
+        // Swapping the signed listing for another fails too
+        std::fs::write(&sums, format!("{}  {}\n", sha256(&new).unwrap(), "other")).unwrap();
+        assert!(install(&release, &exe, &public("release")).is_err());
+        old_untouched();
+
+        sign(format!("{}  {}\n", sha256(&new).unwrap(), asset_name()));
+        install(&release, &exe, &public("release")).unwrap();
+        assert_eq!(std::fs::read_to_string(&exe).unwrap(), "new");
+        let leftovers = std::fs::read_dir(dir.path()).unwrap().count();
+        assert_eq!(
+            leftovers, 8,
+            "only meta-project, new, SHA256SUMS(.sig) and the two key pairs remain"
+        );
+    }
+
+    #[test]
+    fn test_update_notice_is_opt_in() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+        // Off by default, and never for --json, whatever the settings say
+        assert_eq!(update_notice(dir.path(), &ExecuteOptions::default()), None);
+        std::fs::write(
+            dir.path().join(".meta"),
+            r#"{"projects": {}, "settings": {"update_check": true}}"#,
+        )
+        .unwrap();
+        let json = ExecuteOptions {
+            json_output: true,
+            ..ExecuteOptions::default()
+        };
+        assert_eq!(update_notice(dir.path(), &json), None);
+    }
+}
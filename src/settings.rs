@@ -0,0 +1,737 @@
+//! Plugin settings from the workspace .meta config
+//!
+//! Keys the core config parser doesn't know about: the `settings` block and
+//! per-project keys such as `type`, `vcs`, `protected`, `fallback_urls`,
+//! `ref`, `depth`, `filter`, `single_branch`, `submodules`, `sparse`,
+//! `deprecated`, `suppress`, `hooks` and `group`.
+//!
+//! ```json
+//! {
+//!   "projects": {
+//!     "prod-config": { "repo": "git@github.com:org/prod-config.git", "protected": true },
+//!     "api": {
+//!       "repo": "git@github.com:org/api.git",
+//!       "fallback_urls": ["https://mirror.internal/org/api.git"],
+//!       "ref": "v1.2.0",
+//!       "depth": 50,
+//!       "filter": "blob:none",
+//!       "single_branch": false,
+//!       "submodules": true,
+//!       "sparse": ["services/api", "proto"],
+//!       "suppress": [{ "code": "behind", "until": "2026-12-31", "reason": "release freeze" }],
+//!       "hooks": { "pre-push": "cargo clippy -- -D warnings" },
+//!       "group": "lfs",
+//!       "vcs": "jj"
+//!     },
+//!     "vendor/sdk": {
+//!       "type": "archive",
+//!       "url": "https://downloads.example.com/sdk-4.2.tar.gz",
+//!       "sha256": "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+//!     }
+//!   },
+//!   "settings": {
+//!     "aliases": { "ck": "check --fetch" },
+//!     "git": { "min_version": "2.38" },
+//!     "commands": { "check": { "fetch": true, "jobs": 8 } },
+//!     "hooks": { "pre-push": "make lint" },
+//!     "max_stale_days": 7,
+//!     "depth": 1,
+//!     "filter": "blob:limit=1m",
+//!     "single_branch": true,
+//!     "reference": "../object-cache",
+//!     "concurrency": { "lfs": 2 },
+//!     "host_concurrency": { "git.corp.example": 4 },
+//!     "profiles": { "release-2.x": { "branch": "release/2.x", "projects": { "api": "maint/2.x" } } },
+//!     "sort": "tag",
+//!     "update_check": true,
+//!     "strict": true
+//!   }
+//! }
+//! ```
+
+use crate::pool::ConcurrencyGroups;
+use crate::{fetchall, manifest};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// The `settings` block of a .meta config; every field is optional
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub(crate) struct Settings {
+    /// Shortcut name → subcommand line, e.g. `"ck": "check --fetch"`
+    pub aliases: BTreeMap<String, String>,
+    pub git: GitSettings,
+    /// Default for `--strict`: warning-level findings fail the command
+    pub strict: bool,
+    /// Subcommand → default flags, e.g. `"check": {"fetch": true, "jobs": 8}`
+    pub commands: BTreeMap<String, BTreeMap<String, serde_json::Value>>,
+    /// Git hook name → shell command it runs in each project, e.g.
+    /// `"pre-push": "make lint"`
+    pub hooks: BTreeMap<String, String>,
+    /// Clones last fetched longer ago than this are reported as stale
+    pub max_stale_days: Option<u64>,
+    /// History depth for cloning projects without their own `depth`
+    pub depth: Option<u32>,
+    /// Partial clone filter (`git clone --filter`) for projects without
+    /// their own `filter`
+    pub filter: Option<String>,
+    /// Clone only the default or pinned branch of projects without their
+    /// own `single_branch`
+    pub single_branch: Option<bool>,
+    /// Directory of local clones (`<reference>/<path>`), or one object
+    /// cache repo, that clones borrow objects from with
+    /// `--reference-if-able`; relative to the workspace
+    pub reference: Option<String>,
+    /// Concurrency group → how many of its projects run at once
+    pub concurrency: BTreeMap<String, usize>,
+    /// Remote host → how many of its projects run at once, whatever their
+    /// group; bandwidth isn't capped, as git has no setting for it
+    pub host_concurrency: BTreeMap<String, usize>,
+    /// Profile name → the branches `meta project use` checks out
+    pub profiles: BTreeMap<String, Profile>,
+    /// Order every manifest write keeps the project entries in
+    pub sort: Option<SortPolicy>,
+    /// `true` turns on the "new version available" notice
+    pub update_check: Option<bool>,
+}
+
+/// How the entries of `projects` are ordered in a JSON manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SortPolicy {
+    /// By project name
+    Alphabetical,
+    /// By checkout path
+    Path,
+    /// Grouped by first tag under `// --- tag ---` headers, by name within
+    /// a group; untagged projects last
+    Tag,
+}
+
+impl SortPolicy {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        Self::deserialize(serde_json::Value::from(value)).ok()
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Alphabetical => "alphabetical",
+            Self::Path => "path",
+            Self::Tag => "tag",
+        }
+    }
+}
+
+/// Short forms of flags, so a default yields to either spelling
+const SHORT_FLAGS: &[(&str, &str)] = &[
+    ("jobs", "-j"),
+    ("yes", "-y"),
+    ("interactive", "-i"),
+    ("recursive", "-r"),
+];
+
+impl Settings {
+    /// The `commands` defaults of `subcommand` as arguments to append to `args`
+    ///
+    /// Keys are flag names without the dashes. `true` adds the bare flag and
+    /// `false` nothing; strings and numbers add the flag with that value, and
+    /// a list repeats it. Flags already in `args` are left to the command line.
+    pub(crate) fn command_defaults(
+        &self,
+        subcommand: &str,
+        args: &[String],
+    ) -> Result<Vec<String>, String> {
+        let Some(defaults) = self.commands.get(subcommand) else {
+            return Ok(Vec::new());
+        };
+        let given = |name: &str| {
+            let long = format!("--{name}");
+            let short = SHORT_FLAGS
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, s)| *s);
+            args.iter().any(|a| {
+                *a == long || a.starts_with(&format!("{long}=")) || Some(a.as_str()) == short
+            })
+        };
+
+        let mut expanded = Vec::new();
+        for (name, value) in defaults {
+            if given(name) {
+                continue;
+            }
+            let flag = format!("--{name}");
+            let values = match value {
+                serde_json::Value::Array(values) => values.iter().collect(),
+                value => vec![value],
+            };
+            for value in values {
+                match value {
+                    serde_json::Value::Bool(true) => expanded.push(flag.clone()),
+                    serde_json::Value::Bool(false) => {}
+                    serde_json::Value::String(s) => expanded.extend([flag.clone(), s.clone()]),
+                    serde_json::Value::Number(n) => expanded.extend([flag.clone(), n.to_string()]),
+                    other => {
+                        return Err(format!(
+                            "Invalid settings.commands.{subcommand}.{name}: {other} is not a \
+                             boolean, string, number or list of them"
+                        ))
+                    }
+                }
+            }
+        }
+        Ok(expanded)
+    }
+}
+
+/// Which git this plugin runs, checked at command start
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct GitSettings {
+    /// Not honored: a shared .meta can't choose the program this plugin
+    /// runs, so setting it is an error; `META_PROJECT_GIT` picks the binary
+    pub path: Option<PathBuf>,
+    /// Lowest acceptable git version, e.g. `"2.38"`
+    pub min_version: Option<String>,
+}
+
+/// A named set of branches, one per project, that `meta project use` switches to
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Profile {
+    /// Branch for every project not listed in `projects`; without it those
+    /// projects are left alone
+    pub branch: Option<String>,
+    /// Project path → branch
+    pub projects: BTreeMap<String, String>,
+}
+
+impl Profile {
+    /// The branch this profile pins `path` to, if any
+    pub(crate) fn branch_for(&self, path: &str) -> Option<&str> {
+        self.projects
+            .get(path)
+            .or(self.branch.as_ref())
+            .map(String::as_str)
+    }
+}
+
+/// Load the settings of the .meta config in `dir`
+///
+/// A directory without a config, or a config without a `settings` block,
+/// yields the defaults. A malformed block is an error rather than being
+/// silently ignored.
+pub(crate) fn load(dir: &Path) -> Result<Settings, String> {
+    let Some(meta_path) = manifest::find(dir) else {
+        return Ok(Settings::default());
+    };
+    let document = manifest::read(&meta_path)?;
+
+    match document.get("settings") {
+        None | Some(serde_json::Value::Null) => Ok(Settings::default()),
+        Some(block) => Settings::deserialize(block)
+            .map_err(|e| format!("Invalid settings in {}: {e}", meta_path.display())),
+    }
+}
+
+/// The per-project keys of one .meta entry that shape how it is cloned
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ProjectKeys {
+    /// `"protected": true`: left out of destructive or write operations
+    /// unless the user confirms or passes `--include-protected`
+    pub protected: bool,
+    /// `"vcs": "jj"` (the default is `"git"`): a colocated jj repo
+    pub jj: bool,
+    /// `"submodules": true`: cloned with `--recurse-submodules`
+    pub submodules: bool,
+    /// `fallback_urls`: mirrors tried in order when the primary URL fails
+    pub fallbacks: Vec<String>,
+    /// `ref`: the branch, tag or commit `check` expects the clone to be on
+    pub pinned_ref: Option<String>,
+    /// `depth`: shallow clone depth, overriding `settings.depth`
+    pub depth: Option<u32>,
+    /// `filter`: partial clone filter, overriding `settings.filter`
+    pub filter: Option<String>,
+    /// `single_branch`, overriding `settings.single_branch`
+    pub single_branch: Option<bool>,
+    /// `sparse`: the directories of a `--sparse` clone's checkout
+    pub sparse: Vec<String>,
+    /// `priority`: `check --fix --clone-order priority` clones higher first
+    pub priority: i64,
+}
+
+impl ProjectKeys {
+    /// The keys of `entry`, the project at `path`; a malformed value is an
+    /// error naming the project and key
+    fn parse(path: &str, entry: &serde_json::Value) -> Result<Self, String> {
+        let invalid = |key: &str, expected: &str| format!("Invalid {key} for '{path}': {expected}");
+        let jj = match entry.get("vcs").map(|v| v.as_str()) {
+            None | Some(Some("git")) => false,
+            Some(Some("jj")) => true,
+            Some(_) => return Err(invalid("vcs", "expected \"git\" or \"jj\"")),
+        };
+        let submodules = match entry.get("submodules") {
+            None => false,
+            Some(value) => value
+                .as_bool()
+                .ok_or_else(|| invalid("submodules", "expected true or false"))?,
+        };
+        let fallbacks = match entry.get("fallback_urls") {
+            None => Vec::new(),
+            Some(value) => Vec::<String>::deserialize(value)
+                .map_err(|e| invalid("fallback_urls", &e.to_string()))?,
+        };
+        let non_empty = |key: &str, expected: &str| {
+            entry
+                .get(key)
+                .map(|value| {
+                    value
+                        .as_str()
+                        .filter(|s| !s.is_empty())
+                        .map(str::to_string)
+                        .ok_or_else(|| invalid(key, expected))
+                })
+                .transpose()
+        };
+        let depth = entry
+            .get("depth")
+            .map(|value| {
+                value
+                    .as_u64()
+                    .and_then(|d| u32::try_from(d).ok())
+                    .filter(|d| *d > 0)
+                    .ok_or_else(|| invalid("depth", "expected a positive number of commits"))
+            })
+            .transpose()?;
+        let single_branch = entry
+            .get("single_branch")
+            .map(|value| {
+                value
+                    .as_bool()
+                    .ok_or_else(|| invalid("single_branch", "expected true or false"))
+            })
+            .transpose()?;
+        let sparse = match entry.get("sparse") {
+            None => Vec::new(),
+            Some(value) => value
+                .as_array()
+                .and_then(|dirs| {
+                    dirs.iter()
+                        .map(|dir| dir.as_str().filter(|d| !d.is_empty()).map(str::to_string))
+                        .collect::<Option<Vec<String>>>()
+                })
+                .filter(|dirs| !dirs.is_empty())
+                .ok_or_else(|| invalid("sparse", "expected a list of directories"))?,
+        };
+        let priority = match entry.get("priority") {
+            None => 0,
+            Some(value) => value
+                .as_i64()
+                .ok_or_else(|| invalid("priority", "expected a whole number"))?,
+        };
+        Ok(ProjectKeys {
+            protected: entry.get("protected").and_then(|v| v.as_bool()) == Some(true),
+            jj,
+            submodules,
+            fallbacks,
+            pinned_ref: non_empty("ref", "expected a branch, tag or commit")?,
+            depth,
+            filter: non_empty("filter", "expected a filter spec such as blob:none")?,
+            single_branch,
+            sparse,
+            priority,
+        })
+    }
+}
+
+/// The [`ProjectKeys`] of every project in the config at `meta_path`, by
+/// path, read from one parse of the document
+pub(crate) fn project_keys(meta_path: &Path) -> Result<HashMap<String, ProjectKeys>, String> {
+    let document = manifest::read(meta_path)?;
+    project_entries(&document)
+        .map(|(path, entry)| Ok((path.clone(), ProjectKeys::parse(&path, entry)?)))
+        .collect()
+}
+
+/// A `"type": "archive"` project: a tarball unpacked into `path`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ArchiveSource {
+    pub path: String,
+    pub url: String,
+    /// Lowercase hex SHA-256 of the tarball
+    pub sha256: String,
+}
+
+/// The archive projects in the config at `meta_path`, sorted by path
+pub(crate) fn archive_sources(meta_path: &Path) -> Result<Vec<ArchiveSource>, String> {
+    let document = manifest::read(meta_path)?;
+    let mut sources = project_entries(&document)
+        .filter(|(_, entry)| entry.get("type").and_then(|t| t.as_str()) == Some("archive"))
+        .map(|(path, entry)| {
+            let field = |key: &str| entry.get(key).and_then(|v| v.as_str()).unwrap_or_default();
+            let (url, sha256) = (field("url"), field("sha256").to_ascii_lowercase());
+            if url.is_empty() {
+                return Err(format!("Archive project '{path}' needs a url"));
+            }
+            if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(format!(
+                    "Archive project '{path}' needs a sha256 of 64 hex digits"
+                ));
+            }
+            Ok(ArchiveSource {
+                url: url.to_string(),
+                path,
+                sha256,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    sources.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(sources)
+}
+
+/// Per-project `hooks` overriding `settings.hooks`, by path
+pub(crate) fn project_hooks(
+    meta_path: &Path,
+) -> Result<HashMap<String, BTreeMap<String, String>>, String> {
+    let document = manifest::read(meta_path)?;
+    project_entries(&document)
+        .filter_map(|(path, entry)| {
+            let value = entry.get("hooks")?;
+            Some(
+                BTreeMap::<String, String>::deserialize(value)
+                    .map(|hooks| (path.clone(), hooks))
+                    .map_err(|e| format!("Invalid hooks for '{path}': {e}")),
+            )
+        })
+        .collect()
+}
+
+/// The projects' `group`s with the limits from `settings.concurrency`, and
+/// their hosts with those from `settings.host_concurrency`, for the
+/// workspace at `dir`
+pub(crate) fn concurrency_groups(dir: &Path) -> Result<ConcurrencyGroups, String> {
+    let Some(meta_path) = manifest::find(dir) else {
+        return Ok(ConcurrencyGroups::default());
+    };
+    let document = manifest::read(&meta_path)?;
+    let members = project_entries(&document)
+        .filter_map(|(path, entry)| {
+            let value = entry.get("group")?;
+            Some(match value.as_str() {
+                Some(group) => Ok((path, group.to_string())),
+                None => Err(format!("Invalid group for '{path}': expected a string")),
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    let hosts = project_entries(&document)
+        .filter_map(|(path, entry)| {
+            let url = entry.as_str().or_else(|| entry.get("repo")?.as_str())?;
+            Some((path, fetchall::host(url)))
+        })
+        .collect();
+    let settings = load(dir)?;
+    let limits = settings.concurrency.into_iter().collect();
+    Ok(ConcurrencyGroups::new(members, limits).with_hosts(hosts, &settings.host_concurrency))
+}
+
+/// A per-project `suppress` entry hiding one kind of check finding
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Suppression {
+    /// Finding code (see `meta project explain`) or a check rule's ID
+    pub code: String,
+    /// Last day it applies, `YYYY-MM-DD`; open-ended when absent
+    #[serde(default)]
+    pub until: Option<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// `suppress` entries of the projects in the config at `meta_path`, by path
+pub(crate) fn suppressions(meta_path: &Path) -> Result<HashMap<String, Vec<Suppression>>, String> {
+    let document = manifest::read(meta_path)?;
+    project_entries(&document)
+        .filter_map(|(path, entry)| {
+            let value = entry.get("suppress")?;
+            let parsed = Vec::<Suppression>::deserialize(value)
+                .map_err(|e| format!("Invalid suppress for '{path}': {e}"))
+                .and_then(|list| {
+                    match list.iter().find_map(|s| s.until.as_deref().filter(|d| !is_date(d))) {
+                        Some(until) => Err(format!(
+                            "Invalid suppress for '{path}': until '{until}' is not a YYYY-MM-DD date"
+                        )),
+                        None => Ok(list),
+                    }
+                });
+            Some(parsed.map(|list| (path.clone(), list)))
+        })
+        .collect()
+}
+
+fn is_date(value: &str) -> bool {
+    let parts: Vec<&str> = value.split('-').collect();
+    matches!(parts[..], [y, m, d]
+        if y.len() == 4 && m.len() == 2 && d.len() == 2
+            && parts.iter().all(|p| p.bytes().all(|b| b.is_ascii_digit())))
+}
+
+/// A project's `deprecated` marker, written by `meta project deprecate`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deprecation {
+    /// Why, or what to do instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Name of the project that replaces this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub successor: Option<String>,
+    /// When it was deprecated, in seconds since the Unix epoch
+    #[serde(default)]
+    pub since: u64,
+}
+
+/// `deprecated` markers of the projects in the config at `meta_path`, by path
+pub(crate) fn deprecations(meta_path: &Path) -> Result<HashMap<String, Deprecation>, String> {
+    let document = manifest::read(meta_path)?;
+    project_entries(&document)
+        .filter_map(|(path, entry)| {
+            let value = entry.get("deprecated")?;
+            Some(
+                Deprecation::deserialize(value)
+                    .map(|deprecation| (path.clone(), deprecation))
+                    .map_err(|e| format!("Invalid deprecated marker for '{path}': {e}")),
+            )
+        })
+        .collect()
+}
+
+/// Each project entry with its normalized path (`path` key, else its name)
+fn project_entries(
+    document: &serde_json::Value,
+) -> impl Iterator<Item = (String, &serde_json::Value)> {
+    document
+        .get("projects")
+        .and_then(|p| p.as_object())
+        .into_iter()
+        .flatten()
+        .map(|(name, entry)| {
+            let path = entry
+                .get("path")
+                .and_then(|p| p.as_str())
+                .unwrap_or(name)
+                .replace('\\', "/");
+            (path, entry)
+        })
+}
+
+/// Expand an alias into a subcommand and its leading arguments
+///
+/// The value is split on whitespace; a leading `project` is optional so both
+/// `"check --fetch"` and `"project check --fetch"` work.
+pub(crate) fn expand_alias(value: &str) -> Option<(String, Vec<String>)> {
+    let mut words = value.split_whitespace();
+    let mut first = words.next()?;
+    if first == "project" {
+        first = words.next()?;
+    }
+    Some((first.to_string(), words.map(str::to_string).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_without_settings_block() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join(".meta"), r#"{"projects": {}}"#).unwrap();
+        assert_eq!(load(dir.path()), Ok(Settings::default()));
+        assert_eq!(load(&dir.path().join("missing")), Ok(Settings::default()));
+    }
+
+    #[test]
+    fn test_load_aliases_from_json_and_yaml() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join(".meta"),
+            r#"{"projects": {}, "settings": {"aliases": {"ck": "check --fetch"}}}"#,
+        )
+        .unwrap();
+        let settings = load(dir.path()).unwrap();
+        assert_eq!(settings.aliases["ck"], "check --fetch");
+
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join(".meta.yaml"),
+            "projects: {}\nsettings:\n  aliases:\n    st: project remotes --json\n",
+        )
+        .unwrap();
+        let settings = load(dir.path()).unwrap();
+        assert_eq!(settings.aliases["st"], "project remotes --json");
+    }
+
+    #[test]
+    fn test_load_rejects_malformed_settings() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join(".meta"),
+            r#"{"projects": {}, "settings": {"aliases": ["ck"]}}"#,
+        )
+        .unwrap();
+        assert!(load(dir.path()).unwrap_err().contains("Invalid settings"));
+    }
+
+    #[test]
+    fn test_project_keys() {
+        let dir = TempDir::new().unwrap();
+        let meta = dir.path().join(".meta");
+        std::fs::write(
+            &meta,
+            r#"{"projects": {
+                "a": "git@github.com:org/a.git",
+                "b": {"repo": "git@github.com:org/b.git", "path": "libs/b",
+                      "fallback_urls": ["https://mirror/b.git", "https://proxy/b.git"]}
+            }}"#,
+        )
+        .unwrap();
+        let keys = project_keys(&meta).unwrap();
+        assert_eq!(keys["a"], ProjectKeys::default());
+        assert_eq!(
+            keys["libs/b"].fallbacks,
+            vec!["https://mirror/b.git", "https://proxy/b.git"]
+        );
+
+        std::fs::write(
+            &meta,
+            r#"{"projects": {"a": {"repo": "x", "fallback_urls": "https://mirror/a.git"}}}"#,
+        )
+        .unwrap();
+        assert!(project_keys(&meta)
+            .unwrap_err()
+            .contains("Invalid fallback_urls for 'a'"));
+
+        std::fs::write(&meta, r#"{"projects": {"a": {"repo": "x", "depth": 0}}}"#).unwrap();
+        assert!(project_keys(&meta)
+            .unwrap_err()
+            .contains("Invalid depth for 'a'"));
+
+        std::fs::write(
+            &meta,
+            r#"{"projects": {"a": {"repo": "x", "priority": "high"}}}"#,
+        )
+        .unwrap();
+        assert!(project_keys(&meta)
+            .unwrap_err()
+            .contains("Invalid priority for 'a'"));
+    }
+
+    #[test]
+    fn test_concurrency_groups() {
+        let dir = TempDir::new().unwrap();
+        let meta = dir.path().join(".meta");
+        std::fs::write(
+            &meta,
+            r#"{"projects": {
+                "a": {"repo": "git@github.com:org/a.git", "group": "lfs"},
+                "b": {"repo": "git@github.com:org/b.git", "path": "libs/b", "group": "license"},
+                "c": "git@github.com:org/c.git",
+                "d": "https://git.corp.example/org/d.git"
+            }, "settings": {
+                "concurrency": {"lfs": 2},
+                "host_concurrency": {"git.corp.example": 4}
+            }}"#,
+        )
+        .unwrap();
+        let expected = ConcurrencyGroups::new(
+            HashMap::from([
+                ("a".to_string(), "lfs".to_string()),
+                ("libs/b".to_string(), "license".to_string()),
+            ]),
+            HashMap::from([("lfs".to_string(), 2)]),
+        )
+        .with_hosts(
+            HashMap::from([("d".to_string(), "git.corp.example".to_string())]),
+            &BTreeMap::from([("git.corp.example".to_string(), 4)]),
+        );
+        assert_eq!(concurrency_groups(dir.path()).unwrap(), expected);
+
+        std::fs::write(&meta, r#"{"projects": {"a": {"repo": "x", "group": 3}}}"#).unwrap();
+        assert!(concurrency_groups(dir.path())
+            .unwrap_err()
+            .contains("Invalid group for 'a'"));
+    }
+
+    #[test]
+    fn test_protected_paths() {
+        let dir = TempDir::new().unwrap();
+        let meta = dir.path().join(".meta");
+        std::fs::write(
+            &meta,
+            r#"{"projects": {
+                "a": "git@github.com:org/a.git",
+                "b": {"repo": "git@github.com:org/b.git", "protected": true},
+                "c": {"repo": "git@github.com:org/c.git", "path": "libs/c", "protected": true},
+                "d": {"repo": "git@github.com:org/d.git", "protected": false}
+            }}"#,
+        )
+        .unwrap();
+        let keys = project_keys(&meta).unwrap();
+        let mut protected: Vec<&str> = keys
+            .iter()
+            .filter(|(_, keys)| keys.protected)
+            .map(|(path, _)| path.as_str())
+            .collect();
+        protected.sort();
+        assert_eq!(protected, ["b", "libs/c"]);
+    }
+
+    #[test]
+    fn test_command_defaults() {
+        let settings = Settings {
+            commands: serde_json::from_str(
+                r#"{"check": {"fetch": true, "fix": false, "jobs": 8, "filter": ["tag:a", "tag:b"]}}"#,
+            )
+            .unwrap(),
+            ..Settings::default()
+        };
+        let args = |list: &[&str]| list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            settings.command_defaults("check", &[]).unwrap(),
+            args(&["--fetch", "--filter", "tag:a", "--filter", "tag:b", "--jobs", "8"])
+        );
+        // The command line wins, in long or short form
+        assert_eq!(
+            settings
+                .command_defaults("check", &args(&["-j", "2", "--filter=tag:c", "--fetch"]))
+                .unwrap(),
+            Vec::<String>::new()
+        );
+        assert!(settings.command_defaults("list", &[]).unwrap().is_empty());
+
+        let settings = Settings {
+            commands: serde_json::from_str(r#"{"check": {"jobs": {"n": 8}}}"#).unwrap(),
+            ..Settings::default()
+        };
+        assert!(settings
+            .command_defaults("check", &[])
+            .unwrap_err()
+            .contains("settings.commands.check.jobs"));
+    }
+
+    #[test]
+    fn test_expand_alias() {
+        assert_eq!(
+            expand_alias("check --fetch -j 4"),
+            Some((
+                "check".to_string(),
+                vec!["--fetch".to_string(), "-j".to_string(), "4".to_string()]
+            ))
+        );
+        assert_eq!(
+            expand_alias("project remotes"),
+            Some(("remotes".to_string(), vec![]))
+        );
+        assert_eq!(expand_alias("  "), None);
+    }
+}
@@ -0,0 +1,108 @@
+//! Locations and persistence of meta-project's own caches
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[cfg(any(test, feature = "test-harness"))]
+thread_local! {
+    static ROOT: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Use `root` as this thread's cache directory instead of the user's, so
+/// test fixtures never write outside their temp directory
+#[cfg(any(test, feature = "test-harness"))]
+pub(crate) fn set_cache_root(root: &Path) {
+    ROOT.with(|current| *current.borrow_mut() = Some(root.to_path_buf()));
+}
+
+#[cfg(any(test, feature = "test-harness"))]
+fn root_override() -> Option<PathBuf> {
+    ROOT.with(|current| current.borrow().clone())
+}
+
+#[cfg(not(any(test, feature = "test-harness")))]
+fn root_override() -> Option<PathBuf> {
+    None
+}
+
+/// Root of meta-project's cache directory
+///
+/// `$XDG_CACHE_HOME/meta-project`, falling back to `~/.cache/meta-project`.
+/// Unit tests only ever see the root set by [`set_cache_root`].
+pub(crate) fn cache_dir() -> Option<PathBuf> {
+    if let Some(root) = root_override() {
+        return Some(root);
+    }
+    if cfg!(test) {
+        return None;
+    }
+    let base = match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+    };
+    Some(base.join("meta-project"))
+}
+
+/// Per-workspace cache directory, keyed by the workspace's canonical path
+pub(crate) fn workspace_cache_dir(workspace: &Path) -> Option<PathBuf> {
+    let canonical = workspace
+        .canonicalize()
+        .unwrap_or_else(|_| workspace.to_path_buf());
+    let key = fnv1a(canonical.to_string_lossy().as_bytes());
+    Some(cache_dir()?.join("workspaces").join(format!("{key:016x}")))
+}
+
+/// Path of a file in the workspace's cache directory
+pub(crate) fn workspace_cache_file(workspace: &Path, name: &str) -> Option<PathBuf> {
+    Some(workspace_cache_dir(workspace)?.join(name))
+}
+
+/// Read a JSON cache file; any error (missing, corrupt, old format) yields `None`
+pub(crate) fn read_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Write a JSON cache file via a temp file and rename
+pub(crate) fn write_json<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_vec(value)?)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// 64-bit FNV-1a, used for stable cache keys across builds
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_fnv1a_is_stable() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested").join("cache.json");
+        write_json(&path, &vec!["a".to_string()]).unwrap();
+        let read: Option<Vec<String>> = read_json(&path);
+        assert_eq!(read, Some(vec!["a".to_string()]));
+        assert_eq!(
+            read_json::<Vec<String>>(&temp_dir.path().join("absent")),
+            None
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! `meta project stats` — one-screen summary of the workspace
+
+use crate::pool::{default_jobs, parallel_map};
+use crate::{dir_size, git, manifest, parse_jobs, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Marker files that identify a project's ecosystem, checked in order
+const ECOSYSTEMS: &[(&str, &str)] = &[
+    ("Cargo.toml", "rust"),
+    ("package.json", "node"),
+    ("go.mod", "go"),
+    ("pyproject.toml", "python"),
+    ("setup.py", "python"),
+    ("pom.xml", "java"),
+    ("build.gradle", "java"),
+    ("build.gradle.kts", "java"),
+    ("Gemfile", "ruby"),
+    ("composer.json", "php"),
+    ("mix.exs", "elixir"),
+];
+
+/// What one project contributes to the totals
+#[derive(Debug, Default)]
+struct ProjectStats {
+    cloned: bool,
+    dirty: bool,
+    ecosystem: Option<&'static str>,
+    bytes: u64,
+    recent_commits: usize,
+    /// Last fetch (or clone) time, seconds since the Unix epoch
+    last_sync: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct WorkspaceStats {
+    projects: usize,
+    cloned: usize,
+    missing: usize,
+    dirty: usize,
+    by_tag: BTreeMap<String, usize>,
+    by_ecosystem: BTreeMap<String, usize>,
+    disk_bytes: u64,
+    commits_last_30_days: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    oldest_sync: Option<OldestSync>,
+}
+
+#[derive(Debug, Serialize)]
+struct OldestSync {
+    project: String,
+    days_ago: u64,
+}
+
+/// Handle `meta project stats [--json] [--jobs N]`
+pub(crate) fn handle_stats(args: &[String], options: &ExecuteOptions, cwd: &Path) -> CommandResult {
+    let jobs = match parse_jobs(args) {
+        Ok(jobs) => jobs.unwrap_or_else(default_jobs),
+        Err(e) => return CommandResult::Error(e),
+    };
+    let Some(meta_path) = manifest::find(cwd) else {
+        return CommandResult::Error(format!("No .meta config found in {}", cwd.display()));
+    };
+    let projects = match manifest::projects(&meta_path) {
+        Ok((projects, _ignore)) => projects,
+        Err(e) => return CommandResult::Error(format!("Failed to parse meta config: {e}")),
+    };
+
+    let per_project = parallel_map(&projects, jobs, |project| inspect(&cwd.join(&project.path)));
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut stats = WorkspaceStats {
+        projects: projects.len(),
+        ..Default::default()
+    };
+    let mut oldest: Option<(&str, u64)> = None;
+    for (project, project_stats) in projects.iter().zip(&per_project) {
+        for tag in &project.tags {
+            *stats.by_tag.entry(tag.clone()).or_default() += 1;
+        }
+        if !project_stats.cloned {
+            stats.missing += 1;
+            continue;
+        }
+        stats.cloned += 1;
+        stats.dirty += usize::from(project_stats.dirty);
+        let ecosystem = project_stats.ecosystem.unwrap_or("other");
+        *stats.by_ecosystem.entry(ecosystem.to_string()).or_default() += 1;
+        stats.disk_bytes += project_stats.bytes;
+        stats.commits_last_30_days += project_stats.recent_commits;
+        if let Some(last_sync) = project_stats.last_sync {
+            if oldest.is_none_or(|(_, time)| last_sync < time) {
+                oldest = Some((&project.name, last_sync));
+            }
+        }
+    }
+    stats.oldest_sync = oldest.map(|(project, time)| OldestSync {
+        project: project.to_string(),
+        days_ago: now.saturating_sub(time) / 86_400,
+    });
+
+    if options.json_output || args.iter().any(|a| a == "--json") {
+        return match serde_json::to_string_pretty(&stats) {
+            Ok(json) => CommandResult::Message(json),
+            Err(e) => CommandResult::Error(format!("Failed to serialize JSON: {e}")),
+        };
+    }
+    CommandResult::Message(render(&stats))
+}
+
+fn inspect(dir: &Path) -> ProjectStats {
+    if !git::is_repo_root(dir) {
+        return ProjectStats::default();
+    }
+    let git_dir = dir.join(".git");
+    let mtime = |path: &Path| {
+        std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+    };
+    ProjectStats {
+        cloned: true,
+        dirty: git::is_dirty(dir),
+        ecosystem: ECOSYSTEMS
+            .iter()
+            .find(|(marker, _)| dir.join(marker).exists())
+            .map(|(_, ecosystem)| *ecosystem),
+        bytes: dir_size(dir),
+        recent_commits: git::output(dir, &["rev-list", "--count", "--since=30.days", "HEAD"])
+            .and_then(|count| count.parse().ok())
+            .unwrap_or(0),
+        // FETCH_HEAD is rewritten by every fetch; a never-fetched clone
+        // counts from when it was cloned
+        last_sync: mtime(&git_dir.join("FETCH_HEAD")).or_else(|| mtime(&git_dir)),
+    }
+}
+
+fn render(stats: &WorkspaceStats) -> String {
+    let counts = |map: &BTreeMap<String, usize>| {
+        if map.is_empty() {
+            return "none".to_string();
+        }
+        let mut entries: Vec<(&String, &usize)> = map.iter().collect();
+        entries.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let parts: Vec<String> = entries.iter().map(|(k, v)| format!("{k} {v}")).collect();
+        parts.join(", ")
+    };
+    let mut lines = vec![
+        format!(
+            "Projects:      {} ({} cloned, {} missing, {} dirty)",
+            stats.projects, stats.cloned, stats.missing, stats.dirty
+        ),
+        format!("By tag:        {}", counts(&stats.by_tag)),
+        format!("By ecosystem:  {}", counts(&stats.by_ecosystem)),
+        format!("Disk usage:    {}", format_bytes(stats.disk_bytes)),
+        format!("Commits (30d): {}", stats.commits_last_30_days),
+    ];
+    if let Some(oldest) = &stats.oldest_sync {
+        lines.push(format!(
+            "Oldest sync:   {} ({} day(s) ago)",
+            oldest.project, oldest.days_ago
+        ));
+    }
+    lines.join("\n")
+}
+
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::init_clone;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_stats_counts() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            r#"{"projects": {
+                "a": {"repo": "git@github.com:org/a.git", "tags": ["web"]},
+                "b": {"repo": "git@github.com:org/b.git", "tags": ["web", "oss"]}
+            }}"#,
+        )
+        .unwrap();
+        init_clone(temp_dir.path(), "a", "git@github.com:org/a.git");
+        std::fs::write(temp_dir.path().join("a").join("Cargo.toml"), "").unwrap();
+
+        let args = vec!["--json".to_string()];
+        let json = match handle_stats(&args, &ExecuteOptions::default(), temp_dir.path()) {
+            CommandResult::Message(json) => json,
+            _ => panic!("Expected Message result"),
+        };
+        let stats: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(stats["projects"], 2);
+        assert_eq!(stats["cloned"], 1);
+        assert_eq!(stats["missing"], 1);
+        assert_eq!(stats["dirty"], 1, "the untracked Cargo.toml");
+        assert_eq!(stats["by_tag"], serde_json::json!({"web": 2, "oss": 1}));
+        assert_eq!(stats["by_ecosystem"], serde_json::json!({"rust": 1}));
+        assert_eq!(stats["oldest_sync"]["project"], "a");
+        assert!(stats["disk_bytes"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+}
@@ -0,0 +1,71 @@
+//! Status markers for terminal output, with word equivalents for `--plain`
+//!
+//! Plain mode is for screen readers and dumb terminals: no color, no symbols,
+//! and nothing conveyed by color alone.
+
+use colored::Colorize;
+
+/// Marker for a completed operation
+pub(crate) fn ok(plain: bool) -> String {
+    if plain {
+        "OK".to_string()
+    } else {
+        "✓".green().to_string()
+    }
+}
+
+/// Marker for a failed operation
+pub(crate) fn failed(plain: bool) -> String {
+    if plain {
+        "FAILED".to_string()
+    } else {
+        "✗".red().to_string()
+    }
+}
+
+/// Marker for a problem; plain mode names it with `word` (e.g. `MISSING`)
+pub(crate) fn warn(plain: bool, word: &str) -> String {
+    if plain {
+        word.to_string()
+    } else {
+        "⚠".yellow().to_string()
+    }
+}
+
+/// Highlight a warning phrase; plain mode leaves it as is
+pub(crate) fn warn_text(plain: bool, text: &str) -> String {
+    if plain {
+        text.to_string()
+    } else {
+        text.yellow().to_string()
+    }
+}
+
+/// Emphasize a project name
+pub(crate) fn project(plain: bool, name: &str) -> String {
+    if plain {
+        name.to_string()
+    } else {
+        name.bold().to_string()
+    }
+}
+
+/// Turn off color for the rest of the process when plain output is requested
+pub(crate) fn apply(plain: bool) {
+    if plain {
+        colored::control::set_override(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_markers_are_words() {
+        assert_eq!(ok(true), "OK");
+        assert_eq!(failed(true), "FAILED");
+        assert_eq!(warn(true, "DIRTY"), "DIRTY");
+        assert_eq!(project(true, "api"), "api");
+    }
+}
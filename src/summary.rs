@@ -0,0 +1,367 @@
+//! Run artifacts: `--summary-file` Markdown reports, `--report-file` reports
+//! and `--trace-file` traces
+//!
+//! Handlers record one row per project operation as they go. When the run
+//! finishes, the Markdown summary is appended to `--summary-file` (appending
+//! matches how `$GITHUB_STEP_SUMMARY` is meant to be written), the same report
+//! is written to `--report-file` in the format its extension names, and a
+//! Chrome trace of the timed operations is written to `--trace-file`, for
+//! loading in Perfetto or `chrome://tracing`.
+
+use crate::deadline;
+use crate::render::{self, Markdown, Renderer, Report};
+use crate::{flag_value, CommandResult};
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Outcome of one project operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RowStatus {
+    Ok,
+    Failed,
+    Skipped,
+    /// A problem reported without acting on it
+    Finding,
+}
+
+impl RowStatus {
+    fn label(self) -> &'static str {
+        match self {
+            RowStatus::Ok => "✅ ok",
+            RowStatus::Failed => "❌ failed",
+            RowStatus::Skipped => "⏭️ skipped",
+            RowStatus::Finding => "⚠️ finding",
+        }
+    }
+}
+
+/// When an operation started and how long it took
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Span {
+    pub start: Instant,
+    pub duration: Duration,
+}
+
+impl Span {
+    /// A span from `start` until now
+    pub(crate) fn since(start: Instant) -> Self {
+        Span {
+            start,
+            duration: start.elapsed(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SummaryRow {
+    project: String,
+    operation: String,
+    status: RowStatus,
+    detail: String,
+    span: Option<Span>,
+}
+
+/// Per-project operations of a single command run
+#[derive(Debug)]
+pub(crate) struct RunSummary {
+    command: String,
+    started: Instant,
+    rows: Vec<SummaryRow>,
+}
+
+impl RunSummary {
+    pub(crate) fn new(command: &str) -> Self {
+        RunSummary {
+            command: command.to_string(),
+            started: Instant::now(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Record an operation on `project`
+    pub(crate) fn record(
+        &mut self,
+        project: &str,
+        operation: &str,
+        status: RowStatus,
+        detail: impl Into<String>,
+        span: Option<Span>,
+    ) {
+        self.rows.push(SummaryRow {
+            project: project.to_string(),
+            operation: operation.to_string(),
+            status,
+            detail: detail.into(),
+            span,
+        });
+    }
+
+    fn report(&self, result: &CommandResult, elapsed: Duration) -> Report {
+        let (outcome, text) = match result {
+            CommandResult::Error(e) => ("❌ failed", e.as_str()),
+            CommandResult::Message(m) => ("✅ succeeded", m.as_str()),
+            _ => ("✅ succeeded", ""),
+        };
+        let mut notes = vec![format!("{outcome} in {}", format_duration(elapsed))];
+        if !self.rows.is_empty() {
+            let failed = self
+                .rows
+                .iter()
+                .filter(|r| r.status == RowStatus::Failed)
+                .count();
+            notes.push(format!("{} operation(s), {failed} failed", self.rows.len()));
+        }
+        if deadline::timed_out() {
+            notes.push(
+                "⏱️ --timeout reached; remaining network operations were cancelled".to_string(),
+            );
+        }
+
+        Report {
+            title: format!("meta {}", self.command),
+            notes,
+            columns: vec!["Project", "Operation", "Status", "Duration", "Detail"],
+            rows: self
+                .rows
+                .iter()
+                .map(|row| {
+                    vec![
+                        row.project.clone(),
+                        row.operation.clone(),
+                        row.status.label().to_string(),
+                        row.span
+                            .map(|s| format_duration(s.duration))
+                            .unwrap_or_default(),
+                        row.detail.clone(),
+                    ]
+                })
+                .collect(),
+            output: Some(text.to_string()),
+        }
+    }
+
+    fn to_markdown(&self, result: &CommandResult, elapsed: Duration) -> String {
+        Markdown.render(&self.report(result, elapsed))
+    }
+
+    /// Chrome trace-event JSON: the whole run plus one complete ("X") event
+    /// per timed operation
+    ///
+    /// Overlapping operations are spread over lanes (shown as threads) so the
+    /// trace reveals how many ran concurrently and which ones serialized the run.
+    fn to_trace(&self, elapsed: Duration) -> serde_json::Value {
+        let micros = |d: Duration| d.as_micros() as u64;
+        let mut events = vec![serde_json::json!({
+            "name": format!("meta {}", self.command),
+            "cat": "run",
+            "ph": "X",
+            "ts": 0,
+            "dur": micros(elapsed),
+            "pid": 1,
+            "tid": 0,
+        })];
+
+        let mut timed: Vec<(&SummaryRow, Span)> = self
+            .rows
+            .iter()
+            .filter_map(|row| row.span.map(|span| (row, span)))
+            .collect();
+        timed.sort_by_key(|(_, span)| span.start);
+
+        // Greedy interval colouring: reuse the first lane that is free again
+        let mut lane_ends: Vec<Instant> = Vec::new();
+        for (row, span) in timed {
+            let end = span.start + span.duration;
+            let lane = match lane_ends.iter().position(|free| *free <= span.start) {
+                Some(lane) => {
+                    lane_ends[lane] = end;
+                    lane
+                }
+                None => {
+                    lane_ends.push(end);
+                    lane_ends.len() - 1
+                }
+            };
+            events.push(serde_json::json!({
+                "name": format!("{} {}", row.operation, row.project),
+                "cat": row.operation,
+                "ph": "X",
+                "ts": micros(span.start.saturating_duration_since(self.started)),
+                "dur": micros(span.duration),
+                "pid": 1,
+                "tid": lane + 1,
+                "args": {
+                    "project": row.project,
+                    "status": format!("{:?}", row.status).to_lowercase(),
+                    "detail": row.detail,
+                },
+            }));
+        }
+
+        serde_json::json!({ "traceEvents": events, "displayTimeUnit": "ms" })
+    }
+}
+
+/// Write the requested run artifacts and pass `result` through
+///
+/// `--summary-file` is appended to; `--report-file` and `--trace-file` are
+/// replaced. An artifact
+/// that can't be written turns a successful run into an error so CI doesn't
+/// silently lose its report.
+pub(crate) fn finish(
+    args: &[String],
+    summary: &RunSummary,
+    result: CommandResult,
+) -> CommandResult {
+    let elapsed = summary.started.elapsed();
+    let mut errors = Vec::new();
+    if let Some(path) = flag_value(args, "--summary-file") {
+        let markdown = summary.to_markdown(&result, elapsed);
+        if let Err(e) = append(Path::new(path), &markdown) {
+            errors.push(format!("Failed to write summary to {path}: {e}"));
+        }
+    }
+    if let Some(path) = flag_value(args, "--report-file") {
+        let report = render::for_path(Path::new(path)).render(&summary.report(&result, elapsed));
+        if let Err(e) = std::fs::write(path, report) {
+            errors.push(format!("Failed to write report to {path}: {e}"));
+        }
+    }
+    if let Some(path) = flag_value(args, "--trace-file") {
+        let trace = summary.to_trace(elapsed).to_string();
+        if let Err(e) = std::fs::write(path, trace) {
+            errors.push(format!("Failed to write trace to {path}: {e}"));
+        }
+    }
+
+    if errors.is_empty() {
+        return result;
+    }
+    match result {
+        CommandResult::Error(original) => {
+            CommandResult::Error(format!("{original}\n{}", errors.join("\n")))
+        }
+        _ => CommandResult::Error(errors.join("\n")),
+    }
+}
+
+fn append(path: &Path, markdown: &str) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    file.write_all(markdown.as_bytes())
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.1}s", duration.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_summary_markdown() {
+        let mut summary = RunSummary::new("project reset");
+        summary.record(
+            "a",
+            "reset",
+            RowStatus::Ok,
+            "origin/main",
+            Some(Span {
+                start: summary.started,
+                duration: Duration::from_millis(1500),
+            }),
+        );
+        summary.record("b", "reset", RowStatus::Failed, "x | y\nz", None);
+
+        let md = summary.to_markdown(
+            &CommandResult::Error("1 reset(s) failed".to_string()),
+            Duration::from_secs(2),
+        );
+        assert!(md.starts_with("## meta project reset\n\n❌ failed in 2.0s\n"));
+        assert!(md.contains("2 operation(s), 1 failed"));
+        assert!(md.contains("| `a` | reset | ✅ ok | 1.5s | origin/main |"));
+        assert!(md.contains("| `b` | reset | ❌ failed |  | x \\| y<br>z |"));
+        assert!(md.ends_with("```\n1 reset(s) failed\n```\n"));
+    }
+
+    #[test]
+    fn test_trace_spreads_overlapping_operations_over_lanes() {
+        let mut summary = RunSummary::new("project check");
+        let t0 = summary.started;
+        let span = |start_ms: u64, dur_ms: u64| Span {
+            start: t0 + Duration::from_millis(start_ms),
+            duration: Duration::from_millis(dur_ms),
+        };
+        summary.record("a", "fetch", RowStatus::Ok, "", Some(span(0, 100)));
+        summary.record("b", "fetch", RowStatus::Ok, "", Some(span(10, 50)));
+        summary.record(
+            "c",
+            "fetch",
+            RowStatus::Failed,
+            "timeout",
+            Some(span(100, 20)),
+        );
+        summary.record("d", "fetch", RowStatus::Skipped, "", None);
+
+        let trace = summary.to_trace(Duration::from_millis(200));
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0]["name"], "meta project check");
+        assert_eq!(events[0]["dur"], 200_000);
+        let lanes: Vec<(&str, u64)> = events[1..]
+            .iter()
+            .map(|e| {
+                (
+                    e["args"]["project"].as_str().unwrap(),
+                    e["tid"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(lanes, vec![("a", 1), ("b", 2), ("c", 1)]);
+        assert_eq!(events[2]["ts"], 10_000);
+        assert_eq!(events[3]["args"]["status"], "failed");
+    }
+
+    #[test]
+    fn test_finish_appends_to_summary_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("summary.md");
+        std::fs::write(&path, "previous step\n").unwrap();
+        let args = vec![
+            "--summary-file".to_string(),
+            path.to_string_lossy().to_string(),
+        ];
+
+        let summary = RunSummary::new("project check");
+        let result = finish(&args, &summary, CommandResult::Message("done".to_string()));
+        assert!(matches!(result, CommandResult::Message(_)));
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.starts_with("previous step\n## meta project check\n"));
+        assert!(written.contains("```\ndone\n```"));
+    }
+
+    #[test]
+    fn test_finish_writes_html_report() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("report.html");
+        let args = vec![
+            "--report-file".to_string(),
+            path.to_string_lossy().to_string(),
+        ];
+
+        let mut summary = RunSummary::new("project check");
+        summary.record("a", "check", RowStatus::Finding, "behind: 1", None);
+        finish(&args, &summary, CommandResult::Message("done".to_string()));
+
+        let html = std::fs::read_to_string(&path).unwrap();
+        assert!(html.contains("<title>meta project check</title>"));
+        assert!(html.contains("<td>a</td><td>check</td><td>⚠️ finding</td>"));
+        assert!(html.contains("<pre>done</pre>"));
+    }
+}
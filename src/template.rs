@@ -0,0 +1,201 @@
+//! `--format` templates: one line per project, `git for-each-ref` style
+//!
+//! Placeholders are `{name}`-style; `{{` and `}}` are literal braces, and `\t`
+//! and `\n` are expanded so templates survive shell single quotes.
+
+use crate::git;
+use std::path::Path;
+
+/// A value a template can reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Name,
+    Path,
+    Repo,
+    Tags,
+    Branch,
+    Upstream,
+    Ahead,
+    Behind,
+    Dirty,
+}
+
+impl Field {
+    const NAMES: &'static [(&'static str, Field)] = &[
+        ("name", Field::Name),
+        ("path", Field::Path),
+        ("repo", Field::Repo),
+        ("tags", Field::Tags),
+        ("branch", Field::Branch),
+        ("upstream", Field::Upstream),
+        ("ahead", Field::Ahead),
+        ("behind", Field::Behind),
+        ("dirty", Field::Dirty),
+    ];
+
+    fn parse(name: &str) -> Option<Field> {
+        Self::NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, field)| *field)
+    }
+
+    /// Whether rendering this field runs git in the project
+    fn needs_git(self) -> bool {
+        !matches!(self, Field::Name | Field::Path | Field::Repo | Field::Tags)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field(Field),
+}
+
+/// A parsed `--format` template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Template {
+    segments: Vec<Segment>,
+}
+
+/// The manifest side of a project being rendered
+pub(crate) struct Row<'a> {
+    pub name: &'a str,
+    /// Path relative to the listing root
+    pub path: &'a str,
+    pub repo: Option<&'a str>,
+    pub tags: &'a [String],
+}
+
+impl Template {
+    pub(crate) fn parse(template: &str) -> Result<Template, String> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let name: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                    let field = Field::parse(&name).ok_or_else(|| {
+                        let known: Vec<&str> = Field::NAMES.iter().map(|(n, _)| *n).collect();
+                        format!(
+                            "Unknown --format field '{{{name}}}'. Available: {}",
+                            known.join(", ")
+                        )
+                    })?;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Field(field));
+                }
+                '}' => {
+                    return Err(
+                        "Unmatched '}' in --format; use '}}' for a literal brace".to_string()
+                    )
+                }
+                '\\' if chars.peek() == Some(&'t') => {
+                    chars.next();
+                    literal.push('\t');
+                }
+                '\\' if chars.peek() == Some(&'n') => {
+                    chars.next();
+                    literal.push('\n');
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Template { segments })
+    }
+
+    /// Render one project; git fields are empty when `dir` isn't a clone
+    pub(crate) fn render(&self, row: &Row, dir: &Path) -> String {
+        let cloned = self
+            .segments
+            .iter()
+            .any(|s| matches!(s, Segment::Field(f) if f.needs_git()))
+            && git::is_repo_root(dir);
+        let counts = if cloned && self.uses(&[Field::Ahead, Field::Behind]) {
+            git::ahead_behind(dir)
+        } else {
+            None
+        };
+
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Field(field) => out.push_str(&match field {
+                    Field::Name => row.name.to_string(),
+                    Field::Path => row.path.to_string(),
+                    Field::Repo => row.repo.unwrap_or_default().to_string(),
+                    Field::Tags => row.tags.join(","),
+                    _ if !cloned => String::new(),
+                    Field::Branch => git::current_branch(dir).unwrap_or_default(),
+                    Field::Upstream => git::upstream(dir).unwrap_or_default(),
+                    Field::Ahead => counts.map(|(a, _)| a.to_string()).unwrap_or_default(),
+                    Field::Behind => counts.map(|(_, b)| b.to_string()).unwrap_or_default(),
+                    Field::Dirty => git::is_dirty(dir).to_string(),
+                }),
+            }
+        }
+        out
+    }
+
+    fn uses(&self, fields: &[Field]) -> bool {
+        self.segments
+            .iter()
+            .any(|s| matches!(s, Segment::Field(f) if fields.contains(f)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row<'a>(tags: &'a [String]) -> Row<'a> {
+        Row {
+            name: "api",
+            path: "services/api",
+            repo: Some("git@github.com:org/api.git"),
+            tags,
+        }
+    }
+
+    #[test]
+    fn test_render_manifest_fields() {
+        let tags = vec!["backend".to_string(), "rust".to_string()];
+        let template = Template::parse(r"{name}\t{path}\t{tags} {{{repo}}}").unwrap();
+        assert_eq!(
+            template.render(&row(&tags), Path::new("/nonexistent")),
+            "api\tservices/api\tbackend,rust {git@github.com:org/api.git}"
+        );
+    }
+
+    #[test]
+    fn test_git_fields_empty_when_not_cloned() {
+        let template = Template::parse("{name}:{branch}:{behind}").unwrap();
+        assert_eq!(
+            template.render(&row(&[]), Path::new("/nonexistent")),
+            "api::"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Template::parse("{nmae}")
+            .unwrap_err()
+            .contains("Unknown --format field '{nmae}'"));
+        assert!(Template::parse("a}b").unwrap_err().contains("Unmatched"));
+    }
+}
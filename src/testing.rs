@@ -0,0 +1,215 @@
+//! Tempdir-backed git fixtures for driving [`execute_command`]
+//!
+//! Used by this crate's unit tests and, with the `test-harness` feature, by
+//! downstream tools. Everything is local: upstreams are bare repositories in
+//! a temp directory, so no network access or credentials are involved. The
+//! helpers panic on failure, as test helpers do.
+//!
+//! ```ignore
+//! let fixture = Fixture::new();
+//! let upstream = fixture.upstream("api");
+//! fixture.manifest(&[("api", &upstream)]);
+//! fixture.clone_project("api", &upstream);
+//! fixture.push_commit("api", "change");
+//! let result = fixture.run("project check", &["--fetch"]);
+//! ```
+
+use crate::{execute_command, CommandResult, ExecuteOptions};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+thread_local! {
+    static CACHE: RefCell<Option<TempDir>> = const { RefCell::new(None) };
+}
+
+/// Point this thread's meta-project cache at a fresh temp directory, removed
+/// when the thread ends, so commands run by a test never touch the user's
+/// cache; [`Fixture::new`] does this itself
+pub fn isolate_cache() {
+    let dir = TempDir::new().expect("create cache directory");
+    crate::state::set_cache_root(dir.path());
+    CACHE.with(|cache| *cache.borrow_mut() = Some(dir));
+}
+
+/// A temp directory holding upstreams and a workspace, removed on drop
+///
+/// Layout: `<root>/upstreams/<name>.git` (bare), `<root>/sources/<name>`
+/// (non-bare clones used to push new commits), and `<root>/workspace`.
+pub struct Fixture {
+    root: TempDir,
+}
+
+impl Default for Fixture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        let root = TempDir::new().expect("create fixture directory");
+        std::fs::create_dir(root.path().join("workspace")).unwrap();
+        isolate_cache();
+        Fixture { root }
+    }
+
+    /// The workspace directory commands run in
+    pub fn workspace(&self) -> PathBuf {
+        self.root.path().join("workspace")
+    }
+
+    /// Create the bare upstream `name` with one commit on `main`
+    pub fn upstream(&self, name: &str) -> PathBuf {
+        let sources = self.root.path().join("sources");
+        let upstreams = self.root.path().join("upstreams");
+        std::fs::create_dir_all(&upstreams).unwrap();
+        let source = sources.join(name);
+        std::fs::create_dir_all(&source).unwrap();
+        run_git(&source, &["init", "-q", "-b", "main"]);
+        commit(&source, "initial");
+        let upstream = upstreams.join(format!("{name}.git"));
+        run_git(
+            &upstreams,
+            &[
+                "clone",
+                "-q",
+                "--bare",
+                &source.to_string_lossy(),
+                &upstream.to_string_lossy(),
+            ],
+        );
+        run_git(
+            &source,
+            &["remote", "add", "origin", &upstream.to_string_lossy()],
+        );
+        upstream
+    }
+
+    /// Write the workspace .meta declaring `(path, url)` projects
+    pub fn manifest(&self, projects: &[(&str, &Path)]) {
+        let projects: serde_json::Map<String, serde_json::Value> = projects
+            .iter()
+            .map(|(path, url)| (path.to_string(), url.to_string_lossy().into()))
+            .collect();
+        let manifest = serde_json::json!({ "projects": projects });
+        std::fs::write(self.workspace().join(".meta"), manifest.to_string()).unwrap();
+    }
+
+    /// Clone `upstream` into the workspace at `path`
+    pub fn clone_project(&self, path: &str, upstream: &Path) {
+        run_git(
+            &self.workspace(),
+            &["clone", "-q", &upstream.to_string_lossy(), path],
+        );
+    }
+
+    /// Add a commit to upstream `name` (as created by [`Fixture::upstream`])
+    pub fn push_commit(&self, name: &str, message: &str) {
+        let source = self.root.path().join("sources").join(name);
+        commit(&source, message);
+        run_git(&source, &["push", "-q", "origin", "main"]);
+    }
+
+    /// Run a command in the workspace with default options
+    pub fn run(&self, command: &str, args: &[&str]) -> CommandResult {
+        self.run_with(&ExecuteOptions::default(), command, args)
+    }
+
+    /// Run a command in the workspace with `options`
+    pub fn run_with(
+        &self,
+        options: &ExecuteOptions,
+        command: &str,
+        args: &[&str],
+    ) -> CommandResult {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        execute_command(command, &args, options, &[], &self.workspace())
+    }
+}
+
+/// Run git in `dir`, panicking if it fails
+pub fn run_git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {args:?} failed");
+}
+
+/// Record an empty commit with a fixed test identity
+pub fn commit(dir: &Path, message: &str) {
+    run_git(
+        dir,
+        &[
+            "-c",
+            "user.name=test",
+            "-c",
+            "user.email=test@example.com",
+            "commit",
+            "-q",
+            "--allow-empty",
+            "-m",
+            message,
+        ],
+    );
+}
+
+/// Create `<root>/<name>` as a git repo whose origin is `origin`
+pub fn init_clone(root: &Path, name: &str, origin: &str) {
+    let dir = root.join(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    run_git(&dir, &["init", "-q"]);
+    run_git(&dir, &["remote", "add", "origin", origin]);
+}
+
+/// Create a bare repo at `<root>/upstream.git` with one commit on `main`
+///
+/// The non-bare `<root>/source` repo it was cloned from is left in place so
+/// tests can push further commits or branches to the upstream.
+pub fn init_upstream(root: &Path) -> PathBuf {
+    let source = root.join("source");
+    std::fs::create_dir_all(&source).unwrap();
+    run_git(&source, &["init", "-q", "-b", "main"]);
+    commit(&source, "initial");
+    let upstream = root.join("upstream.git");
+    run_git(root, &["clone", "-q", "--bare", "source", "upstream.git"]);
+    upstream
+}
+
+/// Create `<root>/workspace` with a .meta listing `repo1` at `upstream`
+pub fn init_workspace(root: &Path, upstream: &Path) -> PathBuf {
+    let workspace = root.join("workspace");
+    std::fs::create_dir(&workspace).unwrap();
+    std::fs::write(
+        workspace.join(".meta"),
+        format!(r#"{{"projects": {{"repo1": "{}"}}}}"#, upstream.display()),
+    )
+    .unwrap();
+    workspace
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixture_drives_check() {
+        let fixture = Fixture::new();
+        let upstream = fixture.upstream("api");
+        fixture.manifest(&[("api", &upstream)]);
+        fixture.clone_project("api", &upstream);
+        fixture.push_commit("api", "change");
+
+        match fixture.run("project check", &["--fetch"]) {
+            CommandResult::Message(msg) => assert!(msg.contains("behind"), "{msg}"),
+            _ => panic!("Expected Message result"),
+        }
+        match fixture.run("project check", &["--fix", "--fast-forward", "--yes"]) {
+            CommandResult::Message(msg) => assert!(msg.contains("Applied 1 fix"), "{msg}"),
+            _ => panic!("Expected Message result"),
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! `meta project try-merge` — predict cross-repo merge conflicts
+//!
+//! For every project that has the topic branch, merges it into the target
+//! branch in a throwaway worktree and reports which projects would conflict.
+//! The projects' own checkouts, branches and index are never touched.
+
+use crate::pool::{default_jobs, parallel_map};
+use crate::style;
+use crate::summary::{RowStatus, RunSummary, Span};
+use crate::{flag_value, git, parse_jobs, workspace_projects, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use serde::Serialize;
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// What a trial merge of one project showed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+enum Outcome {
+    Clean,
+    Conflict { files: Vec<String> },
+    AlreadyMerged,
+    Skipped { reason: String },
+    Failed { error: String },
+}
+
+#[derive(Debug, Serialize)]
+struct TrialMerge {
+    project: String,
+    #[serde(flatten)]
+    outcome: Outcome,
+    #[serde(skip)]
+    span: Option<Span>,
+}
+
+/// Handle `meta project try-merge <branch> [--into <target>] [--jobs N] [--json]`
+pub(crate) fn handle_try_merge(
+    args: &[String],
+    options: &ExecuteOptions,
+    provided_projects: &[String],
+    cwd: &Path,
+    summary: &mut RunSummary,
+) -> CommandResult {
+    let Some(branch) = positional(args) else {
+        return CommandResult::Error(
+            "Usage: meta project try-merge <branch> [--into <target>]".to_string(),
+        );
+    };
+    let into = flag_value(args, "--into");
+    let jobs = match parse_jobs(args) {
+        Ok(jobs) => jobs.unwrap_or_else(default_jobs),
+        Err(e) => return CommandResult::Error(e),
+    };
+    let projects = match workspace_projects(provided_projects, cwd) {
+        Ok(projects) => projects,
+        Err(e) => return CommandResult::Error(e),
+    };
+
+    let trials = parallel_map(&projects, jobs, |project| {
+        let started = Instant::now();
+        TrialMerge {
+            project: project.path.clone(),
+            outcome: try_merge(&cwd.join(&project.path), branch, into),
+            span: Some(Span::since(started)),
+        }
+    });
+    for trial in &trials {
+        let (status, detail) = match &trial.outcome {
+            Outcome::Clean => (RowStatus::Ok, "merges cleanly".to_string()),
+            Outcome::AlreadyMerged => (RowStatus::Ok, "already merged".to_string()),
+            Outcome::Conflict { files } => (
+                RowStatus::Finding,
+                format!("conflicts in {}", files.join(", ")),
+            ),
+            Outcome::Skipped { reason } => (RowStatus::Skipped, reason.clone()),
+            Outcome::Failed { error } => (RowStatus::Failed, error.clone()),
+        };
+        summary.record(&trial.project, "try-merge", status, detail, trial.span);
+    }
+
+    let conflicts = trials
+        .iter()
+        .filter(|t| matches!(t.outcome, Outcome::Conflict { .. }))
+        .count();
+    let failed = trials
+        .iter()
+        .filter(|t| matches!(t.outcome, Outcome::Failed { .. }))
+        .count();
+    if options.json_output || args.iter().any(|a| a == "--json") {
+        return match serde_json::to_string_pretty(&trials) {
+            Ok(json) if conflicts + failed == 0 => CommandResult::Message(json),
+            Ok(json) => CommandResult::Error(json),
+            Err(e) => CommandResult::Error(format!("Failed to serialize JSON: {e}")),
+        };
+    }
+
+    let mut lines: Vec<String> = trials
+        .iter()
+        .filter(|t| !matches!(t.outcome, Outcome::Skipped { .. }))
+        .map(|trial| {
+            let project = style::project(options.plain, &trial.project);
+            match &trial.outcome {
+                Outcome::Clean => format!("{} {project}: merges cleanly", style::ok(options.plain)),
+                Outcome::AlreadyMerged => {
+                    format!("{} {project}: already merged", style::ok(options.plain))
+                }
+                Outcome::Conflict { files } => {
+                    let files: Vec<String> = files.iter().map(|f| format!("    {f}")).collect();
+                    format!(
+                        "{} {project}: would conflict\n{}",
+                        style::failed(options.plain),
+                        files.join("\n")
+                    )
+                }
+                Outcome::Failed { error } => {
+                    format!("{} {project}: {error}", style::failed(options.plain))
+                }
+                Outcome::Skipped { .. } => unreachable!(),
+            }
+        })
+        .collect();
+    let tried = lines.len();
+    if tried == 0 {
+        return CommandResult::Message(format!("No project has a branch named '{branch}'."));
+    }
+    lines.push(String::new());
+    lines.push(format!(
+        "{tried} project(s) with '{branch}': {conflicts} would conflict, {failed} failed"
+    ));
+    let report = lines.join("\n");
+    if conflicts + failed == 0 {
+        CommandResult::Message(report)
+    } else {
+        CommandResult::Error(report)
+    }
+}
+
+/// The first argument that is neither a flag nor a flag's value
+fn positional(args: &[String]) -> Option<&str> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--into" | "--jobs" | "-j" => {
+                iter.next();
+            }
+            a if a.starts_with('-') => {}
+            a => return Some(a),
+        }
+    }
+    None
+}
+
+/// `name` as a local branch, else as a branch on origin
+fn resolve_branch(dir: &Path, name: &str) -> Option<String> {
+    [
+        format!("refs/heads/{name}"),
+        format!("refs/remotes/origin/{name}"),
+    ]
+    .into_iter()
+    .find(|refname| git::ref_exists(dir, refname))
+}
+
+fn try_merge(dir: &Path, branch: &str, into: Option<&str>) -> Outcome {
+    let skip = |reason: &str| Outcome::Skipped {
+        reason: reason.to_string(),
+    };
+    if !git::is_repo_root(dir) {
+        return skip("not cloned");
+    }
+    let Some(source) = resolve_branch(dir, branch) else {
+        return skip(&format!("no branch '{branch}'"));
+    };
+    let target = match into {
+        Some(into) => match resolve_branch(dir, into) {
+            Some(target) => target,
+            None => return skip(&format!("no branch '{into}'")),
+        },
+        // The remote's default branch
+        None => match git::output(dir, &["symbolic-ref", "-q", "refs/remotes/origin/HEAD"]) {
+            Some(target) => target,
+            None => return skip("origin/HEAD is not set; pass --into"),
+        },
+    };
+    if git::output(dir, &["merge-base", "--is-ancestor", &source, &target]).is_some() {
+        return Outcome::AlreadyMerged;
+    }
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let worktree = std::env::temp_dir().join(format!(
+        "meta-project-try-merge-{}-{nanos}",
+        std::process::id()
+    ));
+    let worktree_arg = worktree.to_string_lossy();
+    if let Err(e) = git::run(
+        dir,
+        &["worktree", "add", "-q", "--detach", &worktree_arg, &target],
+    ) {
+        return Outcome::Failed {
+            error: format!("could not create worktree: {e}"),
+        };
+    }
+    let outcome = merge_in(&worktree, &source);
+    let _ = git::run(dir, &["worktree", "remove", "--force", &worktree_arg]);
+    let _ = std::fs::remove_dir_all(&worktree);
+    let _ = git::run(dir, &["worktree", "prune"]);
+    outcome
+}
+
+/// Merge `source` into the worktree's detached HEAD without committing
+fn merge_in(worktree: &Path, source: &str) -> Outcome {
+    let merged = git::run(
+        worktree,
+        &[
+            "-c",
+            "user.name=meta-project",
+            "-c",
+            "user.email=meta-project@localhost",
+            "merge",
+            "-q",
+            "--no-commit",
+            "--no-ff",
+            source,
+        ],
+    );
+    let Err(e) = merged else {
+        return Outcome::Clean;
+    };
+    match git::output(worktree, &["diff", "--name-only", "--diff-filter=U"]) {
+        Some(files) if !files.is_empty() => Outcome::Conflict {
+            files: files.lines().map(str::to_string).collect(),
+        },
+        _ => Outcome::Failed {
+            error: e.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{commit, run_git};
+    use tempfile::TempDir;
+
+    fn commit_file(dir: &Path, file: &str, content: &str, message: &str) {
+        std::fs::write(dir.join(file), content).unwrap();
+        run_git(dir, &["add", file]);
+        commit(dir, message);
+    }
+
+    fn project(root: &Path, name: &str) -> std::path::PathBuf {
+        let dir = root.join(name);
+        std::fs::create_dir(&dir).unwrap();
+        run_git(&dir, &["init", "-q", "-b", "main"]);
+        commit_file(&dir, "README", "one\n", "initial");
+        run_git(&dir, &["branch", "topic"]);
+        dir
+    }
+
+    #[test]
+    fn test_try_merge_reports_conflicts() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join(".meta"),
+            r#"{"projects": {
+                "clean": "git@github.com:org/clean.git",
+                "clash": "git@github.com:org/clash.git",
+                "other": "git@github.com:org/other.git"
+            }}"#,
+        )
+        .unwrap();
+        let clean = project(temp_dir.path(), "clean");
+        run_git(&clean, &["checkout", "-q", "topic"]);
+        commit_file(&clean, "NEW", "new\n", "add file");
+        run_git(&clean, &["checkout", "-q", "main"]);
+
+        let clash = project(temp_dir.path(), "clash");
+        commit_file(&clash, "README", "main\n", "main edit");
+        run_git(&clash, &["checkout", "-q", "topic"]);
+        commit_file(&clash, "README", "topic\n", "topic edit");
+
+        let other = temp_dir.path().join("other");
+        std::fs::create_dir(&other).unwrap();
+        run_git(&other, &["init", "-q"]);
+
+        let args: Vec<String> = ["topic", "--into", "main", "--json"]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        let mut summary = RunSummary::new("project try-merge");
+        let json = match handle_try_merge(
+            &args,
+            &ExecuteOptions::default(),
+            &[],
+            temp_dir.path(),
+            &mut summary,
+        ) {
+            CommandResult::Error(json) => json,
+            _ => panic!("Expected Error result"),
+        };
+        let trials: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let outcome = |project: &str| {
+            trials
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|t| t["project"] == project)
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(outcome("clean")["outcome"], "clean");
+        assert_eq!(outcome("clash")["outcome"], "conflict");
+        assert_eq!(outcome("clash")["files"], serde_json::json!(["README"]));
+        assert_eq!(outcome("other")["outcome"], "skipped");
+
+        // The trial left the real checkout and worktree list alone
+        assert_eq!(git::current_branch(&clash).as_deref(), Some("topic"));
+        assert!(!git::is_dirty(&clash));
+        let worktrees = git::output(&clash, &["worktree", "list"]).unwrap();
+        assert_eq!(worktrees.lines().count(), 1);
+    }
+}
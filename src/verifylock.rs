@@ -0,0 +1,216 @@
+//! `meta project verify-lock` — a cheap consistency gate for CI
+//!
+//! Cross-checks .meta, meta-lock.json and the clones without touching the
+//! network: every project has to be locked with its manifest URL, every
+//! locked project has to be in the manifest, and each clone has to contain
+//! its locked commit and have it checked out.
+
+use crate::lock::{Lockfile, LOCKFILE_NAME};
+use crate::{git, workspace_projects, ExecuteOptions};
+use meta_plugin_protocol::CommandResult;
+use serde::Serialize;
+use std::path::Path;
+
+/// One inconsistency between the manifest, the lockfile and a clone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "issue", rename_all = "snake_case")]
+enum Issue {
+    /// In .meta but not in the lockfile
+    Unlocked,
+    /// In the lockfile but not in .meta
+    NotInManifest,
+    /// The lockfile records another URL than .meta
+    UrlMismatch { manifest: String, locked: String },
+    /// The clone doesn't contain the locked commit
+    Unreachable { sha: String },
+    /// The clone has another commit checked out
+    Drifted { sha: String, head: String },
+}
+
+#[derive(Debug, Serialize)]
+struct Finding {
+    project: String,
+    #[serde(flatten)]
+    issue: Issue,
+}
+
+/// Handle `meta project verify-lock [--json]`
+pub(crate) fn handle_verify_lock(
+    args: &[String],
+    options: &ExecuteOptions,
+    cwd: &Path,
+) -> CommandResult {
+    let lockfile = match Lockfile::read(&cwd.join(LOCKFILE_NAME)) {
+        Ok(lockfile) => lockfile,
+        Err(e) => return CommandResult::Error(format!("Failed to read {LOCKFILE_NAME}: {e}")),
+    };
+    let projects = match workspace_projects(&[], cwd) {
+        Ok(projects) => projects,
+        Err(e) => return CommandResult::Error(e),
+    };
+
+    let mut findings = Vec::new();
+    let mut not_cloned = Vec::new();
+    for project in &projects {
+        let Some(locked) = lockfile.projects.get(&project.path) else {
+            findings.push(finding(&project.path, Issue::Unlocked));
+            continue;
+        };
+        if locked.url != project.url {
+            findings.push(finding(
+                &project.path,
+                Issue::UrlMismatch {
+                    manifest: project.url.clone(),
+                    locked: locked.url.clone(),
+                },
+            ));
+        }
+        let dir = cwd.join(&project.path);
+        if !git::is_repo_root(&dir) {
+            not_cloned.push(project.path.as_str());
+            continue;
+        }
+        let commit = format!("{}^{{commit}}", locked.sha);
+        if git::output(&dir, &["cat-file", "-e", &commit]).is_none() {
+            findings.push(finding(
+                &project.path,
+                Issue::Unreachable {
+                    sha: locked.sha.clone(),
+                },
+            ));
+            continue;
+        }
+        let head = git::output(&dir, &["rev-parse", "HEAD"]).unwrap_or_default();
+        if head != locked.sha {
+            findings.push(finding(
+                &project.path,
+                Issue::Drifted {
+                    sha: locked.sha.clone(),
+                    head,
+                },
+            ));
+        }
+    }
+    for path in lockfile.projects.keys() {
+        if !projects.iter().any(|p| &p.path == path) {
+            findings.push(finding(path, Issue::NotInManifest));
+        }
+    }
+
+    if options.json_output || args.iter().any(|a| a == "--json") {
+        return match serde_json::to_string_pretty(&findings) {
+            Ok(json) if findings.is_empty() => CommandResult::Message(json),
+            Ok(json) => CommandResult::Error(json),
+            Err(e) => CommandResult::Error(format!("Failed to serialize JSON: {e}")),
+        };
+    }
+    let mut message = if findings.is_empty() {
+        format!(
+            "{LOCKFILE_NAME} is consistent with .meta ({} project(s)).",
+            projects.len()
+        )
+    } else {
+        format!(
+            "{LOCKFILE_NAME} is inconsistent with the workspace ({} issue(s)):\n{}",
+            findings.len(),
+            render(&findings)
+        )
+    };
+    if !not_cloned.is_empty() {
+        message.push_str(&format!(
+            "\nNot cloned, commits not checked: {}",
+            not_cloned.join(", ")
+        ));
+    }
+    if findings.is_empty() {
+        CommandResult::Message(message)
+    } else {
+        CommandResult::Error(message)
+    }
+}
+
+fn finding(project: &str, issue: Issue) -> Finding {
+    Finding {
+        project: project.to_string(),
+        issue,
+    }
+}
+
+fn render(findings: &[Finding]) -> String {
+    findings
+        .iter()
+        .map(|f| {
+            let detail = match &f.issue {
+                Issue::Unlocked => format!("not in {LOCKFILE_NAME} (run 'meta project lock')"),
+                Issue::NotInManifest => "locked but not in .meta".to_string(),
+                Issue::UrlMismatch { manifest, locked } => {
+                    format!("locked from {locked}, .meta says {manifest}")
+                }
+                Issue::Unreachable { sha } => {
+                    format!("locked commit {sha} is not in the clone (fetch first)")
+                }
+                Issue::Drifted { sha, head } => format!("at {head}, locked at {sha}"),
+            };
+            format!("  {}: {detail}", f.project)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{commit, Fixture};
+
+    #[test]
+    fn test_verify_lock() {
+        let fixture = Fixture::new();
+        let api = fixture.upstream("api");
+        let web = fixture.upstream("web");
+        fixture.manifest(&[("api", &api), ("web", &web)]);
+        fixture.clone_project("api", &api);
+        fixture.clone_project("web", &web);
+        let workspace = fixture.workspace();
+
+        match fixture.run("project verify-lock", &[]) {
+            CommandResult::Error(e) => {
+                assert!(e.starts_with("Failed to read meta-lock.json"), "{e}")
+            }
+            _ => panic!("Expected Error result"),
+        }
+        match fixture.run("project lock", &[]) {
+            CommandResult::Message(_) => {}
+            _ => panic!("Expected Message result"),
+        }
+        match fixture.run("project verify-lock", &[]) {
+            CommandResult::Message(msg) => assert_eq!(
+                msg,
+                "meta-lock.json is consistent with .meta (2 project(s))."
+            ),
+            CommandResult::Error(e) => panic!("{e}"),
+            _ => panic!("Expected Message result"),
+        }
+
+        // web leaves the manifest, a new project joins it, api moves on
+        let docs = fixture.upstream("docs");
+        fixture.manifest(&[("api", &api), ("docs", &docs)]);
+        commit(&workspace.join("api"), "after the lock");
+        match fixture.run("project verify-lock", &[]) {
+            CommandResult::Error(e) => {
+                assert!(e.contains("(3 issue(s))"), "{e}");
+                assert!(e.contains("  api: at "), "{e}");
+                assert!(e.contains("  docs: not in meta-lock.json"), "{e}");
+                assert!(e.contains("  web: locked but not in .meta"), "{e}");
+            }
+            _ => panic!("Expected Error result"),
+        }
+        match fixture.run("project verify-lock", &["--json"]) {
+            CommandResult::Error(json) => {
+                let findings: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+                assert_eq!(findings[0]["project"], "api");
+                assert_eq!(findings[0]["issue"], "drifted");
+            }
+            _ => panic!("Expected Error result"),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Workspace-level mutex taken by commands that modify projects
+//!
+//! Two terminals running `reset`, `prune-remotes` or `check --fix` against the
+//! same workspace would otherwise race on the same repositories. The lock is
+//! a file in the workspace cache directory holding the owner's pid; a lock
+//! whose process has exited is stale and taken over.
+
+use crate::state;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Lock file name inside the workspace cache directory
+const LOCK_FILE: &str = "workspace.lock";
+
+/// Interval between attempts while waiting
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Contents of the lock file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct LockOwner {
+    pid: u32,
+    command: String,
+    /// Seconds since the Unix epoch
+    since: u64,
+}
+
+/// A held workspace lock, released on drop
+#[derive(Debug)]
+pub(crate) struct WorkspaceLock {
+    path: PathBuf,
+}
+
+impl Drop for WorkspaceLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Take the lock for the workspace at `cwd` on behalf of `command`
+///
+/// With `--wait` in `args`, blocks until the current holder finishes;
+/// otherwise (or with `--no-wait`) fails immediately, naming the holder.
+pub(crate) fn acquire(cwd: &Path, command: &str, args: &[String]) -> Result<WorkspaceLock, String> {
+    let wait = args.iter().any(|a| a == "--wait") && !args.iter().any(|a| a == "--no-wait");
+    let Some(path) = state::workspace_cache_file(cwd, LOCK_FILE) else {
+        return Err("Cannot locate the cache directory for the workspace lock".to_string());
+    };
+    acquire_at(&path, command, wait)
+}
+
+fn acquire_at(path: &Path, command: &str, wait: bool) -> Result<WorkspaceLock, String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    let owner = LockOwner {
+        pid: std::process::id(),
+        command: command.to_string(),
+        since: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    };
+
+    let mut announced = false;
+    loop {
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+        {
+            Ok(mut file) => {
+                let body = serde_json::to_vec(&owner).map_err(|e| e.to_string())?;
+                file.write_all(&body)
+                    .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+                return Ok(WorkspaceLock {
+                    path: path.to_path_buf(),
+                });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(format!("Failed to create {}: {e}", path.display())),
+        }
+
+        // A lock being written right now reads as unparseable; treat it as held
+        let holder: Option<LockOwner> = state::read_json(path);
+        if let Some(holder) = &holder {
+            if !process_alive(holder.pid) {
+                println!(
+                    "Removing stale workspace lock left by '{}' (pid {}).",
+                    holder.command, holder.pid
+                );
+                let _ = std::fs::remove_file(path);
+                continue;
+            }
+        }
+
+        let described = holder
+            .map(|h| format!("'{}' (pid {})", h.command, h.pid))
+            .unwrap_or_else(|| "another process".to_string());
+        if !wait {
+            return Err(format!(
+                "Workspace is locked by {described}. Re-run with --wait to wait for it, or remove {} if no meta-project command is running.",
+                path.display()
+            ));
+        }
+        if !announced {
+            println!("Waiting for workspace lock held by {described}...");
+            announced = true;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Whether the cache directory `dir` holds a lock whose owner is running
+pub(crate) fn is_held_in(dir: &Path) -> bool {
+    let path = dir.join(LOCK_FILE);
+    if !path.exists() {
+        return false;
+    }
+    state::read_json::<LockOwner>(&path).is_none_or(|holder| process_alive(holder.pid))
+}
+
+/// Whether a process with `pid` is still running
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(true)
+}
+
+/// Without a cheap liveness check, every lock is assumed held; the error
+/// message names the file to remove by hand.
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lock_is_exclusive_and_released_on_drop() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("ws").join(LOCK_FILE);
+
+        let lock = acquire_at(&path, "project reset", false).unwrap();
+        let err = acquire_at(&path, "project check", false).unwrap_err();
+        assert!(err.contains("'project reset'"), "{err}");
+        assert!(err.contains("--wait"), "{err}");
+
+        drop(lock);
+        assert!(!path.exists());
+        assert!(acquire_at(&path, "project check", false).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stale_lock_is_taken_over() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(LOCK_FILE);
+
+        // A process that has certainly exited
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        state::write_json(
+            &path,
+            &LockOwner {
+                pid,
+                command: "project reset".to_string(),
+                since: 0,
+            },
+        )
+        .unwrap();
+
+        let lock = acquire_at(&path, "project check", false).unwrap();
+        let owner: LockOwner = state::read_json(&path).unwrap();
+        assert_eq!(owner.pid, std::process::id());
+        drop(lock);
+    }
+}